@@ -0,0 +1,564 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Microsoft account login.
+//!
+//! The steps are:
+//!
+//! 1. Get a Microsoft token by the OAuth device code flow
+//! 2. Exchange it for an Xbox Live token
+//! 3. Exchange the Xbox Live token for an XSTS token
+//! 4. Log in to Minecraft with the XSTS token and get the profile
+//!
+//! See <https://wiki.vg/Microsoft_Authentication_Scheme> for details.
+
+use std::time::{Duration, Instant};
+
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    launch::{auth::AuthProfile, options::UserType},
+    Result,
+};
+
+/// The XSTS error when the Microsoft account doesn't have an Xbox account
+pub const XERR_NO_XBOX_ACCOUNT: u64 = 2148916233;
+
+/// The XSTS error when the account is a child account and must be added to a family
+pub const XERR_CHILD_ACCOUNT: u64 = 2148916238;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MicrosoftAuthError {
+    /// The user declined the authorization
+    #[error("the authorization is declined")]
+    AuthorizationDeclined,
+    /// The user didn't enter the code before it expired
+    #[error("the device code is expired")]
+    DeviceCodeExpired,
+    /// The token endpoint returned an unknown error, with the response body
+    #[error("failed to get the Microsoft token: {body}")]
+    TokenRequest { body: String },
+    /// The Microsoft account doesn't have an Xbox account, the user should sign up one on
+    /// <https://www.xbox.com/live>
+    #[error("the Microsoft account doesn't have an Xbox account")]
+    NoXboxAccount,
+    /// The account belongs to a child and must be added to a family by an adult
+    #[error("the account is a child account and must be added to a family")]
+    ChildAccount,
+    /// Other XSTS errors, with the `XErr` code and the redirect url
+    #[error("XSTS error {code}, see {redirect}")]
+    Xsts { code: u64, redirect: String },
+    /// A field is missing in the response of a login step
+    #[error("`{0}` is missing in the response")]
+    MissingField(&'static str),
+    /// The account doesn't own Minecraft
+    #[error("the account doesn't own Minecraft")]
+    NoMinecraftProfile,
+}
+
+/// The hosts used in the login, they can be replaced for testing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MicrosoftAuthEndpoints {
+    pub oauth: String,
+    pub xbox_live: String,
+    pub xsts: String,
+    pub minecraft: String,
+}
+
+impl Default for MicrosoftAuthEndpoints {
+    fn default() -> Self {
+        Self {
+            oauth: "https://login.microsoftonline.com".to_string(),
+            xbox_live: "https://user.auth.xboxlive.com".to_string(),
+            xsts: "https://xsts.auth.xboxlive.com".to_string(),
+            minecraft: "https://api.minecraftservices.com".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct DeviceCode {
+    pub device_code: String,
+
+    /// The code the user should enter in `verification_uri`
+    pub user_code: String,
+    pub verification_uri: String,
+
+    /// Seconds
+    pub expires_in: u64,
+
+    /// The seconds to wait between polls
+    pub interval: u64,
+
+    /// The localized message telling the user what to do
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct MicrosoftToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XboxLiveToken {
+    pub token: String,
+    pub user_hash: String,
+
+    /// Only provided by some XSTS responses
+    pub xuid: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct MinecraftProfile {
+    /// The uuid without hyphens
+    pub id: String,
+    pub name: String,
+}
+
+/// Microsoft account login
+///
+/// # Example
+///
+/// ```rust
+/// use mgl_core::auth::microsoft::MicrosoftAuth;
+///
+/// async fn fn_name() {
+///     let auth = MicrosoftAuth::new("your azure client id");
+///     let device_code = auth.request_device_code().await.unwrap();
+///     println!("open {} and enter {}", device_code.verification_uri, device_code.user_code);
+///     let token = auth.poll_for_token(&device_code).await.unwrap();
+///     let profile = auth.authenticate(&token.access_token).await.unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MicrosoftAuth {
+    client: Client,
+    client_id: String,
+    endpoints: MicrosoftAuthEndpoints,
+}
+
+impl MicrosoftAuth {
+    /// * `client_id` - The client id of the Azure application
+    pub fn new(client_id: &str) -> Self {
+        Self::with_endpoints(client_id, MicrosoftAuthEndpoints::default())
+    }
+
+    pub fn with_endpoints(client_id: &str, endpoints: MicrosoftAuthEndpoints) -> Self {
+        Self {
+            client: Client::new(),
+            client_id: client_id.to_string(),
+            endpoints,
+        }
+    }
+
+    pub async fn request_device_code(&self) -> Result<DeviceCode> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/consumers/oauth2/v2.0/devicecode",
+                self.endpoints.oauth
+            ))
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("scope", "XboxLive.signin offline_access"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    /// Wait until the user enters the code, the server is polled every `interval` seconds
+    pub async fn poll_for_token(&self, device_code: &DeviceCode) -> Result<MicrosoftToken> {
+        let deadline = Instant::now() + Duration::from_secs(device_code.expires_in);
+        let mut interval = device_code.interval;
+        loop {
+            let response = self
+                .client
+                .post(format!(
+                    "{}/consumers/oauth2/v2.0/token",
+                    self.endpoints.oauth
+                ))
+                .form(&[
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                    ("client_id", self.client_id.as_str()),
+                    ("device_code", device_code.device_code.as_str()),
+                ])
+                .send()
+                .await?;
+            if response.status().is_success() {
+                return Ok(response.json().await?);
+            }
+            let body: Value = response.json().await?;
+            match body["error"].as_str() {
+                Some("authorization_pending") => {}
+                Some("slow_down") => interval += 5,
+                Some("authorization_declined") => {
+                    return Err(MicrosoftAuthError::AuthorizationDeclined.into())
+                }
+                Some("expired_token") => return Err(MicrosoftAuthError::DeviceCodeExpired.into()),
+                _ => {
+                    let body = body.to_string();
+                    return Err(MicrosoftAuthError::TokenRequest { body }.into());
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(MicrosoftAuthError::DeviceCodeExpired.into());
+            }
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    }
+
+    fn parse_xbox_token(body: &Value) -> Result<XboxLiveToken> {
+        let claims = &body["DisplayClaims"]["xui"][0];
+        Ok(XboxLiveToken {
+            token: body["Token"]
+                .as_str()
+                .ok_or(MicrosoftAuthError::MissingField("Token"))?
+                .to_string(),
+            user_hash: claims["uhs"]
+                .as_str()
+                .ok_or(MicrosoftAuthError::MissingField("uhs"))?
+                .to_string(),
+            xuid: claims["xid"].as_str().map(|xuid| xuid.to_string()),
+        })
+    }
+
+    /// Exchange the Microsoft access token for an Xbox Live token
+    pub async fn xbox_live_auth(&self, microsoft_access_token: &str) -> Result<XboxLiveToken> {
+        let body: Value = self
+            .client
+            .post(format!("{}/user/authenticate", self.endpoints.xbox_live))
+            .header("Accept", "application/json")
+            .json(&json!({
+                "Properties": {
+                    "AuthMethod": "RPS",
+                    "SiteName": "user.auth.xboxlive.com",
+                    "RpsTicket": format!("d={microsoft_access_token}")
+                },
+                "RelyingParty": "http://auth.xboxlive.com",
+                "TokenType": "JWT"
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Self::parse_xbox_token(&body)
+    }
+
+    /// Exchange the Xbox Live token for an XSTS token of Minecraft
+    pub async fn xsts_auth(&self, xbox_live_token: &XboxLiveToken) -> Result<XboxLiveToken> {
+        let response = self
+            .client
+            .post(format!("{}/xsts/authorize", self.endpoints.xsts))
+            .header("Accept", "application/json")
+            .json(&json!({
+                "Properties": {
+                    "SandboxId": "RETAIL",
+                    "UserTokens": [xbox_live_token.token]
+                },
+                "RelyingParty": "rp://api.minecraftservices.com/",
+                "TokenType": "JWT"
+            }))
+            .send()
+            .await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let body: Value = response.json().await?;
+            return Err(match body["XErr"].as_u64().unwrap_or(0) {
+                XERR_NO_XBOX_ACCOUNT => MicrosoftAuthError::NoXboxAccount,
+                XERR_CHILD_ACCOUNT => MicrosoftAuthError::ChildAccount,
+                code => MicrosoftAuthError::Xsts {
+                    code,
+                    redirect: body["Redirect"].as_str().unwrap_or_default().to_string(),
+                },
+            }
+            .into());
+        }
+        let body: Value = response.error_for_status()?.json().await?;
+        Self::parse_xbox_token(&body)
+    }
+
+    /// Log in to Minecraft with the XSTS token, returns the Minecraft access token
+    pub async fn minecraft_login(&self, xsts_token: &XboxLiveToken) -> Result<String> {
+        let body: Value = self
+            .client
+            .post(format!(
+                "{}/authentication/login_with_xbox",
+                self.endpoints.minecraft
+            ))
+            .json(&json!({
+                "identityToken": format!("XBL3.0 x={};{}", xsts_token.user_hash, xsts_token.token)
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(body["access_token"]
+            .as_str()
+            .ok_or(MicrosoftAuthError::MissingField("access_token"))?
+            .to_string())
+    }
+
+    pub async fn get_profile(&self, minecraft_access_token: &str) -> Result<MinecraftProfile> {
+        let response = self
+            .client
+            .get(format!("{}/minecraft/profile", self.endpoints.minecraft))
+            .bearer_auth(minecraft_access_token)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(MicrosoftAuthError::NoMinecraftProfile.into());
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// Run all steps after getting the Microsoft access token
+    pub async fn authenticate(&self, microsoft_access_token: &str) -> Result<AuthProfile> {
+        let xbox_live_token = self.xbox_live_auth(microsoft_access_token).await?;
+        let xsts_token = self.xsts_auth(&xbox_live_token).await?;
+        let access_token = self.minecraft_login(&xsts_token).await?;
+        let profile = self.get_profile(&access_token).await?;
+        Ok(AuthProfile::Token {
+            username: profile.name,
+            uuid: profile.id,
+            access_token,
+            xuid: xsts_token.xuid.or(xbox_live_token.xuid),
+            user_type: UserType::Msa,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Matcher, Server};
+
+    use super::*;
+    use crate::{auth::AuthError, Error};
+
+    /// The Microsoft error in the error
+    fn microsoft_error(err: &Error) -> Option<&MicrosoftAuthError> {
+        match err {
+            Error::Auth(AuthError::Microsoft(err)) => Some(err),
+            _ => None,
+        }
+    }
+
+    fn auth(server: &Server) -> MicrosoftAuth {
+        MicrosoftAuth::with_endpoints(
+            "client",
+            MicrosoftAuthEndpoints {
+                oauth: server.url(),
+                xbox_live: server.url(),
+                xsts: server.url(),
+                minecraft: server.url(),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_device_code() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/consumers/oauth2/v2.0/devicecode")
+            .match_body(Matcher::UrlEncoded("client_id".into(), "client".into()))
+            .with_body(
+                json!({
+                    "user_code": "ABCD1234",
+                    "device_code": "device",
+                    "verification_uri": "https://www.microsoft.com/link",
+                    "expires_in": 900,
+                    "interval": 0,
+                    "message": "To sign in, use a web browser to open the page https://www.microsoft.com/link and enter the code ABCD1234 to authenticate."
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/consumers/oauth2/v2.0/token")
+            .match_body(Matcher::UrlEncoded("device_code".into(), "device".into()))
+            .with_body(
+                json!({
+                    "token_type": "Bearer",
+                    "scope": "XboxLive.signin offline_access",
+                    "expires_in": 3600,
+                    "access_token": "microsoft",
+                    "refresh_token": "refresh"
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/consumers/oauth2/v2.0/token")
+            .match_body(Matcher::UrlEncoded("device_code".into(), "declined".into()))
+            .with_status(400)
+            .with_body(json!({ "error": "authorization_declined" }).to_string())
+            .create_async()
+            .await;
+
+        let auth = auth(&server);
+        let mut device_code = auth.request_device_code().await.unwrap();
+        assert_eq!(device_code.user_code, "ABCD1234");
+        let token = auth.poll_for_token(&device_code).await.unwrap();
+        assert_eq!(token.access_token, "microsoft");
+
+        device_code.device_code = "declined".to_string();
+        let error = auth.poll_for_token(&device_code).await.unwrap_err();
+        assert_eq!(
+            microsoft_error(&error),
+            Some(&MicrosoftAuthError::AuthorizationDeclined)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/user/authenticate")
+            .match_body(Matcher::PartialJson(
+                json!({ "Properties": { "RpsTicket": "d=microsoft" } }),
+            ))
+            .with_body(
+                json!({
+                    "IssueInstant": "2023-08-01T12:00:00.0000000Z",
+                    "NotAfter": "2023-08-15T12:00:00.0000000Z",
+                    "Token": "xbl",
+                    "DisplayClaims": { "xui": [{ "uhs": "hash" }] }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/xsts/authorize")
+            .match_body(Matcher::PartialJson(
+                json!({ "Properties": { "UserTokens": ["xbl"] } }),
+            ))
+            .with_body(
+                json!({
+                    "IssueInstant": "2023-08-01T12:00:00.0000000Z",
+                    "NotAfter": "2023-08-02T04:00:00.0000000Z",
+                    "Token": "xsts",
+                    "DisplayClaims": { "xui": [{ "uhs": "hash" }] }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/authentication/login_with_xbox")
+            .match_body(Matcher::Json(
+                json!({ "identityToken": "XBL3.0 x=hash;xsts" }),
+            ))
+            .with_body(
+                json!({
+                    "username": "c5c0a8a6-1b5e-4b3c-9a43-6c2e9f0e6d44",
+                    "roles": [],
+                    "access_token": "minecraft",
+                    "token_type": "Bearer",
+                    "expires_in": 86400
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let profile = server
+            .mock("GET", "/minecraft/profile")
+            .match_header("Authorization", "Bearer minecraft")
+            .with_body(
+                json!({
+                    "id": "069a79f444e94726a5befca90e38aaf5",
+                    "name": "Notch",
+                    "skins": [],
+                    "capes": []
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let auth = auth(&server);
+        let account = auth.authenticate("microsoft").await.unwrap();
+        assert_eq!(account.username(), "Notch");
+        assert_eq!(account.uuid(), "069a79f444e94726a5befca90e38aaf5");
+        assert_eq!(account.access_token(), "minecraft");
+        profile.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_xsts_errors() {
+        let mut server = Server::new_async().await;
+        for (token, code) in [
+            ("adult", XERR_NO_XBOX_ACCOUNT),
+            ("child", XERR_CHILD_ACCOUNT),
+        ] {
+            server
+                .mock("POST", "/xsts/authorize")
+                .match_body(Matcher::PartialJson(
+                    json!({ "Properties": { "UserTokens": [token] } }),
+                ))
+                .with_status(401)
+                .with_body(
+                    json!({
+                        "Identity": "0",
+                        "XErr": code,
+                        "Message": "",
+                        "Redirect": "https://start.ui.xboxlive.com/AddChildToFamily"
+                    })
+                    .to_string(),
+                )
+                .create_async()
+                .await;
+        }
+        server
+            .mock("GET", "/minecraft/profile")
+            .with_status(404)
+            .with_body(json!({ "error": "NOT_FOUND" }).to_string())
+            .create_async()
+            .await;
+
+        let auth = auth(&server);
+        let error = |token: &str| XboxLiveToken {
+            token: token.to_string(),
+            user_hash: "hash".to_string(),
+            xuid: None,
+        };
+        let no_xbox = auth.xsts_auth(&error("adult")).await.unwrap_err();
+        assert_eq!(
+            microsoft_error(&no_xbox),
+            Some(&MicrosoftAuthError::NoXboxAccount)
+        );
+        let child = auth.xsts_auth(&error("child")).await.unwrap_err();
+        assert_eq!(
+            microsoft_error(&child),
+            Some(&MicrosoftAuthError::ChildAccount)
+        );
+        let no_profile = auth.get_profile("minecraft").await.unwrap_err();
+        assert_eq!(
+            microsoft_error(&no_profile),
+            Some(&MicrosoftAuthError::NoMinecraftProfile)
+        );
+    }
+}
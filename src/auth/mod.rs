@@ -0,0 +1,54 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Log in to get the accounts used to launch the game.
+//!
+//! # Example
+//!
+//! Log in with a Microsoft account by the device code flow:
+//!
+//! ```rust
+//! use mgl_core::auth::microsoft::MicrosoftAuth;
+//!
+//! async fn fn_name() {
+//!     let auth = MicrosoftAuth::new("your azure client id");
+//!     let device_code = auth.request_device_code().await.unwrap();
+//!     println!("{}", device_code.message);
+//!     let token = auth.poll_for_token(&device_code).await.unwrap();
+//!     let profile = auth.authenticate(&token.access_token).await.unwrap();
+//!     println!("{}", profile.username());
+//! }
+//! ```
+
+pub mod microsoft;
+pub mod yggdrasil;
+
+use microsoft::MicrosoftAuthError;
+use yggdrasil::YggdrasilError;
+
+/// Why a login failed, see [`Error::Auth`](crate::Error::Auth)
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error(transparent)]
+    Microsoft(#[from] MicrosoftAuthError),
+    #[error(transparent)]
+    Yggdrasil(#[from] YggdrasilError),
+    /// The yggdrasil account has several profiles, but none of them is selected
+    #[error("{available} profiles are available, but none of them is selected")]
+    NoSelectedProfile { available: usize },
+}
@@ -0,0 +1,324 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Log in to third-party yggdrasil servers, such as skin sites, and launch the game with
+//! [authlib-injector](https://github.com/yushijinhun/authlib-injector).
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::auth::yggdrasil::authlib_injector_login;
+//! use mgl_core::launch::options::LaunchOptions;
+//!
+//! async fn fn_name(options: &mut LaunchOptions) {
+//!     let profile = authlib_injector_login("https://littleskin.cn/api/yggdrasil", "user@example.com", "password")
+//!         .await
+//!         .unwrap();
+//!     // the authlib-injector jar is downloaded when the launch arguments are built
+//!     options.auth = Some(profile);
+//! }
+//! ```
+
+use std::path::PathBuf;
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::AuthError,
+    core::folder::MinecraftLocation,
+    launch::{auth::AuthProfile, options::YggdrasilAgent},
+    utils::download::DownloadError,
+    Result,
+};
+
+/// The metadata of the latest authlib-injector build
+pub const AUTHLIB_INJECTOR_LATEST_URL: &str =
+    "https://authlib-injector.yushi.moe/artifact/latest.json";
+
+/// The path of the authlib-injector jar in the libraries directory
+pub const AUTHLIB_INJECTOR_PATH: &str = "moe/yushi/authlib-injector/authlib-injector.jar";
+
+/// An error returned by the yggdrasil server
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{error}: {message}")]
+pub struct YggdrasilError {
+    /// Like `ForbiddenOperationException`
+    pub error: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YggdrasilProfile {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthenticateResponse {
+    access_token: String,
+    #[serde(default)]
+    available_profiles: Vec<YggdrasilProfile>,
+    selected_profile: Option<YggdrasilProfile>,
+}
+
+/// Log in to a yggdrasil server with the `authenticate` request
+///
+/// * `api_base` - The api root of the server, like `https://littleskin.cn/api/yggdrasil`
+///
+/// The selected profile is used, an error is returned if the account has several profiles but
+/// none of them is selected.
+pub async fn authlib_injector_login(
+    api_base: &str,
+    username: &str,
+    password: &str,
+) -> Result<AuthProfile> {
+    let api_base = api_base.trim_end_matches('/');
+    let response = Client::new()
+        .post(format!("{api_base}/authserver/authenticate"))
+        .json(&json!({
+            "agent": { "name": "Minecraft", "version": 1 },
+            "username": username,
+            "password": password,
+            "requestUser": false
+        }))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        return Err(YggdrasilError {
+            error: body["error"]
+                .as_str()
+                .unwrap_or(status.as_str())
+                .to_string(),
+            message: body["errorMessage"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        }
+        .into());
+    }
+    let response: AuthenticateResponse = response.json().await?;
+    let profile = match (response.selected_profile, response.available_profiles) {
+        (Some(profile), _) => profile,
+        (None, mut profiles) if profiles.len() == 1 => profiles.remove(0),
+        (None, profiles) => {
+            let available = profiles.len();
+            return Err(AuthError::NoSelectedProfile { available }.into());
+        }
+    };
+    Ok(AuthProfile::Yggdrasil {
+        api_base: api_base.to_string(),
+        username: profile.name,
+        uuid: profile.id,
+        access_token: response.access_token,
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AuthlibInjectorArtifact {
+    download_url: String,
+    checksums: AuthlibInjectorChecksums,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AuthlibInjectorChecksums {
+    sha256: String,
+}
+
+/// Download the latest authlib-injector to the libraries directory if it doesn't exist, returns
+/// the path of the jar.
+///
+/// * `metadata_url` - Usually [`AUTHLIB_INJECTOR_LATEST_URL`]
+pub async fn install_authlib_injector(
+    minecraft: &MinecraftLocation,
+    metadata_url: &str,
+) -> Result<PathBuf> {
+    let jar = minecraft.get_library_by_path(AUTHLIB_INJECTOR_PATH);
+    if jar.is_file() {
+        return Ok(jar);
+    }
+    let client = Client::new();
+    let artifact: AuthlibInjectorArtifact = client
+        .get(metadata_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let content = client
+        .get(&artifact.download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let sha256 = format!("{:x}", Sha256::digest(&content));
+    if sha256 != artifact.checksums.sha256 {
+        return Err(DownloadError::HashMismatch {
+            url: artifact.download_url,
+            algorithm: "sha256",
+            expected: artifact.checksums.sha256,
+            actual: sha256,
+        }
+        .into());
+    }
+    tokio::fs::create_dir_all(jar.parent().unwrap()).await?;
+    tokio::fs::write(&jar, content).await?;
+    Ok(jar)
+}
+
+/// The agent used to launch the game with a yggdrasil profile, the jar is downloaded if it
+/// doesn't exist
+pub async fn yggdrasil_agent(
+    profile: &AuthProfile,
+    minecraft: &MinecraftLocation,
+) -> Result<Option<YggdrasilAgent>> {
+    let AuthProfile::Yggdrasil { api_base, .. } = profile else {
+        return Ok(None);
+    };
+    Ok(Some(YggdrasilAgent {
+        jar: install_authlib_injector(minecraft, AUTHLIB_INJECTOR_LATEST_URL).await?,
+        server: api_base.clone(),
+        prefetched: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Matcher, Server};
+
+    use super::*;
+    use crate::Error;
+
+    #[tokio::test]
+    async fn test_login() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/api/yggdrasil/authserver/authenticate")
+            .match_body(Matcher::PartialJson(
+                json!({ "username": "user@example.com", "password": "password" }),
+            ))
+            .with_body(
+                json!({
+                    "accessToken": "token",
+                    "clientToken": "client",
+                    "availableProfiles": [
+                        { "id": "4566e69fc90748ee8d71d7ba5aa00d20", "name": "Player" }
+                    ],
+                    "selectedProfile": { "id": "4566e69fc90748ee8d71d7ba5aa00d20", "name": "Player" }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/api/yggdrasil/authserver/authenticate")
+            .match_body(Matcher::PartialJson(json!({ "password": "wrong" })))
+            .with_status(403)
+            .with_body(
+                json!({
+                    "error": "ForbiddenOperationException",
+                    "errorMessage": "Invalid credentials. Invalid username or password."
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let api_base = format!("{}/api/yggdrasil/", server.url());
+        let profile = authlib_injector_login(&api_base, "user@example.com", "password")
+            .await
+            .unwrap();
+        assert_eq!(profile.username(), "Player");
+        assert_eq!(profile.uuid(), "4566e69fc90748ee8d71d7ba5aa00d20");
+        assert_eq!(profile.access_token(), "token");
+        let AuthProfile::Yggdrasil { api_base, .. } = profile else {
+            panic!("not a yggdrasil profile");
+        };
+        assert_eq!(api_base, format!("{}/api/yggdrasil", server.url()));
+
+        let error = authlib_injector_login(&api_base, "user@example.com", "wrong")
+            .await
+            .unwrap_err();
+        let Error::Auth(AuthError::Yggdrasil(error)) = error else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!(error.error, "ForbiddenOperationException");
+    }
+
+    #[tokio::test]
+    async fn test_install_authlib_injector() {
+        let mut server = Server::new_async().await;
+        let jar = b"authlib-injector";
+        let metadata = server
+            .mock("GET", "/artifact/latest.json")
+            .with_body(
+                json!({
+                    "build_number": 53,
+                    "version": "1.2.5",
+                    "download_url": format!("{}/artifact/53/authlib-injector-1.2.5.jar", server.url()),
+                    "checksums": { "sha256": format!("{:x}", Sha256::digest(jar)) }
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/artifact/53/authlib-injector-1.2.5.jar")
+            .with_body(jar)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let metadata_url = format!("{}/artifact/latest.json", server.url());
+        let path = install_authlib_injector(&minecraft, &metadata_url)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), jar);
+        // the installed jar is reused
+        install_authlib_injector(&minecraft, &metadata_url)
+            .await
+            .unwrap();
+        metadata.assert_async().await;
+
+        let profile = AuthProfile::Yggdrasil {
+            api_base: "https://example.com/api/yggdrasil".to_string(),
+            username: "Player".to_string(),
+            uuid: "4566e69fc90748ee8d71d7ba5aa00d20".to_string(),
+            access_token: "token".to_string(),
+        };
+        let agent = yggdrasil_agent(&profile, &minecraft)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(agent.jar, path);
+        assert_eq!(agent.server, "https://example.com/api/yggdrasil");
+        assert!(yggdrasil_agent(&AuthProfile::offline("Steve"), &minecraft)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
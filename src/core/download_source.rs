@@ -0,0 +1,128 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Download sources of the game files.
+//!
+//! Official urls in version json, asset index and libraries can be rewritten to a mirror.
+//!
+//! # Example
+//!
+//! ```
+//! use mgl_core::core::download_source::DownloadSource;
+//!
+//! let source = DownloadSource::bmclapi();
+//! assert_eq!(
+//!     source.rewrite_url("https://libraries.minecraft.net/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar"),
+//!     "https://bmclapi2.bangbang93.com/maven/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar"
+//! );
+//! ```
+
+pub const OFFICIAL_META_URL: &str = "https://piston-meta.mojang.com";
+pub const OFFICIAL_LIBRARIES_URL: &str = "https://libraries.minecraft.net";
+pub const OFFICIAL_ASSETS_URL: &str = "https://resources.download.minecraft.net";
+
+/// Hosts serving version manifest, version json, asset index and game jars.
+const OFFICIAL_META_HOSTS: [&str; 4] = [
+    "https://piston-meta.mojang.com",
+    "https://launchermeta.mojang.com",
+    "https://piston-data.mojang.com",
+    "https://launcher.mojang.com",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadSource {
+    /// Replaces the hosts of version manifest, version json, asset index and game jars.
+    pub meta_base_url: String,
+
+    /// Replaces `https://libraries.minecraft.net`
+    pub libraries_base_url: String,
+
+    /// Replaces `https://resources.download.minecraft.net`
+    pub assets_base_url: String,
+}
+
+impl Default for DownloadSource {
+    fn default() -> Self {
+        Self::official()
+    }
+}
+
+impl DownloadSource {
+    /// The official source of Mojang
+    pub fn official() -> Self {
+        Self {
+            meta_base_url: OFFICIAL_META_URL.to_string(),
+            libraries_base_url: OFFICIAL_LIBRARIES_URL.to_string(),
+            assets_base_url: OFFICIAL_ASSETS_URL.to_string(),
+        }
+    }
+
+    /// BMCLAPI, see <https://bmclapidoc.bangbang93.com/>
+    pub fn bmclapi() -> Self {
+        Self {
+            meta_base_url: "https://bmclapi2.bangbang93.com".to_string(),
+            libraries_base_url: "https://bmclapi2.bangbang93.com/maven".to_string(),
+            assets_base_url: "https://bmclapi2.bangbang93.com/assets".to_string(),
+        }
+    }
+
+    /// Rewrite an official url to this source.
+    ///
+    /// Urls not from the official hosts are returned unchanged.
+    pub fn rewrite_url(&self, url: &str) -> String {
+        if let Some(path) = url.strip_prefix(OFFICIAL_LIBRARIES_URL) {
+            return format!("{}{path}", self.libraries_base_url.trim_end_matches('/'));
+        }
+        if let Some(path) = url.strip_prefix(OFFICIAL_ASSETS_URL) {
+            return format!("{}{path}", self.assets_base_url.trim_end_matches('/'));
+        }
+        if self.meta_base_url == OFFICIAL_META_URL {
+            return url.to_string();
+        }
+        for host in OFFICIAL_META_HOSTS {
+            if let Some(path) = url.strip_prefix(host) {
+                return format!("{}{path}", self.meta_base_url.trim_end_matches('/'));
+            }
+        }
+        url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_url() {
+        let url = "https://piston-data.mojang.com/v1/objects/84194a2f286ef7c14ed7ce0090dba59902951553/client.jar";
+        assert_eq!(DownloadSource::official().rewrite_url(url), url);
+        assert_eq!(
+            DownloadSource::bmclapi().rewrite_url(url),
+            "https://bmclapi2.bangbang93.com/v1/objects/84194a2f286ef7c14ed7ce0090dba59902951553/client.jar"
+        );
+        assert_eq!(
+            DownloadSource::bmclapi()
+                .rewrite_url("https://resources.download.minecraft.net/ab/abcd"),
+            "https://bmclapi2.bangbang93.com/assets/ab/abcd"
+        );
+        assert_eq!(
+            DownloadSource::bmclapi().rewrite_url("https://maven.fabricmc.net/a.jar"),
+            "https://maven.fabricmc.net/a.jar"
+        );
+    }
+}
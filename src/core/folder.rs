@@ -18,6 +18,28 @@
 
 //! The game folders parser
 //!
+//! # Layout
+//!
+//! ```text
+//! .minecraft
+//! ├── assets
+//! │   ├── indexes/<assets id>.json
+//! │   ├── log_configs/<file>
+//! │   └── objects/<first 2 chars of hash>/<hash>
+//! ├── libraries/<maven path>
+//! ├── versions
+//! │   └── <version id>
+//! │       ├── <version id>.json
+//! │       ├── <version id>.jar
+//! │       └── <version id>-natives/
+//! ├── mods
+//! ├── resourcepacks
+//! ├── saves
+//! ├── screenshots
+//! ├── logs/latest.log
+//! └── options.txt
+//! ```
+//!
 //! # Example
 //!
 //! ```
@@ -35,11 +57,18 @@
 
 use std::ffi::OsStr;
 use std::{
+    env,
     fmt::Display,
     format,
     path::{Path, PathBuf},
 };
 
+use super::version::LibraryInfo;
+use crate::Result;
+
+/// The environment variable to override the location of the minecraft folder
+pub const MINECRAFT_DIR_ENV: &str = "MINECRAFT_DIR";
+
 // todo: resources location
 
 #[derive(Debug, Clone)]
@@ -69,37 +98,126 @@ impl MinecraftLocation {
             mods: path.join("mods"),
             logs: path.join("logs"),
             latest_log: path.join("logs").join("latest.log"),
-            saves: path.join("resourcepacks"),
+            saves: path.join("saves"),
             versions: path.join("versions"),
             options: path.join("options.txt"),
             screenshots: path.join("screenshots"),
         }
     }
 
-    pub fn get_natives_root() -> PathBuf {
-        Path::new("/tmp/mgl-natives").join(uuid::Uuid::new_v4().to_string())
+    /// Read the location from the `MINECRAFT_DIR` environment variable
+    pub fn from_env() -> Option<MinecraftLocation> {
+        env::var_os(MINECRAFT_DIR_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| MinecraftLocation::new(&dir))
+    }
+
+    /// The default location of the official launcher, `None` if the home directory is unknown.
+    ///
+    /// - Windows: `%APPDATA%\.minecraft`
+    /// - macOS: `~/Library/Application Support/minecraft`
+    /// - Linux: `~/.minecraft`
+    pub fn default_location() -> Option<MinecraftLocation> {
+        let var = |key| {
+            env::var_os(key)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+        let home = || var("HOME").or_else(|| var("USERPROFILE"));
+        let root = match env::consts::OS {
+            "windows" => var("APPDATA")
+                .or_else(|| Some(home()?.join("AppData").join("Roaming")))?
+                .join(".minecraft"),
+            "macos" => home()?
+                .join("Library")
+                .join("Application Support")
+                .join("minecraft"),
+            _ => home()?.join(".minecraft"),
+        };
+        Some(MinecraftLocation::new(&root))
+    }
+
+    /// Create the directories required to install versions if they don't exist
+    pub fn ensure_structure(&self) -> Result<()> {
+        for dir in [
+            self.versions.clone(),
+            self.libraries.clone(),
+            self.assets.join("indexes"),
+            self.assets.join("objects"),
+            self.assets.join("virtual"),
+        ] {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Use another directory for the game content like `mods`, `saves` and `options.txt`, while
+    /// `versions`, `libraries` and `assets` are still shared in the root.
+    ///
+    /// This is used by isolated instances.
+    pub fn with_game_root<S: AsRef<OsStr> + ?Sized>(&self, game_root: &S) -> MinecraftLocation {
+        let game_root = Path::new(game_root);
+        MinecraftLocation {
+            root: self.root.clone(),
+            assets: self.assets.clone(),
+            libraries: self.libraries.clone(),
+            versions: self.versions.clone(),
+            resourcepacks: game_root.join("resourcepacks"),
+            mods: game_root.join("mods"),
+            logs: game_root.join("logs"),
+            latest_log: game_root.join("logs").join("latest.log"),
+            saves: game_root.join("saves"),
+            options: game_root.join("options.txt"),
+            screenshots: game_root.join("screenshots"),
+        }
+    }
+
+    /// The game directory of a version in the version isolation mode, `versions/<id>/.minecraft`
+    pub fn get_isolated_game_root<P: AsRef<Path>>(&self, version: P) -> PathBuf {
+        self.get_version_root(version).join(".minecraft")
+    }
+
+    /// The location used in the version isolation mode, `saves`, `resourcepacks`, `mods` and the
+    /// other game content are in [`MinecraftLocation::get_isolated_game_root`]
+    pub fn isolated<P: AsRef<Path>>(&self, version: P) -> MinecraftLocation {
+        self.with_game_root(&self.get_isolated_game_root(version))
     }
 
     pub fn get_version_root<P: AsRef<Path>>(&self, version: P) -> PathBuf {
         self.versions.join(version)
     }
 
+    /// Get the directory where the native libraries of the version are extracted,
+    /// like `versions/1.19.4/1.19.4-natives`
+    pub fn get_natives_directory(&self, version_id: &str) -> PathBuf {
+        self.get_version_root(version_id)
+            .join(format!("{version_id}-natives"))
+    }
+
+    /// The same as [`MinecraftLocation::get_natives_directory`]
+    pub fn get_natives_root(&self, version_id: &str) -> PathBuf {
+        self.get_natives_directory(version_id)
+    }
+
     pub fn get_version_json<P: AsRef<Path> + Display>(&self, version: P) -> PathBuf {
         self.get_version_root(&version)
             .join(format!("{version}.json"))
     }
 
+    /// Get the client jar of the version, like `versions/1.19.4/1.19.4.jar`
+    ///
+    /// * `jar` - The `jar` field of the version json. If it is set, the jar of that version is
+    ///   used instead.
     pub fn get_version_jar<P: AsRef<Path> + Display>(
         &self,
         version: P,
-        r#type: Option<&str>,
+        jar: Option<&str>,
     ) -> PathBuf {
-        if r#type == Some("client") || r#type.is_none() {
-            self.get_version_root(&version)
-                .join(format!("{version}.jar"))
-        } else {
-            self.get_version_root(&version)
-                .join(format!("{version}-{}.jar", r#type.unwrap()))
+        match jar {
+            Some(jar) => self.get_version_root(jar).join(format!("{jar}.jar")),
+            None => self
+                .get_version_root(&version)
+                .join(format!("{version}.jar")),
         }
     }
 
@@ -115,6 +233,10 @@ impl MinecraftLocation {
         self.resourcepacks.join(file_name)
     }
 
+    pub fn get_mods_root(&self) -> PathBuf {
+        self.mods.clone()
+    }
+
     pub fn get_mod<P: AsRef<Path>>(&self, file_name: P) -> PathBuf {
         self.mods.join(file_name)
     }
@@ -123,18 +245,47 @@ impl MinecraftLocation {
         self.logs.join(file_name)
     }
 
+    /// Get the library from its maven path, like `com/google/code/gson/gson/2.10/gson-2.10.jar`
     pub fn get_library_by_path<P: AsRef<Path>>(&self, library_path: P) -> PathBuf {
-        self.libraries.join(library_path)
+        self.libraries.join(normalize_separators(library_path))
     }
 
+    /// Get the library from its maven name, like `com.google.code.gson:gson:2.10`
+    pub fn get_library_by_name(&self, maven_name: &str) -> Result<PathBuf> {
+        Ok(self.get_library_by_path(LibraryInfo::from_name(maven_name)?.path))
+    }
+
+    /// The same as [`MinecraftLocation::get_assets_index_path`]
     pub fn get_assets_index(&self, version_assets: &str) -> PathBuf {
+        self.get_assets_index_path(version_assets)
+    }
+
+    /// Get the asset index json path, like `assets/indexes/5.json`
+    ///
+    /// * `assets_id` - The asset index id of the version, see `ResolvedVersion::assets`.
+    pub fn get_assets_index_path(&self, assets_id: &str) -> PathBuf {
         self.assets
             .join("indexes")
-            .join(format!("{version_assets}.json"))
+            .join(format!("{assets_id}.json"))
+    }
+
+    /// The same as [`MinecraftLocation::get_assets_index_path`]
+    pub fn get_asset_index(&self, assets_id: &str) -> PathBuf {
+        self.get_assets_index_path(assets_id)
+    }
+
+    /// Get the asset object path from its hash, like `assets/objects/ab/abcdef...`
+    pub fn get_asset_object(&self, hash: &str) -> PathBuf {
+        self.assets
+            .join("objects")
+            .join(hash.get(..2).unwrap_or(hash))
+            .join(hash)
     }
 
     pub fn get_log_config<P: AsRef<Path>>(&self, file: P) -> PathBuf {
-        self.assets.join("log_configs").join(file)
+        self.assets
+            .join("log_configs")
+            .join(normalize_separators(file))
     }
 
     pub fn get_level_file<P: AsRef<Path>>(&self, world_name: P) -> PathBuf {
@@ -142,6 +293,16 @@ impl MinecraftLocation {
     }
 }
 
+/// Split a relative path on both `/` and `\\`, so the paths from version jsons use the separator of
+/// the current platform
+fn normalize_separators<P: AsRef<Path>>(path: P) -> PathBuf {
+    path.as_ref()
+        .to_string_lossy()
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
 pub fn get_path(path: &PathBuf) -> String {
     match path.to_str() {
         None => panic!("New path is noe a valid UTF-8 sequence!"),
@@ -149,11 +310,166 @@ pub fn get_path(path: &PathBuf) -> String {
     }
 }
 
-// #[tokio::test]
-// async fn test() {
-//     let minecraft = MinecraftLocation::new("test");
-//     println!(
-//         "{:?}",
-//         MinecraftLocation::get_natives_root()
-//     );
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_game_root() {
+        let minecraft = MinecraftLocation::new(".minecraft");
+        let instance = minecraft.with_game_root("instances/modded");
+        assert_eq!(instance.mods, Path::new("instances/modded/mods"));
+        assert_eq!(instance.saves, Path::new("instances/modded/saves"));
+        assert_eq!(instance.libraries, Path::new(".minecraft/libraries"));
+        assert_eq!(
+            instance.get_version_json("1.20.1"),
+            Path::new(".minecraft/versions/1.20.1/1.20.1.json")
+        );
+    }
+
+    #[test]
+    fn test_ensure_structure() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        minecraft.ensure_structure().unwrap();
+        minecraft.ensure_structure().unwrap();
+        assert!(minecraft.versions.is_dir());
+        assert!(minecraft.libraries.is_dir());
+        for dir in ["indexes", "objects", "virtual"] {
+            assert!(minecraft.assets.join(dir).is_dir());
+        }
+    }
+
+    #[test]
+    fn test_from_env() {
+        env::set_var(MINECRAFT_DIR_ENV, "/games/.minecraft");
+        assert_eq!(
+            MinecraftLocation::from_env().unwrap().versions,
+            Path::new("/games/.minecraft/versions")
+        );
+        env::set_var(MINECRAFT_DIR_ENV, "");
+        assert!(MinecraftLocation::from_env().is_none());
+        env::remove_var(MINECRAFT_DIR_ENV);
+        assert!(MinecraftLocation::from_env().is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_default_location() {
+        let root = MinecraftLocation::default_location().unwrap().root;
+        assert!(root.ends_with(".minecraft"));
+        assert!(root.starts_with(env::var_os("HOME").unwrap()));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_default_location() {
+        let root = MinecraftLocation::default_location().unwrap().root;
+        assert!(root.ends_with("Library/Application Support/minecraft"));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_default_location() {
+        let root = MinecraftLocation::default_location().unwrap().root;
+        assert!(root.ends_with(Path::new("AppData").join("Roaming").join(".minecraft")));
+    }
+
+    #[test]
+    fn test_version_scoped_paths() {
+        let minecraft = MinecraftLocation::new("/games/.minecraft");
+        assert_eq!(
+            minecraft.get_version_root("1.19.4"),
+            Path::new("/games/.minecraft/versions/1.19.4")
+        );
+        assert_eq!(
+            minecraft.get_natives_directory("1.19.4"),
+            Path::new("/games/.minecraft/versions/1.19.4/1.19.4-natives")
+        );
+        assert_eq!(
+            minecraft.get_natives_root("1.19.4"),
+            minecraft.get_natives_directory("1.19.4")
+        );
+        assert_eq!(
+            minecraft.get_assets_index_path("5"),
+            Path::new("/games/.minecraft/assets/indexes/5.json")
+        );
+        assert_eq!(
+            minecraft.get_asset_index("5"),
+            minecraft.get_assets_index_path("5")
+        );
+        assert_eq!(
+            minecraft.get_assets_index("5"),
+            minecraft.get_assets_index_path("5")
+        );
+    }
+
+    #[test]
+    fn test_file_paths() {
+        let minecraft = MinecraftLocation::new(".minecraft");
+        let relative = |path: PathBuf| path.strip_prefix(".minecraft").unwrap().to_path_buf();
+        assert_eq!(
+            relative(minecraft.get_version_jar("1.19.4", None)),
+            Path::new("versions").join("1.19.4").join("1.19.4.jar")
+        );
+        assert_eq!(
+            relative(minecraft.get_version_jar("1.19.4-forge", Some("1.19.4"))),
+            Path::new("versions").join("1.19.4").join("1.19.4.jar")
+        );
+        assert_eq!(
+            relative(minecraft.get_asset_object("abcdef")),
+            Path::new("assets")
+                .join("objects")
+                .join("ab")
+                .join("abcdef")
+        );
+        assert_eq!(
+            relative(minecraft.get_log_config("client-1.12.xml")),
+            Path::new("assets")
+                .join("log_configs")
+                .join("client-1.12.xml")
+        );
+        assert_eq!(relative(minecraft.get_mods_root()), Path::new("mods"));
+    }
+
+    #[test]
+    fn test_library_paths() {
+        let minecraft = MinecraftLocation::new(".minecraft");
+        let gson = Path::new(".minecraft")
+            .join("libraries")
+            .join("com")
+            .join("google")
+            .join("code")
+            .join("gson")
+            .join("gson")
+            .join("2.10")
+            .join("gson-2.10.jar");
+        assert_eq!(
+            minecraft.get_library_by_path("com/google/code/gson/gson/2.10/gson-2.10.jar"),
+            gson
+        );
+        assert_eq!(
+            minecraft.get_library_by_path("com\\google\\code\\gson\\gson\\2.10\\gson-2.10.jar"),
+            gson
+        );
+        assert_eq!(
+            minecraft
+                .get_library_by_name("com.google.code.gson:gson:2.10")
+                .unwrap(),
+            gson
+        );
+        assert_eq!(
+            minecraft
+                .get_library_by_name("net.minecraftforge:forge:1.20.1:universal@zip")
+                .unwrap(),
+            Path::new(".minecraft")
+                .join("libraries")
+                .join("net")
+                .join("minecraftforge")
+                .join("forge")
+                .join("1.20.1")
+                .join("forge-1.20.1-universal.zip")
+        );
+        assert!(minecraft.get_library_by_name("gson").is_err());
+    }
+}
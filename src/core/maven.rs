@@ -0,0 +1,150 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Maven repositories of the libraries.
+//!
+//! The libraries without `downloads` only have a maven name and maybe the repository in `url`,
+//! the same path is tried in the other repositories if their own one fails.
+//!
+//! # Example
+//!
+//! ```
+//! use mgl_core::core::maven::RepoChain;
+//!
+//! let repos = RepoChain::default().with_mirror("https://bmclapi2.bangbang93.com/maven");
+//! assert_eq!(
+//!     repos.urls(None, "net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar")[0],
+//!     "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar"
+//! );
+//! ```
+
+use super::download_source::OFFICIAL_LIBRARIES_URL;
+
+pub const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net";
+pub const FABRIC_MAVEN_URL: &str = "https://maven.fabricmc.net";
+
+/// The old forge maven over http, it's moved to [`FORGE_MAVEN_URL`]
+const LEGACY_FORGE_MAVEN_URL: &str = "http://files.minecraftforge.net/maven";
+
+/// A maven repository, like `https://maven.fabricmc.net`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MavenRepo {
+    /// The base url without the trailing `/`
+    pub url: String,
+}
+
+impl MavenRepo {
+    /// The old forge maven over http is replaced by the https one
+    pub fn new(url: &str) -> Self {
+        let url = url.trim_end_matches('/');
+        let url = match url.strip_prefix(LEGACY_FORGE_MAVEN_URL) {
+            Some(path) => format!("{FORGE_MAVEN_URL}{path}"),
+            None => url.to_string(),
+        };
+        Self { url }
+    }
+
+    pub fn forge() -> Self {
+        Self::new(FORGE_MAVEN_URL)
+    }
+
+    pub fn fabric() -> Self {
+        Self::new(FABRIC_MAVEN_URL)
+    }
+
+    /// The official library server of Mojang
+    pub fn mojang() -> Self {
+        Self::new(OFFICIAL_LIBRARIES_URL)
+    }
+
+    /// The url of a maven path in this repository, like
+    /// `net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar`
+    pub fn artifact_url(&self, path: &str) -> String {
+        format!("{}/{}", self.url, path.trim_start_matches('/'))
+    }
+}
+
+/// The repositories tried in order for the libraries found by their maven names.
+///
+/// The repository of the library itself is always the first, then the forge maven, the fabric
+/// maven, the library server of Mojang and the mirrors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoChain {
+    pub repos: Vec<MavenRepo>,
+}
+
+impl Default for RepoChain {
+    fn default() -> Self {
+        Self {
+            repos: vec![MavenRepo::forge(), MavenRepo::fabric(), MavenRepo::mojang()],
+        }
+    }
+}
+
+impl RepoChain {
+    /// Try the mirror after the repositories already in the chain
+    pub fn with_mirror(mut self, url: &str) -> Self {
+        self.repos.push(MavenRepo::new(url));
+        self
+    }
+
+    /// The urls of a maven path in order, starting from the library's own repository `url`. A
+    /// library without `url` is from the library server of Mojang.
+    pub fn urls(&self, url: Option<&str>, path: &str) -> Vec<String> {
+        let own = url.map_or_else(MavenRepo::mojang, MavenRepo::new);
+        let mut urls: Vec<String> = Vec::with_capacity(self.repos.len() + 1);
+        for repo in std::iter::once(&own).chain(&self.repos) {
+            let url = repo.artifact_url(path);
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maven_repo() {
+        assert_eq!(
+            MavenRepo::new("http://files.minecraftforge.net/maven/").url,
+            "https://maven.minecraftforge.net"
+        );
+        assert_eq!(
+            MavenRepo::new("https://maven.fabricmc.net/").artifact_url("a/b/1/b-1.jar"),
+            "https://maven.fabricmc.net/a/b/1/b-1.jar"
+        );
+    }
+
+    #[test]
+    fn test_repo_chain() {
+        let repos = RepoChain::default().with_mirror("https://mirror.example.com/maven/");
+        assert_eq!(
+            repos.urls(Some("https://maven.fabricmc.net/"), "a/b/1/b-1.jar"),
+            vec![
+                "https://maven.fabricmc.net/a/b/1/b-1.jar",
+                "https://maven.minecraftforge.net/a/b/1/b-1.jar",
+                "https://libraries.minecraft.net/a/b/1/b-1.jar",
+                "https://mirror.example.com/maven/a/b/1/b-1.jar",
+            ]
+        );
+    }
+}
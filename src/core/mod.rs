@@ -35,7 +35,8 @@
 //! use mgl_core::core::folder::MinecraftLocation;
 //! use mgl_core::core::PlatformInfo;
 //! use mgl_core::core::version::Version;
-//! 
+//! use std::str::FromStr;
+//!
 //!  async fn fn_name() {
 //!     let version = Version::from_str("example version.json").unwrap();
 //!     let resolved = version.parse(&MinecraftLocation::new(".minecraft"), &PlatformInfo::new().await).await.unwrap();
@@ -65,10 +66,13 @@
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 
+pub mod download_source;
 pub mod folder;
+pub mod maven;
 pub mod task;
 pub mod version;
 
@@ -92,24 +96,42 @@ pub static DELIMITER: &str = ";";
 #[cfg(not(windows))]
 pub static DELIMITER: &str = ":";
 
+static PLATFORM_INFO: OnceCell<PlatformInfo> = OnceCell::new();
+
 impl PlatformInfo {
     /// get platform information
+    ///
+    /// The platform is only detected on the first call, the later calls return a cached clone.
     pub async fn new() -> Self {
-        let os_type = if cfg!(target_os = "windows") {
-            OsType::Windows
-        } else if cfg!(target_os = "linux") {
-            OsType::Linux
-        } else if cfg!(target_os = "macos") {
-            OsType::Osx
-        } else {
-            panic!("Sorry, but this program does not support your system!")
+        if let Some(platform) = PLATFORM_INFO.get() {
+            return platform.clone();
+        }
+        let platform = Self::detect().await;
+        PLATFORM_INFO.get_or_init(|| platform).clone()
+    }
+
+    /// Map an os name like [`std::env::consts::OS`] to the name used by the rules in the version
+    /// json: `windows`, `osx` or `linux`.
+    ///
+    /// The other Unix-like systems, e.g. `freebsd`, `dragonfly` and `openbsd`, are `linux`, the
+    /// Linux builds of the native libraries work on most of them.
+    pub fn from_os(os: &str) -> &'static str {
+        match os {
+            "windows" => "windows",
+            "macos" | "osx" => "osx",
+            _ => "linux",
+        }
+    }
+
+    async fn detect() -> Self {
+        let name = Self::from_os(std::env::consts::OS);
+        let os_type = match name {
+            "windows" => OsType::Windows,
+            "osx" => OsType::Osx,
+            _ => OsType::Linux,
         };
         Self {
-            name: match os_type {
-                OsType::Windows => "windows".to_string(),
-                OsType::Linux => "linux".to_string(),
-                OsType::Osx => "osx".to_string(),
-            },
+            name: name.to_string(),
             os_type,
             version: {
                 #[cfg(windows)]
@@ -125,14 +147,14 @@ impl PlatformInfo {
                     let stdout = String::from_utf8(output.stdout).unwrap();
 
                     let regex = Regex::new(r"\s+").unwrap();
-                    regex.replace_all(&stdout, ".").to_string()
+                    regex.replace_all(stdout.trim(), ".").to_string()
                 }
                 #[cfg(not(windows))]
                 {
                     let mut command = Command::new("uname");
                     command.args(&["-r"]);
                     let output = command.output().await.unwrap();
-                    String::from_utf8(output.stdout).unwrap()
+                    String::from_utf8(output.stdout).unwrap().trim().to_string()
                 }
             },
             arch: if cfg!(target_arch = "x86_64") {
@@ -152,7 +174,7 @@ impl PlatformInfo {
             } else {
                 "unknown"
             }
-                .to_string(),
+            .to_string(),
         }
     }
 }
@@ -165,7 +187,7 @@ pub struct JavaExec {
 }
 
 impl JavaExec {
-    pub async fn new<P: AsRef<OsStr>+ ?Sized>(home: &P) -> Self {
+    pub async fn new<P: AsRef<OsStr> + ?Sized>(home: &P) -> Self {
         let home = Path::new(home).to_path_buf();
         // let release = tokio::fs::read_to_string(home.join("release"))
         //     .await
@@ -194,3 +216,22 @@ async fn test() {
     let platform = PlatformInfo::new().await;
     println!("{:#?}", platform);
 }
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_cached() {
+    let platform = PlatformInfo::new().await;
+    assert_eq!(platform, PlatformInfo::new().await);
+    assert_eq!(platform.version, platform.version.trim());
+}
+
+#[cfg(test)]
+#[test]
+fn test_from_os() {
+    assert_eq!(PlatformInfo::from_os("windows"), "windows");
+    assert_eq!(PlatformInfo::from_os("macos"), "osx");
+    assert_eq!(PlatformInfo::from_os("linux"), "linux");
+    for os in ["freebsd", "dragonfly", "openbsd", "netbsd"] {
+        assert_eq!(PlatformInfo::from_os(os), "linux");
+    }
+}
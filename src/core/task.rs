@@ -20,20 +20,66 @@
 //!
 //! # Example
 //!
-//! Create listeners, then use them to monitor task progress
+//! Create a reporter, then use it to monitor task progress
 //!
 //! ```
+//! use std::sync::Arc;
 //! use mgl_core::core::folder::MinecraftLocation;
-//! use mgl_core::core::task::TaskEventListeners;
+//! use mgl_core::core::task::{ChannelReporter, TaskEvent};
 //! use mgl_core::install::install;
 //!  async fn fn_name() {
-//!     let listeners = TaskEventListeners::default().on_progress(Box::new(|completed, total, step| {
-//!         println!("progress: {completed}/{total}; step: {step}")
-//!     }));
-//!     install("1.19.4", MinecraftLocation::new(".minecraft"), listeners).await.unwrap();
+//!     let (reporter, mut events) = ChannelReporter::new();
+//!     tokio::spawn(async move {
+//!         while let Some(event) = events.recv().await {
+//!             if let TaskEvent::Progress { done, total, .. } = event {
+//!                 println!("progress: {done}/{total}")
+//!             }
+//!         }
+//!     });
+//!     install("1.19.4", MinecraftLocation::new(".minecraft"), Some(Arc::new(reporter))).await.unwrap();
+//! }
+//! ```
+//!
+//! Use [`ConsoleReporter`] to print the progress in command line tools.
+//!
+//! Cancel a long task, such as installing the assets, from another task
+//!
+//! ```
+//! use mgl_core::core::task::{CancelHandle, Cancelled};
+//!
+//! async fn fn_name(cancel: CancelHandle) {
+//!     let task = cancel.clone();
+//!     tokio::spawn(async move {
+//!         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+//!         task.cancel();
+//!     });
+//!     // pass `&cancel` to the downloads or installers, they return `Cancelled` when cancelled
+//!     if let Err(err) = cancel.check() {
+//!         assert_eq!(err, Cancelled);
+//!     }
 //! }
 //! ```
 
+use std::{
+    fmt::Display,
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use tokio::{
+    process::Command,
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        Notify,
+    },
+};
+
+use crate::Result;
+
 /// Execute the corresponding function when the installation event occurs
 ///
 /// please use `TaskEventListeners::new()` to create a new instance, and use
@@ -110,3 +156,380 @@ impl TaskEventListeners {
         (self.on_failed)();
     }
 }
+
+/// The task is cancelled by a [`CancelHandle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the task is cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Cancel a running task, the clones share the same state
+///
+/// The cancellable functions check it between steps and abort the in-flight requests and child
+/// processes, then return [`Cancelled`]. A default handle is never cancelled unless
+/// [`CancelHandle::cancel`] is called.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle {
+    state: Arc<CancelState>,
+}
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns [`Cancelled`] if the task is cancelled
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            return Err(Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Wait until the task is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            // created before the check, so a cancel between them is not missed
+            let notified = self.state.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Run a command to the end, the child process is killed when the task is cancelled
+    pub async fn run(&self, command: &mut Command) -> Result<ExitStatus> {
+        self.check()?;
+        let mut child = command.kill_on_drop(true).spawn()?;
+        tokio::select! {
+            status = child.wait() => Ok(status?),
+            _ = self.cancelled() => {
+                child.kill().await?;
+                Err(Cancelled.into())
+            }
+        }
+    }
+}
+
+/// An event of a task reported to a [`Reporter`].
+///
+/// A task starts with `Started` and ends with `Completed` or `Failed`. The events of a sub-task
+/// are wrapped in `ChildTask`, so an install is reported as a tree, like `install forge` →
+/// `download libraries` → the files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskEvent {
+    /// `total` is the same unit as the progress
+    Started {
+        name: String,
+        total: u64,
+    },
+    /// `done` and `total` are bytes if the sizes are known, otherwise the number of steps
+    Progress {
+        done: u64,
+        total: u64,
+        bytes_per_sec: u64,
+    },
+    /// An event of the sub-task `name`
+    ChildTask {
+        name: String,
+        event: Box<TaskEvent>,
+    },
+    /// Something went wrong but the task goes on, like a retried download
+    Warning {
+        message: String,
+    },
+    Completed,
+    Failed {
+        error: String,
+    },
+}
+
+/// Receive the events of a task, the installers accept an `Option<Arc<dyn Reporter>>`
+pub trait Reporter: Send + Sync {
+    fn report(&self, event: TaskEvent);
+}
+
+/// Send the events to a tokio unbounded channel
+#[derive(Debug, Clone)]
+pub struct ChannelReporter {
+    sender: UnboundedSender<TaskEvent>,
+}
+
+impl ChannelReporter {
+    /// Create a reporter and the receiver of its events
+    pub fn new() -> (Self, UnboundedReceiver<TaskEvent>) {
+        let (sender, receiver) = unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl Reporter for ChannelReporter {
+    fn report(&self, event: TaskEvent) {
+        // the receiver may be dropped if nobody cares about the progress
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Print the events to stdout, for command line tools
+///
+/// The events deeper than `max_depth` are ignored, the default depth 2 skips the single files.
+#[derive(Debug)]
+pub struct ConsoleReporter {
+    pub max_depth: usize,
+    names: Mutex<Vec<String>>,
+}
+
+impl Default for ConsoleReporter {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            names: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl ConsoleReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Reporter for ConsoleReporter {
+    fn report(&self, event: TaskEvent) {
+        let mut path = vec![];
+        let mut event = event;
+        while let TaskEvent::ChildTask { name, event: child } = event {
+            path.push(name);
+            event = *child;
+        }
+        if path.len() > self.max_depth {
+            return;
+        }
+        let mut names = self.names.lock().unwrap();
+        if path.is_empty() {
+            if let TaskEvent::Started { name, .. } = &event {
+                *names = vec![name.clone()];
+            }
+        }
+        let path = names.iter().chain(path.iter()).cloned().collect::<Vec<_>>();
+        let path = path.join(" > ");
+        match event {
+            TaskEvent::Started { .. } => println!("{path}: started"),
+            TaskEvent::Progress {
+                done,
+                total,
+                bytes_per_sec,
+            } => {
+                let percent = (done * 100).checked_div(total).unwrap_or(100);
+                if bytes_per_sec == 0 {
+                    println!("{path}: {percent}%");
+                } else {
+                    println!("{path}: {percent}% ({} KiB/s)", bytes_per_sec / 1024);
+                }
+            }
+            TaskEvent::Warning { message } => println!("{path}: warning, {message}"),
+            TaskEvent::Completed => println!("{path}: completed"),
+            TaskEvent::Failed { error } => println!("{path}: failed, {error}"),
+            TaskEvent::ChildTask { .. } => unreachable!(),
+        }
+    }
+}
+
+/// Wrap the events of a sub-task in [`TaskEvent::ChildTask`]
+struct ChildReporter {
+    parent: Arc<dyn Reporter>,
+    name: String,
+}
+
+impl Reporter for ChildReporter {
+    fn report(&self, event: TaskEvent) {
+        self.parent.report(TaskEvent::ChildTask {
+            name: self.name.clone(),
+            event: Box::new(event),
+        });
+    }
+}
+
+/// A started task, it sends the events of the task to the reporter if there is one
+#[derive(Clone)]
+pub struct TaskReporter {
+    reporter: Option<Arc<dyn Reporter>>,
+    started: Instant,
+}
+
+impl TaskReporter {
+    /// Start a task and report [`TaskEvent::Started`]
+    pub fn new(reporter: Option<Arc<dyn Reporter>>, name: &str, total: u64) -> Self {
+        let task = Self {
+            reporter,
+            started: Instant::now(),
+        };
+        task.report(TaskEvent::Started {
+            name: name.to_string(),
+            total,
+        });
+        task
+    }
+
+    /// Start a sub-task
+    pub fn child(&self, name: &str, total: u64) -> Self {
+        let reporter = self.reporter.as_ref().map(|parent| {
+            Arc::new(ChildReporter {
+                parent: parent.clone(),
+                name: name.to_string(),
+            }) as Arc<dyn Reporter>
+        });
+        Self::new(reporter, name, total)
+    }
+
+    pub fn report(&self, event: TaskEvent) {
+        if let Some(reporter) = &self.reporter {
+            reporter.report(event);
+        }
+    }
+
+    /// Report the progress, the speed is calculated from the `bytes` transferred since the task
+    /// started
+    pub fn progress(&self, done: u64, total: u64, bytes: u64) {
+        let millis = self.started.elapsed().as_millis().max(1) as u64;
+        self.report(TaskEvent::Progress {
+            done,
+            total,
+            bytes_per_sec: bytes * 1000 / millis,
+        });
+    }
+
+    pub fn warn(&self, message: impl Into<String>) {
+        self.report(TaskEvent::Warning {
+            message: message.into(),
+        });
+    }
+
+    /// Report [`TaskEvent::Completed`] or [`TaskEvent::Failed`] by the result, then return it
+    pub fn finish<T, E: Display>(&self, result: Result<T, E>) -> Result<T, E> {
+        match &result {
+            Ok(_) => self.report(TaskEvent::Completed),
+            Err(err) => self.report(TaskEvent::Failed {
+                error: err.to_string(),
+            }),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::Error;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let cancel = CancelHandle::new();
+        assert!(cancel.check().is_ok());
+        let waiter = {
+            let cancel = cancel.clone();
+            tokio::spawn(async move { cancel.cancelled().await })
+        };
+        cancel.cancel();
+        waiter.await.unwrap();
+        assert_eq!(cancel.check(), Err(Cancelled));
+    }
+
+    #[test]
+    fn test_task_reporter() {
+        let (reporter, mut events) = ChannelReporter::new();
+        let task = TaskReporter::new(Some(Arc::new(reporter)), "install", 2);
+        let child = task.child("download", 10);
+        child.progress(5, 10, 0);
+        child.warn("retrying");
+        child
+            .finish(Err::<(), _>(anyhow::anyhow!("timeout")))
+            .unwrap_err();
+        task.finish(Ok::<_, Error>(())).unwrap();
+
+        let child_event = |event| TaskEvent::ChildTask {
+            name: "download".to_string(),
+            event: Box::new(event),
+        };
+        let mut received = vec![];
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(
+            received,
+            [
+                TaskEvent::Started {
+                    name: "install".to_string(),
+                    total: 2
+                },
+                child_event(TaskEvent::Started {
+                    name: "download".to_string(),
+                    total: 10
+                }),
+                child_event(TaskEvent::Progress {
+                    done: 5,
+                    total: 10,
+                    bytes_per_sec: 0
+                }),
+                child_event(TaskEvent::Warning {
+                    message: "retrying".to_string()
+                }),
+                child_event(TaskEvent::Failed {
+                    error: "timeout".to_string()
+                }),
+                TaskEvent::Completed,
+            ]
+        );
+        // without a reporter, nothing is reported
+        TaskReporter::new(None, "install", 1).child("download", 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run() {
+        let cancel = CancelHandle::new();
+        let status = cancel
+            .run(Command::new("sh").args(["-c", "exit 2"]))
+            .await
+            .unwrap();
+        assert_eq!(status.code(), Some(2));
+
+        let start = Instant::now();
+        let task = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            task.cancel();
+        });
+        let err = cancel
+            .run(Command::new("sleep").arg("10"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Cancelled));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -16,15 +16,41 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, fs::read_to_string, path::PathBuf};
-
-use anyhow::Result;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs::read_to_string,
+    future::Future,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use chrono::DateTime;
+use futures::{stream, Stream};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-
-use crate::core::folder::MinecraftLocation;
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor},
+    Deserialize, Serialize,
+};
+use serde_json::{json, Map, Value};
+use tokio::{
+    sync::mpsc::{self, Sender},
+    task::JoinSet,
+};
+
+use crate::{
+    core::{
+        download_source::DownloadSource,
+        folder::MinecraftLocation,
+        maven::{MavenRepo, RepoChain},
+    },
+    utils::download::{self, HTTP_CLIENT},
+    Error, Result,
+};
 
 use super::PlatformInfo;
 
@@ -52,48 +78,126 @@ static DEFAULT_GAME_ARGS: Lazy<Vec<String>> = Lazy::new(|| {
         "${user_type}".to_string(),
         "--versionType".to_string(),
         "${version_type}".to_string(),
-        "--width".to_string(),
-        "${resolution_width}".to_string(),
-        "--height".to_string(),
-        "${resolution_height}".to_string(),
     ]
 });
 
 static DEFAULT_JVM_ARGS: Lazy<Vec<String>> = Lazy::new(|| {
     vec![
-        "\"-Djava.library.path=${natives_directory}\"".to_string(),
-        // "\"-Djna.tmpdir=${natives_directory}\"".to_string(),
-        // "\"-Dorg.lwjgl.system.SharedLibraryExtractPath=${natives_directory}\"".to_string(),
-        // "\"-Dio.netty.native.workdir=${natives_directory}\"".to_string(),
-        "\"-Dminecraft.launcher.brand=${launcher_name}\"".to_string(),
-        "\"-Dminecraft.launcher.version=${launcher_version}\"".to_string(),
-        "\"-Dfile.encoding=UTF-8\"".to_string(),
-        "\"-Dsun.stdout.encoding=UTF-8\"".to_string(),
-        "\"-Dsun.stderr.encoding=UTF-8\"".to_string(),
-        "\"-Djava.rmi.server.useCodebaseOnly=true\"".to_string(),
-        "\"-XX:MaxInlineSize=420\"".to_string(),
-        "\"-XX:-UseAdaptiveSizePolicy\"".to_string(),
-        "\"-XX:-OmitStackTraceInFastThrow\"".to_string(),
-        "\"-XX:-DontCompileHugeMethods\"".to_string(),
-        "\"-Dcom.sun.jndi.rmi.object.trustURLCodebase=false\"".to_string(),
-        "\"-Dcom.sun.jndi.cosnaming.object.trustURLCodebase=false\"".to_string(),
-        "\"-Dlog4j2.formatMsgNoLookups=true\"".to_string(),
+        "-Djava.library.path=${natives_directory}".to_string(),
+        // "-Djna.tmpdir=${natives_directory}".to_string(),
+        // "-Dorg.lwjgl.system.SharedLibraryExtractPath=${natives_directory}".to_string(),
+        // "-Dio.netty.native.workdir=${natives_directory}".to_string(),
+        "-Dminecraft.launcher.brand=${launcher_name}".to_string(),
+        "-Dminecraft.launcher.version=${launcher_version}".to_string(),
+        "-Dfile.encoding=UTF-8".to_string(),
+        "-Dsun.stdout.encoding=UTF-8".to_string(),
+        "-Dsun.stderr.encoding=UTF-8".to_string(),
+        "-Djava.rmi.server.useCodebaseOnly=true".to_string(),
+        "-XX:MaxInlineSize=420".to_string(),
+        "-XX:-UseAdaptiveSizePolicy".to_string(),
+        "-XX:-OmitStackTraceInFastThrow".to_string(),
+        "-XX:-DontCompileHugeMethods".to_string(),
+        "-Dcom.sun.jndi.rmi.object.trustURLCodebase=false".to_string(),
+        "-Dcom.sun.jndi.cosnaming.object.trustURLCodebase=false".to_string(),
+        "-Dlog4j2.formatMsgNoLookups=true".to_string(),
         "-cp".to_string(),
         "${classpath}".to_string(),
     ]
 });
 
+/// The game arguments enabled by the launch features, in the format of the `arguments` of version
+/// jsons.
+///
+/// The versions before the features accept the same arguments, so they are used for all versions.
+pub(crate) static FEATURE_GAME_ARGS: Lazy<Vec<Value>> = Lazy::new(|| {
+    let rules = |feature: &str| json!([{ "action": "allow", "features": { feature: true } }]);
+    let argument = |feature: &str, flag: &str| {
+        json!({
+            "rules": rules(feature),
+            "value": [format!("--{flag}"), format!("${{{flag}}}")]
+        })
+    };
+    vec![
+        json!({ "rules": rules("is_demo_user"), "value": "--demo" }),
+        json!({
+            "rules": rules("has_custom_resolution"),
+            "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]
+        }),
+        argument("has_quick_plays_support", "quickPlayPath"),
+        argument("is_quick_play_singleplayer", "quickPlaySingleplayer"),
+        argument("is_quick_play_multiplayer", "quickPlayMultiplayer"),
+        argument("is_quick_play_realms", "quickPlayRealms"),
+    ]
+});
+
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct LatestVersion {
     pub release: String,
     pub snapshot: String,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+/// The type of a version, like `release` and `snapshot`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum VersionType {
+    #[default]
+    Release,
+    Snapshot,
+    OldAlpha,
+    OldBeta,
+    /// The types unknown to the official launcher, like the ones of the third-party launchers
+    Custom(String),
+}
+
+impl VersionType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            VersionType::Release => "release",
+            VersionType::Snapshot => "snapshot",
+            VersionType::OldAlpha => "old_alpha",
+            VersionType::OldBeta => "old_beta",
+            VersionType::Custom(r#type) => r#type,
+        }
+    }
+}
+
+impl From<String> for VersionType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "release" => VersionType::Release,
+            "snapshot" => VersionType::Snapshot,
+            "old_alpha" => VersionType::OldAlpha,
+            "old_beta" => VersionType::OldBeta,
+            _ => VersionType::Custom(value),
+        }
+    }
+}
+
+impl From<VersionType> for String {
+    fn from(value: VersionType) -> Self {
+        match value {
+            VersionType::Custom(r#type) => r#type,
+            _ => value.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for VersionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A version in the version manifest.
+///
+/// The versions are ordered by their ids, see [`VersionId`]. A snapshot can't be compared with a
+/// release by the id, so they are ordered by `release_time`, which puts the snapshots after the
+/// previous release and before the next one.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct VersionInfo {
     pub id: String,
-    pub r#type: String,
+    pub r#type: VersionType,
     pub url: String,
     pub time: String,
     pub release_time: String,
@@ -101,6 +205,135 @@ pub struct VersionInfo {
     pub compliance_level: u8,
 }
 
+impl Ord for VersionInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let release_time =
+            |version: &VersionInfo| DateTime::parse_from_rfc3339(&version.release_time).ok();
+        let by_release_time = || release_time(self).cmp(&release_time(other));
+        match (VersionKey::parse(&self.id), VersionKey::parse(&other.id)) {
+            (Some(a), Some(b)) if a.is_snapshot() == b.is_snapshot() => {
+                a.cmp(&b).then_with(by_release_time)
+            }
+            _ => by_release_time(),
+        }
+        .then_with(|| self.id.cmp(&other.id))
+        .then_with(|| self.sha1.cmp(&other.sha1))
+    }
+}
+
+impl PartialOrd for VersionInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A Minecraft version id which can be compared, like `1.19.4`, `1.20.1-pre1`, `23w43a`,
+/// `b1.7.3` and `a1.0.0`.
+///
+/// The alpha versions are before the beta versions, which are before the releases. The
+/// pre-releases and release candidates are before their releases. The snapshots are compared by
+/// the year and the week, and are after all the releases, since the releases they lead to can't be
+/// known from the ids.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionId(String);
+
+impl VersionId {
+    pub fn parse(s: &str) -> Result<VersionId> {
+        match VersionKey::parse(s) {
+            Some(_) => Ok(VersionId(s.to_string())),
+            None => Err(VersionResolveError::InvalidVersionId(s.to_string()).into()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn key(&self) -> VersionKey {
+        // checked in `parse`
+        VersionKey::parse(&self.0).unwrap()
+    }
+}
+
+impl Ord for VersionId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `1.20` and `1.20.0` are the same version, but not the same id
+        self.key()
+            .cmp(&other.key())
+            .then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for VersionId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for VersionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The parsed [`VersionId`], the order of the variants is the order of the versions
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum VersionKey {
+    Alpha([u32; 3]),
+    Beta([u32; 3]),
+    /// The pre-release, like `("pre", 1)` of `1.20.1-pre1`, is before the release
+    Release([u32; 3], PreRelease),
+    /// The year, the week and the letter, like `23w43a`
+    Snapshot(u32, u32, char),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum PreRelease {
+    Pre(String, u32),
+    None,
+}
+
+impl VersionKey {
+    fn parse(id: &str) -> Option<Self> {
+        static SNAPSHOT: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^(\d{2})w(\d{2})([a-z])$").unwrap());
+        static RELEASE: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"^([ab]?)(\d+)\.(\d+)(?:\.(\d+))?(?:-([a-z]+)(\d+))?$").unwrap()
+        });
+        if let Some(caps) = SNAPSHOT.captures(id) {
+            return Some(VersionKey::Snapshot(
+                caps[1].parse().ok()?,
+                caps[2].parse().ok()?,
+                caps[3].chars().next()?,
+            ));
+        }
+        let caps = RELEASE.captures(id)?;
+        let numbers = [
+            caps[2].parse().ok()?,
+            caps[3].parse().ok()?,
+            caps.get(4)
+                .map_or(Some(0), |patch| patch.as_str().parse().ok())?,
+        ];
+        match &caps[1] {
+            "a" => Some(VersionKey::Alpha(numbers)),
+            "b" => Some(VersionKey::Beta(numbers)),
+            _ => {
+                let pre = match (caps.get(5), caps.get(6)) {
+                    (Some(tag), Some(number)) => {
+                        PreRelease::Pre(tag.as_str().to_string(), number.as_str().parse().ok()?)
+                    }
+                    _ => PreRelease::None,
+                };
+                Some(VersionKey::Release(numbers, pre))
+            }
+        }
+    }
+
+    fn is_snapshot(&self) -> bool {
+        matches!(self, VersionKey::Snapshot(..))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct VersionManifest {
     pub latest: LatestVersion,
@@ -109,10 +342,62 @@ pub struct VersionManifest {
 
 impl VersionManifest {
     pub async fn new() -> Result<VersionManifest> {
-        let response =
-            reqwest::get("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json").await?;
+        Self::new_with_source(&DownloadSource::default()).await
+    }
+
+    /// Fetch the version manifest from the `meta_base_url` of the source
+    pub async fn new_with_source(source: &DownloadSource) -> Result<VersionManifest> {
+        let response = reqwest::get(format!(
+            "{}/mc/game/version_manifest_v2.json",
+            source.meta_base_url.trim_end_matches('/')
+        ))
+        .await?
+        .error_for_status()?;
         Ok(response.json::<VersionManifest>().await?)
     }
+
+    /// Download and parse the version json of a version in the manifest.
+    ///
+    /// The url will be rewritten if a source is provided.
+    pub async fn fetch_version_json(
+        info: &VersionInfo,
+        source: Option<&DownloadSource>,
+    ) -> Result<Version> {
+        let url = match source {
+            Some(source) => source.rewrite_url(&info.url),
+            None => info.url.clone(),
+        };
+        Version::from_url(&url).await
+    }
+
+    /// Get the versions of the given types
+    pub fn filtered(&self, include: &[VersionType]) -> Vec<&VersionInfo> {
+        self.versions
+            .iter()
+            .filter(|version| include.contains(&version.r#type))
+            .collect()
+    }
+
+    /// Get all versions sorted by `release_time`, newest first.
+    ///
+    /// The versions with malformed `release_time` are put at the end.
+    pub fn sorted_by_release_time(&self) -> Vec<&VersionInfo> {
+        let mut versions: Vec<_> = self
+            .versions
+            .iter()
+            .map(|version| {
+                let release_time = DateTime::parse_from_rfc3339(&version.release_time).ok();
+                (release_time, version)
+            })
+            .collect();
+        versions.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) => b.cmp(a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        versions.into_iter().map(|(_, version)| version).collect()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -120,16 +405,26 @@ pub struct Download {
     pub sha1: String,
     pub size: u64,
     pub url: String,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetIndex {
-    // pub sha1: String,
+    /// Always present in the official versions, but some third-party versions omit it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<String>,
     pub size: u64,
     pub url: String,
     pub id: String,
     pub total_size: u64,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -138,10 +433,136 @@ pub struct AssetIndexObjectInfo {
     pub size: u32,
 }
 
+impl AssetIndexObjectInfo {
+    /// The first 2 characters of the hash, the whole hash if it's malformed
+    fn prefix(&self) -> &str {
+        self.hash.get(..2).unwrap_or(&self.hash)
+    }
+
+    /// The download url of this object from the given source
+    pub fn url(&self, source: &DownloadSource) -> String {
+        format!(
+            "{}/{}/{}",
+            source.assets_base_url.trim_end_matches('/'),
+            self.prefix(),
+            self.hash
+        )
+    }
+
+    /// The path of this object relative to the assets folder, `objects/<xx>/<hash>`
+    pub fn relative_path(&self) -> String {
+        format!("objects/{}/{}", self.prefix(), self.hash)
+    }
+}
+
 // #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub type AssetIndexObject = HashMap<String, AssetIndexObjectInfo>;
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+/// Parse the `objects` of an asset index while reading it, so the downloads can start before the
+/// whole index is parsed. The other fields of the index are skipped.
+///
+/// The reader is parsed on a blocking thread, a parse error ends the stream with an `Err`.
+///
+/// ### Example
+///
+/// ```rust
+/// use futures::StreamExt;
+/// use mgl_core::core::version::stream_asset_index;
+///
+/// async fn fn_name() {
+///     let file = std::fs::File::open(".minecraft/assets/indexes/5.json").unwrap();
+///     let mut objects = Box::pin(stream_asset_index(file));
+///     while let Some(object) = objects.next().await {
+///         let (name, info) = object.unwrap();
+///         println!("{name}: {}", info.hash);
+///     }
+/// }
+/// ```
+pub fn stream_asset_index<R: Read + Send + 'static>(
+    reader: R,
+) -> impl Stream<Item = Result<(String, AssetIndexObjectInfo)>> {
+    let (sender, receiver) = mpsc::channel(64);
+    tokio::task::spawn_blocking(move || {
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+        let result = AssetIndexSeed(&sender)
+            .deserialize(&mut deserializer)
+            .and_then(|_| deserializer.end());
+        if let Err(err) = result {
+            // the receiver is gone if the stream is dropped
+            let err = VersionResolveError::InvalidJson {
+                location: "the asset index".to_string(),
+                source: err,
+            };
+            let _ = sender.blocking_send(Err(err.into()));
+        }
+    });
+    stream::unfold(receiver, |mut receiver| async move {
+        let object = receiver.recv().await?;
+        Some((object, receiver))
+    })
+}
+
+type AssetObjectSender<'a> = &'a Sender<Result<(String, AssetIndexObjectInfo)>>;
+
+/// Visit the asset index and send the entries of `objects`
+struct AssetIndexSeed<'a>(AssetObjectSender<'a>);
+
+/// Visit the `objects` map and send every entry
+struct AssetObjectsSeed<'a>(AssetObjectSender<'a>);
+
+impl<'de> DeserializeSeed<'de> for AssetIndexSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for AssetIndexSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an asset index")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "objects" {
+                map.next_value_seed(AssetObjectsSeed(self.0))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for AssetObjectsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for AssetObjectsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a map of asset objects")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(entry) = map.next_entry::<String, AssetIndexObjectInfo>()? {
+            if self.0.blocking_send(Ok(entry)).is_err() {
+                return Err(de::Error::custom("the asset index stream is dropped"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct LibraryDownload {
     pub sha1: String,
     pub size: u64,
@@ -156,10 +577,29 @@ pub struct LoggingFile {
     pub id: String,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+/// The `downloads` of a library
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct LibraryDownloads {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<LibraryDownload>,
+    /// The native jars, keyed by the classifiers in `natives`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub classifiers: Option<HashMap<String, LibraryDownload>>,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A library with `downloads`, like most libraries of the vanilla versions
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct NormalLibrary {
     pub name: String,
-    pub downloads: HashMap<String, LibraryDownload>,
+    pub downloads: LibraryDownloads,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -169,43 +609,131 @@ pub struct Rule {
     pub features: Option<HashMap<String, bool>>,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Extract {
     pub exclude: Vec<String>,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+/// A library with `natives`, the classifier of the platform is the native jar
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct NativeLibrary {
     pub name: String,
-    pub downloads: HashMap<String, LibraryDownload>,
-    pub classifiers: HashMap<String, LibraryDownload>,
-    pub rules: Vec<Rule>,
-    pub extract: Extract,
+    /// Missing in the old versions, the jars are found by the maven name in `url`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<LibraryDownloads>,
+    /// The classifiers of the platforms, like `"linux": "natives-linux"`. It may contain
+    /// `${arch}`, which is `32` or `64`.
     pub natives: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extract: Option<Extract>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+/// A library with `downloads` and `rules`, like the natives of lwjgl 3
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct PlatformSpecificLibrary {
     pub name: String,
-    pub downloads: HashMap<String, LibraryDownload>,
-    pub rules: Vec<Rule>,
+    pub downloads: LibraryDownloads,
+    pub rules: Vec<Value>,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+/// A library with only the maven name and the repository, like the libraries of fabric and the
+/// old forge
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct LegacyLibrary {
     pub name: String,
+    /// The maven repository, the official library server is used if it's missing
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub clientreq: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub serverreq: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub checksums: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<Value>>,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+/// A library in the version json.
+///
+/// The variant is decided by the fields: `natives` makes a [`NativeLibrary`], `downloads` makes a
+/// [`PlatformSpecificLibrary`] with `rules` or a [`NormalLibrary`] without, and a library with only
+/// `name` is a [`LegacyLibrary`]. The libraries not matching their shape are kept as `Unknown`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(untagged)]
 pub enum Library {
     Normal(NormalLibrary),
     Native(NativeLibrary),
     PlatformSpecific(PlatformSpecificLibrary),
     Legacy(LegacyLibrary),
+    Unknown(Value),
+}
+
+impl<'de> Deserialize<'de> for Library {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        Ok(Library::from_value(Value::deserialize(deserializer)?))
+    }
+}
+
+impl Library {
+    pub fn from_value(value: Value) -> Self {
+        let has = |field: &str| value.get(field).is_some();
+        let library = if has("natives") {
+            serde_json::from_value(value.clone()).map(Library::Native)
+        } else if has("downloads") && has("rules") {
+            serde_json::from_value(value.clone()).map(Library::PlatformSpecific)
+        } else if has("downloads") {
+            serde_json::from_value(value.clone()).map(Library::Normal)
+        } else if has("name") {
+            serde_json::from_value(value.clone()).map(Library::Legacy)
+        } else {
+            return Library::Unknown(value);
+        };
+        library.unwrap_or(Library::Unknown(value))
+    }
+
+    /// The maven name, like `com.google.code.gson:gson:2.10`
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Library::Normal(library) => Some(&library.name),
+            Library::Native(library) => Some(&library.name),
+            Library::PlatformSpecific(library) => Some(&library.name),
+            Library::Legacy(library) => Some(&library.name),
+            Library::Unknown(value) => value["name"].as_str(),
+        }
+    }
+
+    pub fn rules(&self) -> Option<&[Value]> {
+        match self {
+            Library::Normal(_) => None,
+            Library::Native(library) => library.rules.as_deref(),
+            Library::PlatformSpecific(library) => Some(&library.rules),
+            Library::Legacy(library) => library.rules.as_deref(),
+            Library::Unknown(value) => value["rules"].as_array().map(Vec::as_slice),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -223,8 +751,14 @@ pub struct Platform {
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Arguments {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub game: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub jvm: Option<Vec<Value>>,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -232,6 +766,10 @@ pub struct Logging {
     pub file: LoggingFileDownload,
     pub argument: String,
     pub r#type: String,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -240,6 +778,10 @@ pub struct LoggingFileDownload {
     pub sha1: String,
     pub size: u64,
     pub url: String,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -247,13 +789,17 @@ pub struct LoggingFileDownload {
 pub struct JavaVersion {
     pub component: String,
     pub major_version: i32,
+
+    /// The unknown fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Resolved version.json
 ///
 /// Use `new` to parse a Minecraft version json, and see the detail info of the version,
 /// equivalent to `crate::core::version::Version::parse`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResolvedVersion {
     /// The id of the version, should be identical to the version folder.
     pub id: String,
@@ -270,7 +816,7 @@ pub struct ResolvedVersion {
     pub minimum_launcher_version: i32,
     pub release_time: String,
     pub time: String,
-    pub version_type: String,
+    pub version_type: VersionType,
     pub logging: Option<HashMap<String, Logging>>,
 
     /// Recommended java version.
@@ -290,6 +836,127 @@ pub struct ResolvedVersion {
     /// It's the chain of inherits json path. The root json will be the last element of the array.
     /// The first element is the user provided version.
     pub path_chain: Vec<PathBuf>,
+
+    /// The unknown top-level fields of the version json. The fields of a version override the
+    /// fields with the same name of the version it inherits from.
+    pub extra: Map<String, Value>,
+}
+
+/// The release time of 23w14a, the first version supporting quick play
+const QUICK_PLAY_RELEASE_TIME: &str = "2023-04-05T00:00:00+00:00";
+
+/// The mod loader of a version, see [`ResolvedVersion::detect_mod_loader`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModLoader {
+    Vanilla,
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    OptiFine,
+}
+
+impl ResolvedVersion {
+    /// Write this version as a standalone version json without `inheritsFrom`, which is parsed
+    /// by [`Version::from_value`] to the same libraries, arguments and downloads.
+    ///
+    /// The libraries and the arguments are the resolved ones, so the json is for the platform
+    /// this version is resolved on. A native jar is written as the classifier of its library for
+    /// every os.
+    pub fn to_version_json(&self) -> Value {
+        let arguments = self.arguments.as_ref().map(|arguments| {
+            let strings = |arguments: &[String]| {
+                arguments
+                    .iter()
+                    .map(|argument| Value::String(argument.clone()))
+                    .collect()
+            };
+            Arguments {
+                game: Some(strings(&arguments.game)),
+                jvm: Some(strings(&arguments.jvm)),
+                extra: Map::new(),
+            }
+        });
+        let version = Version {
+            id: self.id.clone(),
+            time: Some(self.time.clone()),
+            r#type: Some(self.version_type.clone()),
+            release_time: Some(self.release_time.clone()),
+            inherits_from: None,
+            minimum_launcher_version: Some(self.minimum_launcher_version),
+            minecraft_arguments: None,
+            arguments,
+            main_class: Some(self.main_class.clone()),
+            libraries: Some(library_entries(&self.libraries)),
+            jar: None,
+            asset_index: self.asset_index.clone(),
+            assets: Some(self.assets.clone()),
+            downloads: self.downloads.clone(),
+            client: None,
+            server: None,
+            logging: self.logging.clone(),
+            java_version: Some(self.java_version.clone()),
+            client_version: (self.minecraft_version != self.id)
+                .then(|| self.minecraft_version.clone()),
+            extra: self.extra.clone(),
+        };
+        serde_json::to_value(version).unwrap()
+    }
+
+    /// Whether the version supports the quick play arguments, added in 23w14a and 1.20.
+    ///
+    /// It's decided by the id of the Minecraft version, or `release_time` if the id is unknown.
+    pub fn supports_quick_play(&self) -> bool {
+        let ids = [Some(&self.minecraft_version), self.inheritances.last()];
+        for id in ids.into_iter().flatten() {
+            match VersionKey::parse(id) {
+                Some(VersionKey::Snapshot(year, week, letter)) => {
+                    return (year, week, letter) >= (23, 14, 'a')
+                }
+                // `Pre("", 0)` is before every pre-release
+                Some(key) => {
+                    return key
+                        >= VersionKey::Release([1, 20, 0], PreRelease::Pre(String::new(), 0))
+                }
+                None => {}
+            }
+        }
+        DateTime::parse_from_rfc3339(&self.release_time).is_ok_and(|time| {
+            time >= DateTime::parse_from_rfc3339(QUICK_PLAY_RELEASE_TIME).unwrap()
+        })
+    }
+
+    /// Detect the mod loader by the main class and the libraries.
+    ///
+    /// A version with OptiFine installed on a mod loader is detected as the mod loader.
+    pub fn detect_mod_loader(&self) -> ModLoader {
+        let main_class = self.main_class.as_str();
+        let has_library = |prefix: &str| {
+            self.libraries
+                .iter()
+                .any(|library| library.download_info.path.starts_with(prefix))
+        };
+        // quilt versions have the libraries of fabric, and neoforge versions have the main class
+        // of forge
+        if main_class.starts_with("org.quiltmc.") || has_library("org/quiltmc/quilt-loader/") {
+            ModLoader::Quilt
+        } else if main_class.starts_with("net.fabricmc.")
+            || has_library("net/fabricmc/fabric-loader/")
+        {
+            ModLoader::Fabric
+        } else if has_library("net/neoforged/") {
+            ModLoader::NeoForge
+        } else if main_class.starts_with("cpw.mods.")
+            || main_class.starts_with("net.minecraftforge.")
+            || has_library("net/minecraftforge/")
+        {
+            ModLoader::Forge
+        } else if has_library("optifine/") || has_library("net/optifine/") {
+            ModLoader::OptiFine
+        } else {
+            ModLoader::Vanilla
+        }
+    }
 }
 
 /// The raw json format provided by Minecraft.
@@ -306,10 +973,7 @@ pub struct ResolvedVersion {
 /// use mgl_core::core::version::Version;
 ///
 /// async fn fn_name() {
-///     let version = reqwest::get("https://piston-meta.mojang.com/v1/packages/715ccf3330885e75b205124f09f8712542cbe7e0/1.20.1.json")
-///         .await
-///         .unwrap()
-///         .json::<Version>()
+///     let version = Version::from_url("https://piston-meta.mojang.com/v1/packages/715ccf3330885e75b205124f09f8712542cbe7e0/1.20.1.json")
 ///         .await
 ///         .unwrap();
 ///     println!("{:#?}", version);
@@ -351,74 +1015,285 @@ pub struct ResolvedVersion {
 #[serde(rename_all = "camelCase")]
 pub struct Version {
     pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<String>,
-    pub r#type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<VersionType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub release_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub inherits_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub minimum_launcher_version: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub minecraft_arguments: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<Arguments>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub main_class: Option<String>,
-    pub libraries: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub libraries: Option<Vec<Library>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub jar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub asset_index: Option<AssetIndex>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub assets: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub downloads: Option<HashMap<String, Download>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub client: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub logging: Option<HashMap<String, Logging>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub java_version: Option<JavaVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub client_version: Option<String>,
+
+    /// The unknown top-level fields, like the ones added by launchers
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(raw: &str) -> Result<Version> {
+        Version::from_json(raw, "the version json")
+    }
 }
 
 impl Version {
-    pub fn from_value(raw: Value) -> Result<Version, serde_json::Error> {
-        serde_json::from_value(raw)
+    /// Deserialize a version json, the missing `id` and the top-level fields with wrong types fail
+    /// with [`VersionParseError`]
+    pub fn from_value(raw: Value) -> Result<Version> {
+        let id = raw.get("id").and_then(Value::as_str).map(str::to_string);
+        if let Err(source) = check_fields(&raw) {
+            return Err(VersionResolveError::InvalidVersion { id, source }.into());
+        }
+        serde_json::from_value(raw).map_err(|source| {
+            VersionResolveError::InvalidJson {
+                location: format!("the version json of {}", id.unwrap_or_default()),
+                source,
+            }
+            .into()
+        })
+    }
+
+    /// Parse a version json read from `location`, which is in the error if it's invalid
+    fn from_json(raw: &str, location: impl Display) -> Result<Version> {
+        let raw = serde_json::from_str(raw).map_err(|source| VersionResolveError::InvalidJson {
+            location: location.to_string(),
+            source,
+        })?;
+        Version::from_value(raw)
     }
 
     pub fn from_versions_folder(
         minecraft: MinecraftLocation,
         version_name: &str,
-    ) -> Result<Version, std::io::Error> {
+    ) -> Result<Version> {
         let versions_folder = minecraft.versions;
         let path = versions_folder
             .join(version_name)
             .join(format!("{}.json", version_name));
 
-        let raw = read_to_string(path)?;
-        let version: Version = serde_json::from_str((&raw).as_ref())?;
-        Ok(version)
+        let raw = read_to_string(&path)?;
+        Version::from_json(&raw, path.display())
+    }
+
+    /// Download and parse a version json, like the one at [`VersionInfo::url`]
+    pub async fn from_url(url: &str) -> Result<Version> {
+        Version::from_url_with_client(url, &HTTP_CLIENT).await
+    }
+
+    /// Same as [`Version::from_url`], with a client shared by the other requests
+    pub async fn from_url_with_client(url: &str, client: &reqwest::Client) -> Result<Version> {
+        let response = client.get(url).send().await?.error_for_status()?;
+        Version::from_value(response.json().await?)
     }
 
-    pub fn from_str(raw: &str) -> Result<Version, serde_json::Error> {
-        serde_json::from_str(raw)
+    /// Write the version json to `path`, indented with 2 spaces. The unknown fields are kept, so
+    /// a version json read and saved is the same json.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|source| {
+            VersionResolveError::InvalidJson {
+                location: path.display().to_string(),
+                source,
+            }
+        })?;
+        std::fs::write(path, json)?;
+        Ok(())
     }
 
     /// parse a Minecraft version json
+    ///
+    /// The inherited versions are read from the versions folder, a missing one fails with
+    /// [`VersionResolveError::MissingInheritedVersion`].
     pub async fn parse(
         &self,
         minecraft: &MinecraftLocation,
         platform: &PlatformInfo,
     ) -> Result<ResolvedVersion> {
+        self.parse_with_options(minecraft, platform, &ParseOptions::default())
+            .await
+    }
+
+    /// Like [`Version::parse`], with the libraries adjusted by `options`
+    pub async fn parse_with_options(
+        &self,
+        minecraft: &MinecraftLocation,
+        platform: &PlatformInfo,
+        options: &ParseOptions,
+    ) -> Result<ResolvedVersion> {
+        self.parse_with_resolver(minecraft, platform, options, |_| async { Ok(None) })
+            .await
+    }
+
+    /// Read the inherited versions, returns the versions from this one to the root, the ids and
+    /// the paths of the inherited ones
+    async fn inheritance_chain<R, F>(
+        &self,
+        minecraft: &MinecraftLocation,
+        mut resolver: R,
+    ) -> Result<(Vec<Version>, Vec<String>, Vec<PathBuf>)>
+    where
+        R: FnMut(String) -> F,
+        F: Future<Output = Result<Option<Version>>>,
+    {
         let mut inherits_from = self.inherits_from.clone();
-        let versions_folder = &minecraft.versions;
         let mut versions = Vec::new();
         let mut inheritances = Vec::new();
         let mut path_chain = Vec::new();
+        let mut chain = vec![self.id.clone()];
         versions.push(self.clone());
         while let Some(inherits_from_unwrap) = inherits_from {
+            follow_inheritance(&mut chain, &inherits_from_unwrap)?;
             inheritances.push(inherits_from_unwrap.clone());
 
-            let path = versions_folder
-                .join(inherits_from_unwrap.clone())
-                .join(format!("{}.json", inherits_from_unwrap.clone()));
+            let path = minecraft.get_version_json(&inherits_from_unwrap);
             path_chain.push(path.clone());
-            let version_json = read_to_string(path)?;
-            let version_json: Version = serde_json::from_str((&version_json).as_ref())?;
+            let version_json: Version = if path.is_file() {
+                read_version_json(&path)?
+            } else {
+                match resolver(inherits_from_unwrap.clone()).await? {
+                    Some(version) => version,
+                    None => {
+                        return Err(VersionResolveError::MissingInheritedVersion {
+                            id: inherits_from_unwrap,
+                            path,
+                        }
+                        .into())
+                    }
+                }
+            };
 
             versions.push(version_json.clone());
             inherits_from = version_json.inherits_from;
         }
+        Ok((versions, inheritances, path_chain))
+    }
+
+    /// Merge the inherited versions into a version json without `inheritsFrom`, which is parsed
+    /// to the same [`ResolvedVersion`] except [`ResolvedVersion::inheritances`] and
+    /// [`ResolvedVersion::path_chain`].
+    ///
+    /// The arguments and the libraries are concatenated from the root, a library in a child
+    /// replaces the same one in its parents. The other fields are from the nearest version having
+    /// them.
+    pub async fn flatten(&self, minecraft: &MinecraftLocation) -> Result<Version> {
+        let (versions, _, _) = self
+            .inheritance_chain(minecraft, |_| async { Ok(None) })
+            .await?;
+        let mut flattened = Version {
+            id: self.id.clone(),
+            time: None,
+            r#type: None,
+            release_time: None,
+            inherits_from: None,
+            minimum_launcher_version: None,
+            minecraft_arguments: None,
+            arguments: None,
+            main_class: None,
+            libraries: None,
+            jar: None,
+            asset_index: None,
+            assets: None,
+            downloads: None,
+            client: None,
+            server: None,
+            logging: None,
+            java_version: None,
+            client_version: self.client_version.clone(),
+            extra: Map::new(),
+        };
+        let mut libraries = Vec::new();
+        for version in versions.into_iter().rev() {
+            flattened.minimum_launcher_version = flattened
+                .minimum_launcher_version
+                .max(version.minimum_launcher_version);
+            if let Some(arguments) = version.arguments {
+                let merged = flattened.arguments.get_or_insert(Arguments {
+                    game: None,
+                    jvm: None,
+                    extra: Map::new(),
+                });
+                for (merged, arguments) in [
+                    (&mut merged.game, arguments.game),
+                    (&mut merged.jvm, arguments.jvm),
+                ] {
+                    if let Some(arguments) = arguments {
+                        merged.get_or_insert_with(Vec::new).extend(arguments);
+                    }
+                }
+                merged.extra.extend(arguments.extra);
+            }
+            libraries.extend(version.libraries.unwrap_or_default());
+            flattened.time = version.time.or(flattened.time);
+            flattened.r#type = version.r#type.or(flattened.r#type);
+            flattened.release_time = version.release_time.or(flattened.release_time);
+            flattened.minecraft_arguments = version
+                .minecraft_arguments
+                .or(flattened.minecraft_arguments);
+            flattened.main_class = version.main_class.or(flattened.main_class);
+            flattened.jar = version.jar.or(flattened.jar);
+            flattened.asset_index = version.asset_index.or(flattened.asset_index);
+            flattened.assets = version.assets.or(flattened.assets);
+            flattened.downloads = version.downloads.or(flattened.downloads);
+            flattened.client = version.client.or(flattened.client);
+            flattened.server = version.server.or(flattened.server);
+            flattened.logging = version.logging.or(flattened.logging);
+            flattened.java_version = version.java_version.or(flattened.java_version);
+            flattened.extra.extend(version.extra);
+        }
+        flattened.libraries = Some(dedup_libraries(libraries));
+        Ok(flattened)
+    }
+
+    /// Like [`Version::parse`], but the inherited versions missing in the versions folder are
+    /// passed to `resolver` by id, which may fetch them, e.g. download them from the manifest.
+    ///
+    /// The resolved versions are not saved, `resolver` should save them if needed. Returning
+    /// `None` fails the parsing with [`VersionResolveError::MissingInheritedVersion`].
+    pub async fn parse_with_resolver<R, F>(
+        &self,
+        minecraft: &MinecraftLocation,
+        platform: &PlatformInfo,
+        options: &ParseOptions,
+        resolver: R,
+    ) -> Result<ResolvedVersion>
+    where
+        R: FnMut(String) -> F,
+        F: Future<Output = Result<Option<Version>>>,
+    {
+        let (mut versions, inheritances, path_chain) =
+            self.inheritance_chain(minecraft, resolver).await?;
 
         let mut assets = "".to_string();
         let mut minimum_launcher_version = 0;
@@ -440,25 +1315,21 @@ impl Version {
         let jvm_args = DEFAULT_JVM_ARGS.clone();
         let mut release_time = "".to_string();
         let mut time = "".to_string();
-        let mut version_type = "".to_string();
+        let mut version_type = VersionType::default();
         let mut logging = HashMap::new();
         let mut main_class = "".to_string();
-        let mut assets_index = AssetIndex {
-            size: 0,
-            url: "".to_string(),
-            id: "".to_string(),
-            total_size: 0,
-        };
+        let mut assets_index = None;
         let mut java_version = JavaVersion {
             component: "jre-legacy".to_string(),
             major_version: 8,
+            extra: Map::new(),
         };
         let mut libraries_raw = Vec::new();
         let mut downloads = HashMap::new();
+        let mut extra = Map::new();
 
         while versions.len() != 0 {
             let version = versions.pop().unwrap();
-            println!("{}", version.id);
             minimum_launcher_version = std::cmp::max(
                 version.minimum_launcher_version.unwrap_or(0),
                 minimum_launcher_version,
@@ -479,26 +1350,30 @@ impl Version {
             assets = version.assets.unwrap_or(assets);
             version_type = version.r#type.unwrap_or(version_type);
             main_class = version.main_class.unwrap_or(main_class);
-            assets_index = version.asset_index.unwrap_or(assets_index);
+            assets_index = version.asset_index.or(assets_index);
             java_version = version.java_version.unwrap_or(java_version);
 
             if let Some(mut libraries) = version.libraries {
                 libraries_raw.append(&mut libraries);
             }
             downloads = version.downloads.unwrap_or(downloads);
+            extra.extend(version.extra);
         }
 
-        if main_class == ""
-            || assets_index
-                == (AssetIndex {
-                    size: 0,
-                    url: "".to_string(),
-                    id: "".to_string(),
-                    total_size: 0,
-                })
-            || downloads.len() == 0
-        {
-            panic!("Bad Version JSON");
+        let missing = |field: &str| {
+            Error::from(VersionResolveError::InvalidVersion {
+                id: Some(self.id.clone()),
+                source: VersionParseError::MissingField(field.to_string()),
+            })
+        };
+        if main_class.is_empty() {
+            return Err(missing("mainClass"));
+        }
+        let Some(assets_index) = assets_index else {
+            return Err(missing("assetIndex"));
+        };
+        if downloads.is_empty() {
+            return Err(missing("downloads"));
         }
         Ok(ResolvedVersion {
             id: self.id.clone(),
@@ -509,43 +1384,219 @@ impl Version {
                 // jvm: resolve_arguments(jvm_args, platform).await,
             }),
             main_class,
-            asset_index: self.asset_index.clone(),
-            assets: self.assets.clone().unwrap_or("".to_string()),
-            downloads: self.downloads.clone(),
-            libraries: resolve_libraries(libraries_raw, platform).await,
+            asset_index: Some(assets_index),
+            assets,
+            downloads: Some(downloads),
+            libraries: resolve_libraries(libraries_raw, platform, options).await,
             minimum_launcher_version,
             release_time,
             time,
             version_type,
-            logging: self.logging.clone(),
-            java_version: self.java_version.clone().unwrap_or(JavaVersion {
-                component: "jre-legacy".to_string(),
-                major_version: 8,
-            }),
+            logging: (!logging.is_empty()).then_some(logging),
+            java_version,
             minecraft_version: self.client_version.clone().unwrap_or(self.id.clone()),
             inheritances,
             path_chain,
+            extra,
         })
     }
 }
 
-#[derive(Debug, Clone)]
+/// Adjust the libraries of a version when parsing it, see [`Version::parse_with_options`]
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Skip the libraries whose maven names start with any of these, like
+    /// `com.mojang:text2speech`. The native jars of a skipped library are skipped too.
+    pub exclude_libraries: Vec<String>,
+
+    /// Add these libraries after the libraries of the version, a library with the same group,
+    /// artifact and classifier as one of the version replaces it
+    pub extra_libraries: Vec<LibraryDownload>,
+
+    /// Replace the LWJGL libraries on the platforms without official natives, it's off by
+    /// default
+    pub lwjgl_fix: Option<LwjglFix>,
+}
+
+impl ParseOptions {
+    fn is_excluded(&self, library: &Library) -> bool {
+        let Some(name) = library.name() else {
+            return false;
+        };
+        let name = LibraryInfo::from_name(name).map_or(name.to_string(), |info| info.name);
+        self.exclude_libraries
+            .iter()
+            .any(|prefix| name.starts_with(prefix.as_str()))
+    }
+}
+
+/// The LWJGL modules replaced by [`LwjglFix`]
+const LWJGL_MODULES: [&str; 7] = [
+    "lwjgl",
+    "lwjgl-glfw",
+    "lwjgl-openal",
+    "lwjgl-opengl",
+    "lwjgl-stb",
+    "lwjgl-tinyfd",
+    "lwjgl-jemalloc",
+];
+
+/// Point the LWJGL libraries at the builds for linux on ARM and Windows on ARM, the version jsons
+/// only list the natives for x86.
+///
+/// The replacements aren't in the version json, so their sha1 is empty and they are downloaded
+/// without verifying.
+#[derive(Debug, Clone)]
+pub struct LwjglFix {
+    /// The maven repository hosting the replacements
+    pub maven: String,
+
+    /// The LWJGL version in the version json to the version replacing it, the libraries of the
+    /// other versions are kept
+    pub versions: HashMap<String, String>,
+}
+
+impl Default for LwjglFix {
+    /// The ARM natives are published to maven central since LWJGL 3.3.0, the older 3.x versions
+    /// are replaced by 3.3.1
+    fn default() -> Self {
+        Self {
+            maven: "https://repo1.maven.org/maven2/".to_string(),
+            versions: [
+                ("3.2.1", "3.3.1"),
+                ("3.2.2", "3.3.1"),
+                ("3.3.1", "3.3.1"),
+                ("3.3.2", "3.3.2"),
+                ("3.3.3", "3.3.3"),
+            ]
+            .into_iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect(),
+        }
+    }
+}
+
+impl LwjglFix {
+    /// The classifier of the natives for the platform, `None` if the official natives work on it
+    fn natives_classifier(platform: &PlatformInfo) -> Option<String> {
+        let arch = match (platform.name.as_str(), platform.arch.as_str()) {
+            ("linux" | "windows", "aarch64" | "arm64") => "arm64",
+            ("linux", "arm") => "arm32",
+            _ => return None,
+        };
+        Some(format!("natives-{}-{arch}", platform.name))
+    }
+
+    /// Replace the LWJGL libraries if the platform has no official natives
+    fn apply(
+        &self,
+        libraries: Vec<ResolvedLibrary>,
+        platform: &PlatformInfo,
+    ) -> Vec<ResolvedLibrary> {
+        let Some(natives) = Self::natives_classifier(platform) else {
+            return libraries;
+        };
+        // the natives of several platforms may be replaced by the same jar
+        let mut paths = HashSet::new();
+        libraries
+            .into_iter()
+            .map(|library| self.replace(library, &natives))
+            .filter(|library| paths.insert(library.download_info.path.clone()))
+            .collect()
+    }
+
+    fn replace(&self, library: ResolvedLibrary, natives: &str) -> ResolvedLibrary {
+        let Ok(info) = LibraryInfo::from_path(&library.download_info.path) else {
+            return library;
+        };
+        if info.group_id != "org.lwjgl" || !LWJGL_MODULES.contains(&info.artifact_id.as_str()) {
+            return library;
+        }
+        let Some(version) = self.versions.get(&info.version) else {
+            return library;
+        };
+        let name = if info.classifier.is_empty() {
+            format!("org.lwjgl:{}:{version}", info.artifact_id)
+        } else if info.classifier.starts_with("natives-") {
+            format!("org.lwjgl:{}:{version}:{natives}", info.artifact_id)
+        } else {
+            return library;
+        };
+        let Ok(replacement) = LibraryInfo::from_name(&name) else {
+            return library;
+        };
+        ResolvedLibrary {
+            download_info: LibraryDownload {
+                sha1: String::new(),
+                size: 0,
+                url: replacement.maven_url(&self.maven),
+                path: replacement.path,
+            },
+            is_native_library: library.is_native_library,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResolvedArguments {
     pub game: Vec<String>,
     pub jvm: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResolvedLibrary {
     pub download_info: LibraryDownload,
     pub is_native_library: bool,
 }
 
-async fn _resolve_arguments(arguments: Vec<Value>, platform: &PlatformInfo) -> Vec<String> {
+impl ResolvedLibrary {
+    /// The urls to download this library from in order.
+    ///
+    /// A library found by its maven name has no sha1, it's tried in its own repository and then
+    /// the repositories in `repos`. The other libraries only have their own url.
+    pub fn urls(&self, repos: &RepoChain) -> Vec<String> {
+        let LibraryDownload {
+            sha1, url, path, ..
+        } = &self.download_info;
+        match url.strip_suffix(path.as_str()) {
+            Some(base) if sha1.is_empty() && !path.is_empty() => repos.urls(Some(base), path),
+            _ => vec![url.clone()],
+        }
+    }
+
+    /// The download of this library to the `libraries` folder, from the url rewritten by
+    /// `source`. The sha1 and the size are verified if they are known, the libraries resolved by
+    /// their names have neither.
+    pub fn to_download(
+        &self,
+        minecraft: &MinecraftLocation,
+        source: &DownloadSource,
+    ) -> download::Download<PathBuf> {
+        let LibraryDownload {
+            sha1,
+            size,
+            url,
+            path,
+        } = &self.download_info;
+        download::Download {
+            sha1: (!sha1.is_empty()).then(|| sha1.clone()),
+            size: (*size > 0).then_some(*size),
+            ..download::Download::new(source.rewrite_url(url), minecraft.get_library_by_path(path))
+        }
+    }
+}
+
+/// Resolve the `arguments` of a version json, the arguments whose rules don't match the platform
+/// or the enabled features are skipped. The values that aren't strings are skipped too.
+pub(crate) fn resolve_arguments(
+    arguments: &[Value],
+    platform: &PlatformInfo,
+    features: &HashMap<String, bool>,
+) -> Vec<String> {
     let mut result = Vec::with_capacity(arguments.len());
     for argument in arguments {
-        if argument.is_string() {
-            result.push(argument.as_str().unwrap().to_string());
+        if let Some(argument) = argument.as_str() {
+            result.push(argument.to_string());
             continue;
         }
         if !argument.is_object() {
@@ -553,115 +1604,473 @@ async fn _resolve_arguments(arguments: Vec<Value>, platform: &PlatformInfo) -> V
         }
         let rules = argument["rules"].as_array();
         if let Some(rules) = rules {
-            if !check_allowed(rules.clone(), platform) {
+            if !check_allowed(rules.clone(), platform, features) {
                 continue;
             };
         }
-        if argument["value"].is_string() {
-            result.push(argument["value"].as_str().unwrap().to_string());
-            continue;
-        }
-        if argument["value"].is_array() {
-            result.extend(
-                argument["value"]
-                    .as_array()
-                    .unwrap()
+        match &argument["value"] {
+            Value::String(value) => result.push(value.clone()),
+            Value::Array(values) => result.extend(
+                values
                     .iter()
-                    .map(|value| value.as_str().unwrap().to_string()),
-            );
+                    .filter_map(|value| value.as_str())
+                    .map(|value| value.to_string()),
+            ),
+            _ => {}
         }
     }
     result
 }
 
-async fn resolve_libraries(libraries: Vec<Value>, platform: &PlatformInfo) -> Vec<ResolvedLibrary> {
-    let mut result = Vec::new();
-    for library in libraries {
-        let rules = library["rules"].as_array();
-        // check rules
-        if let Some(rules) = rules {
-            if !check_allowed(rules.clone(), &platform) {
-                continue;
-            }
+/// The version json lacks a field required to launch, or a field has a wrong type
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VersionParseError {
+    /// The field in the version json, like `assetIndex`
+    #[error("{0} is missing in the version json")]
+    MissingField(String),
+    #[error("{field} in the version json should be {expected}")]
+    TypeMismatch {
+        field: String,
+        /// The json type, like `object`
+        expected: String,
+    },
+}
+
+/// The json types of the top-level fields, checked before deserializing to tell which field is
+/// wrong
+const FIELD_TYPES: [(&str, &str); 11] = [
+    ("id", "string"),
+    ("inheritsFrom", "string"),
+    ("mainClass", "string"),
+    ("minecraftArguments", "string"),
+    ("arguments", "object"),
+    ("libraries", "array"),
+    ("assetIndex", "object"),
+    ("assets", "string"),
+    ("downloads", "object"),
+    ("logging", "object"),
+    ("javaVersion", "object"),
+];
+
+fn check_fields(raw: &Value) -> std::result::Result<(), VersionParseError> {
+    if raw.get("id").is_none() {
+        return Err(VersionParseError::MissingField("id".to_string()));
+    }
+    for (field, expected) in FIELD_TYPES {
+        let matches = match raw.get(field) {
+            None | Some(Value::Null) => true,
+            Some(value) => match expected {
+                "string" => value.is_string(),
+                "object" => value.is_object(),
+                _ => value.is_array(),
+            },
+        };
+        if !matches {
+            return Err(VersionParseError::TypeMismatch {
+                field: field.to_string(),
+                expected: expected.to_string(),
+            });
         }
-        // resolve native lib
-        let classifiers = library["downloads"]["classifiers"].as_object();
-        let natives = library["natives"].as_object();
-        if classifiers.is_some() && natives.is_some() {
-            let classifiers = classifiers.unwrap();
-            let natives = natives.unwrap();
-            let classifier_key = natives[&platform.name].as_str();
-            if classifier_key.is_none() {
-                continue;
+    }
+    Ok(())
+}
+
+/// The most versions in an `inheritsFrom` chain, including the inheriting one
+pub const MAX_INHERITANCE_DEPTH: usize = 16;
+
+/// A version json is invalid or its `inheritsFrom` chain can't be followed, see
+/// [`Error::Version`](crate::Error::Version)
+#[derive(Debug, thiserror::Error)]
+pub enum VersionResolveError {
+    /// A version inherits from itself, directly or not. The chain ends with the repeated id
+    #[error("circular inheritance: {}", chain.join(" -> "))]
+    CircularInheritance { chain: Vec<String> },
+    /// The chain has more than [`MAX_INHERITANCE_DEPTH`] versions
+    #[error("the inheritance is deeper than {MAX_INHERITANCE_DEPTH}: {}", chain.join(" -> "))]
+    InheritanceTooDeep { chain: Vec<String> },
+    /// A version in the `inheritsFrom` chain is not installed
+    #[error("the inherited version {id} is not installed, expected at {}", path.display())]
+    MissingInheritedVersion {
+        /// The id of the inherited version
+        id: String,
+        /// Where the json of the version is expected
+        path: PathBuf,
+    },
+    /// The version json lacks a field or a field has a wrong type
+    #[error(
+        "invalid version json{}: {source}",
+        id.as_ref().map(|id| format!(" of {id}")).unwrap_or_default()
+    )]
+    InvalidVersion {
+        /// `None` if the json has no id
+        id: Option<String>,
+        source: VersionParseError,
+    },
+    /// A json can't be deserialized
+    #[error("can't parse {location}: {source}")]
+    InvalidJson {
+        /// The path of the json, or what it is if it's not read from a file
+        location: String,
+        source: serde_json::Error,
+    },
+    #[error("unknown version id format: {0}")]
+    InvalidVersionId(String),
+    #[error("invalid maven name: {0}")]
+    InvalidMavenName(String),
+    #[error("invalid maven path: {0}")]
+    InvalidMavenPath(String),
+}
+
+/// Append `inherits_from` to the ids followed so far, the first one is the inheriting version
+fn follow_inheritance(
+    chain: &mut Vec<String>,
+    inherits_from: &str,
+) -> std::result::Result<(), VersionResolveError> {
+    let circular = chain.iter().any(|id| id == inherits_from);
+    chain.push(inherits_from.to_string());
+    if circular {
+        return Err(VersionResolveError::CircularInheritance {
+            chain: chain.clone(),
+        });
+    }
+    if chain.len() > MAX_INHERITANCE_DEPTH {
+        return Err(VersionResolveError::InheritanceTooDeep {
+            chain: chain.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Check the version `id` inheriting from `inherits_from` makes no cycle with the installed
+/// versions, used by the installers before writing the version json.
+///
+/// The chain is followed as far as the versions are installed, a missing one is not an error.
+pub fn check_inheritance(
+    minecraft: &MinecraftLocation,
+    id: &str,
+    inherits_from: &str,
+) -> Result<()> {
+    let mut chain = vec![id.to_string()];
+    let mut inherits_from = Some(inherits_from.to_string());
+    while let Some(parent) = inherits_from {
+        follow_inheritance(&mut chain, &parent)?;
+        let path = minecraft.get_version_json(&parent);
+        if !path.is_file() {
+            break;
+        }
+        inherits_from = read_version_json(&path)?.inherits_from;
+    }
+    Ok(())
+}
+
+/// Read an inherited version json, without checking the fields like [`Version::from_value`]
+fn read_version_json(path: &Path) -> Result<Version> {
+    serde_json::from_str(&read_to_string(path)?).map_err(|source| {
+        VersionResolveError::InvalidJson {
+            location: path.display().to_string(),
+            source,
+        }
+        .into()
+    })
+}
+
+/// Remove the libraries with the same group, artifact and classifier, the last one is kept.
+///
+/// The libraries of the inheriting version are after the inherited ones, so the child wins.
+fn dedup_libraries(libraries: Vec<Library>) -> Vec<Library> {
+    let mut keys = HashSet::new();
+    let mut result: Vec<_> = libraries
+        .into_iter()
+        .rev()
+        .filter(|library| match library.name().map(LibraryInfo::from_name) {
+            Some(Ok(info)) => keys.insert((info.group_id, info.artifact_id, info.classifier)),
+            _ => true,
+        })
+        .collect();
+    result.reverse();
+    result
+}
+
+/// Remove the resolved libraries with the same group, artifact and classifier, like the `gson` of
+/// Minecraft and the one of a mod loader, the last one is kept. The libraries of the inheriting
+/// version are after the inherited ones, so the child wins even if it pins an older version. The
+/// native jars have their classifiers, so they aren't removed with the artifact.
+///
+/// The libraries whose paths aren't in the maven layout are kept.
+pub fn deduplicate_libraries(libraries: Vec<ResolvedLibrary>) -> Vec<ResolvedLibrary> {
+    let mut keys = HashSet::new();
+    let mut result: Vec<_> = libraries
+        .into_iter()
+        .rev()
+        .filter(
+            |library| match LibraryInfo::from_path(&library.download_info.path) {
+                Ok(info) => keys.insert((info.group_id, info.artifact_id, info.classifier)),
+                Err(_) => true,
+            },
+        )
+        .collect();
+    result.reverse();
+    result
+}
+
+/// The libraries are resolved concurrently if there are at least this many, the shorter lists are
+/// resolved in place as spawning the tasks costs more than resolving them
+const CONCURRENT_RESOLVE_THRESHOLD: usize = 64;
+
+/// Find the library in the maven repository `url` by its name and classifier
+fn resolve_by_name(
+    name: &str,
+    classifier: Option<&str>,
+    url: Option<&str>,
+    is_native_library: bool,
+) -> Option<ResolvedLibrary> {
+    let name = match classifier {
+        Some(classifier) => format!("{name}:{classifier}"),
+        None => name.to_string(),
+    };
+    let info = LibraryInfo::from_name(&name).ok()?;
+    Some(ResolvedLibrary {
+        download_info: LibraryDownload {
+            sha1: "".to_string(),
+            size: 0,
+            url: url
+                .map_or_else(MavenRepo::mojang, MavenRepo::new)
+                .artifact_url(&info.path),
+            path: info.path,
+        },
+        is_native_library,
+    })
+}
+
+/// Resolve the jars of a library allowed on the platform, the native jar is before the artifact
+fn resolve_library(library: Library, platform: &PlatformInfo) -> Vec<ResolvedLibrary> {
+    let artifact = |download: LibraryDownload| ResolvedLibrary {
+        download_info: download,
+        is_native_library: false,
+    };
+    let mut result = Vec::new();
+    match library {
+        Library::Native(library) => {
+            if let Some(classifier) = library.natives.get(&platform.name) {
+                let bits = if platform.arch.contains("64") {
+                    "64"
+                } else {
+                    "32"
+                };
+                let mut classifier = classifier.replace("${arch}", bits);
+                let classifiers = library
+                    .downloads
+                    .as_ref()
+                    .and_then(|downloads| downloads.classifiers.as_ref());
+                if let Some(qualified) = classifiers.and_then(|classifiers| {
+                    arch_classifier(&classifier, &platform.arch, classifiers)
+                }) {
+                    classifier = qualified;
+                }
+                let download = classifiers.and_then(|classifiers| classifiers.get(&classifier));
+                result.extend(match download {
+                    Some(download) => Some(ResolvedLibrary {
+                        download_info: download.clone(),
+                        is_native_library: true,
+                    }),
+                    None => resolve_by_name(
+                        &library.name,
+                        Some(&classifier),
+                        library.url.as_deref(),
+                        true,
+                    ),
+                });
             }
-            let classifier = classifiers[classifier_key.unwrap()].as_object();
-            if classifier.is_none() {
-                continue;
+            if let Some(download) = library.downloads.and_then(|downloads| downloads.artifact) {
+                result.push(artifact(download));
             }
-            let classifier = classifier.unwrap();
-            result.push(ResolvedLibrary {
-                download_info: LibraryDownload {
-                    sha1: classifier["sha1"].as_str().unwrap_or("").to_string(),
-                    size: classifier["size"].as_u64().unwrap_or(0),
-                    url: match classifier["url"].as_str() {
-                        Some(url) => url.to_string(),
-                        None => continue,
-                    },
-                    path: match classifier["path"].as_str() {
-                        Some(path) => path.to_string(),
-                        None => continue,
-                    },
-                },
-                is_native_library: true,
-            });
         }
-        // resolve common lib
-        if library["downloads"]["artifact"].is_object() {
-            result.push(ResolvedLibrary {
-                download_info: serde_json::from_value(library["downloads"]["artifact"].clone())
-                    .unwrap(),
-                is_native_library: false,
-            });
-            continue;
+        Library::Normal(NormalLibrary {
+            name, downloads, ..
+        })
+        | Library::PlatformSpecific(PlatformSpecificLibrary {
+            name, downloads, ..
+        }) => result.extend(match downloads.artifact {
+            Some(download) => Some(artifact(download)),
+            None => resolve_by_name(&name, None, None, false),
+        }),
+        // the libraries only for the server, like the ones of the legacy forge
+        Library::Legacy(LegacyLibrary {
+            clientreq: Some(false),
+            ..
+        }) => {}
+        Library::Legacy(library) => {
+            result.extend(resolve_by_name(
+                &library.name,
+                None,
+                library.url.as_deref(),
+                false,
+            ));
         }
-        let name = library["name"].as_str();
-        if name == None {
-            continue;
+        Library::Unknown(value) => {
+            if let Some(name) = value["name"].as_str() {
+                result.extend(resolve_by_name(name, None, value["url"].as_str(), false));
+            }
         }
+    }
+    result
+}
 
-        // resolve forge
-
-        let name: Vec<&str> = name.unwrap().split(":").collect();
-        if name.len() != 3 {
-            continue;
+/// Resolve the libraries in `workers` chunks on the tokio runtime, the order of the libraries is
+/// kept
+async fn resolve_libraries_concurrently(
+    libraries: Vec<Library>,
+    platform: &PlatformInfo,
+    workers: usize,
+) -> Vec<ResolvedLibrary> {
+    let chunk_size = libraries.len().div_ceil(workers.max(1)).max(1);
+    let platform = Arc::new(platform.clone());
+    let mut libraries = libraries.into_iter().peekable();
+    let mut tasks = JoinSet::new();
+    let mut chunks = 0;
+    while libraries.peek().is_some() {
+        let chunk: Vec<_> = libraries.by_ref().take(chunk_size).collect();
+        let platform = platform.clone();
+        let index = chunks;
+        tasks.spawn(async move {
+            let resolved: Vec<_> = chunk
+                .into_iter()
+                .flat_map(|library| resolve_library(library, &platform))
+                .collect();
+            (index, resolved)
+        });
+        chunks += 1;
+    }
+    let mut resolved = vec![Vec::new(); chunks];
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, libraries)) => resolved[index] = libraries,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
         }
+    }
+    resolved.concat()
+}
 
-        let package = name.get(0).unwrap().replace(".", "/");
-        let version = name.get(2).unwrap();
-        let name = name.get(1).unwrap();
+/// Resolve the jars of the libraries allowed on the platform.
+///
+/// The `Unknown` libraries are found by their names if possible, or skipped.
+async fn resolve_libraries(
+    libraries: Vec<Library>,
+    platform: &PlatformInfo,
+    options: &ParseOptions,
+) -> Vec<ResolvedLibrary> {
+    // check rules before deduplicating, a library may be listed with different versions for
+    // different platforms
+    let mut libraries: Vec<_> = libraries
+        .into_iter()
+        .filter(|library| match library.rules() {
+            Some(rules) => check_allowed(rules.to_vec(), platform, &HashMap::new()),
+            None => true,
+        })
+        .filter(|library| !options.is_excluded(library))
+        .collect();
+    libraries.extend(options.extra_libraries.iter().map(|download| {
+        // a path not in the maven layout can't replace a library, it's kept as is
+        let name =
+            LibraryInfo::from_path(&download.path).map_or(download.path.clone(), |info| info.name);
+        Library::Normal(NormalLibrary {
+            name,
+            downloads: LibraryDownloads {
+                artifact: Some(download.clone()),
+                classifiers: None,
+                extra: Map::new(),
+            },
+            extra: Map::new(),
+        })
+    }));
+    let libraries = dedup_libraries(libraries);
+    let result = if libraries.len() < CONCURRENT_RESOLVE_THRESHOLD {
+        libraries
+            .into_iter()
+            .flat_map(|library| resolve_library(library, platform))
+            .collect()
+    } else {
+        let workers = std::thread::available_parallelism().map_or(1, |workers| workers.get());
+        resolve_libraries_concurrently(libraries, platform, workers).await
+    };
+    match &options.lwjgl_fix {
+        Some(fix) => fix.apply(result, platform),
+        None => result,
+    }
+}
 
-        let url;
-        if let Some(url_) = library["url"].as_str() {
-            url = url_;
-        } else {
-            url = "http://files.minecraftforge.net/maven/"
+/// Express the resolved libraries as the libraries of a version json. A native jar is the
+/// classifier of a [`NativeLibrary`] for every os, with the artifact following it if any.
+fn library_entries(libraries: &[ResolvedLibrary]) -> Vec<Library> {
+    let mut entries = Vec::new();
+    for library in libraries {
+        let download = library.download_info.clone();
+        let info = LibraryInfo::from_path(&download.path).ok();
+        if library.is_native_library {
+            let (name, classifier) = match info {
+                Some(info) if !info.classifier.is_empty() => (
+                    format!("{}:{}:{}", info.group_id, info.artifact_id, info.version),
+                    info.classifier,
+                ),
+                Some(info) => (info.name, "natives".to_string()),
+                None => (download.path.clone(), "natives".to_string()),
+            };
+            let natives = ["windows", "osx", "linux"]
+                .map(|os| (os.to_string(), classifier.clone()))
+                .into();
+            entries.push(Library::Native(NativeLibrary {
+                name,
+                downloads: Some(LibraryDownloads {
+                    artifact: None,
+                    classifiers: Some(HashMap::from([(classifier, download)])),
+                    extra: Map::new(),
+                }),
+                natives,
+                rules: None,
+                extract: None,
+                url: None,
+                extra: Map::new(),
+            }));
+            continue;
         }
-        let path = format!("{package}/{name}/{version}/{name}-{version}.jar");
-        result.push(ResolvedLibrary {
-            download_info: LibraryDownload {
-                sha1: "".to_string(),
-                size: 0,
-                url: format!("{url}{path}"),
-                path,
+        let name = info.map_or(download.path.clone(), |info| info.name);
+        if let Some(Library::Native(NativeLibrary {
+            name: native_name,
+            downloads: Some(downloads),
+            ..
+        })) = entries.last_mut()
+        {
+            if *native_name == name && downloads.artifact.is_none() {
+                downloads.artifact = Some(download);
+                continue;
+            }
+        }
+        entries.push(Library::Normal(NormalLibrary {
+            name,
+            downloads: LibraryDownloads {
+                artifact: Some(download),
+                classifiers: None,
+                extra: Map::new(),
             },
-            is_native_library: false,
-        });
+            extra: Map::new(),
+        }));
     }
-    result
+    entries
 }
 
 /// Check if all the rules in Rule[] are acceptable in certain OS platform and features.
-fn check_allowed(rules: Vec<Value>, platform: &PlatformInfo) -> bool {
+///
+/// A rule without `os` applies to every platform, a rule with `os` applies only when it matches
+/// the platform. The last applicable rule decides the result, so a bare rule sets the baseline and
+/// the following os-specific rules override it.
+///
+/// A rule with `features` applies only when every feature in it has the same value in `features`,
+/// the missing features are disabled.
+fn check_allowed(
+    rules: Vec<Value>,
+    platform: &PlatformInfo,
+    features: &HashMap<String, bool>,
+) -> bool {
     // by default it's allowed
     if rules.is_empty() {
         return true;
@@ -669,38 +2078,76 @@ fn check_allowed(rules: Vec<Value>, platform: &PlatformInfo) -> bool {
     // else it's disallow by default
     let mut allow = false;
     for rule in rules {
-        let action = rule["action"].as_str().unwrap() == "allow";
-        let os = rule["os"].clone();
-        if !os.is_object() {
-            allow = action;
-            continue;
-        }
-        if !os["name"].is_string() {
-            allow = action;
-            continue;
-        }
-        if platform.name != os["name"].as_str().unwrap() {
+        let action = rule["action"].as_str() == Some("allow");
+        if rule["os"]["features"].is_object() {
             continue;
         }
-        if os["features"].is_object() {
-            return false;
-        }
-        if !os["version"].is_string() {
-            allow = action;
-            continue;
+        if let Some(required) = rule["features"].as_object() {
+            let enabled = required.iter().all(|(feature, value)| {
+                value.as_bool() == Some(features.get(feature).copied().unwrap_or(false))
+            });
+            if !enabled {
+                continue;
+            }
         }
-        let version = os["version"].as_str().unwrap();
-        if Regex::is_match(
-            &Regex::new(version).unwrap(),
-            (&platform.version.to_string()).as_ref(),
-        ) {
+        let os = &rule["os"];
+        if !os.is_object() || check_os(os, platform) {
             allow = action;
         }
-        // todo: check `features`
     }
     allow
 }
 
+/// The arch suffixes of the native classifiers, like `aarch_64` of `natives-linux-aarch_64`
+fn arch_suffixes(arch: &str) -> &'static [&'static str] {
+    match arch {
+        "aarch64" | "arm64" => &["aarch_64", "arm64"],
+        "arm" => &["arm32", "arm"],
+        "x86" => &["x86"],
+        _ => &[],
+    }
+}
+
+/// The classifier qualified with the arch of the platform, if `classifiers` has it. `natives`
+/// only lists a classifier per os, like `natives-linux`, which is the x86 one.
+fn arch_classifier(
+    classifier: &str,
+    arch: &str,
+    classifiers: &HashMap<String, LibraryDownload>,
+) -> Option<String> {
+    arch_suffixes(arch)
+        .iter()
+        .map(|suffix| format!("{classifier}-{suffix}"))
+        .find(|qualified| classifiers.contains_key(qualified))
+}
+
+/// Check if the `os` of a rule matches the platform
+fn check_os(os: &Value, platform: &PlatformInfo) -> bool {
+    if let Some(name) = os["name"].as_str() {
+        if platform.name != name {
+            return false;
+        }
+    }
+    if let Some(version) = os["version"].as_str() {
+        return os_version_regex(version).is_some_and(|regex| regex.is_match(&platform.version));
+    }
+    true
+}
+
+/// The compiled `os.version` patterns, the same few patterns are checked for every library.
+/// The invalid patterns are cached as `None`.
+static OS_VERSION_REGEX: Lazy<Mutex<HashMap<String, Option<Regex>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Compile the `os.version` pattern of a rule, `None` if it's invalid
+fn os_version_regex(pattern: &str) -> Option<Regex> {
+    let mut cache = OS_VERSION_REGEX.lock().unwrap();
+    cache
+        .entry(pattern.to_string())
+        .or_insert_with(|| Regex::new(pattern).ok())
+        .clone()
+}
+
 pub struct LibraryInfo {
     pub group_id: String,
     pub artifact_id: String,
@@ -721,37 +2168,1580 @@ pub struct LibraryInfo {
 }
 
 impl LibraryInfo {
-    // /// Resolve the library info from the maven path.
-    // ///
-    // pub fn forge_maven_path(path: String) {}
-
-    /// Get the base info of the library from its name
-    /// * `lib` - The name of library of the library itself
-    pub fn from_value(lib: &Value) -> Self {
-        let name = lib["name"].as_str().unwrap().to_string();
-        let split_name = name.split("@").collect::<Vec<&str>>();
-        let body = split_name.get(0).unwrap().split(":").collect::<Vec<&str>>();
-        let r#type = split_name.get(1).unwrap_or(&"jar").to_string();
-        let group_id = body.get(0).unwrap().to_string();
-        let artifact_id = body.get(1).unwrap().to_string();
-        let version = body.get(2).unwrap().to_string();
-        let is_snapshot = version.ends_with("SNAPSHOT");
-        let group_path = group_id.replace(".", "/");
-        let base = format!("{group_path}/{artifact_id}/{version}/{artifact_id}-{version}");
-        let classifier = match body.get(3) {
-            Some(classifier) => format!("{base}-{classifier}"),
-            None => "".to_string(),
-        };
-        let path = format!("{base}.{type}");
-        Self {
-            group_id,
-            artifact_id,
-            version,
-            is_snapshot,
-            r#type,
-            classifier,
+    /// Resolve the library info from a maven path, like
+    /// `org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux-arm64.jar`
+    pub fn from_path(path: &str) -> Result<Self> {
+        let invalid = || Error::from(VersionResolveError::InvalidMavenPath(path.to_string()));
+        let parts: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        let [group @ .., artifact_id, version, file] = &parts[..] else {
+            return Err(invalid());
+        };
+        if group.is_empty() {
+            return Err(invalid());
+        }
+        let (file, r#type) = file.rsplit_once('.').ok_or_else(invalid)?;
+        let rest = file
+            .strip_prefix(&format!("{artifact_id}-{version}"))
+            .ok_or_else(invalid)?;
+        let mut name = format!("{}:{artifact_id}:{version}", group.join("."));
+        if let Some(classifier) = rest.strip_prefix('-') {
+            name.push(':');
+            name.push_str(classifier);
+        } else if !rest.is_empty() {
+            return Err(invalid());
+        }
+        if r#type != "jar" {
+            name.push('@');
+            name.push_str(r#type);
+        }
+        Self::from_name(&name)
+    }
+
+    /// Parse the maven name of a library, like `net.minecraftforge:forge:1.20.1:universal@zip`
+    pub fn from_name(name: &str) -> Result<Self> {
+        let (coordinate, r#type) = name.split_once('@').unwrap_or((name, "jar"));
+        let parts: Vec<&str> = coordinate.split(':').collect();
+        let (group_id, artifact_id, version, classifier) = match parts[..] {
+            [group_id, artifact_id, version] => (group_id, artifact_id, version, ""),
+            [group_id, artifact_id, version, classifier] => {
+                (group_id, artifact_id, version, classifier)
+            }
+            _ => return Err(VersionResolveError::InvalidMavenName(name.to_string()).into()),
+        };
+        if [group_id, artifact_id, version, r#type]
+            .iter()
+            .any(|part| part.is_empty())
+        {
+            return Err(VersionResolveError::InvalidMavenName(name.to_string()).into());
+        }
+        let base = format!(
+            "{}/{artifact_id}/{version}/{artifact_id}-{version}",
+            group_id.replace('.', "/")
+        );
+        let path = if classifier.is_empty() {
+            format!("{base}.{type}")
+        } else {
+            format!("{base}-{classifier}.{type}")
+        };
+        Ok(Self {
+            group_id: group_id.to_string(),
+            artifact_id: artifact_id.to_string(),
+            version: version.to_string(),
+            is_snapshot: version.ends_with("SNAPSHOT"),
+            r#type: r#type.to_string(),
+            classifier: classifier.to_string(),
             path,
-            name,
+            name: name.to_string(),
+        })
+    }
+
+    /// The url of the library in a maven repository, like `https://maven.fabricmc.net/`
+    pub fn maven_url(&self, base: &str) -> String {
+        format!("{}/{}", base.trim_end_matches('/'), self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::{download_source::OFFICIAL_META_URL, OsType};
+
+    /// The parse error of an invalid version json
+    fn parse_error(err: &Error) -> Option<&VersionParseError> {
+        match err {
+            Error::Version(VersionResolveError::InvalidVersion { source, .. }) => Some(source),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_version_manifest_with_source() {
+        let mut server = mockito::Server::new_async().await;
+        let version_url = format!("{}/v1/packages/abc/1.20.1.json", OFFICIAL_META_URL);
+        let manifest = server
+            .mock("GET", "/mc/game/version_manifest_v2.json")
+            .with_body(
+                json!({
+                    "latest": { "release": "1.20.1", "snapshot": "1.20.1" },
+                    "versions": [{
+                        "id": "1.20.1",
+                        "type": "release",
+                        "url": version_url,
+                        "time": "2023-06-12T13:25:51+00:00",
+                        "releaseTime": "2023-06-12T13:25:51+00:00",
+                        "sha1": "715ccf3330885e75b205124f09f8712542cbe7e0",
+                        "complianceLevel": 1
+                    }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let version_json = server
+            .mock("GET", "/v1/packages/abc/1.20.1.json")
+            .with_body(
+                json!({ "id": "1.20.1", "mainClass": "net.minecraft.client.main.Main" })
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let source = DownloadSource {
+            meta_base_url: server.url(),
+            ..DownloadSource::official()
+        };
+        let manifest_result = VersionManifest::new_with_source(&source).await.unwrap();
+        assert_eq!(manifest_result.latest.release, "1.20.1");
+        let version =
+            VersionManifest::fetch_version_json(&manifest_result.versions[0], Some(&source))
+                .await
+                .unwrap();
+        assert_eq!(version.id, "1.20.1");
+        assert_eq!(
+            version.main_class.unwrap(),
+            "net.minecraft.client.main.Main"
+        );
+        manifest.assert_async().await;
+        version_json.assert_async().await;
+    }
+
+    #[test]
+    fn test_version_type() {
+        for (raw, r#type) in [
+            ("release", VersionType::Release),
+            ("snapshot", VersionType::Snapshot),
+            ("old_alpha", VersionType::OldAlpha),
+            ("old_beta", VersionType::OldBeta),
+            ("pending", VersionType::Custom("pending".to_string())),
+        ] {
+            assert_eq!(
+                serde_json::from_value::<VersionType>(json!(raw)).unwrap(),
+                r#type
+            );
+            assert_eq!(serde_json::to_value(&r#type).unwrap(), json!(raw));
+            assert_eq!(r#type.to_string(), raw);
+        }
+        let version =
+            Version::from_str(r#"{ "id": "1.20.1-custom", "type": "modified" }"#).unwrap();
+        assert_eq!(
+            version.r#type,
+            Some(VersionType::Custom("modified".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let info = json!({
+            "id": "1.20.1",
+            "type": "release",
+            "url": "https://piston-meta.mojang.com/v1/packages/715ccf3330885e75b205124f09f8712542cbe7e0/1.20.1.json",
+            "time": "2023-06-12T13:25:51+00:00",
+            "releaseTime": "2023-06-12T13:25:51+00:00",
+            "sha1": "715ccf3330885e75b205124f09f8712542cbe7e0"
+        });
+        assert!(serde_json::from_value::<VersionInfo>(info).is_err());
+        let err =
+            Version::from_str(r#"{ "mainClass": "net.minecraft.client.main.Main" }"#).unwrap_err();
+        assert_eq!(
+            parse_error(&err),
+            Some(&VersionParseError::MissingField("id".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_version_parse_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let platform = platform(OsType::Linux, "linux");
+        let mut raw: Value =
+            serde_json::from_str(include_str!("../../mock/versions/1.19.4.json")).unwrap();
+        let parse = |raw: Value| {
+            let minecraft = minecraft.clone();
+            let platform = platform.clone();
+            async move { Version::from_value(raw)?.parse(&minecraft, &platform).await }
+        };
+
+        let mut missing_asset_index = raw.clone();
+        missing_asset_index
+            .as_object_mut()
+            .unwrap()
+            .remove("assetIndex");
+        let err = parse(missing_asset_index).await.unwrap_err();
+        assert_eq!(
+            parse_error(&err),
+            Some(&VersionParseError::MissingField("assetIndex".to_string()))
+        );
+
+        // the old versions have no logging and java version
+        let object = raw.as_object_mut().unwrap();
+        object.remove("logging");
+        object.remove("javaVersion");
+        let resolved = parse(raw.clone()).await.unwrap();
+        assert!(resolved.logging.is_none());
+        assert_eq!(resolved.java_version.major_version, 8);
+
+        raw["javaVersion"] = json!(17);
+        let err = parse(raw).await.unwrap_err();
+        assert_eq!(
+            parse_error(&err),
+            Some(&VersionParseError::TypeMismatch {
+                field: "javaVersion".to_string(),
+                expected: "object".to_string()
+            })
+        );
+    }
+
+    fn version_info(id: &str, r#type: &str, release_time: &str) -> VersionInfo {
+        VersionInfo {
+            id: id.to_string(),
+            r#type: r#type.to_string().into(),
+            url: String::new(),
+            time: release_time.to_string(),
+            release_time: release_time.to_string(),
+            sha1: String::new(),
+            compliance_level: 1,
+        }
+    }
+
+    #[test]
+    fn test_filter_and_sort() {
+        let manifest = VersionManifest {
+            latest: LatestVersion {
+                release: "1.20.1".to_string(),
+                snapshot: "23w31a".to_string(),
+            },
+            versions: vec![
+                version_info("1.19.4", "release", "2023-03-14T12:56:18+00:00"),
+                version_info("a1.0.4", "old_alpha", "2010-07-09T22:00:00+00:00"),
+                version_info("broken", "release", "yesterday"),
+                version_info("23w31a", "snapshot", "2023-08-01T11:03:32+00:00"),
+                version_info("1.20.1", "release", "2023-06-12T13:25:51+00:00"),
+            ],
+        };
+        let ids = |versions: Vec<&VersionInfo>| {
+            versions
+                .into_iter()
+                .map(|version| version.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(manifest.filtered(&[VersionType::Release, VersionType::Snapshot])),
+            vec!["1.19.4", "broken", "23w31a", "1.20.1"]
+        );
+        assert_eq!(
+            ids(manifest.filtered(&[VersionType::OldAlpha])),
+            vec!["a1.0.4"]
+        );
+        assert_eq!(
+            ids(manifest.sorted_by_release_time()),
+            vec!["23w31a", "1.20.1", "1.19.4", "a1.0.4", "broken"]
+        );
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        for raw in [
+            include_str!("../../mock/versions/1.20.1.json"),
+            include_str!("../../mock/versions/1.20.1-forge-47.1.0.json"),
+        ] {
+            let version = Version::from_str(raw).unwrap();
+            let path = dir
+                .path()
+                .join(&version.id)
+                .join(format!("{}.json", version.id));
+            version.save(&path).unwrap();
+            let saved = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Value>(&saved).unwrap(),
+                serde_json::from_str::<Value>(raw).unwrap()
+            );
+            assert!(saved.contains("\n  \"id\""));
+            assert_eq!(Version::from_str(&saved).unwrap(), version);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circular_inheritance() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let version = |id: &str, inherits_from: &str| {
+            Version::from_value(json!({ "id": id, "inheritsFrom": inherits_from })).unwrap()
+        };
+        let linux = platform(OsType::Linux, "linux");
+
+        let looping = version("looping", "looping");
+        looping.save(minecraft.get_version_json("looping")).unwrap();
+        let err = looping.parse(&minecraft, &linux).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Version(VersionResolveError::CircularInheritance { chain })
+                if chain == ["looping", "looping"]
+        ));
+
+        let a = version("a", "b");
+        a.save(minecraft.get_version_json("a")).unwrap();
+        version("b", "a")
+            .save(minecraft.get_version_json("b"))
+            .unwrap();
+        let err = a.parse(&minecraft, &linux).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Version(VersionResolveError::CircularInheritance { chain })
+                if chain == ["a", "b", "a"]
+        ));
+        assert!(a.flatten(&minecraft).await.is_err());
+        assert!(check_inheritance(&minecraft, "c", "a").is_err());
+        assert!(check_inheritance(&minecraft, "c", "a-not-installed").is_ok());
+        assert!(check_inheritance(&minecraft, "b", "b").is_err());
+
+        for i in 0..MAX_INHERITANCE_DEPTH {
+            version(&format!("v{i}"), &format!("v{}", i + 1))
+                .save(minecraft.get_version_json(format!("v{i}")))
+                .unwrap();
+        }
+        let err = version("deep", "v0")
+            .parse(&minecraft, &linux)
+            .await
+            .unwrap_err();
+        let Error::Version(VersionResolveError::InheritanceTooDeep { chain }) = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(chain.len(), MAX_INHERITANCE_DEPTH + 1);
+    }
+
+    #[tokio::test]
+    async fn test_arch_native_classifier() {
+        let download = |classifier: &str| {
+            json!({
+                "path": format!("org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-{classifier}.jar"),
+                "sha1": "0000000000000000000000000000000000000000",
+                "size": 1,
+                "url": format!("https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-{classifier}.jar")
+            })
+        };
+        let library = Library::from_value(json!({
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+            "natives": { "linux": "natives-linux", "osx": "natives-osx" },
+            "downloads": {
+                "classifiers": {
+                    "natives-linux": download("natives-linux"),
+                    "natives-linux-aarch_64": download("natives-linux-aarch_64"),
+                    "natives-osx": download("natives-osx"),
+                    "natives-osx-arm64": download("natives-osx-arm64")
+                }
+            }
+        }));
+        let classifier = |platform: PlatformInfo| {
+            let library = library.clone();
+            async move {
+                let resolved =
+                    resolve_libraries(vec![library], &platform, &ParseOptions::default()).await;
+                assert_eq!(resolved.len(), 1);
+                LibraryInfo::from_path(&resolved[0].download_info.path)
+                    .unwrap()
+                    .classifier
+            }
+        };
+
+        let mut platform_arm64 = platform(OsType::Linux, "linux");
+        platform_arm64.arch = "aarch64".to_string();
+        assert_eq!(classifier(platform_arm64).await, "natives-linux-aarch_64");
+        let mut platform_m1 = platform(OsType::Osx, "osx");
+        platform_m1.arch = "aarch64".to_string();
+        assert_eq!(classifier(platform_m1).await, "natives-osx-arm64");
+        assert_eq!(
+            classifier(platform(OsType::Linux, "linux")).await,
+            "natives-linux"
+        );
+        // no arch variant, the classifier of the os is used
+        let mut platform_arm = platform(OsType::Linux, "linux");
+        platform_arm.arch = "arm".to_string();
+        assert_eq!(classifier(platform_arm).await, "natives-linux");
+    }
+
+    /// The libraries of 1.7.10 forge, copied under different group ids
+    fn copied_libraries(copies: usize) -> Vec<Library> {
+        let libraries = Version::from_str(include_str!("../../mock/versions/1.7.10-forge.json"))
+            .unwrap()
+            .libraries
+            .unwrap();
+        (0..copies)
+            .flat_map(|index| {
+                libraries.iter().map(move |library| {
+                    let mut value = serde_json::to_value(library).unwrap();
+                    let name = value["name"].as_str().unwrap().to_string();
+                    value["name"] = json!(name.replacen(':', &format!(".copy{index}:"), 1));
+                    Library::from_value(value)
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolve_libraries_concurrently() {
+        let libraries = copied_libraries(CONCURRENT_RESOLVE_THRESHOLD * 2);
+        let linux = platform(OsType::Linux, "linux");
+        let sequential: Vec<_> = dedup_libraries(libraries.clone())
+            .into_iter()
+            .flat_map(|library| resolve_library(library, &linux))
+            .collect();
+        assert!(sequential.len() > CONCURRENT_RESOLVE_THRESHOLD);
+        for workers in [1, 3, 8] {
+            let resolved =
+                resolve_libraries_concurrently(dedup_libraries(libraries.clone()), &linux, workers)
+                    .await;
+            assert_eq!(resolved, sequential);
+        }
+        assert_eq!(
+            resolve_libraries(libraries, &linux, &ParseOptions::default()).await,
+            sequential
+        );
+    }
+
+    /// Run with `cargo test --release bench_resolve_libraries -- --ignored --nocapture`
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "benchmark"]
+    async fn bench_resolve_libraries() {
+        let libraries = dedup_libraries(copied_libraries(40));
+        assert!(libraries.len() >= 200);
+        let linux = platform(OsType::Linux, "linux");
+        let workers = std::thread::available_parallelism().map_or(1, |workers| workers.get());
+        let rounds = 100;
+
+        let start = std::time::Instant::now();
+        for _ in 0..rounds {
+            let resolved: Vec<_> = libraries
+                .clone()
+                .into_iter()
+                .flat_map(|library| resolve_library(library, &linux))
+                .collect();
+            std::hint::black_box(resolved);
+        }
+        let sequential = start.elapsed() / rounds;
+
+        let start = std::time::Instant::now();
+        for _ in 0..rounds {
+            let resolved = resolve_libraries_concurrently(libraries.clone(), &linux, workers).await;
+            std::hint::black_box(resolved);
+        }
+        let concurrent = start.elapsed() / rounds;
+
+        println!(
+            "{} libraries, in place: {sequential:?}, {workers} workers: {concurrent:?}",
+            libraries.len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_version_from_url() {
+        let mut server = mockito::Server::new_async().await;
+        let version_json = server
+            .mock("GET", "/1.20.1.json")
+            .with_body(include_str!("../../mock/versions/1.20.1.json"))
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/missing.json")
+            .with_status(404)
+            .create_async()
+            .await;
+        let url = format!("{}/1.20.1.json", server.url());
+
+        let version = Version::from_url(&url).await.unwrap();
+        assert_eq!(
+            version,
+            Version::from_str(include_str!("../../mock/versions/1.20.1.json")).unwrap()
+        );
+        let client = reqwest::Client::new();
+        let version = Version::from_url_with_client(&url, &client).await.unwrap();
+        assert_eq!(version.id, "1.20.1");
+        version_json.assert_async().await;
+
+        let missing = format!("{}/missing.json", server.url());
+        assert!(Version::from_url_with_client(&missing, &client)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_to_version_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        Version::from_str(include_str!("../../mock/versions/1.20.1.json"))
+            .unwrap()
+            .save(minecraft.get_version_json("1.20.1"))
+            .unwrap();
+        let child = Version::from_str(include_str!("../../mock/versions/1.20.1-forge-47.1.0.json"))
+            .unwrap();
+        let linux = platform(OsType::Linux, "linux");
+        let resolved = child.parse(&minecraft, &linux).await.unwrap();
+
+        let json = resolved.to_version_json();
+        assert!(json.get("inheritsFrom").is_none());
+        let standalone = Version::from_value(json).unwrap();
+        let reparsed = standalone
+            .parse(&MinecraftLocation::new(&dir.path().join("empty")), &linux)
+            .await
+            .unwrap();
+        assert_eq!(
+            reparsed,
+            ResolvedVersion {
+                inheritances: vec![],
+                path_chain: vec![],
+                ..resolved.clone()
+            }
+        );
+
+        // the natives and the artifacts of the old versions
+        let libraries = Version::from_str(include_str!("../../mock/versions/1.7.10-forge.json"))
+            .unwrap()
+            .libraries
+            .unwrap();
+        for platform in [linux, platform(OsType::Windows, "windows")] {
+            let natives = ResolvedVersion {
+                libraries: resolve_libraries(
+                    libraries.clone(),
+                    &platform,
+                    &ParseOptions::default(),
+                )
+                .await,
+                ..resolved.clone()
+            };
+            assert!(natives
+                .libraries
+                .iter()
+                .any(|library| library.is_native_library));
+            let reparsed = Version::from_value(natives.to_version_json())
+                .unwrap()
+                .parse(&minecraft, &platform)
+                .await
+                .unwrap();
+            assert_eq!(reparsed.libraries, natives.libraries);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flatten() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(&dir.path().join("inherited"));
+        let root = Version::from_str(include_str!("../../mock/versions/1.20.1.json")).unwrap();
+        root.save(minecraft.get_version_json("1.20.1")).unwrap();
+        let child = Version::from_str(include_str!("../../mock/versions/1.20.1-forge-47.1.0.json"))
+            .unwrap();
+        let linux = platform(OsType::Linux, "linux");
+        let resolved = child.parse(&minecraft, &linux).await.unwrap();
+
+        let flattened = child.flatten(&minecraft).await.unwrap();
+        assert_eq!(flattened.inherits_from, None);
+        assert_eq!(flattened.id, child.id);
+        assert_eq!(flattened.main_class, child.main_class);
+        assert_eq!(flattened.downloads, root.downloads);
+        assert_eq!(flattened.asset_index, root.asset_index);
+        let jvm = flattened.arguments.as_ref().unwrap().jvm.as_ref().unwrap();
+        assert_eq!(
+            jvm.first(),
+            root.arguments
+                .as_ref()
+                .unwrap()
+                .jvm
+                .as_ref()
+                .unwrap()
+                .first()
+        );
+        assert_eq!(jvm.last().unwrap(), "ALL-MODULE-PATH");
+        assert_eq!(
+            flattened.libraries.as_ref().unwrap().len(),
+            root.libraries.as_ref().unwrap().len() + child.libraries.as_ref().unwrap().len()
+        );
+
+        // saved to another game directory, so nothing can be inherited
+        let standalone = MinecraftLocation::new(&dir.path().join("standalone"));
+        let path = standalone.get_version_json(&flattened.id);
+        flattened.save(&path).unwrap();
+        let saved = Version::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(
+            saved.parse(&standalone, &linux).await.unwrap(),
+            ResolvedVersion {
+                inheritances: vec![],
+                path_chain: vec![],
+                ..resolved
+            }
+        );
+
+        assert!(matches!(
+            child.flatten(&standalone).await.unwrap_err(),
+            Error::Version(VersionResolveError::MissingInheritedVersion { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_extra_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let parent = json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "assetIndex": { "id": "5", "sha1": "", "size": 1, "totalSize": 1, "url": "" },
+            "downloads": { "client": { "sha1": "", "size": 1, "url": "" } },
+            "customField": "parent",
+            "_mglComment": "installed by mgl"
+        });
+        std::fs::create_dir_all(minecraft.get_version_root("1.20.1")).unwrap();
+        std::fs::write(minecraft.get_version_json("1.20.1"), parent.to_string()).unwrap();
+        let version = Version::from_value(json!({
+            "id": "1.20.1-fabric",
+            "inheritsFrom": "1.20.1",
+            "customField": { "modpack": "example" }
+        }))
+        .unwrap();
+        assert_eq!(
+            version.extra["customField"],
+            json!({ "modpack": "example" })
+        );
+
+        let resolved = version
+            .parse(&minecraft, &platform(OsType::Linux, "linux"))
+            .await
+            .unwrap();
+        assert_eq!(
+            resolved.extra["customField"],
+            json!({ "modpack": "example" })
+        );
+        assert_eq!(resolved.extra["_mglComment"], "installed by mgl");
+        assert!(!resolved.extra.contains_key("mainClass"));
+    }
+
+    #[tokio::test]
+    async fn test_inherited_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let parent = json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "assets": "5",
+            "assetIndex": { "id": "5", "sha1": "", "size": 1, "totalSize": 1, "url": "" },
+            "downloads": { "client": { "sha1": "", "size": 1, "url": "" } },
+            "javaVersion": { "component": "jre-legacy", "majorVersion": 8 },
+            "logging": {
+                "client": {
+                    "argument": "-Dlog4j.configurationFile=${path}",
+                    "file": { "id": "client-1.12.xml", "sha1": "", "size": 1, "url": "" },
+                    "type": "log4j2-xml"
+                }
+            }
+        });
+        std::fs::create_dir_all(minecraft.get_version_root("1.20.1")).unwrap();
+        std::fs::write(minecraft.get_version_json("1.20.1"), parent.to_string()).unwrap();
+        let version = Version::from_value(json!({
+            "id": "1.20.1-custom",
+            "inheritsFrom": "1.20.1",
+            "javaVersion": { "component": "java-runtime-delta", "majorVersion": 21 }
+        }))
+        .unwrap();
+
+        let resolved = version
+            .parse(&minecraft, &platform(OsType::Linux, "linux"))
+            .await
+            .unwrap();
+        assert_eq!(resolved.java_version.major_version, 21);
+        assert_eq!(resolved.java_version.component, "java-runtime-delta");
+        assert_eq!(resolved.assets, "5");
+        assert_eq!(resolved.asset_index.unwrap().id, "5");
+        assert!(resolved.downloads.unwrap().contains_key("client"));
+        assert_eq!(
+            resolved.logging.unwrap()["client"].file.id,
+            "client-1.12.xml"
+        );
+    }
+
+    fn platform(os_type: OsType, name: &str) -> PlatformInfo {
+        PlatformInfo {
+            arch: "x64".to_string(),
+            name: name.to_string(),
+            os_type,
+            version: "10.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_allowed() {
+        let osx = platform(OsType::Osx, "osx");
+        let windows = platform(OsType::Windows, "windows");
+        let rules = vec![
+            json!({ "action": "allow" }),
+            json!({ "action": "disallow", "os": { "name": "osx" } }),
+        ];
+        assert!(!check_allowed(rules.clone(), &osx, &HashMap::new()));
+        assert!(check_allowed(rules, &windows, &HashMap::new()));
+
+        let rules = vec![json!({ "action": "allow", "os": { "name": "osx" } })];
+        assert!(check_allowed(rules.clone(), &osx, &HashMap::new()));
+        assert!(!check_allowed(rules, &windows, &HashMap::new()));
+
+        let rules =
+            vec![json!({ "action": "allow", "os": { "name": "windows", "version": "^10\\." } })];
+        assert!(check_allowed(rules.clone(), &windows, &HashMap::new()));
+        let windows_7 = PlatformInfo {
+            version: "6.1.7601".to_string(),
+            ..windows.clone()
+        };
+        assert!(!check_allowed(rules.clone(), &windows_7, &HashMap::new()));
+        let windows_11 = PlatformInfo {
+            version: "10.0.22631".to_string(),
+            ..windows.clone()
+        };
+        assert!(check_allowed(rules, &windows_11, &HashMap::new()));
+
+        // an invalid pattern never matches
+        let rules =
+            vec![json!({ "action": "allow", "os": { "name": "windows", "version": "^10\\.(" } })];
+        assert!(!check_allowed(rules.clone(), &windows_11, &HashMap::new()));
+        assert!(!check_allowed(rules, &windows_11, &HashMap::new()));
+
+        let rules = vec![json!({ "action": "allow", "features": { "is_demo_user": true } })];
+        assert!(!check_allowed(rules.clone(), &windows, &HashMap::new()));
+        let features = HashMap::from([("is_demo_user".to_string(), true)]);
+        assert!(check_allowed(rules, &windows, &features));
+        assert!(check_allowed(vec![], &windows, &HashMap::new()));
+    }
+
+    #[test]
+    fn test_resolve_arguments() {
+        let windows = platform(OsType::Windows, "windows");
+        let arguments = vec![
+            json!("--username"),
+            json!(1),
+            json!({ "rules": [{ "action": "allow" }], "value": ["--width", 854, "--height"] }),
+            json!({ "rules": [{ "action": "allow" }], "value": { "unknown": true } }),
+            json!({
+                "rules": [{ "action": "allow", "os": { "name": "osx" } }],
+                "value": "-XstartOnFirstThread"
+            }),
+            json!({ "value": "--demo" }),
+        ];
+        assert_eq!(
+            resolve_arguments(&arguments, &windows, &HashMap::new()),
+            vec!["--username", "--width", "--height", "--demo"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dedup_libraries() {
+        let library = |name: &str| {
+            let info = LibraryInfo::from_name(name).unwrap();
+            Library::from_value(json!({
+                "name": name,
+                "downloads": {
+                    "artifact": { "path": info.path, "sha1": "", "size": 0, "url": "" }
+                }
+            }))
+        };
+        let libraries = vec![
+            library("com.google.code.gson:gson:2.10"),
+            library("org.lwjgl:lwjgl:3.3.1"),
+            library("org.lwjgl:lwjgl:3.3.1:natives-linux"),
+            library("com.google.code.gson:gson:2.10.1"),
+        ];
+        let paths = resolve_libraries(
+            libraries,
+            &platform(OsType::Linux, "linux"),
+            &ParseOptions::default(),
+        )
+        .await
+        .into_iter()
+        .map(|library| library.download_info.path)
+        .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar",
+                "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar",
+                "com/google/code/gson/gson/2.10.1/gson-2.10.1.jar",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_library_round_trip() {
+        let fixtures = [
+            include_str!("../../mock/versions/1.19.4.json"),
+            include_str!("../../mock/versions/1.7.10-forge.json"),
+            include_str!("../../mock/versions/fabric-loader-0.14.21-1.19.4.json"),
+        ];
+        for raw in fixtures {
+            let original: Value = serde_json::from_str(raw).unwrap();
+            let version = Version::from_str(raw).unwrap();
+            let libraries = version.libraries.unwrap();
+            assert!(libraries
+                .iter()
+                .all(|library| !matches!(library, Library::Unknown(_))));
+            assert_eq!(
+                serde_json::to_value(&libraries).unwrap(),
+                original["libraries"]
+            );
+        }
+
+        let unknown = json!({ "downloads": 1 });
+        assert_eq!(
+            Library::from_value(unknown.clone()),
+            Library::Unknown(unknown)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_asset_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let version = Version::from_str(include_str!("../../mock/versions/1.19.4.json")).unwrap();
+        let resolved = version
+            .parse(&minecraft, &platform(OsType::Linux, "linux"))
+            .await
+            .unwrap();
+        let asset_index = resolved.asset_index.unwrap();
+        assert!(asset_index
+            .url
+            .starts_with("https://piston-meta.mojang.com/v1/packages/"));
+        assert_eq!(
+            asset_index.sha1.as_deref(),
+            Some("bc1e11e2e0b6f2dbf2d3d1a6f31a0ea5ac2f4e50")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_asset_index() {
+        use futures::StreamExt;
+
+        let objects: Map<String, Value> = (0..200)
+            .map(|i| {
+                let hash = format!("{i:040x}");
+                (
+                    format!("minecraft/sounds/{i}.ogg"),
+                    json!({ "hash": hash, "size": i }),
+                )
+            })
+            .collect();
+        let raw =
+            json!({ "map_to_resources": false, "objects": objects, "virtual": false }).to_string();
+
+        let eager: AssetIndexObject =
+            serde_json::from_value(serde_json::from_str::<Value>(&raw).unwrap()["objects"].clone())
+                .unwrap();
+        let streamed: AssetIndexObject = stream_asset_index(std::io::Cursor::new(raw))
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(streamed.len(), 200);
+        assert_eq!(streamed, eager);
+
+        let broken = r#"{ "objects": { "a": { "hash": "ab", "size": 1 }, "b": { "hash": "#;
+        let results: Vec<_> = stream_asset_index(broken.as_bytes()).collect().await;
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn test_asset_object_url() {
+        let object = AssetIndexObjectInfo {
+            hash: "bdf48ef6b5d0d23bbb02e17d04865216179f510a".to_string(),
+            size: 112,
+        };
+        assert_eq!(
+            object.relative_path(),
+            "objects/bd/bdf48ef6b5d0d23bbb02e17d04865216179f510a"
+        );
+        assert_eq!(
+            object.url(&DownloadSource::official()),
+            "https://resources.download.minecraft.net/bd/bdf48ef6b5d0d23bbb02e17d04865216179f510a"
+        );
+        assert_eq!(
+            object.url(&DownloadSource::bmclapi()),
+            "https://bmclapi2.bangbang93.com/assets/bd/bdf48ef6b5d0d23bbb02e17d04865216179f510a"
+        );
+
+        // a malformed hash doesn't panic
+        for hash in ["b", "é"] {
+            let object = AssetIndexObjectInfo {
+                hash: hash.to_string(),
+                size: 0,
+            };
+            assert_eq!(object.relative_path(), format!("objects/{hash}/{hash}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_inherited_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let version = Version::from_value(json!({
+            "id": "1.19.4-fabric",
+            "inheritsFrom": "1.19.4"
+        }))
+        .unwrap();
+        let platform = platform(OsType::Linux, "linux");
+
+        let err = version.parse(&minecraft, &platform).await.unwrap_err();
+        let Error::Version(VersionResolveError::MissingInheritedVersion { id, path }) = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(id, "1.19.4");
+        assert_eq!(path, &minecraft.get_version_json("1.19.4"));
+        assert!(err.to_string().contains("1.19.4"));
+
+        let resolved = version
+            .parse_with_resolver(
+                &minecraft,
+                &platform,
+                &ParseOptions::default(),
+                |id| async move {
+                    assert_eq!(id, "1.19.4");
+                    Ok(Some(Version::from_str(include_str!(
+                        "../../mock/versions/1.19.4.json"
+                    ))?))
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(resolved.id, "1.19.4-fabric");
+        assert_eq!(resolved.main_class, "net.minecraft.client.main.Main");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_native_libraries() {
+        let version =
+            Version::from_str(include_str!("../../mock/versions/1.7.10-forge.json")).unwrap();
+
+        let libraries = version.libraries.clone().unwrap();
+        let windows = resolve_libraries(
+            libraries.clone(),
+            &platform(OsType::Windows, "windows"),
+            &ParseOptions::default(),
+        )
+        .await;
+        let urls = windows
+            .iter()
+            .filter(|library| library.is_native_library)
+            .map(|library| library.download_info.url.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.1/lwjgl-platform-2.9.1-natives-windows.jar",
+                "https://libraries.minecraft.net/tv/twitch/twitch-platform/5.16/twitch-platform-5.16-natives-windows-64.jar",
+            ]
+        );
+        assert_eq!(
+            windows[0].download_info.url,
+            "https://maven.minecraftforge.net/net/minecraftforge/forge/1.7.10-10.13.4.1614-1.7.10/forge-1.7.10-10.13.4.1614-1.7.10.jar"
+        );
+
+        let linux = resolve_libraries(
+            libraries,
+            &platform(OsType::Linux, "linux"),
+            &ParseOptions::default(),
+        )
+        .await;
+        assert_eq!(linux.len(), 4);
+        assert!(!linux
+            .iter()
+            .any(|library| library.download_info.path.contains("twitch")));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_legacy_libraries() {
+        let libraries = vec![
+            Library::from_value(json!({
+                "name": "com.mumfrey:liteloader:1.12.2-SNAPSHOT",
+                "url": "http://dl.liteloader.com/versions/"
+            })),
+            Library::from_value(json!({
+                "name": "org.scala-lang:scala-library:2.11.1",
+                "url": "https://maven.minecraftforge.net/",
+                "checksums": ["0a1e1c0e8e3a1b3a4b0e8d1c7ae6b9f3c6ae0f0b"],
+                "serverreq": true,
+                "clientreq": true
+            })),
+            Library::from_value(json!({
+                "name": "com.typesafe.akka:akka-actor_2.11:2.3.3",
+                "url": "https://maven.minecraftforge.net/",
+                "serverreq": true,
+                "clientreq": false
+            })),
+            Library::from_value(json!({ "name": "net.minecraft:launchwrapper:1.12" })),
+        ];
+        assert!(libraries
+            .iter()
+            .all(|library| matches!(library, Library::Legacy(_))));
+        let resolved = resolve_libraries(
+            libraries,
+            &platform(OsType::Linux, "linux"),
+            &ParseOptions::default(),
+        )
+        .await
+        .into_iter()
+        .map(|library| (library.download_info.path, library.download_info.url))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            vec![
+                (
+                    "com/mumfrey/liteloader/1.12.2-SNAPSHOT/liteloader-1.12.2-SNAPSHOT.jar".to_string(),
+                    "http://dl.liteloader.com/versions/com/mumfrey/liteloader/1.12.2-SNAPSHOT/liteloader-1.12.2-SNAPSHOT.jar".to_string()
+                ),
+                (
+                    "org/scala-lang/scala-library/2.11.1/scala-library-2.11.1.jar".to_string(),
+                    "https://maven.minecraftforge.net/org/scala-lang/scala-library/2.11.1/scala-library-2.11.1.jar".to_string()
+                ),
+                (
+                    "net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar".to_string(),
+                    "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deduplicate_libraries() {
+        let library = |path: &str, is_native_library| ResolvedLibrary {
+            download_info: LibraryDownload {
+                sha1: String::new(),
+                size: 0,
+                url: format!("https://libraries.minecraft.net/{path}"),
+                path: path.to_string(),
+            },
+            is_native_library,
+        };
+        let libraries = vec![
+            library("com/google/code/gson/gson/2.9.1/gson-2.9.1.jar", false),
+            library(
+                "com/google/guava/guava/32.1.2-jre/guava-32.1.2-jre.jar",
+                false,
+            ),
+            library("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar", false),
+            library("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar", true),
+            library("custom.jar", false),
+            library("com/google/code/gson/gson/2.10.1/gson-2.10.1.jar", false),
+            library("com/google/guava/guava/31.1-jre/guava-31.1-jre.jar", false),
+            library("custom.jar", false),
+        ];
+        let paths = deduplicate_libraries(libraries)
+            .into_iter()
+            .map(|library| library.download_info.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar",
+                "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar",
+                "custom.jar",
+                "com/google/code/gson/gson/2.10.1/gson-2.10.1.jar",
+                // the older guava pinned by the mod loader is kept
+                "com/google/guava/guava/31.1-jre/guava-31.1-jre.jar",
+                "custom.jar",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_library_to_download() {
+        let path = "com/google/code/gson/gson/2.10/gson-2.10.jar";
+        let library = ResolvedLibrary {
+            download_info: LibraryDownload {
+                sha1: "dd9b193aef96e973d5a11ab13cd17430c2e4306b".to_string(),
+                size: 286235,
+                url: format!("https://libraries.minecraft.net/{path}"),
+                path: path.to_string(),
+            },
+            is_native_library: false,
+        };
+        let minecraft = MinecraftLocation::new("/games/.minecraft");
+
+        let download = library.to_download(&minecraft, &DownloadSource::bmclapi());
+        assert!(download.file.starts_with(&minecraft.libraries));
+        assert!(download.file.ends_with(path));
+        assert_eq!(
+            download.url,
+            format!("https://bmclapi2.bangbang93.com/maven/{path}")
+        );
+        assert_eq!(
+            download.sha1.as_deref(),
+            Some(library.download_info.sha1.as_str())
+        );
+        assert_eq!(download.size, Some(286235));
+
+        let by_name = ResolvedLibrary {
+            download_info: LibraryDownload {
+                sha1: String::new(),
+                size: 0,
+                url: format!("https://maven.fabricmc.net/{path}"),
+                ..library.download_info
+            },
+            is_native_library: false,
+        }
+        .to_download(&minecraft, &DownloadSource::bmclapi());
+        assert_eq!(by_name.url, format!("https://maven.fabricmc.net/{path}"));
+        assert_eq!((by_name.sha1, by_name.size), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_library_urls() {
+        let libraries = vec![
+            Library::from_value(json!({
+                "name": "net.minecraftforge:forge:1.7.10-10.13.4.1614-1.7.10",
+                "url": "http://files.minecraftforge.net/maven/"
+            })),
+            Library::from_value(json!({
+                "name": "net.fabricmc:intermediary:1.20.1",
+                "url": "https://maven.fabricmc.net/"
+            })),
+            Library::from_value(json!({ "name": "net.minecraft:launchwrapper:1.12" })),
+            Library::from_value(json!({
+                "name": "com.google.code.gson:gson:2.10",
+                "downloads": {
+                    "artifact": {
+                        "path": "com/google/code/gson/gson/2.10/gson-2.10.jar",
+                        "sha1": "dd9b193aef96e973d5a11ab13cd17430c2e4306b",
+                        "size": 286235,
+                        "url": "https://libraries.minecraft.net/com/google/code/gson/gson/2.10/gson-2.10.jar"
+                    }
+                }
+            })),
+        ];
+        let repos = RepoChain::default().with_mirror("https://mirror.example.com/maven");
+        let urls = resolve_libraries(
+            libraries,
+            &platform(OsType::Linux, "linux"),
+            &ParseOptions::default(),
+        )
+        .await
+        .iter()
+        .map(|library| library.urls(&repos))
+        .collect::<Vec<_>>();
+        let forge = "net/minecraftforge/forge/1.7.10-10.13.4.1614-1.7.10/forge-1.7.10-10.13.4.1614-1.7.10.jar";
+        let intermediary = "net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar";
+        let launchwrapper = "net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar";
+        let in_repos = |path: &str, repos: &[&str]| {
+            repos
+                .iter()
+                .map(|repo| format!("{repo}/{path}"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            urls,
+            vec![
+                in_repos(
+                    forge,
+                    &[
+                        "https://maven.minecraftforge.net",
+                        "https://maven.fabricmc.net",
+                        "https://libraries.minecraft.net",
+                        "https://mirror.example.com/maven",
+                    ]
+                ),
+                in_repos(
+                    intermediary,
+                    &[
+                        "https://maven.fabricmc.net",
+                        "https://maven.minecraftforge.net",
+                        "https://libraries.minecraft.net",
+                        "https://mirror.example.com/maven",
+                    ]
+                ),
+                in_repos(
+                    launchwrapper,
+                    &[
+                        "https://libraries.minecraft.net",
+                        "https://maven.minecraftforge.net",
+                        "https://maven.fabricmc.net",
+                        "https://mirror.example.com/maven",
+                    ]
+                ),
+                vec![
+                    "https://libraries.minecraft.net/com/google/code/gson/gson/2.10/gson-2.10.jar"
+                        .to_string()
+                ],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_options() {
+        let version =
+            Version::from_str(include_str!("../../mock/versions/1.7.10-forge.json")).unwrap();
+        let libraries = version.libraries.clone().unwrap();
+        let windows = platform(OsType::Windows, "windows");
+        let paths = |libraries: Vec<ResolvedLibrary>| {
+            libraries
+                .into_iter()
+                .map(|library| library.download_info.path)
+                .collect::<Vec<_>>()
+        };
+
+        let options = ParseOptions {
+            exclude_libraries: vec!["org.lwjgl.lwjgl:lwjgl-platform".to_string()],
+            ..Default::default()
+        };
+        let resolved = resolve_libraries(libraries.clone(), &windows, &options).await;
+        assert!(!paths(resolved.clone())
+            .iter()
+            .any(|path| path.contains("lwjgl-platform")));
+        assert_eq!(
+            resolved
+                .iter()
+                .filter(|library| library.is_native_library)
+                .count(),
+            1
+        );
+
+        let launchwrapper = LibraryDownload {
+            sha1: "".to_string(),
+            size: 0,
+            url: "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.13/launchwrapper-1.13.jar".to_string(),
+            path: "net/minecraft/launchwrapper/1.13/launchwrapper-1.13.jar".to_string(),
+        };
+        let agent = LibraryDownload {
+            sha1: "".to_string(),
+            size: 0,
+            url: "https://example.com/authlib-injector.jar".to_string(),
+            path: "authlib-injector.jar".to_string(),
+        };
+        let options = ParseOptions {
+            exclude_libraries: vec!["tv.twitch".to_string()],
+            extra_libraries: vec![launchwrapper, agent],
+            ..Default::default()
+        };
+        let resolved = paths(resolve_libraries(libraries, &windows, &options).await);
+        assert!(!resolved.iter().any(|path| path.contains("twitch")));
+        assert!(!resolved
+            .iter()
+            .any(|path| path.contains("launchwrapper-1.12")));
+        assert!(resolved
+            .contains(&"net/minecraft/launchwrapper/1.13/launchwrapper-1.13.jar".to_string()));
+        assert_eq!(resolved.last().unwrap(), "authlib-injector.jar");
+    }
+
+    #[tokio::test]
+    async fn test_lwjgl_fix() {
+        let mut libraries = Vec::new();
+        for module in LWJGL_MODULES {
+            for classifier in ["", ":natives-linux", ":natives-windows", ":natives-macos"] {
+                let name = format!("org.lwjgl:{module}:3.3.1{classifier}");
+                let info = LibraryInfo::from_name(&name).unwrap();
+                let os = classifier
+                    .trim_start_matches(":natives-")
+                    .replace("macos", "osx");
+                libraries.push(Library::from_value(json!({
+                    "name": name,
+                    "downloads": {
+                        "artifact": {
+                            "path": info.path,
+                            "sha1": "715ccf3330885e75b205124f09f8712542cbe7e0",
+                            "size": 1,
+                            "url": MavenRepo::mojang().artifact_url(&info.path),
+                        }
+                    },
+                    "rules": if classifier.is_empty() {
+                        json!([])
+                    } else {
+                        json!([{ "action": "allow", "os": { "name": os } }])
+                    },
+                })));
+            }
+        }
+        libraries.push(Library::from_value(json!({
+            "name": "com.mojang:blocklist:1.0.10",
+            "downloads": {
+                "artifact": {
+                    "path": "com/mojang/blocklist/1.0.10/blocklist-1.0.10.jar",
+                    "sha1": "5c685c5ffa94c4cd39496c7184c1d122e515ecef",
+                    "size": 964,
+                    "url": "https://libraries.minecraft.net/com/mojang/blocklist/1.0.10/blocklist-1.0.10.jar",
+                }
+            },
+        })));
+        let options = ParseOptions {
+            lwjgl_fix: Some(LwjglFix {
+                maven: "https://mirror.example.com/maven".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut arm64 = platform(OsType::Linux, "linux");
+        arm64.arch = "aarch64".to_string();
+        let resolved = resolve_libraries(libraries.clone(), &arm64, &options).await;
+        assert_eq!(resolved.len(), LWJGL_MODULES.len() * 2 + 1);
+        for module in LWJGL_MODULES {
+            for suffix in ["", "-natives-linux-arm64"] {
+                let path = format!("org/lwjgl/{module}/3.3.1/{module}-3.3.1{suffix}.jar");
+                let library = resolved
+                    .iter()
+                    .find(|library| library.download_info.path == path)
+                    .unwrap();
+                assert_eq!(
+                    library.download_info.url,
+                    format!("https://mirror.example.com/maven/{path}")
+                );
+                assert!(library.download_info.sha1.is_empty());
+            }
+        }
+        assert!(!resolved.last().unwrap().download_info.sha1.is_empty());
+
+        let mut windows_arm64 = platform(OsType::Windows, "windows");
+        windows_arm64.arch = "arm64".to_string();
+        let resolved = resolve_libraries(libraries.clone(), &windows_arm64, &options).await;
+        assert!(resolved.iter().any(|library| library
+            .download_info
+            .path
+            .ends_with("natives-windows-arm64.jar")));
+
+        // the official natives work on x64
+        let resolved =
+            resolve_libraries(libraries, &platform(OsType::Linux, "linux"), &options).await;
+        assert!(resolved
+            .iter()
+            .all(|library| !library.download_info.sha1.is_empty()));
+    }
+
+    #[test]
+    fn test_library_info_from_path() {
+        let info =
+            LibraryInfo::from_path("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux-arm64.jar")
+                .unwrap();
+        assert_eq!(info.name, "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64");
+        assert_eq!(info.classifier, "natives-linux-arm64");
+        let info = LibraryInfo::from_path(
+            "net/minecraftforge/forge/1.20.1-47.1.0/forge-1.20.1-47.1.0-universal.zip",
+        )
+        .unwrap();
+        assert_eq!(
+            info.name,
+            "net.minecraftforge:forge:1.20.1-47.1.0:universal@zip"
+        );
+        assert_eq!(
+            info.path,
+            "net/minecraftforge/forge/1.20.1-47.1.0/forge-1.20.1-47.1.0-universal.zip"
+        );
+        assert!(LibraryInfo::from_path("authlib-injector.jar").is_err());
+        assert!(LibraryInfo::from_path("com/example/lib/1.0/other-1.0.jar").is_err());
+        assert!(LibraryInfo::from_path("lib/1.0/lib-1.0.jar").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_detect_mod_loader() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let detect = |main_class: &str, libraries: &[&str]| {
+            let version = Version::from_value(json!({
+                "id": "1.20.1",
+                "mainClass": main_class,
+                "assetIndex": { "id": "5", "sha1": "", "size": 1, "totalSize": 1, "url": "" },
+                "downloads": { "client": { "sha1": "", "size": 1, "url": "" } },
+                "libraries": libraries
+                    .iter()
+                    .map(|name| json!({ "name": name }))
+                    .collect::<Vec<_>>()
+            }))
+            .unwrap();
+            let minecraft = minecraft.clone();
+            async move {
+                version
+                    .parse(&minecraft, &platform(OsType::Linux, "linux"))
+                    .await
+                    .unwrap()
+                    .detect_mod_loader()
+            }
+        };
+
+        let vanilla = "net.minecraft.client.main.Main";
+        assert_eq!(
+            detect(vanilla, &["com.google.code.gson:gson:2.10"]).await,
+            ModLoader::Vanilla
+        );
+        assert_eq!(
+            detect(
+                "net.fabricmc.loader.impl.launch.knot.KnotClient",
+                &["net.fabricmc:fabric-loader:0.14.21"]
+            )
+            .await,
+            ModLoader::Fabric
+        );
+        assert_eq!(
+            detect(
+                "org.quiltmc.loader.impl.launch.knot.KnotClient",
+                &[
+                    "net.fabricmc:intermediary:1.20.1",
+                    "org.quiltmc:quilt-loader:0.19.2"
+                ]
+            )
+            .await,
+            ModLoader::Quilt
+        );
+        assert_eq!(
+            detect("cpw.mods.bootstraplauncher.BootstrapLauncher", &[]).await,
+            ModLoader::Forge
+        );
+        assert_eq!(
+            detect(
+                "net.minecraft.launchwrapper.Launch",
+                &["net.minecraftforge:forge:1.7.10-10.13.4.1614-1.7.10"]
+            )
+            .await,
+            ModLoader::Forge
+        );
+        assert_eq!(
+            detect(
+                "cpw.mods.bootstraplauncher.BootstrapLauncher",
+                &["net.neoforged.fancymodloader:loader:47.1.3"]
+            )
+            .await,
+            ModLoader::NeoForge
+        );
+        assert_eq!(
+            detect(
+                "net.minecraft.launchwrapper.Launch",
+                &["optifine:OptiFine:1.20.1_HD_U_I5"]
+            )
+            .await,
+            ModLoader::OptiFine
+        );
+    }
+
+    #[test]
+    fn test_version_id() {
+        let ids = [
+            "a1.0.4",
+            "b1.7.3",
+            "1.7.10",
+            "1.19.4",
+            "1.20",
+            "1.20.1-pre1",
+            "1.20.1-rc1",
+            "1.20.1",
+            "22w45a",
+            "23w43a",
+            "23w43b",
+        ];
+        let mut sorted = ids
+            .iter()
+            .rev()
+            .map(|id| VersionId::parse(id).unwrap())
+            .collect::<Vec<_>>();
+        sorted.sort();
+        assert_eq!(
+            sorted.iter().map(VersionId::as_str).collect::<Vec<_>>(),
+            ids
+        );
+        assert!(VersionId::parse("1.20").unwrap() < VersionId::parse("1.20.0").unwrap());
+        assert!(VersionId::parse("1.20-custom").is_err());
+        assert!(VersionId::parse("fabric-loader-0.14.21-1.19.4").is_err());
+    }
+
+    #[test]
+    fn test_version_info_order() {
+        let version = |id: &str, release_time: &str| VersionInfo {
+            id: id.to_string(),
+            r#type: VersionType::default(),
+            url: "".to_string(),
+            time: release_time.to_string(),
+            release_time: release_time.to_string(),
+            sha1: "".to_string(),
+            compliance_level: 1,
+        };
+        let mut versions = [
+            version("1.20", "2023-06-07T09:35:22+00:00"),
+            version("23w18a", "2023-05-03T11:16:38+00:00"),
+            version("1.19.4", "2023-03-14T12:56:18+00:00"),
+            version("23w03a", "2023-01-18T13:31:12+00:00"),
+            version("1.19.3", "2022-12-07T08:17:18+00:00"),
+            version("1.9", "2016-02-29T13:49:54+00:00"),
+        ];
+        versions.sort();
+        assert_eq!(
+            versions.iter().map(|v| v.id.as_str()).collect::<Vec<_>>(),
+            ["1.9", "1.19.3", "23w03a", "1.19.4", "23w18a", "1.20"]
+        );
+    }
+
+    #[test]
+    fn test_library_info_classifier() {
+        let library = LibraryInfo::from_name("net.minecraftforge:forge:1.20.1:universal").unwrap();
+        assert_eq!(library.classifier, "universal");
+        assert_eq!(
+            library.path,
+            "net/minecraftforge/forge/1.20.1/forge-1.20.1-universal.jar"
+        );
+
+        let library = LibraryInfo::from_name("com.google.code.gson:gson:2.10").unwrap();
+        assert_eq!(library.classifier, "");
+        assert_eq!(library.path, "com/google/code/gson/gson/2.10/gson-2.10.jar");
+        assert_eq!(
+            library.maven_url("https://libraries.minecraft.net/"),
+            "https://libraries.minecraft.net/com/google/code/gson/gson/2.10/gson-2.10.jar"
+        );
+    }
+
+    #[test]
+    fn test_library_info_paths() {
+        // the names and the `downloads.artifact.path` in the version jsons of vanilla, fabric and
+        // forge
+        let libraries = [
+            (
+                "org.lwjgl:lwjgl:3.3.1:natives-linux",
+                "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar",
+            ),
+            (
+                "ca.weblite:java-objc-bridge:1.1",
+                "ca/weblite/java-objc-bridge/1.1/java-objc-bridge-1.1.jar",
+            ),
+            (
+                "net.fabricmc:fabric-loader:0.14.21",
+                "net/fabricmc/fabric-loader/0.14.21/fabric-loader-0.14.21.jar",
+            ),
+            (
+                "net.fabricmc:intermediary:1.20.1",
+                "net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar",
+            ),
+            (
+                "net.minecraftforge:forge:1.20.1-47.1.0:universal",
+                "net/minecraftforge/forge/1.20.1-47.1.0/forge-1.20.1-47.1.0-universal.jar",
+            ),
+            (
+                "de.oceanlabs.mcp:mcp_config:1.20.1-20230612.114412@zip",
+                "de/oceanlabs/mcp/mcp_config/1.20.1-20230612.114412/mcp_config-1.20.1-20230612.114412.zip",
+            ),
+            (
+                "net.minecraft:client:1.20.1-20230612.114412:mappings@txt",
+                "net/minecraft/client/1.20.1-20230612.114412/client-1.20.1-20230612.114412-mappings.txt",
+            ),
+            (
+                "cpw.mods:securejarhandler:2.1.10",
+                "cpw/mods/securejarhandler/2.1.10/securejarhandler-2.1.10.jar",
+            ),
+        ];
+        for (name, path) in libraries {
+            let library = LibraryInfo::from_name(name).unwrap();
+            assert_eq!(library.path, path);
+            assert_eq!(library.name, name);
+        }
+        let library =
+            LibraryInfo::from_name("net.minecraft:client:1.20.1-20230612.114412:mappings@txt")
+                .unwrap();
+        assert_eq!(library.classifier, "mappings");
+        assert_eq!(library.r#type, "txt");
+
+        for name in [
+            "",
+            "de.oceanlabs.mcp:mcp_config@zip",
+            "net.fabricmc::0.14.21",
+            "a:b:c:d:e",
+            "a:b:c@",
+        ] {
+            assert!(LibraryInfo::from_name(name).is_err(), "{name}");
         }
     }
 }
@@ -0,0 +1,229 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The error of the crate, grouped by where it comes from.
+//!
+//! The functions return [`Result`], match on [`Error`] to tell a network failure from a corrupt
+//! file, an invalid version or a cancelled task. Every variant keeps the url, the path or the
+//! version id it's about, so the message is enough without a backtrace.
+//!
+//! # Example
+//!
+//! ```
+//! use mgl_core::{
+//!     core::task::Cancelled,
+//!     utils::download::DownloadError,
+//!     Error,
+//! };
+//!
+//! fn download() -> mgl_core::Result<(), DownloadError> {
+//!     Err(DownloadError::Status(404))
+//! }
+//!
+//! fn install() -> mgl_core::Result<()> {
+//!     download()?;
+//!     Ok(())
+//! }
+//!
+//! assert!(matches!(install(), Err(Error::Download(DownloadError::Status(404)))));
+//! assert!(matches!(Error::from(Cancelled), Error::Cancelled));
+//! ```
+
+use crate::{
+    auth::{microsoft::MicrosoftAuthError, yggdrasil::YggdrasilError, AuthError},
+    core::{task::Cancelled, version::VersionResolveError},
+    install::InstallError,
+    utils::download::DownloadError,
+};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An error of the crate, see the [module documentation](self).
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A file can't be downloaded
+    #[error("download failed: {0}")]
+    Download(#[source] DownloadError),
+    /// A version json is invalid or its `inheritsFrom` chain can't be followed
+    #[error("invalid version: {0}")]
+    Version(#[from] VersionResolveError),
+    /// An installation failed
+    #[error("install failed: {0}")]
+    Install(#[from] InstallError),
+    /// The login failed
+    #[error("login failed: {0}")]
+    Auth(#[from] AuthError),
+    /// A file can't be read or written
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The task is cancelled by a [`CancelHandle`](crate::core::task::CancelHandle)
+    #[error("{}", Cancelled)]
+    Cancelled,
+}
+
+impl From<DownloadError> for Error {
+    /// A cancelled download is [`Error::Cancelled`]
+    fn from(err: DownloadError) -> Self {
+        match err {
+            DownloadError::Cancelled => Error::Cancelled,
+            err => Error::Download(err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Download(DownloadError::Network(err))
+    }
+}
+
+impl From<MicrosoftAuthError> for Error {
+    fn from(err: MicrosoftAuthError) -> Self {
+        Error::Auth(err.into())
+    }
+}
+
+impl From<YggdrasilError> for Error {
+    fn from(err: YggdrasilError) -> Self {
+        Error::Auth(err.into())
+    }
+}
+
+impl From<Cancelled> for Error {
+    fn from(_: Cancelled) -> Self {
+        Error::Cancelled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::core::version::{VersionParseError, VersionResolveError};
+
+    use super::*;
+
+    #[test]
+    fn test_error_conversion() {
+        let err = Error::from(DownloadError::HashMismatch {
+            url: "https://libraries.minecraft.net/a.jar".to_string(),
+            algorithm: "sha1",
+            expected: "aaa".to_string(),
+            actual: "bbb".to_string(),
+        });
+        assert!(matches!(
+            err,
+            Error::Download(DownloadError::HashMismatch { .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "download failed: sha1 mismatch of https://libraries.minecraft.net/a.jar, expected aaa, got bbb"
+        );
+        assert_eq!(
+            Error::from(DownloadError::Status(404)).to_string(),
+            "download failed: server responded 404 Not Found"
+        );
+
+        let err = Error::from(VersionResolveError::CircularInheritance {
+            chain: vec!["a".to_string(), "b".to_string(), "a".to_string()],
+        });
+        assert!(matches!(err, Error::Version(_)));
+        assert_eq!(
+            err.to_string(),
+            "invalid version: circular inheritance: a -> b -> a"
+        );
+        let err = Error::from(VersionResolveError::InvalidVersion {
+            id: Some("1.20.1".to_string()),
+            source: VersionParseError::MissingField("mainClass".to_string()),
+        });
+        assert_eq!(
+            err.to_string(),
+            "invalid version: invalid version json of 1.20.1: mainClass is missing in the version json"
+        );
+        let err = Error::from(VersionResolveError::MissingInheritedVersion {
+            id: "1.20.1".to_string(),
+            path: PathBuf::from("versions/1.20.1/1.20.1.json"),
+        });
+        assert_eq!(
+            err.to_string(),
+            "invalid version: the inherited version 1.20.1 is not installed, expected at versions/1.20.1/1.20.1.json"
+        );
+
+        let err = Error::from(InstallError::PartialInstall {
+            failures: vec![(
+                "https://example.com/a.jar".to_string(),
+                "timeout".to_string(),
+            )],
+        });
+        assert!(matches!(err, Error::Install(_)));
+        assert!(err
+            .to_string()
+            .starts_with("install failed: 1 files failed"));
+        let err = Error::from(InstallError::VersionNotFound {
+            id: "1.99".to_string(),
+        });
+        assert_eq!(
+            err.to_string(),
+            "install failed: version 1.99 is not found in the version manifest"
+        );
+
+        let err = Error::from(AuthError::from(YggdrasilError {
+            error: "ForbiddenOperationException".to_string(),
+            message: "Invalid credentials".to_string(),
+        }));
+        assert!(matches!(err, Error::Auth(AuthError::Yggdrasil(_))));
+        assert_eq!(
+            err.to_string(),
+            "login failed: ForbiddenOperationException: Invalid credentials"
+        );
+        let err = Error::from(MicrosoftAuthError::NoMinecraftProfile);
+        assert!(matches!(err, Error::Auth(AuthError::Microsoft(_))));
+        assert_eq!(
+            err.to_string(),
+            "login failed: the account doesn't own Minecraft"
+        );
+
+        let io = std::fs::read("/nonexistent/version.json").unwrap_err();
+        let err = Error::from(io);
+        assert!(matches!(err, Error::Io(_)));
+        assert!(err.to_string().starts_with("io error: "));
+
+        // a cancelled download is a cancelled task
+        assert!(matches!(
+            Error::from(DownloadError::Cancelled),
+            Error::Cancelled
+        ));
+        assert!(matches!(Error::from(Cancelled), Error::Cancelled));
+        assert_eq!(Error::Cancelled.to_string(), "the task is cancelled");
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error as _;
+
+        let err = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(err
+            .source()
+            .is_some_and(|source| source.is::<std::io::Error>()));
+        let err = Error::from(DownloadError::Status(404));
+        assert!(err
+            .source()
+            .is_some_and(|source| source.is::<DownloadError>()));
+        assert!(Error::Cancelled.source().is_none());
+    }
+}
@@ -0,0 +1,225 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Edit `options.txt` in the game directory.
+//!
+//! Every line of `options.txt` is `key:value`, the lines are kept as they are unless their
+//! values are changed.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::game_data::options::GameOptions;
+//!
+//! fn fn_name() {
+//!     let mut options = GameOptions::load(".minecraft").unwrap();
+//!     options.set("lang", "en_us");
+//!     options.save().unwrap();
+//! }
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Result;
+
+pub const OPTIONS_FILE: &str = "options.txt";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameOptions {
+    pub path: PathBuf,
+    lines: Vec<String>,
+
+    /// `\r\n` if the file is written on Windows, it's kept when saving
+    line_ending: &'static str,
+}
+
+impl GameOptions {
+    /// Read `options.txt` in the game directory, a missing file is read as empty
+    pub fn load<P: AsRef<Path>>(game_dir: P) -> Result<Self> {
+        let path = game_dir.as_ref().join(OPTIONS_FILE);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            path,
+            lines: content.lines().map(|line| line.to_string()).collect(),
+            line_ending: if content.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+        })
+    }
+
+    /// Write the options to a temporary file and rename it to `options.txt`, so the game never
+    /// reads a partially written file
+    pub fn save(&self) -> Result<()> {
+        let mut content = self.lines.join(self.line_ending);
+        content.push_str(self.line_ending);
+        let temp_path = self.path.with_extension("txt.tmp");
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| line.split_once(':').map(|(k, _)| k) == Some(key))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let line = &self.lines[self.position(key)?];
+        line.split_once(':').map(|(_, value)| value)
+    }
+
+    /// Set the value of a key, a new key is appended to the end
+    pub fn set(&mut self, key: &str, value: &str) {
+        let line = format!("{key}:{value}");
+        match self.position(key) {
+            Some(index) => self.lines[index] = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    /// Get a list value like `resourcePacks:["vanilla","file/pack.zip"]`
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .and_then(|value| serde_json::from_str(value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_list(&mut self, key: &str, values: &[String]) {
+        self.set(key, &serde_json::to_string(values).unwrap());
+    }
+
+    fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key)?.parse().ok()
+    }
+
+    pub fn lang(&self) -> Option<&str> {
+        self.get("lang")
+    }
+
+    pub fn set_lang(&mut self, lang: &str) {
+        self.set("lang", lang);
+    }
+
+    pub fn fullscreen(&self) -> Option<bool> {
+        self.get_parsed("fullscreen")
+    }
+
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.set("fullscreen", &fullscreen.to_string());
+    }
+
+    /// `0` is auto
+    pub fn gui_scale(&self) -> Option<u32> {
+        self.get_parsed("guiScale")
+    }
+
+    pub fn set_gui_scale(&mut self, gui_scale: u32) {
+        self.set("guiScale", &gui_scale.to_string());
+    }
+
+    pub fn max_fps(&self) -> Option<u32> {
+        self.get_parsed("maxFps")
+    }
+
+    pub fn set_max_fps(&mut self, max_fps: u32) {
+        self.set("maxFps", &max_fps.to_string());
+    }
+
+    /// The enabled resource packs, like `vanilla` and `file/<file name>`
+    pub fn resource_packs(&self) -> Vec<String> {
+        self.get_list("resourcePacks")
+    }
+
+    pub fn set_resource_packs(&mut self, resource_packs: &[String]) {
+        self.set_list("resourcePacks", resource_packs);
+    }
+
+    /// The address of the last joined server
+    pub fn last_server(&self) -> Option<&str> {
+        self.get("lastServer")
+    }
+
+    pub fn set_last_server(&mut self, last_server: &str) {
+        self.set("lastServer", last_server);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_options() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(OPTIONS_FILE),
+            "version:3465\nresourcePacks:[\"vanilla\"]\nlang:en_us\n",
+        )
+        .unwrap();
+        let mut options = GameOptions::load(dir.path()).unwrap();
+        assert_eq!(options.get("lang"), Some("en_us"));
+        assert_eq!(options.get_list("resourcePacks"), vec!["vanilla"]);
+        options.set_list(
+            "resourcePacks",
+            &["vanilla".to_string(), "file/pack.zip".to_string()],
+        );
+        options.set("fullscreen", "true");
+        options.save().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(OPTIONS_FILE)).unwrap(),
+            "version:3465\nresourcePacks:[\"vanilla\",\"file/pack.zip\"]\nlang:en_us\nfullscreen:true\n"
+        );
+    }
+
+    #[test]
+    fn test_typed_options() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(OPTIONS_FILE),
+            "version:3465\r\nguiScale:2\r\nmaxFps:abc\r\nresourcePacks:[]\r\nlastServer:mc.example.com:25565\r\n",
+        )
+        .unwrap();
+        let mut options = GameOptions::load(dir.path()).unwrap();
+        assert_eq!(options.gui_scale(), Some(2));
+        assert_eq!(options.max_fps(), None);
+        assert_eq!(options.fullscreen(), None);
+        assert_eq!(options.last_server(), Some("mc.example.com:25565"));
+        assert!(options.resource_packs().is_empty());
+
+        options.set_resource_packs(&["vanilla".to_string(), "file/a,b.zip".to_string()]);
+        options.set_max_fps(120);
+        options.save().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(OPTIONS_FILE)).unwrap(),
+            "version:3465\r\nguiScale:2\r\nmaxFps:120\r\nresourcePacks:[\"vanilla\",\"file/a,b.zip\"]\r\nlastServer:mc.example.com:25565\r\n"
+        );
+        let options = GameOptions::load(dir.path()).unwrap();
+        assert_eq!(options.resource_packs(), vec!["vanilla", "file/a,b.zip"]);
+        assert!(!dir.path().join("options.txt.tmp").exists());
+    }
+}
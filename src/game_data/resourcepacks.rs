@@ -0,0 +1,432 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Manage the resource packs in `resourcepacks` and the shader packs in `shaderpacks`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::game_data::resourcepacks::{enable_resource_pack, scan_resource_packs};
+//!
+//! fn fn_name() {
+//!     for pack in scan_resource_packs(".minecraft/resourcepacks").unwrap() {
+//!         println!("{}: {:?}", pack.name, pack.metadata);
+//!     }
+//!     enable_resource_pack(".minecraft", "Faithful.zip").unwrap();
+//! }
+//! ```
+
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use zip::ZipArchive;
+
+use super::options::GameOptions;
+
+pub const PACK_MCMETA: &str = "pack.mcmeta";
+pub const PACK_ICON: &str = "pack.png";
+
+const RESOURCE_PACKS_DIR: &str = "resourcepacks";
+const RESOURCE_PACKS_KEY: &str = "resourcePacks";
+const INCOMPATIBLE_RESOURCE_PACKS_KEY: &str = "incompatibleResourcePacks";
+
+/// The Minecraft versions of each `pack_format`, as `(format, first version, last version)`
+const PACK_FORMATS: [(u32, &str, &str); 17] = [
+    (1, "1.6.1", "1.8.9"),
+    (2, "1.9", "1.10.2"),
+    (3, "1.11", "1.12.2"),
+    (4, "1.13", "1.14.4"),
+    (5, "1.15", "1.16.1"),
+    (6, "1.16.2", "1.16.5"),
+    (7, "1.17", "1.17.1"),
+    (8, "1.18", "1.18.2"),
+    (9, "1.19", "1.19.2"),
+    (12, "1.19.3", "1.19.3"),
+    (13, "1.19.4", "1.19.4"),
+    (15, "1.20", "1.20.1"),
+    (18, "1.20.2", "1.20.2"),
+    (22, "1.20.3", "1.20.4"),
+    (32, "1.20.5", "1.20.6"),
+    (34, "1.21", "1.21.1"),
+    (42, "1.21.2", "1.21.3"),
+];
+
+/// Get the Minecraft versions compatible with a `pack_format`, as `(first, last)`
+pub fn pack_format_versions(pack_format: u32) -> Option<(&'static str, &'static str)> {
+    PACK_FORMATS
+        .iter()
+        .find(|(format, _, _)| *format == pack_format)
+        .map(|(_, first, last)| (*first, *last))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackMcmeta {
+    pub pack_format: u32,
+
+    /// The plain text of the description, text components are flattened
+    pub description: String,
+}
+
+impl PackMcmeta {
+    pub fn parse(raw: &str) -> Result<Self> {
+        // some packs are saved with BOM
+        let json: Value = serde_json::from_str(raw.trim_start_matches('\u{feff}'))?;
+        let pack = &json["pack"];
+        let pack_format = pack["pack_format"]
+            .as_u64()
+            .ok_or(anyhow!("pack.pack_format is missing"))?;
+        Ok(Self {
+            pack_format: pack_format as u32,
+            description: text_component_to_string(&pack["description"]),
+        })
+    }
+}
+
+/// Flatten a json text component to plain text
+fn text_component_to_string(component: &Value) -> String {
+    match component {
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Array(components) => components.iter().map(text_component_to_string).collect(),
+        Value::Object(object) => {
+            let mut text = object
+                .get("text")
+                .or_else(|| object.get("translate"))
+                .map(text_component_to_string)
+                .unwrap_or_default();
+            if let Some(Value::Array(extra)) = object.get("extra") {
+                text.extend(extra.iter().map(text_component_to_string));
+            }
+            text
+        }
+        Value::Null => String::new(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourcePack {
+    /// The file or folder name, it's the name used in `options.txt`
+    pub name: String,
+    pub path: PathBuf,
+    pub is_directory: bool,
+
+    /// `None` if `pack.mcmeta` is missing or malformed
+    pub metadata: Option<PackMcmeta>,
+    pub icon: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderPack {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_directory: bool,
+}
+
+fn read_pack_file(path: &Path, is_directory: bool, name: &str) -> Option<Vec<u8>> {
+    if is_directory {
+        return fs::read(path.join(name)).ok();
+    }
+    let mut archive = ZipArchive::new(fs::File::open(path).ok()?).ok()?;
+    let mut file = archive.by_name(name).ok()?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+/// List the zips and folders in a directory sorted by name, a missing directory is empty
+fn list_packs(dir: &Path) -> Result<Vec<(String, PathBuf, bool)>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut packs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_directory = path.is_dir();
+        if !is_directory && !is_zip(&path) {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        packs.push((name, path, is_directory));
+    }
+    packs.sort();
+    Ok(packs)
+}
+
+/// Read `pack.mcmeta` of a pack zip or folder
+pub fn get_metadata<P: AsRef<Path>>(path: P) -> Result<PackMcmeta> {
+    let path = path.as_ref();
+    let raw = read_pack_file(path, path.is_dir(), PACK_MCMETA)
+        .ok_or(anyhow!("{PACK_MCMETA} is not found in {}", path.display()))?;
+    PackMcmeta::parse(&String::from_utf8_lossy(&raw))
+}
+
+/// Scan the resource packs in a directory like `.minecraft/resourcepacks`
+pub fn scan_resource_packs<P: AsRef<Path>>(dir: P) -> Result<Vec<ResourcePack>> {
+    Ok(list_packs(dir.as_ref())?
+        .into_iter()
+        .map(|(name, path, is_directory)| {
+            let metadata = read_pack_file(&path, is_directory, PACK_MCMETA)
+                .and_then(|raw| PackMcmeta::parse(&String::from_utf8_lossy(&raw)).ok());
+            let icon = read_pack_file(&path, is_directory, PACK_ICON);
+            ResourcePack {
+                name,
+                path,
+                is_directory,
+                metadata,
+                icon,
+            }
+        })
+        .collect())
+}
+
+/// List the valid resource packs of a game directory, the packs without a readable
+/// `pack.mcmeta` are skipped
+pub fn list_resource_packs<P: AsRef<Path>>(game_dir: P) -> Result<Vec<ResourcePack>> {
+    Ok(
+        scan_resource_packs(game_dir.as_ref().join(RESOURCE_PACKS_DIR))?
+            .into_iter()
+            .filter(|pack| pack.metadata.is_some())
+            .collect(),
+    )
+}
+
+/// Scan the shader packs in a directory like `.minecraft/shaderpacks`.
+///
+/// A folder is a shader pack only if it contains `shaders`.
+pub fn scan_shader_packs<P: AsRef<Path>>(dir: P) -> Result<Vec<ShaderPack>> {
+    Ok(list_packs(dir.as_ref())?
+        .into_iter()
+        .filter(|(_, path, is_directory)| !is_directory || path.join("shaders").is_dir())
+        .map(|(name, path, is_directory)| ShaderPack {
+            name,
+            path,
+            is_directory,
+        })
+        .collect())
+}
+
+/// Copy or move a pack zip into a directory like `.minecraft/resourcepacks`, an existing pack
+/// with the same name is not overwritten
+pub fn install_pack<P: AsRef<Path>, Q: AsRef<Path>>(
+    pack: P,
+    dir: Q,
+    move_file: bool,
+) -> Result<PathBuf> {
+    let pack = pack.as_ref();
+    let file_name = pack
+        .file_name()
+        .ok_or(anyhow!("{} is not a file", pack.display()))?;
+    let target = dir.as_ref().join(file_name);
+    if target.exists() {
+        return Err(anyhow!("{} already exists", target.display()));
+    }
+    fs::create_dir_all(dir.as_ref())?;
+    if move_file {
+        if fs::rename(pack, &target).is_err() {
+            // rename doesn't work across file systems
+            fs::copy(pack, &target)?;
+            fs::remove_file(pack)?;
+        }
+    } else {
+        fs::copy(pack, &target)?;
+    }
+    Ok(target)
+}
+
+/// Remove a pack file or folder
+pub fn remove_pack<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Enable a resource pack in `options.txt` of the game directory, it's added on top of the
+/// enabled packs
+pub fn enable_resource_pack<P: AsRef<Path>>(game_dir: P, name: &str) -> Result<()> {
+    let mut options = GameOptions::load(game_dir)?;
+    let entry = format!("file/{name}");
+    let mut packs = options.get_list(RESOURCE_PACKS_KEY);
+    if !packs.contains(&entry) {
+        packs.push(entry);
+        options.set_list(RESOURCE_PACKS_KEY, &packs);
+        options.save()?;
+    }
+    Ok(())
+}
+
+/// Disable a resource pack in `options.txt` of the game directory
+pub fn disable_resource_pack<P: AsRef<Path>>(game_dir: P, name: &str) -> Result<()> {
+    let mut options = GameOptions::load(game_dir)?;
+    let entry = format!("file/{name}");
+    for key in [RESOURCE_PACKS_KEY, INCOMPATIBLE_RESOURCE_PACKS_KEY] {
+        let mut packs = options.get_list(key);
+        if packs.contains(&entry) {
+            packs.retain(|pack| pack != &entry);
+            options.set_list(key, &packs);
+        }
+    }
+    options.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::write::FileOptions;
+
+    use super::*;
+    use crate::game_data::options::OPTIONS_FILE;
+
+    #[test]
+    fn test_parse_mcmeta() {
+        let mcmeta =
+            PackMcmeta::parse(r#"{ "pack": { "pack_format": 15, "description": "Faithful" } }"#)
+                .unwrap();
+        assert_eq!(mcmeta.pack_format, 15);
+        assert_eq!(mcmeta.description, "Faithful");
+        assert_eq!(pack_format_versions(15), Some(("1.20", "1.20.1")));
+
+        let mcmeta = PackMcmeta::parse(
+            r#"{ "pack": { "pack_format": 9, "description": [
+                { "text": "Fancy ", "color": "gold" },
+                { "text": "Pack", "extra": [{ "text": " v2" }] }
+            ] } }"#,
+        )
+        .unwrap();
+        assert_eq!(mcmeta.description, "Fancy Pack v2");
+
+        assert!(PackMcmeta::parse(r#"{ "pack": { "pack_format": 15, "#).is_err());
+        assert!(PackMcmeta::parse(r#"{ "pack": { "description": "no format" } }"#).is_err());
+    }
+
+    #[test]
+    fn test_scan_packs() {
+        let dir = tempfile::tempdir().unwrap();
+        let resourcepacks = dir.path().join("resourcepacks");
+        fs::create_dir_all(resourcepacks.join("Folder Pack")).unwrap();
+        fs::write(
+            resourcepacks.join("Folder Pack").join(PACK_MCMETA),
+            r#"{ "pack": { "pack_format": 13, "description": "folder" } }"#,
+        )
+        .unwrap();
+        let mut zip = zip::ZipWriter::new(fs::File::create(resourcepacks.join("Zip.zip")).unwrap());
+        zip.start_file(PACK_MCMETA, FileOptions::default()).unwrap();
+        zip.write_all(br#"{ "pack": { "pack_format": 15, "description": "zip" } }"#)
+            .unwrap();
+        zip.start_file(PACK_ICON, FileOptions::default()).unwrap();
+        zip.write_all(b"png").unwrap();
+        zip.finish().unwrap();
+        fs::write(resourcepacks.join("Broken.zip"), b"not a zip").unwrap();
+        fs::write(resourcepacks.join("readme.txt"), b"").unwrap();
+
+        let packs = scan_resource_packs(&resourcepacks).unwrap();
+        assert_eq!(
+            packs
+                .iter()
+                .map(|pack| pack.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Broken.zip", "Folder Pack", "Zip.zip"]
+        );
+        assert_eq!(packs[0].metadata, None);
+        assert!(packs[1].is_directory);
+        assert_eq!(packs[1].metadata.as_ref().unwrap().pack_format, 13);
+        assert_eq!(packs[2].icon.as_deref(), Some(&b"png"[..]));
+        assert_eq!(
+            get_metadata(resourcepacks.join("Zip.zip")).unwrap(),
+            PackMcmeta {
+                pack_format: 15,
+                description: "zip".to_string()
+            }
+        );
+        assert_eq!(
+            get_metadata(resourcepacks.join("Folder Pack"))
+                .unwrap()
+                .pack_format,
+            13
+        );
+        assert!(get_metadata(resourcepacks.join("Broken.zip")).is_err());
+        assert_eq!(
+            list_resource_packs(dir.path()).unwrap(),
+            vec![packs[1].clone(), packs[2].clone()]
+        );
+        assert!(list_resource_packs(dir.path().join("missing"))
+            .unwrap()
+            .is_empty());
+
+        let shaderpacks = dir.path().join("shaderpacks");
+        fs::create_dir_all(shaderpacks.join("BSL").join("shaders")).unwrap();
+        fs::create_dir_all(shaderpacks.join("empty")).unwrap();
+        fs::write(shaderpacks.join("Complementary.zip"), b"").unwrap();
+        let shaders = scan_shader_packs(&shaderpacks).unwrap();
+        assert_eq!(
+            shaders
+                .iter()
+                .map(|pack| pack.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["BSL", "Complementary.zip"]
+        );
+    }
+
+    #[test]
+    fn test_install_and_enable() {
+        let dir = tempfile::tempdir().unwrap();
+        let download = dir.path().join("Faithful.zip");
+        fs::write(&download, b"zip").unwrap();
+        let resourcepacks = dir.path().join("resourcepacks");
+        let pack = install_pack(&download, &resourcepacks, false).unwrap();
+        assert!(download.exists());
+        assert!(install_pack(&download, &resourcepacks, true).is_err());
+
+        fs::write(
+            dir.path().join(OPTIONS_FILE),
+            "lang:en_us\nresourcePacks:[\"vanilla\"]\nincompatibleResourcePacks:[\"file/Faithful.zip\"]\n",
+        )
+        .unwrap();
+        enable_resource_pack(dir.path(), "Faithful.zip").unwrap();
+        enable_resource_pack(dir.path(), "Faithful.zip").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join(OPTIONS_FILE)).unwrap(),
+            "lang:en_us\nresourcePacks:[\"vanilla\",\"file/Faithful.zip\"]\nincompatibleResourcePacks:[\"file/Faithful.zip\"]\n"
+        );
+        disable_resource_pack(dir.path(), "Faithful.zip").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join(OPTIONS_FILE)).unwrap(),
+            "lang:en_us\nresourcePacks:[\"vanilla\"]\nincompatibleResourcePacks:[]\n"
+        );
+
+        remove_pack(&pack).unwrap();
+        assert!(!pack.exists());
+    }
+}
@@ -0,0 +1,179 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! List, open and delete the screenshots in `screenshots`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::game_data::screenshots::{list_screenshots, open_screenshot};
+//!
+//! fn fn_name() {
+//!     let screenshots = list_screenshots(".minecraft").unwrap();
+//!     for screenshot in &screenshots {
+//!         println!("{}: {}x{}", screenshot.filename, screenshot.width, screenshot.height);
+//!     }
+//!     open_screenshot(&screenshots[0]).unwrap();
+//! }
+//! ```
+
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+
+const SCREENSHOTS_DIR: &str = "screenshots";
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// The format of the screenshot names, like `2023-07-01_12.34.56.png` or
+/// `2023-07-01_12.34.56_1.png`
+const SCREENSHOT_TIME_FORMAT: &str = "%Y-%m-%d_%H.%M.%S";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    pub path: PathBuf,
+    pub filename: String,
+
+    /// Parsed from the filename, the modified time of the file if the name is not the default one
+    pub taken_at: SystemTime,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Read the size of a png from its `IHDR` chunk, only the first 24 bytes are read
+fn read_png_size(path: &Path) -> Result<(u32, u32)> {
+    let mut header = [0; 24];
+    fs::File::open(path)?.read_exact(&mut header)?;
+    if header[..8] != PNG_SIGNATURE || &header[12..16] != b"IHDR" {
+        return Err(anyhow!("{} is not a png", path.display()));
+    }
+    let width = u32::from_be_bytes(header[16..20].try_into()?);
+    let height = u32::from_be_bytes(header[20..24].try_into()?);
+    Ok((width, height))
+}
+
+/// The local time in the name of a screenshot
+fn parse_taken_at(filename: &str) -> Option<SystemTime> {
+    let time = NaiveDateTime::parse_from_str(filename.get(..19)?, SCREENSHOT_TIME_FORMAT).ok()?;
+    Some(Local.from_local_datetime(&time).earliest()?.into())
+}
+
+/// List the screenshots of a game directory from the newest, the files not being a png are
+/// skipped
+pub fn list_screenshots<P: AsRef<Path>>(game_dir: P) -> Result<Vec<Screenshot>> {
+    let dir = game_dir.as_ref().join(SCREENSHOTS_DIR);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut screenshots = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_png = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if !is_png || !path.is_file() {
+            continue;
+        }
+        let Ok((width, height)) = read_png_size(&path) else {
+            continue;
+        };
+        let filename = entry.file_name().to_string_lossy().to_string();
+        let taken_at = match parse_taken_at(&filename) {
+            Some(taken_at) => taken_at,
+            None => entry.metadata()?.modified()?,
+        };
+        screenshots.push(Screenshot {
+            path,
+            filename,
+            taken_at,
+            width,
+            height,
+        });
+    }
+    screenshots.sort_by(|a, b| {
+        b.taken_at
+            .cmp(&a.taken_at)
+            .then_with(|| b.filename.cmp(&a.filename))
+    });
+    Ok(screenshots)
+}
+
+/// Open a screenshot with the default image viewer of the system
+pub fn open_screenshot(screenshot: &Screenshot) -> Result<()> {
+    Ok(opener::open(&screenshot.path)?)
+}
+
+/// Move a screenshot to the trash of the system
+pub fn delete_screenshot(screenshot: &Screenshot) -> Result<()> {
+    Ok(trash::delete(&screenshot.path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(13u32.to_be_bytes());
+        png.extend(b"IHDR");
+        png.extend(width.to_be_bytes());
+        png.extend(height.to_be_bytes());
+        png.extend([8, 6, 0, 0, 0]);
+        png
+    }
+
+    #[test]
+    fn test_list_screenshots() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list_screenshots(dir.path()).unwrap().is_empty());
+
+        let screenshots = dir.path().join(SCREENSHOTS_DIR);
+        fs::create_dir_all(&screenshots).unwrap();
+        fs::write(screenshots.join("2023-07-01_12.34.56.png"), png(1920, 1080)).unwrap();
+        fs::write(screenshots.join("2023-07-01_12.34.56_1.png"), png(854, 480)).unwrap();
+        fs::write(screenshots.join("2021-01-01_00.00.00.png"), png(1, 1)).unwrap();
+        fs::write(screenshots.join("renamed.PNG"), png(3840, 2160)).unwrap();
+        fs::write(screenshots.join("broken.png"), b"not a png").unwrap();
+        fs::write(screenshots.join("notes.txt"), b"").unwrap();
+
+        let list = list_screenshots(dir.path()).unwrap();
+        assert_eq!(
+            list.iter()
+                .map(|screenshot| screenshot.filename.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "renamed.PNG",
+                "2023-07-01_12.34.56_1.png",
+                "2023-07-01_12.34.56.png",
+                "2021-01-01_00.00.00.png"
+            ]
+        );
+        assert_eq!((list[0].width, list[0].height), (3840, 2160));
+        assert_eq!((list[2].width, list[2].height), (1920, 1080));
+        let taken_at = Local.with_ymd_and_hms(2023, 7, 1, 12, 34, 56).unwrap();
+        assert_eq!(list[2].taken_at, SystemTime::from(taken_at));
+        assert_eq!(list[1].taken_at, list[2].taken_at);
+    }
+}
@@ -0,0 +1,431 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! CurseForge modpacks.
+//!
+//! A modpack is a zip file containing `manifest.json`, the mods listed in the manifest are
+//! downloaded by the CurseForge API, and the other files are stored in `overrides/`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::path::Path;
+//! use mgl_core::core::folder::MinecraftLocation;
+//! use mgl_core::install::cursepack::install_cursepack;
+//! use mgl_core::mod_platform::curseforge::CurseForgeClient;
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let curseforge = CurseForgeClient::new("your api key");
+//!     let pack = install_cursepack(Path::new("pack.zip"), &minecraft, &curseforge, |progress| {
+//!         println!("{progress:?}");
+//!     })
+//!     .await
+//!     .unwrap();
+//!     println!("{} mods are installed to {:?}", pack.mods_count, pack.profile.game_dir);
+//! }
+//! ```
+
+use std::{fs::File, path::Path};
+
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+use crate::{
+    core::{folder::MinecraftLocation, task::CancelHandle},
+    install::{
+        modpack::{count_mods, extract_overrides, install_loader, modpack_game_dir, InstalledPack},
+        InstallError, DEFAULT_CONCURRENCY,
+    },
+    instance::{InstanceLoader, LoaderType},
+    mod_platform::curseforge::CurseForgeClient,
+    profile::GameProfile,
+    utils::download::{download_all, Download},
+    Result,
+};
+
+/// The name of the manifest file in the modpack
+pub const CURSEPACK_MANIFEST_FILE: &str = "manifest.json";
+
+/// Corresponds to `manifest.json`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CursePackManifest {
+    pub minecraft: CursePackMinecraft,
+    /// Always `minecraftModpack`
+    pub manifest_type: String,
+    pub manifest_version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub author: String,
+    pub files: Vec<CursePackFile>,
+    /// The folder containing the other files, usually `overrides`
+    #[serde(default = "default_overrides")]
+    pub overrides: String,
+}
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CursePackMinecraft {
+    pub version: String,
+    #[serde(default)]
+    pub mod_loaders: Vec<CursePackModLoader>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CursePackModLoader {
+    /// Like `forge-47.1.0` or `fabric-0.14.21`
+    pub id: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CursePackFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u32,
+    #[serde(rename = "fileID")]
+    pub file_id: u32,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+impl CursePackManifest {
+    /// The primary mod loader of the modpack
+    pub fn loader(&self) -> Result<Option<InstanceLoader>> {
+        let loaders = &self.minecraft.mod_loaders;
+        let Some(loader) = loaders
+            .iter()
+            .find(|loader| loader.primary)
+            .or(loaders.first())
+        else {
+            return Ok(None);
+        };
+        let (name, version) = loader
+            .id
+            .split_once('-')
+            .ok_or_else(|| InstallError::invalid(&loader.id, "not a mod loader"))?;
+        let r#type = match name {
+            "forge" => LoaderType::Forge,
+            "neoforge" => LoaderType::NeoForge,
+            "fabric" => LoaderType::Fabric,
+            "quilt" => LoaderType::Quilt,
+            _ => return Err(InstallError::invalid(&loader.id, "unknown mod loader").into()),
+        };
+        Ok(Some(InstanceLoader {
+            r#type,
+            version: version.to_string(),
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursePackProgress {
+    /// The mods listed in the manifest are downloading
+    Downloading {
+        completed: usize,
+        total: usize,
+    },
+    ExtractingOverrides,
+    InstallingLoader(InstanceLoader),
+}
+
+/// Install a CurseForge modpack.
+///
+/// The required files in `manifest.json` are downloaded to `mods/` by the CurseForge API and
+/// verified with sha1, then the overrides are extracted and the mod loader is installed. The game
+/// directory of the modpack is `<minecraft>/modpacks/<name>`, it must not exist yet.
+///
+/// The files whose authors disabled third-party distribution are returned as
+/// [`InstallError::PartialInstall`], they should be downloaded from the website. The vanilla
+/// version is not installed here.
+pub async fn install_cursepack(
+    pack_path: &Path,
+    minecraft: &MinecraftLocation,
+    curseforge: &CurseForgeClient,
+    on_progress: impl Fn(CursePackProgress),
+) -> Result<InstalledPack> {
+    let mut archive =
+        ZipArchive::new(File::open(pack_path)?).map_err(InstallError::archive(pack_path))?;
+    let manifest_file = archive
+        .by_name(CURSEPACK_MANIFEST_FILE)
+        .map_err(InstallError::archive(pack_path))?;
+    let manifest: CursePackManifest = serde_json::from_reader(manifest_file).map_err(
+        InstallError::json(pack_path.join(CURSEPACK_MANIFEST_FILE).display()),
+    )?;
+    let minecraft_version = &manifest.minecraft.version;
+    let loader = manifest.loader()?;
+    if loader
+        .as_ref()
+        .is_some_and(|loader| loader.r#type == LoaderType::NeoForge)
+    {
+        return Err(InstallError::UnsupportedLoader("NeoForge".to_string()).into());
+    }
+    let game_dir = modpack_game_dir(minecraft, &manifest.name)?;
+
+    let file_ids = manifest
+        .files
+        .iter()
+        .filter(|file| file.required)
+        .map(|file| file.file_id)
+        .collect::<Vec<_>>();
+    let files = if file_ids.is_empty() {
+        vec![]
+    } else {
+        curseforge.get_files(&file_ids).await?
+    };
+    let mut failures = vec![];
+    let mut tasks = vec![];
+    for file in files {
+        let Some(url) = file.download_url else {
+            failures.push((
+                format!(
+                    "https://www.curseforge.com/projects/{}/files/{}",
+                    file.mod_id, file.id
+                ),
+                "the author has disabled third-party distribution".to_string(),
+            ));
+            continue;
+        };
+        if file.file_name.contains(['/', '\\']) || file.file_name.starts_with('.') {
+            return Err(InstallError::invalid(&file.file_name, "unsafe file name").into());
+        }
+        tasks.push(Download {
+            sha1: file
+                .hashes
+                .iter()
+                .find(|hash| hash.algo == 1)
+                .map(|hash| hash.value.clone()),
+            ..Download::new(url, game_dir.join("mods").join(&file.file_name))
+                .with_size(file.file_length)
+        });
+    }
+    failures.extend(
+        download_all(
+            tasks,
+            DEFAULT_CONCURRENCY,
+            &CancelHandle::default(),
+            |completed, total| on_progress(CursePackProgress::Downloading { completed, total }),
+        )
+        .await?,
+    );
+    if !failures.is_empty() {
+        return Err(InstallError::PartialInstall { failures }.into());
+    }
+
+    on_progress(CursePackProgress::ExtractingOverrides);
+    std::fs::create_dir_all(&game_dir)?;
+    extract_overrides(&mut archive, pack_path, &manifest.overrides, &game_dir)?;
+
+    let version_id = match &loader {
+        Some(loader) => {
+            on_progress(CursePackProgress::InstallingLoader(loader.clone()));
+            install_loader(minecraft_version, loader, minecraft).await?
+        }
+        None => minecraft_version.clone(),
+    };
+    Ok(InstalledPack {
+        profile: GameProfile::new(&manifest.name, &version_id, &game_dir),
+        mods_count: count_mods(&game_dir),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write, time::Duration};
+
+    use serde_json::json;
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{utils::sha1::calculate_sha1_from_read, Error};
+
+    use super::*;
+
+    fn write_pack(path: &Path, manifest: &serde_json::Value) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        zip.start_file(CURSEPACK_MANIFEST_FILE, FileOptions::default())
+            .unwrap();
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        zip.start_file("overrides/config/jei.toml", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"enabled = true").unwrap();
+        zip.finish().unwrap();
+    }
+
+    fn file(
+        id: u32,
+        file_name: &str,
+        download_url: Option<String>,
+        sha1: &str,
+    ) -> serde_json::Value {
+        json!({
+            "id": id, "gameId": 432, "modId": id * 10, "isAvailable": true,
+            "displayName": file_name, "fileName": file_name, "releaseType": 1,
+            "hashes": [{ "value": sha1, "algo": 1 }],
+            "fileDate": "2023-01-01T00:00:00Z", "fileLength": 3,
+            "downloadUrl": download_url, "gameVersions": ["1.20.1"],
+            "dependencies": [], "fileFingerprint": 1
+        })
+    }
+
+    #[test]
+    fn test_loader() {
+        let manifest: CursePackManifest = serde_json::from_value(json!({
+            "minecraft": {
+                "version": "1.20.1",
+                "modLoaders": [
+                    { "id": "fabric-0.14.21", "primary": false },
+                    { "id": "forge-47.1.0", "primary": true }
+                ]
+            },
+            "manifestType": "minecraftModpack",
+            "manifestVersion": 1,
+            "name": "Pack",
+            "files": [{ "projectID": 1, "fileID": 2 }]
+        }))
+        .unwrap();
+        assert!(manifest.files[0].required);
+        assert_eq!(manifest.overrides, "overrides");
+        assert_eq!(
+            manifest.loader().unwrap(),
+            Some(InstanceLoader {
+                r#type: LoaderType::Forge,
+                version: "47.1.0".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_install_cursepack() {
+        let mut server = mockito::Server::new_async().await;
+        let sha1 = calculate_sha1_from_read(&mut b"jei".as_slice());
+        server
+            .mock("POST", "/v1/mods/files")
+            .with_status(429)
+            .expect(1)
+            .create_async()
+            .await;
+        let files = server
+            .mock("POST", "/v1/mods/files")
+            .match_body(mockito::Matcher::Json(json!({ "fileIds": [2] })))
+            .with_body(
+                json!({ "data": [file(2, "jei.jar", Some(format!("{}/files/jei.jar", server.url())), &sha1)] })
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/files/jei.jar")
+            .with_body("jei")
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let pack_path = dir.path().join("pack.zip");
+        write_pack(
+            &pack_path,
+            &json!({
+                "minecraft": { "version": "1.20.1", "modLoaders": [] },
+                "manifestType": "minecraftModpack",
+                "manifestVersion": 1,
+                "name": "Pack",
+                "version": "1.0.0",
+                "author": "Steve",
+                "files": [
+                    { "projectID": 20, "fileID": 2, "required": true },
+                    { "projectID": 30, "fileID": 3, "required": false }
+                ],
+                "overrides": "overrides"
+            }),
+        );
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        let curseforge = CurseForgeClient::with_base_url("key", &server.url())
+            .with_backoff(Duration::from_millis(10), 3);
+        let progress = std::sync::Mutex::new(Vec::new());
+        let pack = install_cursepack(&pack_path, &minecraft, &curseforge, |event| {
+            progress.lock().unwrap().push(event)
+        })
+        .await
+        .unwrap();
+        files.assert_async().await;
+
+        let game_dir = minecraft.root.join("modpacks/Pack");
+        assert_eq!(pack.mods_count, 1);
+        assert_eq!(pack.profile.version_id, "1.20.1");
+        assert_eq!(pack.profile.game_dir, game_dir);
+        assert_eq!(fs::read(game_dir.join("mods/jei.jar")).unwrap(), b"jei");
+        assert_eq!(
+            fs::read(game_dir.join("config/jei.toml")).unwrap(),
+            b"enabled = true"
+        );
+        assert_eq!(
+            progress.into_inner().unwrap(),
+            vec![
+                CursePackProgress::Downloading {
+                    completed: 1,
+                    total: 1
+                },
+                CursePackProgress::ExtractingOverrides
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_distribution_denied() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/v1/mods/files")
+            .with_body(json!({ "data": [file(2, "optifine.jar", None, "")] }).to_string())
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let pack_path = dir.path().join("pack.zip");
+        write_pack(
+            &pack_path,
+            &json!({
+                "minecraft": { "version": "1.20.1", "modLoaders": [] },
+                "manifestType": "minecraftModpack",
+                "manifestVersion": 1,
+                "name": "Pack",
+                "files": [{ "projectID": 20, "fileID": 2 }]
+            }),
+        );
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        let curseforge = CurseForgeClient::with_base_url("key", &server.url());
+        let err = install_cursepack(&pack_path, &minecraft, &curseforge, |_| {})
+            .await
+            .unwrap_err();
+        let Error::Install(InstallError::PartialInstall { failures }) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(
+            failures[0].0,
+            "https://www.curseforge.com/projects/20/files/2"
+        );
+    }
+}
@@ -16,10 +16,17 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use anyhow::Result;
+use std::sync::Arc;
+
+use chrono::{SecondsFormat, Utc};
 use tokio::fs;
 
-use crate::core::folder::MinecraftLocation;
+use crate::core::{
+    folder::MinecraftLocation,
+    task::{Reporter, TaskReporter},
+    version::check_inheritance,
+};
+use crate::Result;
 
 use super::*;
 
@@ -30,6 +37,7 @@ use super::*;
 /// * `loader` - The fabric loader version.
 /// * `minecraft_location` - The minecraft location.
 /// * `options` - The install options.
+/// * `reporter` - Receive the events of the task `install fabric`.
 ///
 /// ### Example
 ///
@@ -42,13 +50,23 @@ use super::*;
 ///     let loader = FabricLoaderArtifact::new("1.19.4", "xxx").await; // xxx is your fabric loader version
 ///     let minecraft_location = MinecraftLocation::new("test");
 ///     let options = None;
-///     install_fabric(loader, minecraft_location, options).await;
+///     install_fabric(loader, minecraft_location, options, None).await;
 /// }
 /// ```
 pub async fn install_fabric(
     loader: FabricLoaderArtifact,
     minecraft_location: MinecraftLocation,
     options: Option<FabricInstallOptions>,
+    reporter: Option<Arc<dyn Reporter>>,
+) -> Result<String> {
+    let task = TaskReporter::new(reporter, "install fabric", 1);
+    task.finish(write_fabric_version(loader, minecraft_location, options).await)
+}
+
+async fn write_fabric_version(
+    loader: FabricLoaderArtifact,
+    minecraft_location: MinecraftLocation,
+    options: Option<FabricInstallOptions>,
 ) -> Result<String> {
     let options = match options {
         None => FabricInstallOptions {
@@ -62,7 +80,8 @@ pub async fn install_fabric(
     let yarn: Option<String>;
     let side = options.size.unwrap_or(FabricInstallSide::Client);
     let mut id = options.version_id;
-    let mut minecraft_version = "".to_string();
+    // the intermediary is always published under the minecraft version it maps
+    let mut minecraft_version = loader.intermediary.version.clone();
 
     match options.yarn_version {
         Some(yarn_version) => match yarn_version {
@@ -70,12 +89,14 @@ pub async fn install_fabric(
                 yarn = Some(yarn_version);
             }
             YarnVersion::FabricArtifactVersion(yarn_version) => {
+                if let Some(game_version) = yarn_version.game_version {
+                    minecraft_version = game_version;
+                }
                 yarn = Some(yarn_version.version);
             }
         },
         None => {
             yarn = None;
-            minecraft_version = loader.intermediary.version;
         }
     }
     if let None = id {
@@ -108,25 +129,23 @@ pub async fn install_fabric(
         });
     }
     libraries.extend(loader.launcher_meta.libraries.common.iter().cloned());
-    match side {
-        FabricInstallSide::Client => {
-            libraries.extend(loader.launcher_meta.libraries.client.iter().cloned())
-        }
-        FabricInstallSide::Server => {
-            libraries.extend(loader.launcher_meta.libraries.server.iter().cloned())
-        }
-    }
-    let main_class = match side {
-        FabricInstallSide::Client => loader.launcher_meta.main_class["client"]
-            .as_str()
-            .unwrap_or(loader.launcher_meta.main_class.as_str().unwrap_or(""))
-            .to_string(),
-        FabricInstallSide::Server => loader.launcher_meta.main_class["server"]
-            .as_str()
-            .unwrap_or(loader.launcher_meta.main_class.as_str().unwrap_or(""))
-            .to_string(),
+    // the server has no jvm arguments of the client, e.g. the ones only for the game window
+    let (side_libraries, side_name, jvm_arguments) = match side {
+        FabricInstallSide::Client => (
+            &loader.launcher_meta.libraries.client,
+            "client",
+            Some(vec![]),
+        ),
+        FabricInstallSide::Server => (&loader.launcher_meta.libraries.server, "server", None),
     };
+    libraries.extend(side_libraries.iter().cloned());
+    let main_class = loader.launcher_meta.main_class[side_name]
+        .as_str()
+        .unwrap_or(loader.launcher_meta.main_class.as_str().unwrap_or(""))
+        .to_string();
     let inherits_from = options.inherits_from.unwrap_or(minecraft_version);
+    check_inheritance(&minecraft_location, id.as_deref().unwrap_or(""), &inherits_from)?;
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
 
     let json_file_path = minecraft_location.get_version_json(&id.clone().unwrap());
     fs::create_dir_all(json_file_path.parent().unwrap())
@@ -145,27 +164,28 @@ pub async fn install_fabric(
         id: String,
         inherits_from: String,
         main_class: String,
-        libraries: String,
+        libraries: Vec<LauncherMetaLibrariesItems>,
         arguments: FabricVersionJSONArg,
         release_time: String,
         time: String,
     }
     #[derive(Serialize)]
     struct FabricVersionJSONArg {
-        game: Vec<i32>,
-        jvm: Vec<i32>,
+        game: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        jvm: Option<Vec<String>>,
     }
     let version_json = FabricVersionJSON {
         id: id.clone().unwrap_or("".to_string()),
         inherits_from,
         main_class,
-        libraries: serde_json::to_string(&libraries).unwrap_or("".to_string()),
+        libraries,
         arguments: FabricVersionJSONArg {
             game: vec![],
-            jvm: vec![],
+            jvm: jvm_arguments,
         },
-        release_time: "2023-05-13T15:58:54.493Z".to_string(),
-        time: "2023-05-13T15:58:54.493Z".to_string(),
+        release_time: now.clone(),
+        time: now,
     };
     let json_data = serde_json::to_string_pretty(&version_json)
         .unwrap_or("".to_string())
@@ -179,5 +199,155 @@ pub async fn install_fabric(
 // async fn test() {
 //     let artifact = FabricLoaderArtifact::new("1.19.4", "0.1.0.48").await;
 //     let location = MinecraftLocation::new("test");
-//     install_fabric(artifact, location, None).await.unwrap();
+//     install_fabric(artifact, location, None, None).await.unwrap();
 // }
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use serde_json::json;
+
+    use super::*;
+
+    fn mock_loader() -> FabricLoaderArtifact {
+        mock_loader_with_libraries(json!({ "client": [], "common": [], "server": [] }))
+    }
+
+    fn mock_loader_with_libraries(libraries: Value) -> FabricLoaderArtifact {
+        serde_json::from_value(json!({
+            "loader": {
+                "separator": ".",
+                "build": 21,
+                "maven": "net.fabricmc:fabric-loader:0.14.21",
+                "version": "0.14.21",
+                "stable": true
+            },
+            "intermediary": {
+                "maven": "net.fabricmc:intermediary:1.20.1",
+                "version": "1.20.1",
+                "stable": true
+            },
+            "launcherMeta": {
+                "version": 1,
+                "libraries": libraries,
+                "mainClass": {
+                    "client": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+                    "server": "net.fabricmc.loader.impl.launch.knot.KnotServer"
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_fabric_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = MinecraftLocation::new(dir.path());
+        let yarn = FabricArtifactVersion {
+            game_version: Some("1.20.1".to_string()),
+            separator: Some("+build.".to_string()),
+            build: Some(10),
+            maven: "net.fabricmc:yarn:1.20.1+build.10".to_string(),
+            version: "1.20.1+build.10".to_string(),
+            stable: true,
+        };
+        let options = FabricInstallOptions {
+            inherits_from: None,
+            version_id: None,
+            size: None,
+            yarn_version: Some(YarnVersion::FabricArtifactVersion(yarn)),
+        };
+        let id = install_fabric(mock_loader(), location.clone(), Some(options), None)
+            .await
+            .unwrap();
+        assert_eq!(id, "1.20.1-loader0.14.21");
+
+        let json: Value = serde_json::from_str(
+            &fs::read_to_string(location.get_version_json(&id))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["inheritsFrom"], "1.20.1");
+        let release_time =
+            DateTime::parse_from_rfc3339(json["releaseTime"].as_str().unwrap()).unwrap();
+        let age = Utc::now().signed_duration_since(release_time);
+        assert!(age.num_seconds() >= 0 && age.num_minutes() < 1);
+        assert_eq!(json["time"], json["releaseTime"]);
+
+        let id = install_fabric(mock_loader(), location.clone(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(id, "1.20.1-fabric0.14.21");
+        let json: Value = serde_json::from_str(
+            &fs::read_to_string(location.get_version_json(&id))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["inheritsFrom"], "1.20.1");
+    }
+
+    #[tokio::test]
+    async fn test_write_fabric_server_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = MinecraftLocation::new(dir.path());
+        let loader = mock_loader_with_libraries(json!({
+            "client": [{ "name": "org.ow2.asm:asm-client:9.5", "url": "https://maven.fabricmc.net/" }],
+            "common": [{ "name": "org.ow2.asm:asm:9.5", "url": "https://maven.fabricmc.net/" }],
+            "server": [{ "name": "org.ow2.asm:asm-server:9.5", "url": "https://maven.fabricmc.net/" }]
+        }));
+        let options = FabricInstallOptions {
+            inherits_from: None,
+            version_id: Some("1.20.1-fabric-server".to_string()),
+            size: Some(FabricInstallSide::Server),
+            yarn_version: None,
+        };
+        let id = install_fabric(loader, location.clone(), Some(options), None)
+            .await
+            .unwrap();
+        assert_eq!(id, "1.20.1-fabric-server");
+
+        let json: Value = serde_json::from_str(
+            &fs::read_to_string(location.get_version_json(&id))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            json["mainClass"],
+            "net.fabricmc.loader.impl.launch.knot.KnotServer"
+        );
+        let libraries: Vec<&str> = json["libraries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|library| library["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            libraries,
+            [
+                "net.fabricmc:fabric-loader:0.14.21",
+                "net.fabricmc:intermediary:1.20.1",
+                "org.ow2.asm:asm:9.5",
+                "org.ow2.asm:asm-server:9.5"
+            ]
+        );
+        assert_eq!(json["arguments"], json!({ "game": [] }));
+
+        let id = install_fabric(mock_loader(), location.clone(), None, None)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_str(
+            &fs::read_to_string(location.get_version_json(&id))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            json["mainClass"],
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert_eq!(json["arguments"], json!({ "game": [], "jvm": [] }));
+    }
+}
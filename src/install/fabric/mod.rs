@@ -22,7 +22,7 @@ use serde_json::Value;
 pub mod install;
 pub mod version_list;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct FabricArtifactVersion {
     pub game_version: Option<String>,
@@ -111,6 +111,13 @@ pub struct YarnArtifactList(Vec<FabricArtifactVersion>);
 #[serde(rename_all = "camelCase")]
 pub struct LoaderArtifactList(Vec<FabricArtifactVersion>);
 
+/// A Minecraft version supported by Fabric
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct FabricSupportedVersion {
+    pub version: String,
+    pub stable: bool,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LauncherMeta {
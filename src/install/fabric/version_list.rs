@@ -16,8 +16,16 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use once_cell::sync::OnceCell;
+
+use crate::Result;
+
 use super::*;
 
+const FABRIC_META_URL: &str = "https://meta.fabricmc.net";
+
+static SUPPORTED_VERSIONS: OnceCell<Vec<FabricSupportedVersion>> = OnceCell::new();
+
 impl FabricArtifacts {
     /// get fabric artifacts
     pub async fn new() -> Self {
@@ -92,3 +100,130 @@ impl FabricLoaderArtifact {
             .unwrap()
     }
 }
+
+async fn fetch_fabric_supported_versions(meta_url: &str) -> Result<Vec<FabricSupportedVersion>> {
+    Ok(reqwest::get(format!("{meta_url}/v2/versions/game"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Get all Minecraft versions supported by Fabric.
+///
+/// The list is fetched once and cached for the whole session.
+pub async fn get_fabric_supported_versions() -> Result<Vec<FabricSupportedVersion>> {
+    if let Some(versions) = SUPPORTED_VERSIONS.get() {
+        return Ok(versions.clone());
+    }
+    let versions = fetch_fabric_supported_versions(FABRIC_META_URL).await?;
+    Ok(SUPPORTED_VERSIONS.get_or_init(|| versions).clone())
+}
+
+async fn fetch_loader_versions(meta_url: &str) -> Result<Vec<FabricArtifactVersion>> {
+    Ok(reqwest::get(format!("{meta_url}/v2/versions/loader"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+async fn fetch_yarn_versions(
+    meta_url: &str,
+    minecraft_version: &str,
+) -> Result<Vec<FabricArtifactVersion>> {
+    Ok(
+        reqwest::get(format!("{meta_url}/v2/versions/yarn/{minecraft_version}"))
+            .await?
+            .error_for_status()?
+            .json()
+            .await?,
+    )
+}
+
+/// Get all Fabric loader versions, newest first
+pub async fn get_loader_versions() -> Result<Vec<FabricArtifactVersion>> {
+    fetch_loader_versions(FABRIC_META_URL).await
+}
+
+/// Get the yarn mappings of a Minecraft version, newest first
+pub async fn get_yarn_versions(minecraft_version: &str) -> Result<Vec<FabricArtifactVersion>> {
+    fetch_yarn_versions(FABRIC_META_URL, minecraft_version).await
+}
+
+/// Get the newest stable Fabric loader, the unstable ones are skipped even if they are newer
+pub async fn latest_stable_loader() -> Result<Option<FabricArtifactVersion>> {
+    Ok(select_latest_stable(get_loader_versions().await?))
+}
+
+/// The first stable version in a list sorted newest first
+fn select_latest_stable(versions: Vec<FabricArtifactVersion>) -> Option<FabricArtifactVersion> {
+    versions.into_iter().find(|version| version.stable)
+}
+
+/// Whether a stable Minecraft version is supported by Fabric
+pub async fn is_fabric_supported(minecraft_version: &str) -> Result<bool> {
+    Ok(get_fabric_supported_versions()
+        .await?
+        .iter()
+        .any(|version| version.version == minecraft_version && version.stable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_supported_versions() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/v2/versions/game")
+            .with_body(
+                r#"[
+                    { "version": "23w31a", "stable": false },
+                    { "version": "1.20.1", "stable": true }
+                ]"#,
+            )
+            .create_async()
+            .await;
+        let versions = fetch_fabric_supported_versions(&server.url())
+            .await
+            .unwrap();
+        assert_eq!(
+            versions[1],
+            FabricSupportedVersion {
+                version: "1.20.1".to_string(),
+                stable: true,
+            }
+        );
+        assert!(!versions[0].stable);
+    }
+
+    #[tokio::test]
+    async fn test_loader_versions() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/v2/versions/loader")
+            .with_body(include_str!("../../../mock/fabric/loader.json"))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v2/versions/yarn/1.20.1")
+            .with_body(include_str!("../../../mock/fabric/yarn-1.20.1.json"))
+            .create_async()
+            .await;
+
+        let loaders = fetch_loader_versions(&server.url()).await.unwrap();
+        assert_eq!(loaders.len(), 4);
+        assert!(!loaders[0].stable);
+        let latest = select_latest_stable(loaders).unwrap();
+        assert_eq!(latest.version, "0.14.21");
+        assert_eq!(latest.maven, "net.fabricmc:fabric-loader:0.14.21");
+
+        let yarns = fetch_yarn_versions(&server.url(), "1.20.1").await.unwrap();
+        assert_eq!(yarns[0].game_version.as_deref(), Some("1.20.1"));
+        assert_eq!(yarns[0].version, "1.20.1+build.10");
+        assert!(yarns[0].stable);
+        assert!(!yarns[1].stable);
+    }
+}
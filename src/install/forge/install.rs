@@ -19,32 +19,36 @@
 use std::{
     fs::File,
     io::{self, Read},
-    path::PathBuf,
-    str::FromStr,
+    path::{Path, PathBuf},
+    sync::Arc,
     thread,
     time::Duration,
 };
 
-use anyhow::Result;
-use reqwest::Response;
 use zip::ZipArchive;
 
 use crate::{
-    core::{folder::MinecraftLocation, version::LibraryDownload},
+    core::{
+        folder::MinecraftLocation,
+        task::{Reporter, TaskReporter},
+        version::{LibraryDownload, VersionResolveError},
+    },
     install::forge::{
         install_profile::{InstallProfile, InstallProfileLegacy},
         legacy_install::install_legacy_forge_from_zip,
         new_install::unpack_forge_installer,
     },
+    install::InstallError,
     utils::{
-        download::{download, Download},
+        download::{download_cancellable, Download},
         unzip::filter_entries,
     },
+    Result,
 };
 
 use super::*;
 
-const DEFAULT_FORGE_MAVEN: &str = "http://files.minecraftforge.net/maven";
+const DEFAULT_FORGE_MAVEN: &str = crate::core::maven::FORGE_MAVEN_URL;
 
 // todo: 使用 Steve-xmh/forge-install-bootstrapper 修复新版forge安装
 
@@ -52,8 +56,8 @@ async fn download_forge_installer(
     forge_version: &str,
     required_version: RequiredVersion,
     minecraft: &MinecraftLocation,
-    _options: &Option<InstallForgeOptions>,
-) -> Result<(String, Response)> {
+    options: &Option<InstallForgeOptions>,
+) -> Result<String> {
     let path = if let Some(installer) = &required_version.installer {
         String::from(&installer.path)
     } else {
@@ -84,13 +88,12 @@ async fn download_forge_installer(
         .to_str()
         .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?
         .to_string();
-    let response = download(Download {
-        url: library.url,
-        file: file_path.clone(),
-        sha1: None,
-    })
-    .await;
-    Ok((file_path, response?))
+    let cancel = options
+        .as_ref()
+        .and_then(|options| options.cancel.clone())
+        .unwrap_or_default();
+    download_cancellable(Download::new(library.url, file_path.clone()), &cancel).await?;
+    Ok(file_path)
 }
 
 async fn walk_forge_installer_entries<R: Read + io::Seek>(
@@ -140,14 +143,29 @@ async fn walk_forge_installer_entries<R: Read + io::Seek>(
     }
 }
 
+/// Install forge, the task `install forge` is reported to `reporter`, with the sub-tasks
+/// `download installer` and `unpack installer`.
 pub async fn install_forge(
     version: RequiredVersion,
     minecraft: MinecraftLocation,
     options: Option<InstallForgeOptions>,
+    reporter: Option<Arc<dyn Reporter>>,
+) -> Result<()> {
+    let task = TaskReporter::new(reporter, "install forge", 2);
+    task.finish(install_forge_reported(version, minecraft, options, &task).await)
+}
+
+async fn install_forge_reported(
+    version: RequiredVersion,
+    minecraft: MinecraftLocation,
+    options: Option<InstallForgeOptions>,
+    task: &TaskReporter,
 ) -> Result<()> {
     let mcversion: Vec<_> = version.mcversion.split(".").collect();
     let minor = *mcversion.get(1).unwrap();
-    let minor_version = minor.parse::<i32>()?;
+    let minor_version = minor
+        .parse::<i32>()
+        .map_err(|_| VersionResolveError::InvalidVersionId(version.mcversion.clone()))?;
 
     let forge_version = if minor_version >= 7 && minor_version <= 8 {
         format!(
@@ -158,18 +176,37 @@ pub async fn install_forge(
         format!("{}-{}", version.mcversion, version.version)
     };
 
-    let (installer_jar_path, _installer_jar) =
-        download_forge_installer(&forge_version, version, &minecraft, &options).await?;
+    let step = task.child("download installer", 1);
+    let installer_jar_path =
+        step.finish(download_forge_installer(&forge_version, version, &minecraft, &options).await)?;
+    task.progress(1, 2, 0);
+    let step = task.child("unpack installer", 1);
+    let result = unpack_installer(&installer_jar_path, &forge_version, minecraft, options).await;
+    step.finish(result)?;
+    task.progress(2, 2, 0);
+    Ok(())
+}
+
+async fn unpack_installer(
+    installer_jar_path: &str,
+    forge_version: &str,
+    minecraft: MinecraftLocation,
+    options: Option<InstallForgeOptions>,
+) -> Result<()> {
     println!("{}", installer_jar_path);
     thread::sleep(Duration::from_secs(1));
-    let installer_jar = ZipArchive::new(File::open(&installer_jar_path)?)?;
+    let archive_error = InstallError::archive(Path::new(installer_jar_path));
+    let installer_jar = ZipArchive::new(File::open(installer_jar_path)?).map_err(archive_error)?;
 
-    let entries = walk_forge_installer_entries(installer_jar, &forge_version).await;
-    let mut installer_jar = ZipArchive::new(File::open(&installer_jar_path)?)?;
+    let entries = walk_forge_installer_entries(installer_jar, forge_version).await;
+    let mut installer_jar = ZipArchive::new(File::open(installer_jar_path)?)
+        .map_err(InstallError::archive(Path::new(installer_jar_path)))?;
 
     let install_profile_json = match &entries.install_profile_json {
         None => panic!("Bad forge installer jar!"),
-        Some(data) => String::from_utf8(data.content.clone())?,
+        Some(data) => String::from_utf8(data.content.clone()).map_err(|_| {
+            InstallError::invalid("forge installer", "install_profile.json is not utf-8")
+        })?,
     };
     println!("{}", install_profile_json);
     let forge_type = if let Some(_) = &entries.install_profile_json {
@@ -185,20 +222,22 @@ pub async fn install_forge(
     };
     match forge_type {
         ForgeType::New => {
-            let profile: InstallProfile = serde_json::from_str(&install_profile_json)?;
+            let profile: InstallProfile = serde_json::from_str(&install_profile_json)
+                .map_err(InstallError::json("install_profile.json"))?;
             let _version_id = unpack_forge_installer(
                 &mut installer_jar,
                 entries,
-                &forge_version,
+                &forge_version.to_string(),
                 minecraft,
-                PathBuf::from_str((&installer_jar_path).as_ref())?,
+                PathBuf::from(installer_jar_path),
                 profile,
                 options,
             )
             .await;
         }
         ForgeType::Legacy => {
-            let profile: InstallProfileLegacy = serde_json::from_str(&install_profile_json)?;
+            let profile: InstallProfileLegacy = serde_json::from_str(&install_profile_json)
+                .map_err(InstallError::json("install_profile.json"))?;
             let entries = ForgeLegacyInstallerEntriesPatten {
                 install_profile_json: entries
                     .install_profile_json
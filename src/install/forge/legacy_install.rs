@@ -16,10 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use anyhow::Result;
 use tokio::fs::{self, create_dir_all};
 
-use crate::core::{folder::MinecraftLocation, version::LibraryInfo};
+use crate::core::{
+    folder::MinecraftLocation,
+    version::{check_inheritance, LibraryInfo},
+};
+use crate::{install::InstallError, Result};
 
 use super::{*, install_profile::InstallProfileLegacy};
 
@@ -37,6 +40,7 @@ pub(super) async fn install_legacy_forge_from_zip(
             inherits_from: None,
             version_id: None,
             java: None,
+            cancel: None,
         },
     };
     let mut version_json = profile.version_info.unwrap();
@@ -47,6 +51,9 @@ pub(super) async fn install_legacy_forge_from_zip(
         None => version_json.inherits_from,
         Some(inherits_from) => Some(inherits_from),
     };
+    if let Some(inherits_from) = &version_json.inherits_from {
+        check_inheritance(&minecraft, &version_json.id, inherits_from)?;
+    }
 
     let root_path = minecraft.get_version_root(&version_json.id);
     let version_json_path = root_path.join(format!("{}.json", version_json.id));
@@ -57,18 +64,15 @@ pub(super) async fn install_legacy_forge_from_zip(
     let library = version_json.libraries.clone().unwrap();
     let library = library
         .iter()
-        .find(|l| {
-            l["name"]
-                .as_str()
-                .unwrap()
-                .starts_with("net.minecraftforge:forge")
-        })
-        .unwrap();
-    let library = LibraryInfo::from_value(library);
+        .find_map(|l| l.name().filter(|name| name.starts_with("net.minecraftforge:forge")))
+        .ok_or_else(|| {
+            InstallError::invalid(&version_json.id, "forge is not found in the libraries")
+        })?;
+    let library = LibraryInfo::from_name(library)?;
 
     fs::write(
         version_json_path,
-        serde_json::to_string_pretty(&version_json)?,
+        serde_json::to_string_pretty(&version_json).map_err(std::io::Error::from)?,
     )
         .await
         ?;
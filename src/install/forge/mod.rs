@@ -18,6 +18,7 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::task::CancelHandle;
 use crate::utils::unzip::Entry;
 
 pub mod install;
@@ -180,6 +181,10 @@ pub struct InstallForgeOptions {
 
     /// New forge (>=1.13) require java to install. Can be a executor or java executable path.
     pub java: Option<String>,
+
+    /// Cancel the install, the download of the installer is aborted
+    #[serde(skip)]
+    pub cancel: Option<CancelHandle>,
 }
 
 pub enum ForgeType {
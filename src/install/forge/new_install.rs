@@ -22,14 +22,18 @@ use std::{
     path::PathBuf,
 };
 
-use anyhow::Result;
 use serde_json::Value;
 use tokio::fs::{self, create_dir_all};
 use zip::ZipArchive;
 
 use crate::{
-    core::{folder::MinecraftLocation, version::Version},
+    core::{
+        folder::MinecraftLocation,
+        version::{check_inheritance, Version},
+    },
+    install::InstallError,
     utils::unzip::decompression_files,
+    Result,
 };
 
 use super::{
@@ -47,8 +51,10 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
     options: Option<InstallForgeOptions>,
 ) -> Result<String> {
     let version_json_raw = entries.version_json.unwrap().content;
+    let version_json_raw = String::from_utf8(version_json_raw)
+        .map_err(|_| InstallError::invalid("forge installer", "version.json is not utf-8"))?;
     let mut version_json: Value =
-        serde_json::from_str((&String::from_utf8(version_json_raw)?).as_ref())?;
+        serde_json::from_str(&version_json_raw).map_err(InstallError::json("version.json"))?;
 
     //  apply override for inheritsFrom
     if let Some(options) = options {
@@ -59,6 +65,12 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
             version_json["inheritsFrom"] = Value::String(inherits_from);
         }
     }
+    if let (Some(id), Some(inherits_from)) = (
+        version_json["id"].as_str(),
+        version_json["inheritsFrom"].as_str(),
+    ) {
+        check_inheritance(&minecraft, id, inherits_from)?;
+    }
 
     //   resolve all the required paths
     let root_path = minecraft.root.clone();
@@ -174,12 +186,13 @@ pub(super) async fn unpack_forge_installer<R: Read + io::Seek>(
     }
 
     create_dir_all(install_json_path.parent().unwrap()).await?;
-    fs::write(install_json_path, serde_json::to_string_pretty(&profile)?).await?;
+    let install_json = serde_json::to_string_pretty(&profile).map_err(io::Error::from)?;
+    fs::write(install_json_path, install_json).await?;
 
     create_dir_all(version_json_path.parent().unwrap()).await?;
     fs::write(
         version_json_path,
-        serde_json::to_string_pretty(&version_json)?,
+        serde_json::to_string_pretty(&version_json).map_err(io::Error::from)?,
     )
         .await?;
 
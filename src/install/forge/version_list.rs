@@ -16,10 +16,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::Result;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ForgeVersionListItem {
     pub _id: String,
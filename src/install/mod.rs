@@ -16,92 +16,139 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use anyhow::Result;
-use reqwest::Url;
+use std::{
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    str::FromStr,
+    sync::Arc,
+};
+
 use serde_json::Value;
-use tokio::io::AsyncWriteExt;
 
 use crate::core::version::ResolvedLibrary;
 use crate::{
     core::{
+        download_source::DownloadSource,
         folder::{get_path, MinecraftLocation},
-        task::TaskEventListeners,
-        version::{self, AssetIndex, AssetIndexObject, ResolvedVersion, VersionManifest},
+        task::{CancelHandle, Reporter, TaskEventListeners, TaskReporter},
+        version::{
+            self, AssetIndex, AssetIndexObject, LibraryDownload, ResolvedVersion, VersionInfo,
+            VersionManifest,
+        },
         PlatformInfo,
     },
-    utils::download::{download_files, Download},
+    utils::{
+        download::{
+            download_all_reported, download_cancellable, download_files, download_verified,
+            Checksum, Download, DownloadError,
+        },
+        sha1::calculate_sha1_from_read,
+    },
+    Error, Result,
 };
 
+use profile::ProfileStage;
+
+pub mod cursepack;
 pub mod fabric;
 pub mod forge;
+pub mod modpack;
+pub mod mrpack;
+pub mod multimc;
 pub mod optifine;
+pub mod profile;
 pub mod quilt;
+pub mod server;
+pub mod verify;
 
+/// The downloads of the libraries from their own urls rewritten by `source`, the libraries
+/// replaced by `LwjglFix` or resolved by their names have no sha1 and aren't verified
 pub(crate) fn generate_libraries_download_list(
     libraries: Vec<ResolvedLibrary>,
     minecraft_location: &MinecraftLocation,
+    source: &DownloadSource,
 ) -> Vec<Download<String>> {
     libraries
-        .clone()
         .into_iter()
-        .map(|library| Download {
-            url: if library.is_native_library {
-                println!("find native library url: {}", &library.download_info.url);
-                library.download_info.url
-            } else {
-                format!(
-                    "https://download.mcbbs.net/maven/{}",
-                    library.download_info.path
-                )
-            },
-            file: minecraft_location
-                .libraries
-                .join(library.download_info.path)
-                .to_str()
-                .unwrap()
-                .to_string(),
-            sha1: Some(library.download_info.sha1),
+        .map(|library| {
+            let LibraryDownload {
+                sha1,
+                size,
+                url,
+                path,
+            } = library.download_info;
+            let file = minecraft_location
+                .get_library_by_path(path)
+                .to_string_lossy()
+                .to_string();
+            Download {
+                sha1: (!sha1.is_empty()).then_some(sha1),
+                size: (size > 0).then_some(size),
+                ..Download::new(source.rewrite_url(&url), file)
+            }
         })
         .collect()
 }
 
+/// Parse the objects of an asset index downloaded from `url`
+fn parse_asset_objects(raw: &str, url: &str) -> Result<AssetIndexObject> {
+    let json: Value = serde_json::from_str(raw).map_err(InstallError::json(url))?;
+    let objects =
+        serde_json::from_value(json["objects"].clone()).map_err(InstallError::json(url))?;
+    Ok(objects)
+}
+
+/// Check the downloaded asset index against its sha1 before trusting the objects in it
+fn verify_asset_index(asset_index: &AssetIndex, raw: &str) -> Result<()> {
+    let Some(sha1) = &asset_index.sha1 else {
+        return Ok(());
+    };
+    let checksum = Checksum::Sha1(sha1.clone());
+    let hash = checksum.calculate(&mut raw.as_bytes())?;
+    if !checksum.matches(&hash) {
+        return Err(DownloadError::HashMismatch {
+            url: asset_index.url.clone(),
+            algorithm: checksum.algorithm(),
+            expected: sha1.clone(),
+            actual: hash,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// The downloads of the asset objects and the asset index, from `source`
 pub(crate) async fn generate_assets_download_list(
     asset_index: AssetIndex,
     minecraft_location: &MinecraftLocation,
+    source: &DownloadSource,
 ) -> Result<Vec<Download<String>>> {
-    let asset_index_url = Url::parse((&asset_index.url).as_ref())?;
-    let asset_index_raw = reqwest::get(asset_index_url).await?.text().await?;
-    let asset_index_json: Value = serde_json::from_str((&asset_index_raw).as_ref())?;
-    let asset_index_object: AssetIndexObject =
-        serde_json::from_value(asset_index_json["objects"].clone())?;
-    let mut assets: Vec<_> = asset_index_object
-        .into_iter()
-        .map(|obj| Download {
-            url: format!(
-                "https://download.mcbbs.net/assets/{}/{}",
-                &obj.1.hash[0..2],
-                obj.1.hash
-            ),
-            file: minecraft_location
-                .assets
-                .join("objects")
-                .join(&obj.1.hash[0..2])
-                .join(&obj.1.hash)
-                .to_str()
-                .unwrap()
-                .to_string(),
-            sha1: Some(obj.1.hash),
-        })
-        .collect();
+    let asset_index_url = source.rewrite_url(&asset_index.url);
+    let asset_index_raw = reqwest::get(&asset_index_url)
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    verify_asset_index(&asset_index, &asset_index_raw)?;
+    let objects = parse_asset_objects(&asset_index_raw, &asset_index_url)?;
+    let mut assets: Vec<_> =
+        generate_asset_objects_download_list(&objects, minecraft_location, source)
+            .into_iter()
+            .map(|task| Download {
+                sha1: task.sha1,
+                checksum: task.checksum,
+                size: task.size,
+                retry: task.retry,
+                ..Download::new(task.url, get_path(&task.file))
+            })
+            .collect();
     assets.push(Download {
-        url: asset_index.url,
-        file: get_path(
-            &minecraft_location
-                .assets
-                .join("indexes")
-                .join(format!("{}.json", asset_index.id)),
-        ),
-        sha1: None,
+        sha1: asset_index.sha1,
+        ..Download::new(
+            source.rewrite_url(&asset_index.url),
+            get_path(&minecraft_location.get_asset_index(&asset_index.id)),
+        )
+        .with_size(asset_index.size)
     });
     Ok(assets)
 }
@@ -115,77 +162,401 @@ pub async fn install_dependencies(
     minecraft_location: MinecraftLocation,
     listeners: TaskEventListeners,
 ) -> Result<()> {
+    let source = DownloadSource::default();
     let mut download_list = Vec::new();
 
     download_list.extend(generate_libraries_download_list(
         version.libraries,
         &minecraft_location,
+        &source,
     ));
     download_list.extend(
-        generate_assets_download_list(version.asset_index.unwrap(), &minecraft_location).await?,
+        generate_assets_download_list(version.asset_index.unwrap(), &minecraft_location, &source)
+            .await?,
     );
     download_files(download_list, listeners, false).await?;
 
     Ok(())
 }
 
-/// Quick game install
+/// Install the vanilla version json and client jar of a version.
 ///
-/// Note: This operation does not ensure that all files are complete,
-/// please execute the [`install_dependencies`] function before the first startup
-pub async fn install(
-    version_id: &str,
-    minecraft_location: MinecraftLocation,
-    listeners: TaskEventListeners,
+/// The version json is verified with `VersionInfo.sha1` and saved to `versions/<id>/<id>.json`,
+/// then the client jar is downloaded to `versions/<id>/<id>.jar`. Libraries and assets are not
+/// installed, mod loader installers start from this.
+pub async fn install_version(
+    version_info: &VersionInfo,
+    minecraft: &MinecraftLocation,
+    source: DownloadSource,
+    cancel: &CancelHandle,
 ) -> Result<()> {
-    let platform = PlatformInfo::new().await;
-
-    let versions = VersionManifest::new().await?.versions;
-    let version_metadata: Vec<_> = versions
-        .into_iter()
-        .filter(|v| v.id == version_id)
-        .collect();
-    if version_metadata.len() != 1 {
-        panic!("Bad version manifest!!!")
-    };
-    let version_metadata = version_metadata.get(0).unwrap();
-
-    let version_json_raw = reqwest::get(version_metadata.url.clone())
+    cancel.check()?;
+    let id = &version_info.id;
+    let version_json_url = source.rewrite_url(&version_info.url);
+    let version_json_raw = reqwest::get(&version_json_url)
         .await?
+        .error_for_status()?
         .text()
         .await?;
-    let version = version::Version::from_str(&version_json_raw)?
-        .parse(&minecraft_location, &platform)
-        .await?;
-    let id = &version.id;
+    let version_json_sha1 = calculate_sha1_from_read(&mut version_json_raw.as_bytes());
+    if version_json_sha1 != version_info.sha1 {
+        return Err(DownloadError::HashMismatch {
+            url: version_json_url,
+            algorithm: "sha1",
+            expected: version_info.sha1.clone(),
+            actual: version_json_sha1,
+        }
+        .into());
+    }
+    let version = version::Version::from_str(&version_json_raw)?;
 
-    let version_json_path = minecraft_location.versions.join(format!("{id}/{id}.json"));
+    let version_json_path = minecraft.get_version_json(id);
     tokio::fs::create_dir_all(version_json_path.parent().unwrap()).await?;
-    let mut file = tokio::fs::File::create(&version_json_path).await?;
-    file.write_all(version_json_raw.as_bytes()).await?;
-
-    let mut download_list = vec![];
-    download_list.push(Download {
-        url: format!("https://download.mcbbs.net/version/{version_id}/client"),
-        file: get_path(&minecraft_location.versions.join(format!("{id}/{id}.jar"))),
-        sha1: None,
-    });
+    tokio::fs::write(&version_json_path, &version_json_raw).await?;
 
-    download_list.extend(generate_libraries_download_list(
-        version.libraries,
-        &minecraft_location,
-    ));
-    download_list.extend(
-        generate_assets_download_list(
-            version
-                .asset_index
-                .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?,
-            &minecraft_location,
+    let client = version
+        .downloads
+        .as_ref()
+        .and_then(|downloads| downloads.get("client"))
+        .ok_or_else(|| InstallError::MissingDownload {
+            id: id.clone(),
+            download: "client".to_string(),
+        })?;
+    let client_jar_path = minecraft.get_version_jar(id, None);
+    let client_jar_url = source.rewrite_url(&client.url);
+    download_cancellable(
+        Download::new(client_jar_url.clone(), client_jar_path.clone())
+            .with_sha1(client.sha1.clone())
+            .with_size(client.size),
+        cancel,
+    )
+    .await?;
+    let client_jar_sha1 = calculate_sha1_from_read(&mut std::fs::File::open(&client_jar_path)?);
+    if client_jar_sha1 != client.sha1 {
+        tokio::fs::remove_file(&client_jar_path).await?;
+        return Err(DownloadError::HashMismatch {
+            url: client_jar_url,
+            algorithm: "sha1",
+            expected: client.sha1.clone(),
+            actual: client_jar_sha1,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Why an installation failed, see [`Error::Install`](crate::Error::Install)
+#[derive(Debug, thiserror::Error)]
+pub enum InstallError {
+    /// The version is not in the version manifest
+    #[error("version {id} is not found in the version manifest")]
+    VersionNotFound { id: String },
+    /// The version json has no download of this name, like `client` or `server`
+    #[error("version {id} does not provide a {download} jar")]
+    MissingDownload { id: String, download: String },
+    /// The mod loader, like `NeoForge`
+    #[error("installing {0} is not supported yet")]
+    UnsupportedLoader(String),
+    /// A modpack, an instance or a mod reference lacks something required or has an invalid
+    /// value
+    #[error("invalid {location}: {reason}")]
+    Invalid {
+        /// The path of the file, or the url or id of what is invalid
+        location: String,
+        reason: String,
+    },
+    /// A mod of a profile can't be found on its platform
+    #[error("{0} is not found")]
+    ModNotFound(String),
+    /// The destination is used already, like the game directory of a modpack
+    #[error("{} already exists", path.display())]
+    AlreadyExists { path: PathBuf },
+    /// A zip archive can't be read
+    #[error("can't read the archive {}: {source}", path.display())]
+    Archive {
+        path: PathBuf,
+        source: zip::result::ZipError,
+    },
+    /// A json can't be parsed
+    #[error("can't parse {location}: {source}")]
+    InvalidJson {
+        /// The path or the url of the json
+        location: String,
+        source: serde_json::Error,
+    },
+    /// An installer process failed, like the server installer of a mod loader
+    #[error("{program} exited with {status}")]
+    ProcessFailed { program: String, status: ExitStatus },
+    /// Some files failed to install, the others are installed
+    #[error("{} files failed to install:{}", failures.len(), list_failures(failures))]
+    PartialInstall {
+        /// The urls of the failed files and the reasons
+        failures: Vec<(String, String)>,
+    },
+    /// A stage of [`install_profile`](profile::install_profile) failed, the stages before it are
+    /// kept
+    #[error("failed to install the {stage}: {source}")]
+    Profile {
+        stage: ProfileStage,
+        source: Box<Error>,
+    },
+}
+
+impl InstallError {
+    /// An [`InstallError::Invalid`] of what is at `location`
+    pub(crate) fn invalid(location: impl std::fmt::Display, reason: impl Into<String>) -> Self {
+        InstallError::Invalid {
+            location: location.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Map the error of reading the zip archive at `path`, used as
+    /// `.map_err(InstallError::archive(path))`
+    pub(crate) fn archive(path: &Path) -> impl FnOnce(zip::result::ZipError) -> Self + '_ {
+        move |source| InstallError::Archive {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// Map the error of parsing the json at `location`, a path or an url
+    pub(crate) fn json(location: impl std::fmt::Display) -> impl FnOnce(serde_json::Error) -> Self {
+        move |source| InstallError::InvalidJson {
+            location: location.to_string(),
+            source,
+        }
+    }
+}
+
+fn list_failures(failures: &[(String, String)]) -> String {
+    failures
+        .iter()
+        .map(|(url, reason)| format!("\n{url}: {reason}"))
+        .collect()
+}
+
+/// The default number of concurrent downloads
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+/// Build the downloads of asset objects, the files are saved to `assets/objects/<xx>/<hash>`
+fn generate_asset_objects_download_list(
+    objects: &AssetIndexObject,
+    minecraft: &MinecraftLocation,
+    source: &DownloadSource,
+) -> Vec<Download<PathBuf>> {
+    let mut tasks: Vec<_> = objects
+        .values()
+        .map(|object| {
+            Download::new(object.url(source), minecraft.get_asset_object(&object.hash))
+                .with_sha1(object.hash.clone())
+                .with_size(object.size as u64)
+        })
+        .collect();
+    // the same object may be referenced by multiple names
+    tasks.sort_by(|a, b| a.file.cmp(&b.file));
+    tasks.dedup_by(|a, b| a.file == b.file);
+    tasks
+}
+
+/// Download the asset objects of an asset index, at most `concurrency` files at the same time.
+///
+/// The objects already present with the right hash are skipped. The failed objects are returned
+/// as [`InstallError::PartialInstall`]. The task `install assets` is reported to `reporter`, the
+/// objects are the files of its sub-task `download assets`.
+pub async fn download_assets(
+    objects: &AssetIndexObject,
+    minecraft: &MinecraftLocation,
+    source: &DownloadSource,
+    concurrency: usize,
+    cancel: &CancelHandle,
+    reporter: Option<Arc<dyn Reporter>>,
+) -> Result<()> {
+    let task = TaskReporter::new(reporter, "install assets", 1);
+    let tasks = generate_asset_objects_download_list(objects, minecraft, source);
+    let result = async {
+        let failures =
+            download_all_reported(tasks, concurrency, cancel, &task, "download assets").await?;
+        if !failures.is_empty() {
+            return Err(InstallError::PartialInstall { failures }.into());
+        }
+        Ok(())
+    }
+    .await;
+    task.finish(result)
+}
+
+/// Build the download of the client log4j config, the file is saved to `assets/log_configs/<id>`
+fn generate_log_config_download(
+    version: &ResolvedVersion,
+    minecraft: &MinecraftLocation,
+    source: &DownloadSource,
+) -> Option<Download<PathBuf>> {
+    let logging = version.logging.as_ref()?.get("client")?;
+    Some(
+        Download::new(
+            source.rewrite_url(&logging.file.url),
+            minecraft.get_log_config(&logging.file.id),
         )
-        .await?,
+        .with_sha1(logging.file.sha1.clone())
+        .with_size(logging.file.size),
+    )
+}
+
+/// Download the client log4j config of a version, returns the path of the config, or `None` if
+/// the version doesn't have one.
+///
+/// The launch arguments only use the config when the file exists. The configs provided by
+/// Mojang already disable message lookups, so old versions are protected from the JNDI exploit.
+pub async fn install_log_config(
+    version: &ResolvedVersion,
+    minecraft: &MinecraftLocation,
+    source: &DownloadSource,
+    cancel: &CancelHandle,
+) -> Result<Option<PathBuf>> {
+    let Some(task) = generate_log_config_download(version, minecraft, source) else {
+        return Ok(None);
+    };
+    let file = task.file.clone();
+    download_verified(task, cancel).await?;
+    Ok(Some(file))
+}
+
+/// Extract the native libraries to the natives directory, `META-INF` is skipped
+fn extract_natives(
+    libraries: &[ResolvedLibrary],
+    minecraft: &MinecraftLocation,
+    natives_directory: &Path,
+) -> Result<()> {
+    for library in libraries.iter().filter(|lib| lib.is_native_library) {
+        let path = minecraft.get_library_by_path(&library.download_info.path);
+        let file = std::fs::File::open(&path)?;
+        let mut archive = zip::ZipArchive::new(file).map_err(InstallError::archive(&path))?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(InstallError::archive(&path))?;
+            let Some(name) = entry.enclosed_name().map(|name| name.to_path_buf()) else {
+                continue;
+            };
+            if entry.is_dir() || name.starts_with("META-INF") {
+                continue;
+            }
+            let path = natives_directory.join(name);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::io::copy(&mut entry, &mut std::fs::File::create(path)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Install everything required to launch a version.
+///
+/// It installs the version json and client jar by [`install_version`], then downloads libraries,
+/// assets and logging config concurrently, and extracts native libraries. The task
+/// `install <id>` is reported to `reporter`, with the sub-tasks `install version`,
+/// `download files` and `extract natives`.
+///
+/// The files failed to download don't stop the others, they are returned as
+/// [`InstallError::PartialInstall`] at the end. When `cancel` is cancelled, the install stops and
+/// returns [`Error::Cancelled`], the files already installed are kept.
+pub async fn install_complete(
+    version_info: &VersionInfo,
+    minecraft: &MinecraftLocation,
+    source: DownloadSource,
+    reporter: Option<Arc<dyn Reporter>>,
+    cancel: &CancelHandle,
+) -> Result<ResolvedVersion> {
+    let task = TaskReporter::new(reporter, &format!("install {}", version_info.id), 3);
+    let result = install_complete_reported(version_info, minecraft, source, &task, cancel).await;
+    task.finish(result)
+}
+
+async fn install_complete_reported(
+    version_info: &VersionInfo,
+    minecraft: &MinecraftLocation,
+    source: DownloadSource,
+    task: &TaskReporter,
+    cancel: &CancelHandle,
+) -> Result<ResolvedVersion> {
+    let step = task.child("install version", 1);
+    step.finish(install_version(version_info, minecraft, source.clone(), cancel).await)?;
+    task.progress(1, 3, 0);
+    let platform = PlatformInfo::new().await;
+    let version = version::Version::from_versions_folder(minecraft.clone(), &version_info.id)?
+        .parse(minecraft, &platform)
+        .await?;
+
+    let mut tasks = Vec::new();
+    tasks.extend(
+        version
+            .libraries
+            .iter()
+            .filter(|library| !library.download_info.url.is_empty())
+            .map(|library| library.to_download(minecraft, &source)),
     );
+    if let Some(asset_index) = &version.asset_index {
+        cancel.check()?;
+        let asset_index_url = source.rewrite_url(&asset_index.url);
+        let asset_index_raw = reqwest::get(&asset_index_url)
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        verify_asset_index(asset_index, &asset_index_raw)?;
+        let asset_index_path = minecraft.get_asset_index(&asset_index.id);
+        tokio::fs::create_dir_all(asset_index_path.parent().unwrap()).await?;
+        tokio::fs::write(&asset_index_path, &asset_index_raw).await?;
+        let objects = parse_asset_objects(&asset_index_raw, &asset_index_url)?;
+        tasks.extend(generate_asset_objects_download_list(
+            &objects, minecraft, &source,
+        ));
+    }
+    tasks.extend(generate_log_config_download(&version, minecraft, &source));
+    // the same library may be listed more than once
+    tasks.sort_by(|a, b| a.file.cmp(&b.file));
+    tasks.dedup_by(|a, b| a.file == b.file);
 
-    download_files(download_list, listeners, false).await?;
+    let failures =
+        download_all_reported(tasks, DEFAULT_CONCURRENCY, cancel, task, "download files").await?;
+    if !failures.is_empty() {
+        return Err(InstallError::PartialInstall { failures }.into());
+    }
+    task.progress(2, 3, 0);
+
+    let step = task.child("extract natives", 1);
+    step.finish(extract_natives(
+        &version.libraries,
+        minecraft,
+        &minecraft.get_natives_root(&version.id),
+    ))?;
+    task.progress(3, 3, 0);
+    Ok(version)
+}
+
+/// Quick game install
+///
+/// Install a version in the official version manifest by [`install_complete`], from the
+/// default download source.
+pub async fn install(
+    version_id: &str,
+    minecraft_location: MinecraftLocation,
+    reporter: Option<Arc<dyn Reporter>>,
+) -> Result<()> {
+    let version_info = VersionManifest::new()
+        .await?
+        .versions
+        .into_iter()
+        .find(|version| version.id == version_id)
+        .ok_or_else(|| InstallError::VersionNotFound {
+            id: version_id.to_string(),
+        })?;
+    install_complete(
+        &version_info,
+        &minecraft_location,
+        DownloadSource::default(),
+        reporter,
+        &CancelHandle::new(),
+    )
+    .await?;
     Ok(())
 }
 
@@ -220,3 +591,485 @@ pub async fn install(
 //         install(&version_id, MinecraftLocation::new("test"), listeners).await.unwrap();
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Map};
+
+    use crate::core::task::{ChannelReporter, TaskEvent};
+
+    use super::*;
+
+    #[test]
+    fn test_libraries_download_list() {
+        let minecraft = MinecraftLocation::new("/minecraft");
+        let library = |url: &str, path: &str, sha1: &str| ResolvedLibrary {
+            download_info: LibraryDownload {
+                sha1: sha1.to_string(),
+                size: 0,
+                url: url.to_string(),
+                path: path.to_string(),
+            },
+            is_native_library: false,
+        };
+        let downloads = generate_libraries_download_list(
+            vec![
+                library(
+                    "https://libraries.minecraft.net/com/google/code/gson/gson/2.10/gson-2.10.jar",
+                    "com/google/code/gson/gson/2.10/gson-2.10.jar",
+                    "dd9b193aef96e973d5a11ab13cd17430c2e4306b",
+                ),
+                library(
+                    "https://maven.fabricmc.net/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar",
+                    "net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar",
+                    "",
+                ),
+            ],
+            &minecraft,
+            &DownloadSource::bmclapi(),
+        );
+        assert_eq!(
+            downloads[0].url,
+            "https://bmclapi2.bangbang93.com/maven/com/google/code/gson/gson/2.10/gson-2.10.jar"
+        );
+        assert_eq!(
+            PathBuf::from(&downloads[0].file),
+            minecraft.get_library_by_path("com/google/code/gson/gson/2.10/gson-2.10.jar")
+        );
+        assert!(downloads[0].sha1.is_some());
+        assert_eq!(
+            downloads[1].url,
+            "https://maven.fabricmc.net/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar"
+        );
+        assert_eq!(downloads[1].sha1, None);
+        assert_eq!(downloads[1].size, None);
+    }
+
+    #[tokio::test]
+    async fn test_install_version() {
+        let mut server = mockito::Server::new_async().await;
+        let client_jar = b"client jar";
+        let version_json = json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "downloads": {
+                "client": {
+                    "sha1": calculate_sha1_from_read(&mut client_jar.as_slice()),
+                    "size": client_jar.len(),
+                    "url": "https://piston-data.mojang.com/v1/objects/abc/client.jar"
+                }
+            }
+        })
+        .to_string();
+        let manifest = json!({
+            "latest": { "release": "1.20.1", "snapshot": "1.20.1" },
+            "versions": [{
+                "id": "1.20.1",
+                "type": "release",
+                "url": "https://piston-meta.mojang.com/v1/packages/def/1.20.1.json",
+                "time": "2023-06-12T13:25:51+00:00",
+                "releaseTime": "2023-06-12T13:25:51+00:00",
+                "sha1": calculate_sha1_from_read(&mut version_json.as_bytes()),
+                "complianceLevel": 1
+            }]
+        });
+        server
+            .mock("GET", "/mc/game/version_manifest_v2.json")
+            .with_body(manifest.to_string())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/packages/def/1.20.1.json")
+            .with_body(&version_json)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/objects/abc/client.jar")
+            .with_body(client_jar)
+            .create_async()
+            .await;
+
+        let source = DownloadSource {
+            meta_base_url: server.url(),
+            ..DownloadSource::official()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let version_info = VersionManifest::new_with_source(&source)
+            .await
+            .unwrap()
+            .versions
+            .remove(0);
+        install_version(&version_info, &minecraft, source, &CancelHandle::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(minecraft.get_version_json("1.20.1")).unwrap(),
+            version_json
+        );
+        assert_eq!(
+            std::fs::read(minecraft.get_version_jar("1.20.1", None)).unwrap(),
+            client_jar
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_assets() {
+        let mut server = mockito::Server::new_async().await;
+        let mut objects = AssetIndexObject::new();
+        let mut mocks = Vec::new();
+        for i in 0..50 {
+            let content = format!("asset {i}");
+            let hash = calculate_sha1_from_read(&mut content.as_bytes());
+            mocks.push(
+                server
+                    .mock("GET", format!("/{}/{hash}", &hash[0..2]).as_str())
+                    .with_body(&content)
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+            objects.insert(
+                format!("minecraft/sounds/{i}.ogg"),
+                version::AssetIndexObjectInfo {
+                    hash,
+                    size: content.len() as u32,
+                },
+            );
+        }
+        let source = DownloadSource {
+            assets_base_url: server.url(),
+            ..DownloadSource::official()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+
+        download_assets(&objects, &minecraft, &source, 8, &CancelHandle::new(), None)
+            .await
+            .unwrap();
+        for object in objects.values() {
+            let path = minecraft.get_asset_object(&object.hash);
+            assert_eq!(
+                calculate_sha1_from_read(&mut std::fs::File::open(path).unwrap()),
+                object.hash
+            );
+        }
+
+        download_assets(&objects, &minecraft, &source, 8, &CancelHandle::new(), None)
+            .await
+            .unwrap();
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_asset_index_mismatch() {
+        let mut server = mockito::Server::new_async().await;
+        let raw = json!({ "objects": {} }).to_string();
+        server
+            .mock("GET", "/5.json")
+            .with_body(&raw)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let source = DownloadSource::default();
+        let asset_index = AssetIndex {
+            sha1: Some("0".repeat(40)),
+            size: raw.len() as u64,
+            url: format!("{}/5.json", server.url()),
+            id: "5".to_string(),
+            total_size: 0,
+            extra: Map::new(),
+        };
+
+        let err = generate_assets_download_list(asset_index.clone(), &minecraft, &source)
+            .await
+            .unwrap_err();
+        let Error::Download(DownloadError::HashMismatch { url, expected, .. }) = &err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(url, &asset_index.url);
+        assert_eq!(expected, &"0".repeat(40));
+
+        let sha1 = calculate_sha1_from_read(&mut raw.as_bytes());
+        let assets = generate_assets_download_list(
+            AssetIndex {
+                sha1: Some(sha1.clone()),
+                ..asset_index
+            },
+            &minecraft,
+            &source,
+        )
+        .await
+        .unwrap();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].sha1, Some(sha1));
+    }
+
+    #[tokio::test]
+    async fn test_install_log_config() {
+        let mut server = mockito::Server::new_async().await;
+        let config = b"<Configuration />";
+        let mock = server
+            .mock("GET", "/client-1.12.xml")
+            .with_body(config)
+            .expect(1)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let version_json = json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "assetIndex": { "id": "5", "sha1": "", "size": 1, "totalSize": 1, "url": "" },
+            "downloads": { "client": { "sha1": "", "size": 0, "url": "" } },
+            "logging": {
+                "client": {
+                    "argument": "-Dlog4j.configurationFile=${path}",
+                    "file": {
+                        "id": "client-1.12.xml",
+                        "sha1": calculate_sha1_from_read(&mut config.as_slice()),
+                        "size": config.len(),
+                        "url": format!("{}/client-1.12.xml", server.url())
+                    },
+                    "type": "log4j2-xml"
+                }
+            }
+        })
+        .to_string();
+        let path = minecraft.get_version_json("1.20.1");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, version_json).unwrap();
+        let version = version::Version::from_versions_folder(minecraft.clone(), "1.20.1")
+            .unwrap()
+            .parse(&minecraft, &PlatformInfo::new().await)
+            .await
+            .unwrap();
+
+        let source = DownloadSource::default();
+        let file = install_log_config(&version, &minecraft, &source, &CancelHandle::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(file, minecraft.get_log_config("client-1.12.xml"));
+        assert_eq!(std::fs::read(&file).unwrap(), config);
+        // the verified file is not downloaded again
+        install_log_config(&version, &minecraft, &source, &CancelHandle::new())
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires network"]
+    async fn test_install_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let version_info = VersionManifest::new()
+            .await
+            .unwrap()
+            .versions
+            .into_iter()
+            .find(|version| version.id == "1.19.4")
+            .unwrap();
+        let (reporter, mut receiver) = ChannelReporter::new();
+        let version = install_complete(
+            &version_info,
+            &minecraft,
+            DownloadSource::default(),
+            Some(Arc::new(reporter)),
+            &CancelHandle::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(minecraft.get_version_jar("1.19.4", None).is_file());
+        assert!(std::fs::read_dir(minecraft.assets.join("objects"))
+            .unwrap()
+            .next()
+            .is_some());
+        assert!(std::fs::read_dir(minecraft.get_natives_root(&version.id))
+            .unwrap()
+            .next()
+            .is_some());
+        let mut last = None;
+        while let Ok(event) = receiver.try_recv() {
+            if let TaskEvent::Progress { done, total, .. } = event {
+                last = Some((done, total));
+            }
+        }
+        let (done, total) = last.unwrap();
+        assert_eq!(done, total);
+    }
+
+    /// Render an event as `<sub-task path>: <event>`
+    fn render_event(event: TaskEvent) -> String {
+        let mut path = vec![];
+        let mut event = event;
+        while let TaskEvent::ChildTask { name, event: child } = event {
+            path.push(name);
+            event = *child;
+        }
+        let event = match event {
+            TaskEvent::Started { total, .. } => format!("started {total}"),
+            TaskEvent::Progress { done, total, .. } => format!("progress {done}/{total}"),
+            TaskEvent::Warning { message } => format!("warning {message}"),
+            TaskEvent::Completed => "completed".to_string(),
+            TaskEvent::Failed { error } => format!("failed {error}"),
+            TaskEvent::ChildTask { .. } => unreachable!(),
+        };
+        format!("{}: {event}", path.join("/"))
+    }
+
+    #[tokio::test]
+    async fn test_install_complete_events() {
+        let mut server = mockito::Server::new_async().await;
+        let client_jar = b"client jar";
+        let library = b"lib";
+        let object = b"sound";
+        let object_hash = calculate_sha1_from_read(&mut object.as_slice());
+        let asset_index = json!({
+            "objects": {
+                "minecraft/sounds/click.ogg": { "hash": object_hash, "size": object.len() }
+            }
+        })
+        .to_string();
+        let version_json = json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "assetIndex": {
+                "id": "5",
+                "sha1": calculate_sha1_from_read(&mut asset_index.as_bytes()),
+                "size": asset_index.len(),
+                "totalSize": object.len(),
+                "url": "https://piston-meta.mojang.com/v1/packages/ghi/5.json"
+            },
+            "downloads": {
+                "client": {
+                    "sha1": calculate_sha1_from_read(&mut client_jar.as_slice()),
+                    "size": client_jar.len(),
+                    "url": "https://piston-data.mojang.com/v1/objects/abc/client.jar"
+                }
+            },
+            "libraries": [{
+                "name": "com.example:lib:1.0",
+                "downloads": {
+                    "artifact": {
+                        "path": "com/example/lib/1.0/lib-1.0.jar",
+                        "sha1": calculate_sha1_from_read(&mut library.as_slice()),
+                        "size": library.len(),
+                        "url": "https://libraries.minecraft.net/com/example/lib/1.0/lib-1.0.jar"
+                    }
+                }
+            }]
+        })
+        .to_string();
+        server
+            .mock("GET", "/v1/packages/def/1.20.1.json")
+            .with_body(&version_json)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/objects/abc/client.jar")
+            .with_body(client_jar)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/packages/ghi/5.json")
+            .with_body(&asset_index)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/com/example/lib/1.0/lib-1.0.jar")
+            .with_body(library)
+            .create_async()
+            .await;
+        server
+            .mock(
+                "GET",
+                format!("/{}/{object_hash}", &object_hash[0..2]).as_str(),
+            )
+            .with_body(object)
+            .create_async()
+            .await;
+        let source = DownloadSource {
+            meta_base_url: server.url(),
+            libraries_base_url: server.url(),
+            assets_base_url: server.url(),
+        };
+        let version_info: VersionInfo = serde_json::from_value(json!({
+            "id": "1.20.1",
+            "type": "release",
+            "url": "https://piston-meta.mojang.com/v1/packages/def/1.20.1.json",
+            "time": "2023-06-12T13:25:51+00:00",
+            "releaseTime": "2023-06-12T13:25:51+00:00",
+            "sha1": calculate_sha1_from_read(&mut version_json.as_bytes()),
+            "complianceLevel": 1
+        }))
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+
+        let (reporter, mut receiver) = ChannelReporter::new();
+        install_complete(
+            &version_info,
+            &minecraft,
+            source,
+            Some(Arc::new(reporter)),
+            &CancelHandle::new(),
+        )
+        .await
+        .unwrap();
+
+        let mut events = vec![];
+        let mut files = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            if let TaskEvent::Started { name, .. } = &event {
+                assert_eq!(name, "install 1.20.1");
+            }
+            let event = render_event(event);
+            if event.starts_with("download files/") {
+                files.push(event);
+            } else if event.starts_with("download files: progress") {
+                // the order of the two files is not fixed
+                if event.ends_with("8/8") {
+                    events.push(event);
+                }
+            } else {
+                events.push(event);
+            }
+        }
+        assert_eq!(
+            events,
+            [
+                ": started 3",
+                "install version: started 1",
+                "install version: completed",
+                ": progress 1/3",
+                // weighted by the sizes of the library and the asset object
+                "download files: started 8",
+                "download files: progress 8/8",
+                "download files: completed",
+                ": progress 2/3",
+                "extract natives: started 1",
+                "extract natives: completed",
+                ": progress 3/3",
+                ": completed",
+            ]
+        );
+        let library_url = format!("{}/com/example/lib/1.0/lib-1.0.jar", server.url());
+        let object_url = format!("{}/{}/{object_hash}", server.url(), &object_hash[0..2]);
+        files.sort();
+        let mut expected = vec![
+            format!("download files/{library_url}: started 3"),
+            format!("download files/{library_url}: completed"),
+            format!("download files/{object_url}: started 5"),
+            format!("download files/{object_url}: completed"),
+        ];
+        expected.sort();
+        assert_eq!(files, expected);
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The parts shared by the modpack formats, see [`super::mrpack`] and [`super::cursepack`].
+
+use std::{
+    fs::File,
+    path::{Component, Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    core::folder::MinecraftLocation,
+    install::{
+        fabric::{install::install_fabric, FabricLoaderArtifact},
+        forge::{install::install_forge, RequiredVersion},
+        quilt::install::install_quilt_version,
+        InstallError,
+    },
+    instance::{InstanceLoader, LoaderType},
+    profile::GameProfile,
+    Result,
+};
+
+/// The folder under the minecraft root containing the game directories of the modpacks
+pub const MODPACKS_DIR: &str = "modpacks";
+
+#[derive(Debug, Clone)]
+pub struct InstalledPack {
+    pub profile: GameProfile,
+
+    /// The number of files in `mods/` after the install
+    pub mods_count: usize,
+}
+
+/// The version id created by the installer of the loader
+pub(crate) fn loader_version_id(
+    minecraft_version: &str,
+    loader: Option<&InstanceLoader>,
+) -> String {
+    match loader {
+        None => minecraft_version.to_string(),
+        Some(InstanceLoader { r#type, version }) => match r#type {
+            LoaderType::Fabric => format!("{minecraft_version}-fabric{version}"),
+            LoaderType::Quilt => format!("quilt-loader-{version}-{minecraft_version}"),
+            LoaderType::Forge => format!("{minecraft_version}-forge-{version}"),
+            LoaderType::NeoForge => format!("neoforge-{version}"),
+        },
+    }
+}
+
+/// Split a version id created by [`loader_version_id`] into the minecraft version and the loader
+pub(crate) fn parse_loader_version_id(
+    version_id: &str,
+) -> Result<(String, Option<InstanceLoader>)> {
+    let loader = |r#type, version: &str| {
+        Some(InstanceLoader {
+            r#type,
+            version: version.to_string(),
+        })
+    };
+    if version_id.starts_with("neoforge-") {
+        return Err(InstallError::invalid(version_id, "unknown minecraft version").into());
+    }
+    if let Some(rest) = version_id.strip_prefix("quilt-loader-") {
+        let (version, minecraft_version) = rest
+            .rsplit_once('-')
+            .ok_or_else(|| InstallError::invalid(version_id, "not a quilt version id"))?;
+        return Ok((
+            minecraft_version.to_string(),
+            loader(LoaderType::Quilt, version),
+        ));
+    }
+    if let Some((minecraft_version, version)) = version_id.split_once("-fabric") {
+        return Ok((
+            minecraft_version.to_string(),
+            loader(LoaderType::Fabric, version),
+        ));
+    }
+    if let Some((minecraft_version, version)) = version_id.split_once("-forge") {
+        // like `1.20.1-forge-47.1.0` or `1.12.2-forge1.12.2-14.23.5.2859`
+        let version = version.trim_start_matches('-');
+        let version = version
+            .strip_prefix(&format!("{minecraft_version}-"))
+            .unwrap_or(version);
+        return Ok((
+            minecraft_version.to_string(),
+            loader(LoaderType::Forge, version),
+        ));
+    }
+    Ok((version_id.to_string(), None))
+}
+
+/// Install the mod loader, returns the installed version id
+pub(crate) async fn install_loader(
+    minecraft_version: &str,
+    loader: &InstanceLoader,
+    minecraft: &MinecraftLocation,
+) -> Result<String> {
+    let version_id = loader_version_id(minecraft_version, Some(loader));
+    match loader.r#type {
+        LoaderType::Forge => {
+            let version = RequiredVersion {
+                installer: None,
+                mcversion: minecraft_version.to_string(),
+                version: loader.version.clone(),
+            };
+            install_forge(version, minecraft.clone(), None, None).await?;
+            Ok(version_id)
+        }
+        LoaderType::Fabric => {
+            let artifact = FabricLoaderArtifact::new(minecraft_version, &loader.version).await;
+            install_fabric(artifact, minecraft.clone(), None, None).await
+        }
+        LoaderType::Quilt => {
+            install_quilt_version(minecraft_version, &loader.version, minecraft.clone(), None)
+                .await
+        }
+        LoaderType::NeoForge => Err(InstallError::UnsupportedLoader("NeoForge".to_string()).into()),
+    }
+}
+
+/// Make sure the path in the modpack stays in the game directory
+pub(crate) fn safe_relative_path(path: &str) -> Result<PathBuf> {
+    let path = Path::new(path);
+    if path.as_os_str().is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(InstallError::invalid(path.display(), "unsafe path in the modpack").into());
+    }
+    Ok(path.to_path_buf())
+}
+
+/// The game directory of a new modpack, `<minecraft>/modpacks/<name>`, it must not exist yet
+pub(crate) fn modpack_game_dir(minecraft: &MinecraftLocation, name: &str) -> Result<PathBuf> {
+    let game_dir = minecraft
+        .root
+        .join(MODPACKS_DIR)
+        .join(profile_dir_name(name));
+    if game_dir.exists() {
+        return Err(InstallError::AlreadyExists { path: game_dir }.into());
+    }
+    Ok(game_dir)
+}
+
+fn profile_dir_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|char| match char {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            char => char,
+        })
+        .collect();
+    match name.trim().trim_matches('.') {
+        "" => "modpack".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Extract the files under `prefix/` in the archive at `archive_path` to the game directory
+pub(crate) fn extract_overrides(
+    archive: &mut ZipArchive<File>,
+    archive_path: &Path,
+    prefix: &str,
+    game_dir: &Path,
+) -> Result<()> {
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(InstallError::archive(archive_path))?;
+        let Some(name) = entry.enclosed_name().map(|name| name.to_path_buf()) else {
+            continue;
+        };
+        let Ok(relative) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let path = game_dir.join(relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::io::copy(&mut entry, &mut File::create(&path)?)?;
+    }
+    Ok(())
+}
+
+/// The number of files in `mods/`
+pub(crate) fn count_mods(game_dir: &Path) -> usize {
+    match std::fs::read_dir(game_dir.join("mods")) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .count(),
+        Err(_) => 0,
+    }
+}
@@ -0,0 +1,371 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use glob::{MatchOptions, Pattern};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    core::version::Version, install::InstallError, mod_platform::modrinth::ModrinthClient,
+    utils::sha1::calculate_sha1_from_read, Result,
+};
+
+use super::{MrpackFile, MrpackIndex, MrpackMetadata, MRPACK_INDEX_FILE, MRPACK_OVERRIDES};
+
+/// Folders whose files are looked up on Modrinth
+pub const MODRINTH_HOSTED_FOLDERS: [&str; 3] = ["mods", "resourcepacks", "shaderpacks"];
+
+/// Files excluded from the modpack by default
+pub const DEFAULT_EXCLUDES: [&str; 4] = ["saves/**", "logs/**", "crash-reports/**", "options.txt"];
+
+pub(crate) const USER_AGENT: &str = concat!(
+    "Broken-Deer/magical-launcher-core/",
+    env!("CARGO_PKG_VERSION")
+);
+
+#[derive(Debug, Clone)]
+pub struct MrpackExportOptions {
+    /// Glob patterns relative to the profile directory, only the matched files are exported.
+    ///
+    /// Default is `["**"]`
+    pub include: Vec<String>,
+
+    /// Glob patterns relative to the profile directory, the matched files are not exported.
+    ///
+    /// Default is [`DEFAULT_EXCLUDES`]
+    pub exclude: Vec<String>,
+
+    /// The version json of the instance, default is `<profile_dir>/<profile dir name>.json`
+    pub version_json: Option<PathBuf>,
+
+    /// The client used to look up files on Modrinth
+    pub client: Option<ModrinthClient>,
+}
+
+impl Default for MrpackExportOptions {
+    fn default() -> Self {
+        Self {
+            include: vec!["**".to_string()],
+            exclude: DEFAULT_EXCLUDES.iter().map(|v| v.to_string()).collect(),
+            version_json: None,
+            client: None,
+        }
+    }
+}
+
+/// Export an instance as a Modrinth modpack.
+///
+/// Files in `mods/`, `resourcepacks/` and `shaderpacks/` which can be found on Modrinth are listed
+/// in `files`, all the other files are stored in `overrides/`. The game version and mod loader
+/// are read from the version json of the instance.
+pub async fn export_mrpack(
+    profile_dir: &Path,
+    metadata: MrpackMetadata,
+    output_path: &Path,
+    options: Option<MrpackExportOptions>,
+) -> Result<()> {
+    let options = options.unwrap_or_default();
+    let profile_name = profile_dir
+        .file_name()
+        .ok_or_else(|| InstallError::invalid(profile_dir.display(), "not a profile directory"))?
+        .to_string_lossy()
+        .to_string();
+    let version_json_path = options
+        .version_json
+        .clone()
+        .unwrap_or(profile_dir.join(format!("{profile_name}.json")));
+    let version = Version::from_str(&std::fs::read_to_string(&version_json_path)?)?;
+    let dependencies = resolve_dependencies(&version);
+
+    let include = compile_patterns(&options.include)?;
+    let exclude = compile_patterns(&options.exclude)?;
+    // the files of the version itself are not a part of the instance
+    let version_files = [
+        format!("{profile_name}.json"),
+        format!("{profile_name}.jar"),
+        format!("{profile_name}-natives"),
+    ];
+    let match_options = MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let files = list_files(profile_dir)?
+        .into_iter()
+        .filter(|path| {
+            let first = path.split('/').next().unwrap_or_default();
+            !version_files.iter().any(|file| file == first)
+        })
+        .filter(|path| {
+            include
+                .iter()
+                .any(|pattern| pattern.matches_with(path, match_options))
+                && !exclude
+                    .iter()
+                    .any(|pattern| pattern.matches_with(path, match_options))
+        })
+        .collect::<Vec<_>>();
+
+    let mut hashes = BTreeMap::new();
+    for path in &files {
+        let first = path.split('/').next().unwrap_or_default();
+        if MODRINTH_HOSTED_FOLDERS.contains(&first) {
+            let sha1 = calculate_sha1_from_read(&mut File::open(profile_dir.join(path))?);
+            hashes.insert(path.clone(), sha1);
+        }
+    }
+    let versions = if hashes.is_empty() {
+        Default::default()
+    } else {
+        let client = options
+            .client
+            .clone()
+            .unwrap_or(ModrinthClient::new(USER_AGENT));
+        client
+            .get_versions_from_hashes(&hashes.values().cloned().collect::<Vec<_>>(), "sha1")
+            .await?
+    };
+
+    let mut index = MrpackIndex {
+        format_version: 1,
+        game: "minecraft".to_string(),
+        version_id: metadata.version_id,
+        name: metadata.name,
+        summary: metadata.summary,
+        files: vec![],
+        dependencies,
+    };
+    let mut overrides = Vec::new();
+    for path in files {
+        let file = hashes
+            .get(&path)
+            .and_then(|sha1| Some((sha1, versions.get(sha1)?)))
+            .and_then(|(sha1, version)| version.file_by_hash(sha1));
+        match file {
+            Some(file) => index.files.push(MrpackFile {
+                path,
+                hashes: file.hashes.clone().into_iter().collect(),
+                env: None,
+                downloads: vec![file.url.clone()],
+                file_size: file.size,
+            }),
+            None => overrides.push(path),
+        }
+    }
+
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer = ZipWriter::new(File::create(output_path)?);
+    let file_options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    writer
+        .start_file(MRPACK_INDEX_FILE, file_options)
+        .map_err(InstallError::archive(output_path))?;
+    let index = serde_json::to_string_pretty(&index).map_err(std::io::Error::from)?;
+    writer.write_all(index.as_bytes())?;
+    for path in overrides {
+        writer
+            .start_file(format!("{MRPACK_OVERRIDES}/{path}"), file_options)
+            .map_err(InstallError::archive(output_path))?;
+        std::io::copy(&mut File::open(profile_dir.join(&path))?, &mut writer)?;
+    }
+    writer
+        .finish()
+        .map_err(InstallError::archive(output_path))?;
+    Ok(())
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+    Ok(patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern)
+                .map_err(|err| InstallError::invalid(format!("pattern {pattern}"), err.msg))
+        })
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// List all files in the directory recursively, as `/` separated relative paths
+fn list_files(dir: &Path) -> Result<Vec<String>> {
+    let mut result = Vec::new();
+    let mut folders = vec![dir.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        for entry in std::fs::read_dir(folder)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                folders.push(path);
+            } else {
+                let relative = path
+                    .strip_prefix(dir)
+                    .expect("the listed files are under the directory")
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                result.push(relative);
+            }
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Get the game version and mod loader from the version json
+fn resolve_dependencies(version: &Version) -> BTreeMap<String, String> {
+    let mut dependencies = BTreeMap::new();
+    let minecraft = version
+        .inherits_from
+        .clone()
+        .or(version.client_version.clone())
+        .unwrap_or(version.id.clone());
+    let libraries = version.libraries.clone().unwrap_or_default();
+    for library in libraries {
+        let Some(name) = library.name() else {
+            continue;
+        };
+        let parts = name.split(':').collect::<Vec<_>>();
+        let [group, artifact, library_version, ..] = parts.as_slice() else {
+            continue;
+        };
+        let (key, loader_version) = match (*group, *artifact) {
+            ("net.fabricmc", "fabric-loader") => ("fabric-loader", library_version.to_string()),
+            ("org.quiltmc", "quilt-loader") => ("quilt-loader", library_version.to_string()),
+            ("net.neoforged", "neoforge") => ("neoforge", library_version.to_string()),
+            // like `1.20.1-47.1.0`
+            ("net.minecraftforge", "forge" | "fmlloader") => (
+                "forge",
+                library_version
+                    .strip_prefix(&format!("{minecraft}-"))
+                    .unwrap_or(library_version)
+                    .to_string(),
+            ),
+            _ => continue,
+        };
+        dependencies.insert(key.to_string(), loader_version);
+    }
+    dependencies.insert("minecraft".to_string(), minecraft);
+    dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_export_mrpack() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = dir.path().join("1.20.1-fabric");
+        for folder in ["mods", "config", "saves/world", "1.20.1-fabric-natives"] {
+            fs::create_dir_all(profile.join(folder)).unwrap();
+        }
+        fs::write(
+            profile.join("1.20.1-fabric.json"),
+            serde_json::json!({
+                "id": "1.20.1-fabric",
+                "inheritsFrom": "1.20.1",
+                "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+                "libraries": [
+                    { "name": "net.fabricmc:sponge-mixin:0.12.5+mixin.0.8.5" },
+                    { "name": "net.fabricmc:fabric-loader:0.14.21" }
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(profile.join("1.20.1-fabric-natives/lwjgl.so"), b"").unwrap();
+        fs::write(profile.join("mods/fabric-api.jar"), b"fabric api").unwrap();
+        fs::write(profile.join("mods/custom.jar"), b"custom").unwrap();
+        fs::write(profile.join("config/sodium.json"), b"{}").unwrap();
+        fs::write(profile.join("saves/world/level.dat"), b"").unwrap();
+        fs::write(profile.join("options.txt"), b"").unwrap();
+
+        let sha1 = calculate_sha1_from_read(&mut b"fabric api".as_slice());
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/v2/version_files")
+            .with_body(
+                serde_json::json!({
+                    sha1.clone(): {
+                        "id": "IZskON6d",
+                        "project_id": "P7dR8mSH",
+                        "name": "Fabric API",
+                        "version_number": "0.87.0+1.20.1",
+                        "game_versions": ["1.20.1"],
+                        "loaders": ["fabric"],
+                        "files": [{
+                            "hashes": { "sha1": sha1, "sha512": "abc" },
+                            "url": "https://cdn.modrinth.com/data/P7dR8mSH/versions/IZskON6d/fabric-api.jar",
+                            "filename": "fabric-api.jar",
+                            "primary": true,
+                            "size": 10
+                        }]
+                    }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let output = dir.path().join("pack.mrpack");
+        export_mrpack(
+            &profile,
+            MrpackMetadata {
+                name: "Test".to_string(),
+                version_id: "1.0.0".to_string(),
+                summary: None,
+            },
+            &output,
+            Some(MrpackExportOptions {
+                client: Some(ModrinthClient::with_base_url("test", &server.url())),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        let mut names = archive.file_names().map(String::from).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "modrinth.index.json",
+                "overrides/config/sodium.json",
+                "overrides/mods/custom.jar"
+            ]
+        );
+        let index: MrpackIndex =
+            serde_json::from_reader(archive.by_name(MRPACK_INDEX_FILE).unwrap()).unwrap();
+        assert_eq!(index.dependencies["minecraft"], "1.20.1");
+        assert_eq!(index.dependencies["fabric-loader"], "0.14.21");
+        assert_eq!(index.files.len(), 1);
+        assert_eq!(index.files[0].path, "mods/fabric-api.jar");
+        assert_eq!(index.files[0].hashes["sha512"], "abc");
+        assert_eq!(index.files[0].file_size, 10);
+    }
+}
@@ -0,0 +1,418 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use futures::StreamExt;
+use zip::ZipArchive;
+
+use crate::{
+    core::{folder::MinecraftLocation, task::CancelHandle},
+    install::{
+        modpack::{
+            count_mods, extract_overrides, loader_version_id, modpack_game_dir, safe_relative_path,
+            InstalledPack,
+        },
+        InstallError, DEFAULT_CONCURRENCY,
+    },
+    profile::GameProfile,
+    utils::download::{download_from_urls, Download, DownloadError},
+    Result,
+};
+
+use super::{MrpackIndex, MRPACK_CLIENT_OVERRIDES, MRPACK_INDEX_FILE, MRPACK_OVERRIDES};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MrpackProgress {
+    /// The files listed in the index are downloading
+    Downloading {
+        completed: usize,
+        total: usize,
+    },
+    ExtractingOverrides,
+}
+
+/// Install a Modrinth modpack.
+///
+/// The files listed in `modrinth.index.json` are downloaded and verified with sha1, the urls of
+/// a file are tried in order. Then `overrides/` and `client-overrides/` are extracted. The game
+/// directory of the modpack is `<minecraft>/modpacks/<name>`, it must not exist yet.
+///
+/// The returned profile uses the version id created by the installer of the mod loader, like
+/// `1.20.1-fabric0.14.21`, the version and the loader are not installed here.
+///
+/// * `java` - The java executable used by the profile, empty to use the default one
+/// * `cancel` - Abort the downloads, [`Error::Cancelled`](crate::Error::Cancelled) is returned
+///   and nothing is extracted
+pub async fn install_mrpack(
+    mrpack_path: &Path,
+    minecraft: &MinecraftLocation,
+    java: &str,
+    cancel: &CancelHandle,
+    on_progress: impl Fn(MrpackProgress),
+) -> Result<InstalledPack> {
+    let mut archive =
+        ZipArchive::new(File::open(mrpack_path)?).map_err(InstallError::archive(mrpack_path))?;
+    let index_file = archive
+        .by_name(MRPACK_INDEX_FILE)
+        .map_err(InstallError::archive(mrpack_path))?;
+    let index: MrpackIndex = serde_json::from_reader(index_file).map_err(InstallError::json(
+        mrpack_path.join(MRPACK_INDEX_FILE).display(),
+    ))?;
+    let minecraft_version = index.minecraft_version().ok_or_else(|| {
+        InstallError::invalid(
+            mrpack_path.display(),
+            "the minecraft version is not specified",
+        )
+    })?;
+    let version_id = loader_version_id(minecraft_version, index.loader().as_ref());
+
+    let game_dir = modpack_game_dir(minecraft, &index.name)?;
+
+    let mut tasks = Vec::new();
+    for file in &index.files {
+        if file
+            .env
+            .as_ref()
+            .is_some_and(|env| env.client == "unsupported")
+        {
+            continue;
+        }
+        if file.downloads.is_empty() {
+            let reason = format!("{} has no download url", file.path);
+            return Err(InstallError::invalid(mrpack_path.display(), reason).into());
+        }
+        let task = Download {
+            sha1: file.hashes.get("sha1").cloned(),
+            ..Download::new(
+                String::new(),
+                game_dir.join(safe_relative_path(&file.path)?),
+            )
+            .with_size(file.file_size)
+        };
+        tasks.push((task, &file.downloads));
+    }
+
+    // every url listed for a file is tried before it fails
+    let total = tasks.len();
+    let mut completed = 0;
+    let mut failures = Vec::new();
+    let mut results = futures::stream::iter(tasks)
+        .map(|(task, urls)| async move {
+            let result = download_from_urls(task, urls, false, cancel).await;
+            (urls, result)
+        })
+        .buffer_unordered(DEFAULT_CONCURRENCY);
+    while let Some((urls, result)) = results.next().await {
+        if let Err(err) = result {
+            if matches!(err, DownloadError::Cancelled) {
+                continue;
+            }
+            failures.push((urls[0].clone(), err.to_string()));
+        }
+        completed += 1;
+        on_progress(MrpackProgress::Downloading { completed, total });
+    }
+    cancel.check()?;
+    if !failures.is_empty() {
+        return Err(InstallError::PartialInstall { failures }.into());
+    }
+
+    on_progress(MrpackProgress::ExtractingOverrides);
+    std::fs::create_dir_all(&game_dir)?;
+    extract_overrides(&mut archive, mrpack_path, MRPACK_OVERRIDES, &game_dir)?;
+    extract_overrides(
+        &mut archive,
+        mrpack_path,
+        MRPACK_CLIENT_OVERRIDES,
+        &game_dir,
+    )?;
+
+    let mods_count = count_mods(&game_dir);
+    let mut profile = GameProfile::new(&index.name, &version_id, &game_dir);
+    if !java.is_empty() {
+        profile.java_path = Some(PathBuf::from(java));
+    }
+    Ok(InstalledPack {
+        profile,
+        mods_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use serde_json::json;
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{
+        install::mrpack::{
+            export::{export_mrpack, MrpackExportOptions},
+            MrpackMetadata,
+        },
+        mod_platform::modrinth::ModrinthClient,
+        utils::sha1::calculate_sha1_from_read,
+        Error,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = dir.path().join("1.20.1-fabric");
+        for folder in ["mods", "config"] {
+            fs::create_dir_all(profile.join(folder)).unwrap();
+        }
+        fs::write(
+            profile.join("1.20.1-fabric.json"),
+            json!({
+                "id": "1.20.1-fabric",
+                "inheritsFrom": "1.20.1",
+                "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+                "libraries": [{ "name": "net.fabricmc:fabric-loader:0.14.21" }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(profile.join("mods/fabric-api.jar"), b"fabric api").unwrap();
+        fs::write(profile.join("mods/custom.jar"), b"custom").unwrap();
+        fs::write(profile.join("config/sodium.json"), b"{}").unwrap();
+
+        let sha1 = calculate_sha1_from_read(&mut b"fabric api".as_slice());
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/v2/version_files")
+            .with_body(
+                json!({
+                    sha1.clone(): {
+                        "id": "IZskON6d",
+                        "project_id": "P7dR8mSH",
+                        "name": "Fabric API",
+                        "version_number": "0.87.0+1.20.1",
+                        "game_versions": ["1.20.1"],
+                        "loaders": ["fabric"],
+                        "files": [{
+                            "hashes": { "sha1": sha1, "sha512": "abc" },
+                            "url": format!("{}/data/P7dR8mSH/fabric-api.jar", server.url()),
+                            "filename": "fabric-api.jar",
+                            "primary": true,
+                            "size": 10
+                        }]
+                    }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let download = server
+            .mock("GET", "/data/P7dR8mSH/fabric-api.jar")
+            .with_body(b"fabric api")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let output = dir.path().join("pack.mrpack");
+        export_mrpack(
+            &profile,
+            MrpackMetadata {
+                name: "Test: Pack".to_string(),
+                version_id: "1.0.0".to_string(),
+                summary: None,
+            },
+            &output,
+            Some(MrpackExportOptions {
+                client: Some(ModrinthClient::with_base_url("test", &server.url())),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        let progress = std::sync::Mutex::new(Vec::new());
+        let pack = install_mrpack(
+            &output,
+            &minecraft,
+            "/usr/bin/java",
+            &CancelHandle::default(),
+            |event| progress.lock().unwrap().push(event),
+        )
+        .await
+        .unwrap();
+        download.assert_async().await;
+
+        let game_dir = minecraft.root.join("modpacks/Test_ Pack");
+        assert_eq!(pack.mods_count, 2);
+        assert_eq!(pack.profile.name, "Test: Pack");
+        assert_eq!(pack.profile.version_id, "1.20.1-fabric0.14.21");
+        assert_eq!(pack.profile.game_dir, game_dir);
+        assert_eq!(pack.profile.java_path, Some(PathBuf::from("/usr/bin/java")));
+        assert_eq!(
+            fs::read(game_dir.join("mods/fabric-api.jar")).unwrap(),
+            b"fabric api"
+        );
+        assert_eq!(
+            fs::read(game_dir.join("mods/custom.jar")).unwrap(),
+            b"custom"
+        );
+        assert_eq!(
+            fs::read(game_dir.join("config/sodium.json")).unwrap(),
+            b"{}"
+        );
+        assert_eq!(
+            progress.into_inner().unwrap(),
+            vec![
+                MrpackProgress::Downloading {
+                    completed: 1,
+                    total: 1
+                },
+                MrpackProgress::ExtractingOverrides
+            ]
+        );
+
+        // the game directory already exists
+        assert!(
+            install_mrpack(&output, &minecraft, "", &CancelHandle::default(), |_| {})
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("evil.mrpack");
+        let mut zip = ZipWriter::new(File::create(&output).unwrap());
+        zip.start_file(MRPACK_INDEX_FILE, FileOptions::default())
+            .unwrap();
+        let index = json!({
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "1.0.0",
+            "name": "Evil",
+            "files": [{
+                "path": "../../evil.jar",
+                "hashes": { "sha1": "" },
+                "downloads": ["https://example.com/evil.jar"],
+                "fileSize": 0
+            }],
+            "dependencies": { "minecraft": "1.20.1" }
+        });
+        zip.write_all(index.to_string().as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        let err = install_mrpack(&output, &minecraft, "", &CancelHandle::default(), |_| {})
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unsafe path"));
+        assert!(!minecraft.root.join("modpacks").exists());
+    }
+
+    /// A pack with a single file downloaded from `downloads`
+    fn write_pack(output: &Path, downloads: &[String]) {
+        let mut zip = ZipWriter::new(File::create(output).unwrap());
+        zip.start_file(MRPACK_INDEX_FILE, FileOptions::default())
+            .unwrap();
+        let index = json!({
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "1.0.0",
+            "name": "Mirrors",
+            "files": [{
+                "path": "mods/a.jar",
+                "hashes": { "sha1": calculate_sha1_from_read(&mut b"content".as_slice()) },
+                "downloads": downloads,
+                "fileSize": 7
+            }],
+            "dependencies": { "minecraft": "1.20.1" }
+        });
+        zip.write_all(index.to_string().as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fallback_urls() {
+        let mut server = mockito::Server::new_async().await;
+        let missing = server
+            .mock("GET", "/missing/a.jar")
+            .with_status(404)
+            .create_async()
+            .await;
+        let jar = server
+            .mock("GET", "/mirror/a.jar")
+            .with_body("content")
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("mirrors.mrpack");
+        let missing_url = format!("{}/missing/a.jar", server.url());
+        write_pack(
+            &output,
+            &[
+                missing_url.clone(),
+                format!("{}/mirror/a.jar", server.url()),
+            ],
+        );
+
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        install_mrpack(&output, &minecraft, "", &CancelHandle::default(), |_| {})
+            .await
+            .unwrap();
+        missing.assert_async().await;
+        jar.assert_async().await;
+        assert_eq!(
+            fs::read(minecraft.root.join("modpacks/Mirrors/mods/a.jar")).unwrap(),
+            b"content"
+        );
+
+        // every url fails
+        let output = dir.path().join("missing.mrpack");
+        write_pack(&output, std::slice::from_ref(&missing_url));
+        let minecraft = MinecraftLocation::new(&dir.path().join("missing"));
+        let err = install_mrpack(&output, &minecraft, "", &CancelHandle::default(), |_| {})
+            .await
+            .unwrap_err();
+        let Error::Install(InstallError::PartialInstall { failures }) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, missing_url);
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("mirrors.mrpack");
+        write_pack(&output, &["https://example.com/a.jar".to_string()]);
+
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        let cancel = CancelHandle::default();
+        cancel.cancel();
+        let err = install_mrpack(&output, &minecraft, "", &cancel, |_| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Cancelled));
+        assert!(!minecraft.root.join("modpacks/Mirrors").exists());
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Modrinth modpacks (`.mrpack`).
+//!
+//! A modpack is a zip file containing `modrinth.index.json`, the files listed in the index are
+//! downloaded from Modrinth, and the other files are stored in `overrides/`.
+//!
+//! See <https://docs.modrinth.com/docs/modpacks/format_definition/> for the format.
+//!
+//! # Example
+//!
+//! Install a modpack:
+//!
+//! ```rust
+//! use std::path::Path;
+//! use mgl_core::core::{folder::MinecraftLocation, task::CancelHandle};
+//! use mgl_core::install::mrpack::install::install_mrpack;
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let cancel = CancelHandle::default();
+//!     let pack = install_mrpack(
+//!         Path::new("my-pack.mrpack"),
+//!         &minecraft,
+//!         "java",
+//!         &cancel,
+//!         |progress| println!("{progress:?}"),
+//!     )
+//!     .await
+//!     .unwrap();
+//!     println!("{} mods are installed to {:?}", pack.mods_count, pack.profile.game_dir);
+//! }
+//! ```
+//!
+//! Export an instance:
+//!
+//! ```rust
+//! use std::path::Path;
+//! use mgl_core::install::mrpack::{export::export_mrpack, MrpackMetadata};
+//!
+//! async fn fn_name() {
+//!     let metadata = MrpackMetadata {
+//!         name: "My Pack".to_string(),
+//!         version_id: "1.0.0".to_string(),
+//!         summary: None,
+//!     };
+//!     export_mrpack(
+//!         Path::new(".minecraft/versions/1.20.1-fabric"),
+//!         metadata,
+//!         Path::new("my-pack.mrpack"),
+//!         None,
+//!     )
+//!     .await
+//!     .unwrap();
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::instance::{InstanceLoader, LoaderType};
+
+pub mod export;
+pub mod install;
+
+/// The name of the index file in the modpack
+pub const MRPACK_INDEX_FILE: &str = "modrinth.index.json";
+
+/// The folder in the modpack containing the files not hosted on Modrinth
+pub const MRPACK_OVERRIDES: &str = "overrides";
+
+/// The folder in the modpack containing the files only used by the client, they are applied after
+/// [`MRPACK_OVERRIDES`]
+pub const MRPACK_CLIENT_OVERRIDES: &str = "client-overrides";
+
+/// The user defined metadata of a modpack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MrpackMetadata {
+    pub name: String,
+    /// The version of the modpack
+    pub version_id: String,
+    pub summary: Option<String>,
+}
+
+/// Corresponds to `modrinth.index.json`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackIndex {
+    /// Always `1`
+    pub format_version: u32,
+    /// Always `minecraft`
+    pub game: String,
+    pub version_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<MrpackFile>,
+    /// `minecraft`, `forge`, `neoforge`, `fabric-loader` or `quilt-loader` to their versions
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl MrpackIndex {
+    pub fn minecraft_version(&self) -> Option<&str> {
+        self.dependencies.get("minecraft").map(String::as_str)
+    }
+
+    /// The mod loader required by the modpack
+    pub fn loader(&self) -> Option<InstanceLoader> {
+        [
+            ("fabric-loader", LoaderType::Fabric),
+            ("quilt-loader", LoaderType::Quilt),
+            ("forge", LoaderType::Forge),
+            ("neoforge", LoaderType::NeoForge),
+        ]
+        .into_iter()
+        .find_map(|(key, r#type)| {
+            self.dependencies.get(key).map(|version| InstanceLoader {
+                r#type,
+                version: version.clone(),
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackFile {
+    /// The destination path relative to the game directory
+    pub path: String,
+    /// `sha1` and `sha512`
+    pub hashes: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<MrpackEnv>,
+    pub downloads: Vec<String>,
+    pub file_size: u64,
+}
+
+/// Whether the file is used on client and server, `required`, `optional` or `unsupported`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MrpackEnv {
+    pub client: String,
+    pub server: String,
+}
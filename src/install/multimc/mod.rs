@@ -0,0 +1,475 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! MultiMC and Prism Launcher instances.
+//!
+//! An instance folder contains `instance.cfg` with the settings, `mmc-pack.json` with the
+//! components (Minecraft and the mod loader), and the game directory `.minecraft`, or
+//! `minecraft` in the instances created by old MultiMC.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::path::Path;
+//! use mgl_core::core::folder::MinecraftLocation;
+//! use mgl_core::install::multimc::{export_to_multimc_instance, import_multimc_instance};
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let instance_dir = Path::new("PrismLauncher/instances/Fabulously Optimized");
+//!     let profile = import_multimc_instance(instance_dir, &minecraft).await.unwrap();
+//!     println!("{} uses {}", profile.name, profile.version_id);
+//!     export_to_multimc_instance(&profile, Path::new("exported")).unwrap();
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        download_source::DownloadSource, folder::MinecraftLocation, task::CancelHandle,
+        version::VersionManifest,
+    },
+    install::InstallError,
+    install::{
+        install_complete,
+        modpack::{install_loader, loader_version_id, parse_loader_version_id},
+    },
+    instance::{copy_dir, InstanceLoader, LoaderType},
+    launch::jvm::MemoryConfig,
+    profile::GameProfile,
+    Result,
+};
+
+/// The components of the instance
+pub const MMC_PACK_FILE: &str = "mmc-pack.json";
+
+/// The settings of the instance
+pub const INSTANCE_CFG_FILE: &str = "instance.cfg";
+
+const MINECRAFT_UID: &str = "net.minecraft";
+const INTERMEDIARY_UID: &str = "net.fabricmc.intermediary";
+const LOADER_UIDS: [(&str, LoaderType); 4] = [
+    ("net.fabricmc.fabric-loader", LoaderType::Fabric),
+    ("org.quiltmc.quilt-loader", LoaderType::Quilt),
+    ("net.minecraftforge", LoaderType::Forge),
+    ("net.neoforged", LoaderType::NeoForge),
+];
+
+/// Corresponds to `mmc-pack.json`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MmcPack {
+    pub components: Vec<MmcComponent>,
+    pub format_version: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MmcComponent {
+    /// Like `net.minecraft` or `net.fabricmc.fabric-loader`
+    pub uid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The resolved version, used when `version` is absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_version: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub important: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dependency_only: bool,
+}
+
+impl MmcComponent {
+    fn new(uid: &str, version: &str) -> Self {
+        Self {
+            uid: uid.to_string(),
+            version: Some(version.to_string()),
+            cached_version: None,
+            important: false,
+            dependency_only: false,
+        }
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref().or(self.cached_version.as_deref())
+    }
+}
+
+impl MmcPack {
+    /// Create the components of a minecraft version and a mod loader
+    pub fn new(minecraft_version: &str, loader: Option<&InstanceLoader>) -> Self {
+        let mut components = vec![MmcComponent {
+            important: true,
+            ..MmcComponent::new(MINECRAFT_UID, minecraft_version)
+        }];
+        if let Some(loader) = loader {
+            if matches!(loader.r#type, LoaderType::Fabric | LoaderType::Quilt) {
+                components.push(MmcComponent {
+                    dependency_only: true,
+                    ..MmcComponent::new(INTERMEDIARY_UID, minecraft_version)
+                });
+            }
+            let (uid, _) = LOADER_UIDS
+                .iter()
+                .find(|(_, r#type)| *r#type == loader.r#type)
+                .unwrap();
+            components.push(MmcComponent::new(uid, &loader.version));
+        }
+        Self {
+            components,
+            format_version: 1,
+        }
+    }
+
+    pub fn minecraft_version(&self) -> Option<&str> {
+        self.components
+            .iter()
+            .find(|component| component.uid == MINECRAFT_UID)
+            .and_then(|component| component.version())
+    }
+
+    /// The mod loader, the first one is used if there are more than one
+    pub fn loader(&self) -> Option<InstanceLoader> {
+        self.components.iter().find_map(|component| {
+            let (_, r#type) = LOADER_UIDS.iter().find(|(uid, _)| *uid == component.uid)?;
+            Some(InstanceLoader {
+                r#type: *r#type,
+                version: component.version()?.to_string(),
+            })
+        })
+    }
+}
+
+/// Parse the `key=value` lines of `instance.cfg`, the sections and comments are ignored
+fn parse_instance_cfg(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with(['[', '#', ';']))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            (key.trim().to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// The game directory of the instance, `.minecraft` unless only `minecraft` exists
+fn instance_game_dir(instance_dir: &Path) -> PathBuf {
+    let legacy = instance_dir.join("minecraft");
+    let game_dir = instance_dir.join(".minecraft");
+    if !game_dir.exists() && legacy.is_dir() {
+        legacy
+    } else {
+        game_dir
+    }
+}
+
+/// Install the vanilla version if its version json doesn't exist
+async fn install_missing_vanilla(
+    minecraft_version: &str,
+    minecraft: &MinecraftLocation,
+) -> Result<()> {
+    if minecraft.get_version_json(minecraft_version).is_file() {
+        return Ok(());
+    }
+    let version_info = VersionManifest::new()
+        .await?
+        .versions
+        .into_iter()
+        .find(|version| version.id == minecraft_version)
+        .ok_or_else(|| InstallError::VersionNotFound {
+            id: minecraft_version.to_string(),
+        })?;
+    install_complete(
+        &version_info,
+        minecraft,
+        DownloadSource::default(),
+        None,
+        &CancelHandle::new(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Import a MultiMC or Prism Launcher instance.
+///
+/// The Minecraft version and the mod loader are read from `mmc-pack.json` and installed to
+/// `minecraft` if they are missing. The profile uses the game directory of the instance in place,
+/// nothing is copied. The name, memory, java path and JVM arguments are read from `instance.cfg`
+/// if they are overridden there.
+pub async fn import_multimc_instance(
+    instance_dir: &Path,
+    minecraft: &MinecraftLocation,
+) -> Result<GameProfile> {
+    let pack_path = instance_dir.join(MMC_PACK_FILE);
+    let pack: MmcPack = serde_json::from_str(&fs::read_to_string(&pack_path)?)
+        .map_err(InstallError::json(pack_path.display()))?;
+    let cfg = match fs::read_to_string(instance_dir.join(INSTANCE_CFG_FILE)) {
+        Ok(content) => parse_instance_cfg(&content),
+        Err(_) => HashMap::new(),
+    };
+    let minecraft_version = pack
+        .minecraft_version()
+        .ok_or_else(|| InstallError::invalid(pack_path.display(), "no Minecraft component"))?;
+    let loader = pack.loader();
+    if loader
+        .as_ref()
+        .is_some_and(|loader| loader.r#type == LoaderType::NeoForge)
+    {
+        return Err(InstallError::UnsupportedLoader("NeoForge".to_string()).into());
+    }
+
+    install_missing_vanilla(minecraft_version, minecraft).await?;
+    let version_id = match &loader {
+        None => minecraft_version.to_string(),
+        Some(loader) => {
+            let version_id = loader_version_id(minecraft_version, Some(loader));
+            if minecraft.get_version_json(&version_id).is_file() {
+                version_id
+            } else {
+                install_loader(minecraft_version, loader, minecraft).await?
+            }
+        }
+    };
+
+    let game_dir = instance_game_dir(instance_dir);
+    fs::create_dir_all(&game_dir)?;
+    let name = match cfg.get("name") {
+        Some(name) if !name.is_empty() => name.clone(),
+        _ => instance_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(version_id.clone()),
+    };
+    let mut profile = GameProfile::new(&name, &version_id, game_dir);
+    let overridden = |key: &str| cfg.get(key).is_some_and(|value| value == "true");
+    if overridden("OverrideMemory") {
+        if let (Some(min_mb), Some(max_mb)) = (cfg.get("MinMemAlloc"), cfg.get("MaxMemAlloc")) {
+            let parse = |value: &String| {
+                value.parse().map_err(|_| {
+                    let cfg_path = instance_dir.join(INSTANCE_CFG_FILE);
+                    InstallError::invalid(cfg_path.display(), format!("bad memory size {value}"))
+                })
+            };
+            profile.memory = Some(MemoryConfig {
+                min_mb: parse(min_mb)?,
+                max_mb: parse(max_mb)?,
+            });
+        }
+    }
+    if overridden("OverrideJavaLocation") {
+        profile.java_path = cfg.get("JavaPath").map(PathBuf::from);
+    }
+    if overridden("OverrideJavaArgs") {
+        if let Some(args) = cfg.get("JvmArgs") {
+            profile.jvm_args = args.split_whitespace().map(String::from).collect();
+        }
+    }
+    Ok(profile)
+}
+
+/// Export a profile as a MultiMC instance in `dest_dir`, which can be imported by MultiMC,
+/// Prism Launcher or [`import_multimc_instance`].
+///
+/// The game directory of the profile is copied to `dest_dir/.minecraft`. `dest_dir` must not be
+/// an instance already.
+pub fn export_to_multimc_instance(profile: &GameProfile, dest_dir: &Path) -> Result<()> {
+    let (minecraft_version, loader) = parse_loader_version_id(&profile.version_id)?;
+    if dest_dir.join(MMC_PACK_FILE).exists() {
+        return Err(InstallError::AlreadyExists {
+            path: dest_dir.join(MMC_PACK_FILE),
+        }
+        .into());
+    }
+    fs::create_dir_all(dest_dir)?;
+    let pack = MmcPack::new(&minecraft_version, loader.as_ref());
+    fs::write(
+        dest_dir.join(MMC_PACK_FILE),
+        serde_json::to_string_pretty(&pack).map_err(std::io::Error::from)?,
+    )?;
+
+    let mut cfg = vec![
+        "InstanceType=OneSix".to_string(),
+        format!("name={}", profile.name),
+    ];
+    if let Some(memory) = &profile.memory {
+        cfg.push("OverrideMemory=true".to_string());
+        cfg.push(format!("MinMemAlloc={}", memory.min_mb));
+        cfg.push(format!("MaxMemAlloc={}", memory.max_mb));
+    }
+    if let Some(java_path) = &profile.java_path {
+        cfg.push("OverrideJavaLocation=true".to_string());
+        cfg.push(format!("JavaPath={}", java_path.display()));
+    }
+    if !profile.jvm_args.is_empty() {
+        cfg.push("OverrideJavaArgs=true".to_string());
+        cfg.push(format!("JvmArgs={}", profile.jvm_args.join(" ")));
+    }
+    fs::write(dest_dir.join(INSTANCE_CFG_FILE), cfg.join("\n") + "\n")?;
+
+    let game_dir = dest_dir.join(".minecraft");
+    if profile.game_dir.is_dir() {
+        copy_dir(&profile.game_dir, &game_dir, &[])?;
+    } else {
+        fs::create_dir_all(game_dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Pretend the versions are installed, so nothing is downloaded
+    fn install_versions(minecraft: &MinecraftLocation, ids: &[&str]) {
+        for id in ids {
+            let path = minecraft.get_version_json(id);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        install_versions(&minecraft, &["1.20.1", "1.20.1-fabric0.14.21"]);
+        let instance_dir = dir.path().join("instances/Fabric");
+        fs::create_dir_all(instance_dir.join("minecraft/mods")).unwrap();
+        fs::write(
+            instance_dir.join(MMC_PACK_FILE),
+            json!({
+                "components": [
+                    { "uid": "org.lwjgl3", "version": "3.3.1", "dependencyOnly": true },
+                    { "uid": "net.minecraft", "version": "1.20.1", "important": true },
+                    { "uid": "net.fabricmc.intermediary", "version": "1.20.1" },
+                    { "uid": "net.fabricmc.fabric-loader", "cachedVersion": "0.14.21" }
+                ],
+                "formatVersion": 1
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            instance_dir.join(INSTANCE_CFG_FILE),
+            "[General]\nInstanceType=OneSix\nname=\"My Fabric\"\nOverrideMemory=true\n\
+             MinMemAlloc=1024\nMaxMemAlloc=4096\nOverrideJavaArgs=false\nJvmArgs=-Dignored\n",
+        )
+        .unwrap();
+
+        let profile = import_multimc_instance(&instance_dir, &minecraft)
+            .await
+            .unwrap();
+        assert_eq!(profile.name, "My Fabric");
+        assert_eq!(profile.version_id, "1.20.1-fabric0.14.21");
+        // the legacy game directory of MultiMC
+        assert_eq!(profile.game_dir, instance_dir.join("minecraft"));
+        assert_eq!(
+            profile.memory,
+            Some(MemoryConfig {
+                min_mb: 1024,
+                max_mb: 4096
+            })
+        );
+        assert!(profile.jvm_args.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(&dir.path().join(".minecraft"));
+        install_versions(&minecraft, &["1.20.1", "1.20.1-forge-47.1.0"]);
+        let game_dir = dir.path().join("profiles/forge");
+        fs::create_dir_all(game_dir.join("config")).unwrap();
+        fs::write(game_dir.join("config/forge.toml"), "a = 1").unwrap();
+        let mut profile = GameProfile::new("Forge", "1.20.1-forge-47.1.0", &game_dir);
+        profile.java_path = Some(PathBuf::from("/usr/bin/java"));
+        profile.jvm_args = vec!["-XX:+UseG1GC".to_string(), "-Dfoo=bar".to_string()];
+
+        let instance_dir = dir.path().join("instances/Forge");
+        export_to_multimc_instance(&profile, &instance_dir).unwrap();
+        let pack: MmcPack =
+            serde_json::from_str(&fs::read_to_string(instance_dir.join(MMC_PACK_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(pack.minecraft_version(), Some("1.20.1"));
+        assert_eq!(
+            pack.loader(),
+            Some(InstanceLoader {
+                r#type: LoaderType::Forge,
+                version: "47.1.0".to_string()
+            })
+        );
+        assert!(export_to_multimc_instance(&profile, &instance_dir).is_err());
+
+        let imported = import_multimc_instance(&instance_dir, &minecraft)
+            .await
+            .unwrap();
+        assert_eq!(imported.name, profile.name);
+        assert_eq!(imported.version_id, profile.version_id);
+        assert_eq!(imported.java_path, profile.java_path);
+        assert_eq!(imported.jvm_args, profile.jvm_args);
+        assert_eq!(imported.memory, None);
+        assert_eq!(
+            fs::read_to_string(imported.game_dir.join("config/forge.toml")).unwrap(),
+            "a = 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_version_id() {
+        let loader = |r#type, version: &str| {
+            Some(InstanceLoader {
+                r#type,
+                version: version.to_string(),
+            })
+        };
+        for (minecraft_version, loader) in [
+            ("1.20.1", None),
+            ("1.20.1", loader(LoaderType::Fabric, "0.14.21")),
+            ("1.20.1", loader(LoaderType::Quilt, "0.19.2")),
+            ("1.20.1", loader(LoaderType::Forge, "47.1.0")),
+        ] {
+            let version_id = loader_version_id(minecraft_version, loader.as_ref());
+            assert_eq!(
+                parse_loader_version_id(&version_id).unwrap(),
+                (minecraft_version.to_string(), loader)
+            );
+        }
+        assert_eq!(
+            parse_loader_version_id("1.12.2-forge1.12.2-14.23.5.2859").unwrap(),
+            (
+                "1.12.2".to_string(),
+                loader(LoaderType::Forge, "14.23.5.2859")
+            )
+        );
+        assert!(parse_loader_version_id("neoforge-20.4.80").is_err());
+    }
+}
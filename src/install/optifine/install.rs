@@ -16,43 +16,43 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{ffi::OsStr, fmt::Display, path::Path};
+use std::{ffi::OsStr, path::Path, sync::Arc};
 
-use anyhow::Result;
 use tokio::{fs, io::AsyncWriteExt};
 
 use crate::{
-    core::folder::MinecraftLocation,
+    core::{
+        folder::MinecraftLocation,
+        task::{Reporter, TaskReporter},
+    },
+    install::InstallError,
+    launch::jvm::check_java,
     utils::download::{download, Download},
+    Result,
 };
 use crate::core::DELIMITER;
 
-use super::{InstallOptifineOptions, DEFAULT_META_URL};
+use super::{InstallOptifineOptions, OptifineMetaSource};
 
 const OPTIFINE_INSTALL_HELPER: &[u8] = include_bytes!("./optifine-installer.jar");
 
 /// Download forge installer
-pub async fn download_optifine_installer<P, D>(
+pub async fn download_optifine_installer<P>(
     minecraft_version: &str,
     optifine_type: &str,
     optifine_patch: &str,
     dest_path: P,
-    remote: Option<D>,
+    source: Option<OptifineMetaSource>,
 ) -> Result<()>
     where
         P: AsRef<Path> + AsRef<OsStr>,
-        D: Display,
 {
-    let url = match remote {
-        None => format!("{DEFAULT_META_URL}/{minecraft_version}/{optifine_type}/{optifine_patch}"),
-        Some(remote) => format!("{remote}/{minecraft_version}/{optifine_type}/{optifine_patch}"),
-    };
-    download(Download {
-        url,
-        file: dest_path,
-        sha1: None,
-    })
-        .await?;
+    let source = source.unwrap_or_default();
+    let url = format!(
+        "{}/{minecraft_version}/{optifine_type}/{optifine_patch}",
+        source.base_url()
+    );
+    download(Download::new(url, dest_path)).await?;
 
     Ok(())
 }
@@ -64,6 +64,10 @@ pub async fn download_optifine_installer<P, D>(
 /// #### Note:
 ///
 /// if you need to install as mod, use download_optifine_install function
+///
+/// The task `install optifine` is reported to `reporter`, with the sub-tasks `download installer`
+/// and `run installer`.
+#[allow(clippy::too_many_arguments)]
 pub async fn install_optifine(
     minecraft: MinecraftLocation,
     version_name: &str,
@@ -72,30 +76,69 @@ pub async fn install_optifine(
     optifine_patch: &str,
     java_executable_path: &str,
     options: Option<InstallOptifineOptions>,
+    reporter: Option<Arc<dyn Reporter>>,
 ) -> Result<()> {
+    let task = TaskReporter::new(reporter, "install optifine", 2);
+    let result = install_optifine_reported(
+        minecraft,
+        version_name,
+        minecraft_version,
+        optifine_type,
+        optifine_patch,
+        java_executable_path,
+        options,
+        &task,
+    )
+    .await;
+    task.finish(result)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn install_optifine_reported(
+    minecraft: MinecraftLocation,
+    version_name: &str,
+    minecraft_version: &str,
+    optifine_type: &str,
+    optifine_patch: &str,
+    java_executable_path: &str,
+    options: Option<InstallOptifineOptions>,
+    task: &TaskReporter,
+) -> Result<()> {
+    // the installer is built for Java 8
+    let java = Path::new(java_executable_path).to_path_buf();
+    tokio::task::spawn_blocking(move || check_java(&java, 8))
+        .await
+        .map_err(std::io::Error::from)?
+        .map_err(|err| InstallError::invalid(java_executable_path, err.to_string()))?;
     let options = match options {
         None => InstallOptifineOptions {
             use_forge_tweaker: None,
             inherits_from: None,
             version_id: None,
-            remote: None,
+            source: None,
         },
         Some(options) => options,
     };
-    let full_path = minecraft.get_library_by_path(format!("net/optifine/{minecraft_version}-{optifine_type}-{optifine_patch}/Optifine-{minecraft_version}-{optifine_type}-{optifine_patch}.jar"));
+    let full_path = minecraft.get_library_by_name(&format!(
+        "net.optifine:Optifine:{minecraft_version}-{optifine_type}-{optifine_patch}"
+    ))?;
     let full_path = full_path.to_str().unwrap();
 
-    download_optifine_installer(
-        minecraft_version,
-        optifine_type,
-        optifine_patch,
-        full_path,
-        options.remote,
-    )
-        .await?;
-
-    let installer_path = minecraft
-        .get_library_by_path("net/stevexmh/optifine-installer/0.0.0/optifine-installer.jar");
+    let step = task.child("download installer", 1);
+    step.finish(
+        download_optifine_installer(
+            minecraft_version,
+            optifine_type,
+            optifine_patch,
+            full_path,
+            options.source,
+        )
+        .await,
+    )?;
+    task.progress(1, 2, 0);
+
+    let installer_path =
+        minecraft.get_library_by_name("net.stevexmh:optifine-installer:0.0.0")?;
     let installer_path = installer_path.to_str().unwrap();
 
     fs::create_dir_all(Path::new(&installer_path).parent().unwrap())
@@ -132,7 +175,9 @@ pub async fn install_optifine(
         version_name,
     ]);
 
-    command.status().await?;
+    let step = task.child("run installer", 1);
+    step.finish(command.status().await)?;
+    task.progress(2, 2, 0);
 
     Ok(())
 }
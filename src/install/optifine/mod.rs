@@ -19,10 +19,35 @@
 pub mod install;
 pub mod version_list;
 
-const DEFAULT_META_URL: &str = "https://download.mcbbs.net/optifine";
+/// The BMCLApi mirror of optifine, it is also used for listing optifine versions.
+///
+/// See <https://bmclapidoc.bangbang93.com/> for more information.
+pub const DEFAULT_OPTIFINE_META_URL: &str = "https://bmclapi2.bangbang93.com/optifine";
 
 // todo: 支持optifine安装选项
 
+/// Where to fetch optifine version list and installers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum OptifineMetaSource {
+    /// Use [`DEFAULT_OPTIFINE_META_URL`]
+    #[default]
+    Bmclapi,
+
+    /// Use another mirror or a locally hosted copy which has the same layout as BMCLApi,
+    /// like `https://example.com/optifine`
+    Custom(String),
+}
+
+impl OptifineMetaSource {
+    /// Get the base url of this source, without trailing slash
+    pub fn base_url(&self) -> &str {
+        match self {
+            OptifineMetaSource::Bmclapi => DEFAULT_OPTIFINE_META_URL,
+            OptifineMetaSource::Custom(url) => url.trim_end_matches('/'),
+        }
+    }
+}
+
 pub struct InstallOptifineOptions {
     /// Use "optifine.OptiFineForgeTweaker" instead of "optifine.OptiFineTweaker" for tweakClass.
     ///
@@ -39,6 +64,6 @@ pub struct InstallOptifineOptions {
     /// If this is absent, the installed version id will be either generated or provided by installer.
     pub version_id: Option<String>,
 
-    /// The remote url of the Optifine installer.
-    pub remote: Option<String>,
+    /// Where to download the Optifine installer, default is [`OptifineMetaSource::Bmclapi`].
+    pub source: Option<OptifineMetaSource>,
 }
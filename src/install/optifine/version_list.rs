@@ -16,10 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use anyhow::Result;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 
-use super::DEFAULT_META_URL;
+use crate::Result;
+
+use super::{OptifineMetaSource, DEFAULT_OPTIFINE_META_URL};
+
+static VERSION_LIST: OnceCell<Vec<OptifineVersionInfo>> = OnceCell::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptifineVersionListItem {
@@ -29,18 +33,49 @@ pub struct OptifineVersionListItem {
     pub r#type: String,
     pub __v: i32,
     pub filename: String,
+    /// Like `Forge 45.0.9` or `Forge N/A`, missing in some old versions
+    #[serde(default)]
     pub forge: String,
 }
 
+/// An OptiFine version, the `type` and `patch` are used to install it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptifineVersionInfo {
+    pub minecraft_version: String,
+    /// Like `HD_U`
+    pub r#type: String,
+    /// Like `I4`
+    pub patch: String,
+    /// Like `OptiFine_1.19.4_HD_U_I4.jar`
+    pub filename: String,
+    /// The compatible Forge version like `45.0.9`, `None` if it's not compatible with Forge
+    pub forge: Option<String>,
+}
+
+impl From<OptifineVersionListItem> for OptifineVersionInfo {
+    fn from(item: OptifineVersionListItem) -> Self {
+        let forge = item
+            .forge
+            .trim()
+            .trim_start_matches("Forge")
+            .trim()
+            .to_string();
+        Self {
+            minecraft_version: item.mcversion,
+            r#type: item.r#type,
+            patch: item.patch,
+            filename: item.filename,
+            forge: (!forge.is_empty() && forge != "N/A").then_some(forge),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptifineVersionList(Vec<OptifineVersionListItem>);
 
 impl OptifineVersionList {
-    pub async fn new(mcversion: &str, remote: Option<String>) -> Result<Self> {
-        let url = match remote {
-            Some(remote) => format!("{remote}/{mcversion}"),
-            None => format!("{DEFAULT_META_URL}/{mcversion}"),
-        };
+    pub async fn new(mcversion: &str, source: Option<OptifineMetaSource>) -> Result<Self> {
+        let url = format!("{}/{mcversion}", source.unwrap_or_default().base_url());
         Ok(reqwest::get(url)
             .await?
             .json::<OptifineVersionList>()
@@ -48,8 +83,86 @@ impl OptifineVersionList {
     }
 }
 
+async fn fetch_optifine_version_list(meta_url: &str) -> Result<Vec<OptifineVersionInfo>> {
+    let items: Vec<OptifineVersionListItem> = reqwest::get(format!("{meta_url}/versionlist"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(items.into_iter().map(OptifineVersionInfo::from).collect())
+}
+
+/// Get the OptiFine versions of a Minecraft version, or all versions if it's `None`.
+///
+/// The list is fetched from BMCLApi once and cached for the whole session.
+pub async fn get_optifine_version_list(
+    minecraft_version: Option<&str>,
+) -> Result<Vec<OptifineVersionInfo>> {
+    let versions = match VERSION_LIST.get() {
+        Some(versions) => versions,
+        None => {
+            let versions = fetch_optifine_version_list(DEFAULT_OPTIFINE_META_URL).await?;
+            VERSION_LIST.get_or_init(|| versions)
+        }
+    };
+    Ok(versions
+        .iter()
+        .filter(|version| minecraft_version.is_none_or(|mc| version.minecraft_version == mc))
+        .cloned()
+        .collect())
+}
+
 #[tokio::test]
 async fn test() {
     let list = OptifineVersionList::new("1.19.4", None).await;
     println!("{:#?}", list);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_version_list() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/versionlist")
+            .with_body(
+                r#"[
+                    {
+                        "_id": "6419b3e8a2d4ba1d1a1e6f58",
+                        "mcversion": "1.19.4",
+                        "patch": "I4",
+                        "type": "HD_U",
+                        "__v": 0,
+                        "filename": "OptiFine_1.19.4_HD_U_I4.jar",
+                        "forge": "Forge 45.0.9"
+                    },
+                    {
+                        "_id": "5b8f0f4ec8a3c6001f9d8e6b",
+                        "mcversion": "1.7.10",
+                        "patch": "E7",
+                        "type": "HD_U",
+                        "__v": 0,
+                        "filename": "OptiFine_1.7.10_HD_U_E7.jar",
+                        "forge": "Forge N/A"
+                    }
+                ]"#,
+            )
+            .create_async()
+            .await;
+
+        let versions = fetch_optifine_version_list(&server.url()).await.unwrap();
+        assert_eq!(
+            versions[0],
+            OptifineVersionInfo {
+                minecraft_version: "1.19.4".to_string(),
+                r#type: "HD_U".to_string(),
+                patch: "I4".to_string(),
+                filename: "OptiFine_1.19.4_HD_U_I4.jar".to_string(),
+                forge: Some("45.0.9".to_string()),
+            }
+        );
+        assert_eq!(versions[1].forge, None);
+    }
+}
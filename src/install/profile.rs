@@ -0,0 +1,674 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Install a modded profile in one call: the minecraft version, the mod loader, an instance and
+//! its mods.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::core::folder::MinecraftLocation;
+//! use mgl_core::install::profile::{install_profile, ModReference, ProfileLoader, ProfileSpec};
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let spec = ProfileSpec {
+//!         name: "Fabric".to_string(),
+//!         minecraft_version: "1.20.1".to_string(),
+//!         loader: Some(ProfileLoader::Fabric {
+//!             loader: "0.15.7".to_string(),
+//!         }),
+//!         mods: vec![ModReference::Modrinth {
+//!             project: "fabric-api".to_string(),
+//!             version: "0.92.0+1.20.1".to_string(),
+//!         }],
+//!     };
+//!     let installed = install_profile(spec, &minecraft, &Default::default(), None)
+//!         .await
+//!         .unwrap();
+//!     println!("{} in {}", installed.version_id, installed.instance_dir.display());
+//! }
+//! ```
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use reqwest::Url;
+
+use crate::{
+    core::{
+        download_source::DownloadSource,
+        folder::MinecraftLocation,
+        task::{CancelHandle, Reporter, TaskReporter},
+        version::{Version, VersionManifest},
+        PlatformInfo,
+    },
+    install::{
+        install_complete_reported,
+        modpack::{install_loader, loader_version_id, safe_relative_path},
+        mrpack::export::USER_AGENT,
+        optifine::install::install_optifine,
+        verify::verify_installation,
+        InstallError, DEFAULT_CONCURRENCY,
+    },
+    instance::{Instance, InstanceLoader, InstanceManager, LoaderType},
+    mod_platform::{curseforge::CurseForgeClient, modrinth::ModrinthClient},
+    utils::download::{download_all, Download},
+    Error, Result,
+};
+
+/// The folder under the minecraft root containing the instances, unless
+/// [`ProfileInstallOptions::instances_dir`] is set
+pub const INSTANCES_DIR: &str = "instances";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileLoader {
+    Fabric {
+        loader: String,
+    },
+    Quilt {
+        loader: String,
+    },
+    /// The forge version without the minecraft version, like `47.1.0`
+    Forge {
+        build: String,
+    },
+    NeoForge {
+        build: String,
+    },
+    /// Like `HD_U` and `I5`
+    OptiFine {
+        r#type: String,
+        patch: String,
+    },
+}
+
+impl ProfileLoader {
+    /// The loader saved in the instance, `None` for OptiFine
+    pub fn instance_loader(&self) -> Option<InstanceLoader> {
+        let (r#type, version) = match self {
+            ProfileLoader::Fabric { loader } => (LoaderType::Fabric, loader),
+            ProfileLoader::Quilt { loader } => (LoaderType::Quilt, loader),
+            ProfileLoader::Forge { build } => (LoaderType::Forge, build),
+            ProfileLoader::NeoForge { build } => (LoaderType::NeoForge, build),
+            ProfileLoader::OptiFine { .. } => return None,
+        };
+        Some(InstanceLoader {
+            r#type,
+            version: version.clone(),
+        })
+    }
+}
+
+/// A mod downloaded to the `mods` folder of the instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModReference {
+    /// `version` is the id or the version number of a version of `project`
+    Modrinth {
+        project: String,
+        version: String,
+    },
+    CurseForge {
+        file_id: u32,
+    },
+    /// The file name is the last segment of the url if not set. Without `sha1` the file is only
+    /// downloaded if it doesn't exist.
+    Url {
+        url: String,
+        file_name: Option<String>,
+        sha1: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSpec {
+    /// The name of the instance, the existing instance with this name is updated
+    pub name: String,
+    pub minecraft_version: String,
+    pub loader: Option<ProfileLoader>,
+    pub mods: Vec<ModReference>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileInstallOptions {
+    pub source: DownloadSource,
+
+    /// Default is the client of the official api
+    pub modrinth: Option<ModrinthClient>,
+
+    /// Required by [`ModReference::CurseForge`]
+    pub curseforge: Option<CurseForgeClient>,
+
+    /// Default is `<minecraft>/instances`
+    pub instances_dir: Option<PathBuf>,
+
+    /// The java executable running the OptiFine installer, default is `java`
+    pub java: Option<PathBuf>,
+
+    pub cancel: Option<CancelHandle>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledProfile {
+    /// The version to launch, like `1.20.1-fabric0.15.7`
+    pub version_id: String,
+    pub instance_dir: PathBuf,
+}
+
+/// The stages of [`install_profile`] in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileStage {
+    Minecraft,
+    Loader,
+    Instance,
+    Mods,
+}
+
+impl Display for ProfileStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ProfileStage::Minecraft => "minecraft version",
+            ProfileStage::Loader => "mod loader",
+            ProfileStage::Instance => "instance",
+            ProfileStage::Mods => "mods",
+        })
+    }
+}
+
+/// Install the minecraft version, the mod loader, the instance and the mods of a profile, in
+/// this order.
+///
+/// The pieces already installed are reused, so installing a profile again only installs what's
+/// missing: a verified minecraft version, an existing loader version and the downloaded mods are
+/// skipped. A failed stage is returned as [`InstallError::Profile`], the stages before it are
+/// kept. A cancelled install returns [`Error::Cancelled`] in any stage.
+///
+/// The task `install profile` is reported to `reporter`, with a sub-task for every stage.
+pub async fn install_profile(
+    spec: ProfileSpec,
+    minecraft: &MinecraftLocation,
+    options: &ProfileInstallOptions,
+    reporter: Option<Arc<dyn Reporter>>,
+) -> Result<InstalledProfile> {
+    let task = TaskReporter::new(reporter, "install profile", 4);
+    let result = install_profile_reported(spec, minecraft, options, &task).await;
+    task.finish(result)
+}
+
+async fn install_profile_reported(
+    spec: ProfileSpec,
+    minecraft: &MinecraftLocation,
+    options: &ProfileInstallOptions,
+    task: &TaskReporter,
+) -> Result<InstalledProfile> {
+    let cancel = options.cancel.clone().unwrap_or_default();
+    let failed = |stage| {
+        move |source| match source {
+            Error::Cancelled => Error::Cancelled,
+            source => InstallError::Profile {
+                stage,
+                source: Box::new(source),
+            }
+            .into(),
+        }
+    };
+
+    let step = task.child("install minecraft", 3);
+    let result = install_minecraft(&spec.minecraft_version, minecraft, options, &step, &cancel);
+    step.finish(result.await)
+        .map_err(failed(ProfileStage::Minecraft))?;
+    task.progress(1, 4, 0);
+
+    let step = task.child("install loader", 1);
+    let version_id = step
+        .finish(install_profile_loader(&spec, minecraft, options, &cancel).await)
+        .map_err(failed(ProfileStage::Loader))?;
+    task.progress(2, 4, 0);
+
+    let step = task.child("save instance", 1);
+    let instance = step
+        .finish(save_instance(&spec, &version_id, minecraft, options))
+        .map_err(failed(ProfileStage::Instance))?;
+    task.progress(3, 4, 0);
+
+    let step = task.child("install mods", spec.mods.len() as u64);
+    let mods_dir = instance.path.join("mods");
+    step.finish(install_mods(&spec.mods, &mods_dir, options, &step, &cancel).await)
+        .map_err(failed(ProfileStage::Mods))?;
+    task.progress(4, 4, 0);
+
+    Ok(InstalledProfile {
+        version_id,
+        instance_dir: instance.path,
+    })
+}
+
+/// Install a version in the version manifest, unless it's installed and verified
+async fn install_minecraft(
+    id: &str,
+    minecraft: &MinecraftLocation,
+    options: &ProfileInstallOptions,
+    task: &TaskReporter,
+    cancel: &CancelHandle,
+) -> Result<()> {
+    if let Ok(version) = Version::from_versions_folder(minecraft.clone(), id) {
+        let platform = PlatformInfo::new().await;
+        if let Ok(version) = version.parse(minecraft, &platform).await {
+            if verify_installation(&version, minecraft).is_empty() {
+                return Ok(());
+            }
+        }
+    }
+    cancel.check()?;
+    let manifest = VersionManifest::new_with_source(&options.source).await?;
+    let version_info = manifest
+        .versions
+        .iter()
+        .find(|version| version.id == id)
+        .ok_or_else(|| InstallError::VersionNotFound { id: id.to_string() })?;
+    install_complete_reported(
+        version_info,
+        minecraft,
+        options.source.clone(),
+        task,
+        cancel,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Install the mod loader unless its version exists, returns the version id to launch
+async fn install_profile_loader(
+    spec: &ProfileSpec,
+    minecraft: &MinecraftLocation,
+    options: &ProfileInstallOptions,
+    cancel: &CancelHandle,
+) -> Result<String> {
+    let minecraft_version = spec.minecraft_version.as_str();
+    let Some(loader) = &spec.loader else {
+        return Ok(minecraft_version.to_string());
+    };
+    let version_id = match loader {
+        ProfileLoader::OptiFine { r#type, patch } => {
+            format!("{minecraft_version}-OptiFine_{type}_{patch}")
+        }
+        loader => loader_version_id(minecraft_version, loader.instance_loader().as_ref()),
+    };
+    if minecraft.get_version_json(&version_id).is_file() {
+        return Ok(version_id);
+    }
+    cancel.check()?;
+    match (loader, loader.instance_loader()) {
+        (ProfileLoader::OptiFine { r#type, patch }, _) => {
+            let java = options.java.clone().unwrap_or(PathBuf::from("java"));
+            install_optifine(
+                minecraft.clone(),
+                &version_id,
+                minecraft_version,
+                r#type,
+                patch,
+                &java.to_string_lossy(),
+                None,
+                None,
+            )
+            .await?;
+            Ok(version_id)
+        }
+        (_, Some(loader)) => install_loader(minecraft_version, &loader, minecraft).await,
+        (_, None) => unreachable!("only OptiFine has no instance loader"),
+    }
+}
+
+/// Create the instance, or update the version and the loader of the existing one
+fn save_instance(
+    spec: &ProfileSpec,
+    version_id: &str,
+    minecraft: &MinecraftLocation,
+    options: &ProfileInstallOptions,
+) -> Result<Instance> {
+    let manager = InstanceManager::new(
+        options
+            .instances_dir
+            .clone()
+            .unwrap_or(minecraft.root.join(INSTANCES_DIR)),
+    );
+    // the instance manager still reports anyhow errors
+    let instance_error = |err: anyhow::Error| {
+        InstallError::invalid(format!("instance {}", spec.name), err.to_string())
+    };
+    let mut instance = match manager.get(&spec.name) {
+        Ok(instance) => instance,
+        Err(_) => manager
+            .create(&spec.name, version_id)
+            .map_err(instance_error)?,
+    };
+    instance.version_id = version_id.to_string();
+    instance.loader = spec
+        .loader
+        .as_ref()
+        .and_then(ProfileLoader::instance_loader);
+    instance.save().map_err(instance_error)?;
+    Ok(instance)
+}
+
+/// Download the mods to `mods_dir`, the downloaded files are verified and kept
+async fn install_mods(
+    mods: &[ModReference],
+    mods_dir: &Path,
+    options: &ProfileInstallOptions,
+    task: &TaskReporter,
+    cancel: &CancelHandle,
+) -> Result<()> {
+    let modrinth = options
+        .modrinth
+        .clone()
+        .unwrap_or(ModrinthClient::new(USER_AGENT));
+    let mut tasks = Vec::new();
+    for reference in mods {
+        cancel.check()?;
+        let download = resolve_mod(reference, &modrinth, options.curseforge.as_ref()).await?;
+        let file = mods_dir.join(safe_relative_path(&download.file)?);
+        // the files without sha1 can't be verified, the existing ones are kept
+        if download.sha1.is_none() && file.is_file() {
+            continue;
+        }
+        tasks.push(Download {
+            sha1: download.sha1,
+            size: download.size,
+            ..Download::new(download.url, file)
+        });
+    }
+    let failures = download_all(tasks, DEFAULT_CONCURRENCY, cancel, |completed, total| {
+        task.progress(completed as u64, total as u64, 0)
+    })
+    .await?;
+    if !failures.is_empty() {
+        return Err(InstallError::PartialInstall { failures }.into());
+    }
+    Ok(())
+}
+
+/// Find the file of a mod, the `file` of the download is the file name
+async fn resolve_mod(
+    reference: &ModReference,
+    modrinth: &ModrinthClient,
+    curseforge: Option<&CurseForgeClient>,
+) -> Result<Download<String>> {
+    let download = |url: String, file: String, sha1: Option<String>, size: Option<u64>| Download {
+        sha1,
+        size,
+        ..Download::new(url, file)
+    };
+    match reference {
+        ModReference::Modrinth { project, version } => {
+            let found = modrinth
+                .get_project_versions(project, &[], &[])
+                .await?
+                .into_iter()
+                .find(|found| &found.id == version || &found.version_number == version)
+                .ok_or_else(|| {
+                    InstallError::ModNotFound(format!("version {version} of {project} on Modrinth"))
+                })?;
+            let file = found
+                .files
+                .iter()
+                .find(|file| file.primary)
+                .or(found.files.first())
+                .ok_or_else(|| {
+                    InstallError::invalid(format!("version {version} of {project}"), "no files")
+                })?;
+            Ok(download(
+                file.url.clone(),
+                file.filename.clone(),
+                file.hashes.get("sha1").cloned(),
+                Some(file.size),
+            ))
+        }
+        ModReference::CurseForge { file_id } => {
+            let curseforge = curseforge.ok_or_else(|| {
+                InstallError::invalid(
+                    format!("the CurseForge file {file_id}"),
+                    "a CurseForge client is required to download it",
+                )
+            })?;
+            let file = curseforge
+                .get_files(&[*file_id])
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    InstallError::ModNotFound(format!("the CurseForge file {file_id}"))
+                })?;
+            let url = match &file.download_url {
+                Some(url) => url.clone(),
+                None => {
+                    curseforge
+                        .get_mod_file_download_url(file.mod_id, file.id)
+                        .await?
+                }
+            };
+            let sha1 = file
+                .hashes
+                .iter()
+                .find(|hash| hash.algo == 1)
+                .map(|hash| hash.value.clone());
+            Ok(download(url, file.file_name, sha1, Some(file.file_length)))
+        }
+        ModReference::Url {
+            url,
+            file_name,
+            sha1,
+        } => {
+            let file_name = match file_name {
+                Some(file_name) => file_name.clone(),
+                None => Url::parse(url)
+                    .map_err(|err| InstallError::invalid(url, err.to_string()))?
+                    .path_segments()
+                    .and_then(|mut segments| segments.next_back())
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| InstallError::invalid(url, "no file name in the url"))?
+                    .to_string(),
+            };
+            Ok(download(url.clone(), file_name, sha1.clone(), None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::utils::sha1::calculate_sha1_from_read;
+
+    fn sha1(content: &str) -> String {
+        calculate_sha1_from_read(&mut content.as_bytes())
+    }
+
+    #[tokio::test]
+    async fn test_install_profile() {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+        let version_json = json!({
+            "id": "1.20.1",
+            "type": "release",
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": "",
+            "libraries": [],
+            "assetIndex": {
+                "id": "5",
+                "size": 13,
+                "totalSize": 0,
+                "url": format!("{url}/indexes/5.json")
+            },
+            "downloads": {
+                "client": { "sha1": sha1("client"), "size": 6, "url": format!("{url}/client.jar") }
+            }
+        })
+        .to_string();
+        let manifest = server
+            .mock("GET", "/mc/game/version_manifest_v2.json")
+            .with_body(
+                json!({
+                    "latest": { "release": "1.20.1", "snapshot": "1.20.1" },
+                    "versions": [{
+                        "id": "1.20.1",
+                        "type": "release",
+                        "url": format!("{url}/1.20.1.json"),
+                        "time": "2023-06-12T13:25:51+00:00",
+                        "releaseTime": "2023-06-12T13:25:51+00:00",
+                        "sha1": sha1(&version_json),
+                        "complianceLevel": 1
+                    }]
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/1.20.1.json")
+            .with_body(&version_json)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/client.jar")
+            .with_body("client")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/indexes/5.json")
+            .with_body(r#"{"objects":{}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v2/project/sodium/version")
+            .with_body(
+                json!([{
+                    "id": "OihdIimA",
+                    "project_id": "AANobbMI",
+                    "name": "Sodium 0.5.3",
+                    "version_number": "mc1.20.1-0.5.3",
+                    "game_versions": ["1.20.1"],
+                    "loaders": ["fabric"],
+                    "files": [{
+                        "hashes": { "sha1": sha1("sodium") },
+                        "url": format!("{url}/files/sodium-fabric-0.5.3.jar"),
+                        "filename": "sodium-fabric-0.5.3.jar",
+                        "primary": true,
+                        "size": 6
+                    }]
+                }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let sodium = server
+            .mock("GET", "/files/sodium-fabric-0.5.3.jar")
+            .with_body("sodium")
+            .expect(1)
+            .create_async()
+            .await;
+        let extra = server
+            .mock("GET", "/files/extra.jar")
+            .with_body("extra")
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/files/missing.jar")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let options = ProfileInstallOptions {
+            source: DownloadSource {
+                meta_base_url: url.clone(),
+                libraries_base_url: url.clone(),
+                assets_base_url: url.clone(),
+            },
+            modrinth: Some(ModrinthClient::with_base_url(USER_AGENT, &url)),
+            ..Default::default()
+        };
+        let mut spec = ProfileSpec {
+            name: "Sodium".to_string(),
+            minecraft_version: "1.20.1".to_string(),
+            loader: None,
+            mods: vec![
+                ModReference::Modrinth {
+                    project: "sodium".to_string(),
+                    version: "mc1.20.1-0.5.3".to_string(),
+                },
+                ModReference::Url {
+                    url: format!("{url}/files/extra.jar"),
+                    file_name: None,
+                    sha1: None,
+                },
+            ],
+        };
+
+        let installed = install_profile(spec.clone(), &minecraft, &options, None)
+            .await
+            .unwrap();
+        assert_eq!(installed.version_id, "1.20.1");
+        assert_eq!(installed.instance_dir, dir.path().join("instances/Sodium"));
+        let mods = installed.instance_dir.join("mods");
+        assert_eq!(
+            std::fs::read_to_string(mods.join("sodium-fabric-0.5.3.jar")).unwrap(),
+            "sodium"
+        );
+        assert_eq!(
+            std::fs::read_to_string(mods.join("extra.jar")).unwrap(),
+            "extra"
+        );
+        let instance = Instance::load(&installed.instance_dir).unwrap();
+        assert_eq!(instance.version_id, "1.20.1");
+
+        // the installed version and mods are reused
+        install_profile(spec.clone(), &minecraft, &options, None)
+            .await
+            .unwrap();
+        manifest.assert_async().await;
+        sodium.assert_async().await;
+        extra.assert_async().await;
+
+        spec.mods.push(ModReference::Url {
+            url: format!("{url}/files/missing.jar"),
+            file_name: None,
+            sha1: None,
+        });
+        let err = install_profile(spec, &minecraft, &options, None)
+            .await
+            .unwrap_err();
+        let Error::Install(InstallError::Profile { stage, source }) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(stage, ProfileStage::Mods);
+        assert!(matches!(
+            *source,
+            Error::Install(InstallError::PartialInstall { .. })
+        ));
+        assert!(minecraft.get_version_json("1.20.1").is_file());
+        assert!(mods.join("sodium-fabric-0.5.3.jar").is_file());
+    }
+}
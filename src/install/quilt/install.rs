@@ -16,42 +16,33 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use tokio::fs::{self, create_dir_all};
-
-use crate::core::{folder::MinecraftLocation, version::Version};
+use crate::core::{
+    folder::MinecraftLocation,
+    version::{check_inheritance, Version},
+};
+use crate::Result;
 
 use super::DEFAULT_META_URL;
 
+/// Install the quilt loader of a Minecraft version, returns the installed version id.
+///
+/// The version json is fetched from the quilt meta, `remote` replaces the official one.
 pub async fn install_quilt_version(
     mcversion: &str,
     quilt_version: &str,
     minecraft: MinecraftLocation,
     remote: Option<String>,
-) {
+) -> Result<String> {
     let remote = remote.unwrap_or(DEFAULT_META_URL.to_string());
     let url = format!("{remote}/v3/versions/loader/{mcversion}/{quilt_version}/profile/json");
 
-    let response = reqwest::get(url).await.unwrap();
-
-    let quilt_version: Version = response.json().await.unwrap();
-
-    let version_name = quilt_version.id.clone();
-
-    let json_path = minecraft.get_version_json(&version_name);
-    println!("{:?}", json_path);
-    // let libraries = quilt_version.libraries.clone().unwrap();
-    // let hashed = libraries.iter().find(|l| match l["name"].as_str() {
-    //     None => false,
-    //     Some(name) => name.starts_with("org.quiltmc:hashed"),
-    // });
+    let response = reqwest::get(url).await?.error_for_status()?;
+    let quilt_version = Version::from_value(response.json().await?)?;
+    let inherits_from = quilt_version.inherits_from.as_deref().unwrap_or(mcversion);
+    check_inheritance(&minecraft, &quilt_version.id, inherits_from)?;
 
-    create_dir_all(json_path.parent().unwrap()).await.unwrap();
-    fs::write(
-        json_path,
-        serde_json::to_string_pretty(&quilt_version).unwrap(),
-    )
-        .await
-        .unwrap();
+    quilt_version.save(minecraft.get_version_json(&quilt_version.id))?;
+    Ok(quilt_version.id)
 }
 
 #[tokio::test]
@@ -59,5 +50,51 @@ async fn test() {
     let mcversion = "1.19.3";
     let quilt_version = "0.19.1";
     let minecraft = MinecraftLocation::new("test");
-    install_quilt_version(mcversion, quilt_version, minecraft, None).await;
+    install_quilt_version(mcversion, quilt_version, minecraft, None)
+        .await
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_install_quilt_version() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/v3/versions/loader/1.20.1/0.19.2/profile/json")
+            .with_body(
+                json!({
+                    "id": "quilt-loader-0.19.2-1.20.1",
+                    "inheritsFrom": "1.20.1",
+                    "mainClass": "org.quiltmc.loader.impl.launch.knot.KnotClient",
+                    "libraries": []
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v3/versions/loader/1.20.1/0.0.0/profile/json")
+            .with_status(404)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+
+        let id = install_quilt_version("1.20.1", "0.19.2", minecraft.clone(), Some(server.url()))
+            .await
+            .unwrap();
+        assert_eq!(id, "quilt-loader-0.19.2-1.20.1");
+        assert!(minecraft.get_version_json(&id).is_file());
+
+        assert!(
+            install_quilt_version("1.20.1", "0.0.0", minecraft, Some(server.url()))
+                .await
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,515 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Dedicated server installation
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::path::Path;
+//! use mgl_core::install::server::{generate_server_start_script, install_server_with_options, ServerInstallOptions};
+//!
+//! async fn fn_name() {
+//!     let options = ServerInstallOptions {
+//!         accept_eula: true,
+//!         ..Default::default()
+//!     };
+//!     let server_jar = install_server_with_options("1.20.1", Path::new("server"), None, Some(options))
+//!         .await
+//!         .unwrap();
+//!     let script = generate_server_start_script(&server_jar, "java", 2048, &[]);
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use tokio::fs;
+
+use crate::{
+    core::{
+        folder::MinecraftLocation,
+        task::CancelHandle,
+        version::{Version, VersionManifest},
+        PlatformInfo,
+    },
+    install::InstallError,
+    utils::download::{download, download_verified, Download},
+    Result,
+};
+
+/// The file name of the vanilla server jar, like `minecraft_server.1.20.1.jar`
+pub fn server_jar_name(version_id: &str) -> String {
+    format!("minecraft_server.{version_id}.jar")
+}
+
+/// The mod loader to install on top of the vanilla server
+#[derive(Debug, Clone)]
+pub enum ServerModLoader {
+    /// Run the fabric installer in server mode.
+    Fabric {
+        /// The fabric loader version, like `0.14.21`
+        loader_version: String,
+
+        /// The fabric installer version, like `0.11.2`
+        installer_version: String,
+    },
+
+    /// Run the forge installer with `--installServer`.
+    Forge {
+        /// The forge version, like `47.1.0`
+        forge_version: String,
+    },
+}
+
+/// The settings written to `server.properties`
+#[derive(Debug, Clone)]
+pub struct ServerProperties {
+    pub server_port: u16,
+    pub motd: String,
+    pub max_players: u32,
+    pub online_mode: bool,
+    pub level_name: String,
+    pub difficulty: String,
+    pub gamemode: String,
+    pub pvp: bool,
+    pub view_distance: u32,
+
+    /// Other properties, will be written as `key=value` after the fields above.
+    pub extra: BTreeMap<String, String>,
+}
+
+impl Default for ServerProperties {
+    fn default() -> Self {
+        Self {
+            server_port: 25565,
+            motd: "A Minecraft Server".to_string(),
+            max_players: 20,
+            online_mode: true,
+            level_name: "world".to_string(),
+            difficulty: "easy".to_string(),
+            gamemode: "survival".to_string(),
+            pvp: true,
+            view_distance: 10,
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+impl ServerProperties {
+    /// Generate the content of `server.properties`
+    pub fn to_properties_string(&self) -> String {
+        let mut lines = vec![
+            "#Minecraft server properties".to_string(),
+            format!("server-port={}", self.server_port),
+            format!("motd={}", escape_property(&self.motd)),
+            format!("max-players={}", self.max_players),
+            format!("online-mode={}", self.online_mode),
+            format!("level-name={}", escape_property(&self.level_name)),
+            format!("difficulty={}", self.difficulty),
+            format!("gamemode={}", self.gamemode),
+            format!("pvp={}", self.pvp),
+            format!("view-distance={}", self.view_distance),
+        ];
+        lines.extend(
+            self.extra
+                .iter()
+                .map(|(key, value)| format!("{key}={}", escape_property(value))),
+        );
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+/// Escape a value to be written into a java `.properties` file
+fn escape_property(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace(':', "\\:")
+        .replace('=', "\\=")
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerInstallOptions {
+    /// Write `eula=true` to `eula.txt`.
+    ///
+    /// By setting this, you indicate that the user agrees to the
+    /// [Minecraft EULA](https://aka.ms/MinecraftEULA).
+    pub accept_eula: bool,
+
+    /// Generate `server.properties` with these settings.
+    ///
+    /// If this is absent, the server will generate the default one at the first startup.
+    pub properties: Option<ServerProperties>,
+
+    /// Allow installing into a directory which already contains a world.
+    pub force: bool,
+
+    /// Install a mod loader on top of the vanilla server.
+    pub loader: Option<ServerModLoader>,
+
+    /// The java executable used to run the mod loader installer, default is `java`.
+    pub java: Option<String>,
+}
+
+/// Write `eula.txt` to the server directory
+pub async fn write_eula<P: AsRef<Path>>(server_dir: P, accept: bool) -> Result<()> {
+    let server_dir = server_dir.as_ref();
+    fs::create_dir_all(server_dir).await?;
+    fs::write(
+        server_dir.join("eula.txt"),
+        format!(
+            "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\neula={accept}\n"
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn get_version_json(
+    version_id: &str,
+    minecraft: Option<&MinecraftLocation>,
+) -> Result<Version> {
+    if let Some(minecraft) = minecraft {
+        if let Ok(version) = Version::from_versions_folder(minecraft.clone(), version_id) {
+            return Ok(version);
+        }
+    }
+    let version_info = VersionManifest::new()
+        .await?
+        .versions
+        .into_iter()
+        .find(|v| v.id == version_id)
+        .ok_or_else(|| InstallError::VersionNotFound {
+            id: version_id.to_string(),
+        })?;
+    let raw = reqwest::get(version_info.url)
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Version::from_str(&raw)
+}
+
+/// Install the server jar of a version.
+///
+/// It will download `downloads.server` of the version to `dest_dir/minecraft_server.<version>.jar`
+/// and verify its sha1.
+///
+/// If `minecraft` is provided, the version json will be read from it when the version is installed,
+/// and the libraries of the version which exist in it will be copied to `dest_dir/libraries`.
+/// Otherwise the version json will be fetched from the version manifest.
+///
+/// Returns the path of the server jar.
+pub async fn install_server(
+    version_id: &str,
+    dest_dir: &Path,
+    minecraft: Option<&MinecraftLocation>,
+) -> Result<PathBuf> {
+    let version = get_version_json(version_id, minecraft).await?;
+    let server = version
+        .downloads
+        .as_ref()
+        .and_then(|downloads| downloads.get("server"))
+        .ok_or_else(|| InstallError::MissingDownload {
+            id: version_id.to_string(),
+            download: "server".to_string(),
+        })?;
+
+    let server_jar = dest_dir.join(server_jar_name(version_id));
+    download_verified(
+        Download::new(server.url.clone(), server_jar.clone())
+            .with_sha1(server.sha1.clone())
+            .with_size(server.size),
+        &CancelHandle::default(),
+    )
+    .await?;
+
+    if let Some(minecraft) = minecraft {
+        let platform = PlatformInfo::new().await;
+        let libraries = version.parse(minecraft, &platform).await?.libraries;
+        for library in libraries.iter().filter(|lib| !lib.is_native_library) {
+            let from = minecraft.get_library_by_path(&library.download_info.path);
+            if !from.is_file() {
+                continue;
+            }
+            let to = dest_dir.join("libraries").join(&library.download_info.path);
+            fs::create_dir_all(to.parent().unwrap()).await?;
+            fs::copy(from, to).await?;
+        }
+    }
+
+    Ok(server_jar)
+}
+
+/// Install a dedicated server, then write `eula.txt` and `server.properties`, and run the
+/// installer of the mod loader if required.
+///
+/// See [`install_server`] for the usage of `minecraft`. Returns the path of the server jar.
+pub async fn install_server_with_options(
+    version_id: &str,
+    server_dir: &Path,
+    minecraft: Option<&MinecraftLocation>,
+    options: Option<ServerInstallOptions>,
+) -> Result<PathBuf> {
+    let options = options.unwrap_or_default();
+    let properties = options.properties.clone().unwrap_or_default();
+
+    let world_dir = server_dir.join(&properties.level_name);
+    if !options.force && world_dir.exists() {
+        return Err(InstallError::AlreadyExists { path: world_dir }.into());
+    }
+
+    let server_jar = install_server(version_id, server_dir, minecraft).await?;
+
+    write_eula(server_dir, options.accept_eula).await?;
+    if let Some(properties) = &options.properties {
+        fs::write(
+            server_dir.join("server.properties"),
+            properties.to_properties_string(),
+        )
+        .await?;
+    }
+
+    if let Some(loader) = &options.loader {
+        let java = options.java.clone().unwrap_or("java".to_string());
+        install_server_loader(version_id, server_dir, loader, &java).await?;
+    }
+
+    Ok(server_jar)
+}
+
+async fn install_server_loader(
+    version_id: &str,
+    server_dir: &Path,
+    loader: &ServerModLoader,
+    java: &str,
+) -> Result<()> {
+    let (url, args) = match loader {
+        ServerModLoader::Fabric {
+            loader_version,
+            installer_version,
+        } => (
+            format!("https://maven.fabricmc.net/net/fabricmc/fabric-installer/{installer_version}/fabric-installer-{installer_version}.jar"),
+            vec![
+                "server".to_string(),
+                "-mcversion".to_string(),
+                version_id.to_string(),
+                "-loader".to_string(),
+                loader_version.to_string(),
+                "-dir".to_string(),
+                server_dir.to_string_lossy().to_string(),
+            ],
+        ),
+        ServerModLoader::Forge { forge_version } => {
+            let forge_version = format!("{version_id}-{forge_version}");
+            (
+                format!("https://maven.minecraftforge.net/net/minecraftforge/forge/{forge_version}/forge-{forge_version}-installer.jar"),
+                vec![
+                    "--installServer".to_string(),
+                    server_dir.to_string_lossy().to_string(),
+                ],
+            )
+        }
+    };
+    let installer = server_dir.join("installer.jar");
+    download(Download::new(url, installer.clone())).await?;
+
+    let status = tokio::process::Command::new(java)
+        .arg("-jar")
+        .arg(&installer)
+        .args(args)
+        .current_dir(server_dir)
+        .status()
+        .await?;
+    fs::remove_file(&installer).await?;
+    if !status.success() {
+        return Err(InstallError::ProcessFailed {
+            program: "the server installer".to_string(),
+            status,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Generate the command line to start the server installed by [`install_server`]
+///
+/// * `memory` - The max memory in MB, it will be used for both `-Xms` and `-Xmx`.
+pub fn generate_server_launch_command(
+    server_jar: &Path,
+    java: &str,
+    memory: u32,
+    extra_args: &[&str],
+) -> Vec<String> {
+    let mut command = vec![
+        java.to_string(),
+        format!("-Xms{memory}M"),
+        format!("-Xmx{memory}M"),
+    ];
+    command.extend(extra_args.iter().map(|arg| arg.to_string()));
+    command.extend([
+        "-jar".to_string(),
+        server_jar.to_string_lossy().to_string(),
+        "nogui".to_string(),
+    ]);
+    command
+}
+
+/// Quote an argument of a shell script, nothing in single quotes is expanded
+fn quote_sh(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Quote an argument of a batch file, `%` is doubled so it isn't expanded
+fn quote_batch(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('"', "\"\"").replace('%', "%%"))
+}
+
+/// Generate a start script of the server, it is a batch file on windows, and a shell script on
+/// other platforms.
+///
+/// The script must be saved in the directory of the jar, it changes the working directory to its
+/// own directory and starts the jar by its file name.
+pub fn generate_server_start_script(
+    server_jar: &Path,
+    java: &str,
+    memory_mb: u32,
+    extra_args: &[&str],
+) -> String {
+    let server_jar = server_jar.file_name().map_or(server_jar, Path::new);
+    let quote = if cfg!(windows) { quote_batch } else { quote_sh };
+    let command = generate_server_launch_command(server_jar, java, memory_mb, extra_args)
+        .iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<String>>()
+        .join(" ");
+    if cfg!(windows) {
+        format!("@echo off\r\ncd /d \"%~dp0\"\r\n{command}\r\npause\r\n")
+    } else {
+        format!("#!/bin/sh\ncd \"$(dirname \"$0\")\"\nexec {command}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_properties() {
+        let mut properties = ServerProperties {
+            motd: "Hello=World".to_string(),
+            online_mode: false,
+            ..Default::default()
+        };
+        properties
+            .extra
+            .insert("enable-command-block".to_string(), "true".to_string());
+        let content = properties.to_properties_string();
+        assert!(content.contains("server-port=25565\n"));
+        assert!(content.contains("motd=Hello\\=World\n"));
+        assert!(content.contains("online-mode=false\n"));
+        assert!(content.contains("level-name=world\n"));
+        assert!(content.ends_with("enable-command-block=true\n"));
+    }
+
+    #[test]
+    fn test_start_script() {
+        let script = generate_server_start_script(
+            Path::new("my server/minecraft_server.1.20.1.jar"),
+            "java",
+            2048,
+            &["-XX:+UseG1GC"],
+        );
+        if cfg!(windows) {
+            assert!(script.contains(
+                "\"java\" \"-Xms2048M\" \"-Xmx2048M\" \"-XX:+UseG1GC\" \"-jar\" \"minecraft_server.1.20.1.jar\" \"nogui\""
+            ));
+        } else {
+            assert!(script.contains(
+                "'java' '-Xms2048M' '-Xmx2048M' '-XX:+UseG1GC' '-jar' 'minecraft_server.1.20.1.jar' 'nogui'"
+            ));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_start_script() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let server_dir = dir.path().join("my server");
+        std::fs::create_dir(&server_dir).unwrap();
+        let jar = server_dir.join("minecraft_server.1.20.1.jar");
+        std::fs::write(&jar, b"").unwrap();
+        // prints the working directory, then the arguments it gets and if the jar is found
+        let java = dir.path().join("fake java");
+        std::fs::write(
+            &java,
+            "#!/bin/sh\npwd\nfor arg in \"$@\"; do echo \"$arg\"; done\n\
+             [ -f minecraft_server.1.20.1.jar ] && echo found\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&java, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let script = server_dir.join("start.sh");
+        let tricky = "-Dmotd=\"$HOME\" `id` 'quoted'";
+        std::fs::write(
+            &script,
+            generate_server_start_script(&jar, java.to_str().unwrap(), 1024, &[tricky]),
+        )
+        .unwrap();
+
+        let output = std::process::Command::new("sh")
+            .arg(&script)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let lines = stdout.lines().collect::<Vec<_>>();
+        assert_eq!(
+            Path::new(lines[0]).canonicalize().unwrap(),
+            server_dir.canonicalize().unwrap()
+        );
+        assert_eq!(
+            lines[1..],
+            [
+                "-Xms1024M",
+                "-Xmx1024M",
+                tricky,
+                "-jar",
+                "minecraft_server.1.20.1.jar",
+                "nogui",
+                "found"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_eula() {
+        let dir = tempfile::tempdir().unwrap();
+        write_eula(dir.path(), true).await.unwrap();
+        let content = std::fs::read_to_string(dir.path().join("eula.txt")).unwrap();
+        assert!(content.ends_with("eula=true\n"));
+        write_eula(dir.path(), false).await.unwrap();
+        let content = std::fs::read_to_string(dir.path().join("eula.txt")).unwrap();
+        assert!(content.ends_with("eula=false\n"));
+    }
+}
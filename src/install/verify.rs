@@ -0,0 +1,246 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Check the integrity of an installed version before launching.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::core::{folder::MinecraftLocation, version::Version, PlatformInfo};
+//! use mgl_core::install::verify::verify_installation;
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let version = Version::from_versions_folder(minecraft.clone(), "1.20.1")
+//!         .unwrap()
+//!         .parse(&minecraft, &PlatformInfo::new().await)
+//!         .await
+//!         .unwrap();
+//!     for file in verify_installation(&version, &minecraft) {
+//!         println!("{}: {:?}", file.path.display(), file.reason);
+//!     }
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::{
+    core::{
+        folder::MinecraftLocation,
+        version::{AssetIndexObject, ResolvedVersion},
+    },
+    utils::sha1::calculate_sha1_from_read,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingReason {
+    Missing,
+    WrongSize,
+    BadHash,
+}
+
+/// A file that needs to be installed again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingFile {
+    pub path: PathBuf,
+    pub reason: MissingReason,
+}
+
+/// Check a file, a size of `0` or an empty sha1 means unknown and is not checked
+fn check_file(path: &Path, size: u64, sha1: &str) -> Option<MissingReason> {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Some(MissingReason::Missing);
+    };
+    if !metadata.is_file() {
+        return Some(MissingReason::Missing);
+    }
+    if size != 0 && metadata.len() != size {
+        return Some(MissingReason::WrongSize);
+    }
+    if !sha1.is_empty() {
+        let Ok(mut file) = std::fs::File::open(path) else {
+            return Some(MissingReason::Missing);
+        };
+        if calculate_sha1_from_read(&mut file) != sha1 {
+            return Some(MissingReason::BadHash);
+        }
+    }
+    None
+}
+
+/// Check the client jar, libraries, asset index and asset objects of a version.
+///
+/// Libraries without sha1, like the ones resolved from Forge installers, are only size-checked.
+/// If the asset index is missing, the asset objects are not checked.
+pub fn verify_installation(
+    version: &ResolvedVersion,
+    minecraft: &MinecraftLocation,
+) -> Vec<MissingFile> {
+    let mut files = Vec::new();
+    if let Some(client) = version
+        .downloads
+        .as_ref()
+        .and_then(|downloads| downloads.get("client"))
+    {
+        files.push((
+            minecraft.get_version_jar(&version.id, None),
+            client.size,
+            client.sha1.clone(),
+        ));
+    }
+    files.extend(version.libraries.iter().map(|library| {
+        (
+            minecraft.get_library_by_path(&library.download_info.path),
+            library.download_info.size,
+            library.download_info.sha1.clone(),
+        )
+    }));
+
+    let mut result: Vec<MissingFile> = files
+        .into_iter()
+        .filter_map(|(path, size, sha1)| {
+            check_file(&path, size, &sha1).map(|reason| MissingFile { path, reason })
+        })
+        .collect();
+
+    if let Some(asset_index) = &version.asset_index {
+        let asset_index_path = minecraft.get_asset_index(&asset_index.id);
+        let objects = std::fs::read_to_string(&asset_index_path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+            .and_then(|json| {
+                serde_json::from_value::<AssetIndexObject>(json["objects"].clone()).ok()
+            });
+        match objects {
+            None => result.push(MissingFile {
+                path: asset_index_path,
+                reason: MissingReason::Missing,
+            }),
+            Some(objects) => {
+                let mut objects: Vec<_> = objects.into_values().collect();
+                objects.sort_by(|a, b| a.hash.cmp(&b.hash));
+                objects.dedup_by(|a, b| a.hash == b.hash);
+                result.extend(objects.into_iter().filter_map(|object| {
+                    let path = minecraft.get_asset_object(&object.hash);
+                    check_file(&path, object.size as u64, &object.hash)
+                        .map(|reason| MissingFile { path, reason })
+                }));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::{version::Version, PlatformInfo};
+
+    fn write(path: &Path, content: &[u8]) -> (String, u64) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+        (
+            calculate_sha1_from_read(&mut &content[..]),
+            content.len() as u64,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_verify_installation() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+
+        let (client_sha1, client_size) =
+            write(&minecraft.get_version_jar("1.20.1", None), b"client jar");
+        let lib_path = "org/example/lib/1.0/lib-1.0.jar";
+        let (lib_sha1, lib_size) = write(&minecraft.get_library_by_path(lib_path), b"library");
+        let forge_path = "net/minecraftforge/forge/1.20.1/forge-1.20.1-client.jar";
+        write(&minecraft.get_library_by_path(forge_path), b"forge");
+        let (object_sha1, object_size) = {
+            let content = b"asset";
+            let hash = calculate_sha1_from_read(&mut &content[..]);
+            let path = minecraft.get_asset_object(&hash);
+            write(&path, content)
+        };
+        let asset_index = json!({
+            "objects": { "icon.png": { "hash": object_sha1, "size": object_size } }
+        })
+        .to_string();
+        write(&minecraft.get_asset_index("5"), asset_index.as_bytes());
+        let version_json = json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "assets": "5",
+            "assetIndex": {
+                "id": "5",
+                "sha1": "",
+                "size": asset_index.len(),
+                "totalSize": object_size,
+                "url": "https://piston-meta.mojang.com/v1/packages/5.json"
+            },
+            "downloads": {
+                "client": { "sha1": client_sha1, "size": client_size, "url": "" }
+            },
+            "libraries": [
+                {
+                    "name": "org.example:lib:1.0",
+                    "downloads": {
+                        "artifact": { "path": lib_path, "sha1": lib_sha1, "size": lib_size, "url": "" }
+                    }
+                },
+                {
+                    "name": "net.minecraftforge:forge:1.20.1:client",
+                    "downloads": {
+                        "artifact": { "path": forge_path, "sha1": "", "size": 0, "url": "" }
+                    }
+                }
+            ]
+        })
+        .to_string();
+        write(
+            &minecraft.get_version_json("1.20.1"),
+            version_json.as_bytes(),
+        );
+        let version = Version::from_versions_folder(minecraft.clone(), "1.20.1")
+            .unwrap()
+            .parse(&minecraft, &PlatformInfo::new().await)
+            .await
+            .unwrap();
+        assert_eq!(verify_installation(&version, &minecraft), vec![]);
+
+        std::fs::remove_file(minecraft.get_library_by_path(lib_path)).unwrap();
+        std::fs::write(minecraft.get_version_jar("1.20.1", None), b"client jaR").unwrap();
+        assert_eq!(
+            verify_installation(&version, &minecraft),
+            vec![
+                MissingFile {
+                    path: minecraft.get_version_jar("1.20.1", None),
+                    reason: MissingReason::BadHash,
+                },
+                MissingFile {
+                    path: minecraft.get_library_by_path(lib_path),
+                    reason: MissingReason::Missing,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Instances with isolated game directories.
+//!
+//! Every instance has its own folder containing `mods`, `saves`, `config`, `options.txt` and
+//! so on, its settings are persisted as `instance.toml` in the folder. `versions`, `libraries`
+//! and `assets` are still shared in the root [`MinecraftLocation`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::core::folder::MinecraftLocation;
+//! use mgl_core::instance::InstanceManager;
+//!
+//! async fn fn_name() {
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let manager = InstanceManager::new(minecraft.root.join("instances"));
+//!     let instance = manager.create("Modded", "1.20.1-fabric").unwrap();
+//!     let options = instance.launch_options(&minecraft).await.unwrap();
+//! }
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::folder::MinecraftLocation,
+    launch::{jvm::MemoryConfig, options::LaunchOptions},
+};
+
+/// The settings file in the instance folder
+pub const INSTANCE_FILE: &str = "instance.toml";
+
+/// Folders shared in the root [`MinecraftLocation`], they are not copied when duplicating
+const SHARED_FOLDERS: [&str; 3] = ["versions", "libraries", "assets"];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoaderType {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstanceLoader {
+    pub r#type: LoaderType,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Instance {
+    pub name: String,
+
+    /// The instance folder, it's not persisted
+    #[serde(skip)]
+    pub path: PathBuf,
+
+    /// The installed version to launch, like `1.20.1-fabric`
+    pub version_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loader: Option<InstanceLoader>,
+
+    /// Use this java executable instead of the global one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryConfig>,
+
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+
+    #[serde(default)]
+    pub game_args: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+impl Instance {
+    pub fn new<P: AsRef<Path>>(name: &str, path: P, version_id: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.as_ref().to_path_buf(),
+            version_id: version_id.to_string(),
+            loader: None,
+            java: None,
+            memory: None,
+            jvm_args: vec![],
+            game_args: vec![],
+            last_played: None,
+        }
+    }
+
+    /// Read `instance.toml` in the instance folder
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut instance: Instance =
+            toml::from_str(&fs::read_to_string(path.join(INSTANCE_FILE))?)?;
+        instance.path = path.to_path_buf();
+        Ok(instance)
+    }
+
+    /// Write `instance.toml` to the instance folder
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.path)?;
+        fs::write(self.path.join(INSTANCE_FILE), toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The folders of this instance, with `versions`, `libraries` and `assets` shared in `minecraft`
+    pub fn minecraft_location(&self, minecraft: &MinecraftLocation) -> MinecraftLocation {
+        minecraft.with_game_root(&self.path)
+    }
+
+    /// Create the launch options, `${game_directory}` is the instance folder
+    pub async fn launch_options(&self, minecraft: &MinecraftLocation) -> Result<LaunchOptions> {
+        let mut options = LaunchOptions::new(&self.version_id, minecraft.clone()).await?;
+        options.game_path = self.path.clone();
+        if let Some(java) = &self.java {
+            options.java_path = java.clone();
+        }
+        if let Some(memory) = &self.memory {
+            options.min_memory = Some(memory.min_mb);
+            options.max_memory = Some(memory.max_mb);
+        }
+        options.extra_jvm_args.extend(self.jvm_args.iter().cloned());
+        options.extra_mc_args.extend(self.game_args.iter().cloned());
+        Ok(options)
+    }
+
+    /// Update `last_played` to now and save
+    pub fn mark_played(&mut self) -> Result<()> {
+        self.last_played = Some(Utc::now());
+        self.save()
+    }
+}
+
+/// Manage the instances in a folder, every sub folder with `instance.toml` is an instance
+#[derive(Debug, Clone)]
+pub struct InstanceManager {
+    pub instances_dir: PathBuf,
+}
+
+impl InstanceManager {
+    pub fn new<P: AsRef<Path>>(instances_dir: P) -> Self {
+        Self {
+            instances_dir: instances_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Get the folder of an instance, the name is validated
+    fn instance_dir(&self, name: &str) -> Result<PathBuf> {
+        let invalid = name.is_empty()
+            || name == "."
+            || name == ".."
+            || name
+                .chars()
+                .any(|c| matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'));
+        if invalid {
+            return Err(anyhow!("invalid instance name: {name}"));
+        }
+        Ok(self.instances_dir.join(name))
+    }
+
+    /// Get the folder of a new instance, fails if it's already used
+    fn new_instance_dir(&self, name: &str) -> Result<PathBuf> {
+        let dir = self.instance_dir(name)?;
+        if dir.exists() {
+            return Err(anyhow!("instance {name} already exists"));
+        }
+        Ok(dir)
+    }
+
+    pub fn create(&self, name: &str, version_id: &str) -> Result<Instance> {
+        let instance = Instance::new(name, self.new_instance_dir(name)?, version_id);
+        instance.save()?;
+        Ok(instance)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Instance> {
+        Instance::load(self.instance_dir(name)?)
+    }
+
+    /// List all instances sorted by name, folders without a valid `instance.toml` are skipped
+    pub fn list(&self) -> Result<Vec<Instance>> {
+        if !self.instances_dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut instances = fs::read_dir(&self.instances_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join(INSTANCE_FILE).is_file())
+            .filter_map(|entry| Instance::load(entry.path()).ok())
+            .collect::<Vec<_>>();
+        instances.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(instances)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let dir = self.instance_dir(name)?;
+        if !dir.join(INSTANCE_FILE).is_file() {
+            return Err(anyhow!("instance {name} does not exist"));
+        }
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<Instance> {
+        let mut instance = self.get(name)?;
+        let new_dir = self.new_instance_dir(new_name)?;
+        fs::rename(&instance.path, &new_dir)?;
+        instance.name = new_name.to_string();
+        instance.path = new_dir;
+        instance.save()?;
+        Ok(instance)
+    }
+
+    /// Copy an instance with its saves and configs. The shared folders like `libraries` are not
+    /// copied if the instance contains them.
+    pub fn duplicate(&self, name: &str, new_name: &str) -> Result<Instance> {
+        let mut instance = self.get(name)?;
+        let new_dir = self.new_instance_dir(new_name)?;
+        copy_dir(&instance.path, &new_dir, &SHARED_FOLDERS)?;
+        instance.name = new_name.to_string();
+        instance.path = new_dir;
+        instance.last_played = None;
+        instance.save()?;
+        Ok(instance)
+    }
+}
+
+/// Copy a directory recursively, the entries named in `excludes` are skipped at the top level
+pub(crate) fn copy_dir(from: &Path, to: &Path, excludes: &[&str]) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if excludes.iter().any(|exclude| name == *exclude) {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(&name), &[])?;
+        } else {
+            fs::copy(&path, to.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_and_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = InstanceManager::new(dir.path());
+        manager.create("Vanilla", "1.20.1").unwrap();
+        manager.create("Modded", "1.20.1-fabric").unwrap();
+        assert!(manager.create("Modded", "1.20.1").is_err());
+        assert!(manager.create("../escape", "1.20.1").is_err());
+
+        assert!(manager.rename("Vanilla", "Modded").is_err());
+        let renamed = manager.rename("Vanilla", "Survival").unwrap();
+        assert_eq!(renamed.path, dir.path().join("Survival"));
+        assert!(!dir.path().join("Vanilla").exists());
+
+        let names = manager
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|instance| instance.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Modded", "Survival"]);
+
+        manager.delete("Modded").unwrap();
+        assert!(manager.get("Modded").is_err());
+    }
+
+    #[test]
+    fn test_duplicate() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = InstanceManager::new(dir.path());
+        let mut instance = manager.create("Modded", "1.20.1-fabric").unwrap();
+        instance.mark_played().unwrap();
+        fs::create_dir_all(instance.path.join("saves/world")).unwrap();
+        fs::write(instance.path.join("saves/world/level.dat"), b"level").unwrap();
+        fs::create_dir_all(instance.path.join("libraries")).unwrap();
+
+        let copy = manager.duplicate("Modded", "Modded Copy").unwrap();
+        assert_eq!(
+            fs::read(copy.path.join("saves/world/level.dat")).unwrap(),
+            b"level"
+        );
+        assert!(!copy.path.join("libraries").exists());
+        assert_eq!(copy.last_played, None);
+        assert_eq!(
+            manager.get("Modded Copy").unwrap().version_id,
+            "1.20.1-fabric"
+        );
+    }
+
+    #[test]
+    fn test_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut instance = Instance::new("Modded", dir.path(), "1.20.1-fabric");
+        instance.loader = Some(InstanceLoader {
+            r#type: LoaderType::Fabric,
+            version: "0.14.21".to_string(),
+        });
+        instance.memory = Some(MemoryConfig {
+            min_mb: 512,
+            max_mb: 4096,
+        });
+        instance.jvm_args = vec!["-XX:+UseG1GC".to_string()];
+        instance.save().unwrap();
+
+        let content = fs::read_to_string(dir.path().join(INSTANCE_FILE)).unwrap();
+        let value: toml::Table = toml::from_str(&content).unwrap();
+        assert_eq!(value["name"].as_str(), Some("Modded"));
+        assert_eq!(value["version_id"].as_str(), Some("1.20.1-fabric"));
+        assert_eq!(value["loader"]["type"].as_str(), Some("fabric"));
+        assert_eq!(value["memory"]["max_mb"].as_integer(), Some(4096));
+        assert!(value.get("path").is_none());
+        assert_eq!(Instance::load(dir.path()).unwrap(), instance);
+    }
+}
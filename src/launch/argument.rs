@@ -16,40 +16,91 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, env::vars, path::PathBuf};
+use std::{collections::HashMap, ffi::OsString, fmt::Display, process::Command};
 
 use anyhow::Result;
-use regex::Regex;
-use tokio::{fs, process::Command};
+use tokio::fs;
 use zip::ZipArchive;
 
 use crate::{
+    auth::yggdrasil::yggdrasil_agent,
     core::{
-        folder::MinecraftLocation, version::ResolvedVersion, JavaExec, OsType, PlatformInfo,
-        DELIMITER,
+        folder::MinecraftLocation,
+        version::{deduplicate_libraries, resolve_arguments, ResolvedVersion, FEATURE_GAME_ARGS},
+        JavaExec, OsType, PlatformInfo, DELIMITER,
     },
-    utils::unzip::decompression_all,
+    utils::{
+        template::{substitute, UnknownVarPolicy},
+        unzip::decompression_all,
+    },
+};
+
+use super::{
+    auth::AuthProfile,
+    jvm::{
+        detect_java_version, generate_gc_args, generate_memory_args, is_gc_supported, JavaVersion,
+        MemoryWarning,
+    },
+    options::{LaunchOptions, ProcessPriority, QuickPlay, UserType, GC},
 };
 
-use super::options::{LaunchOptions, ProcessPriority, UserType, GC};
+/// Something in the launch options is ignored or may not work well, the game launches anyway
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchWarning {
+    Memory(MemoryWarning),
+    /// The GC is not supported by the Java, the default GC is used
+    UnsupportedGc {
+        gc: GC,
+        java_major: u32,
+    },
+    /// The version supports neither the quick play nor its legacy arguments, it's ignored
+    QuickPlayIgnored {
+        version_id: String,
+    },
+    /// A hook failed but the failure is ignored, like the `post_exit` hook
+    HookFailed {
+        error: String,
+    },
+}
+
+impl Display for LaunchWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LaunchWarning::Memory(warning) => warning.fmt(f),
+            LaunchWarning::UnsupportedGc { gc, java_major } => write!(
+                f,
+                "{gc:?} is not supported by Java {java_major}, the default GC is used"
+            ),
+            LaunchWarning::QuickPlayIgnored { version_id } => {
+                write!(f, "{version_id} does not support quick play, ignored")
+            }
+            LaunchWarning::HookFailed { error } => write!(f, "{error}, ignored"),
+        }
+    }
+}
 
 /// launch arguments for launch
 ///
-/// You can use `from_launch_options` to generate launch parameters and use `to_launch_command` to
-/// convert to shell commands
+/// You can use `from_launch_options` to generate launch parameters and use `to_async_command` to
+/// build the command running them
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct LaunchArguments(Vec<String>);
 
 const DEFAULT_GAME_ICON: &[u8] = include_bytes!("./assets/minecraft.icns");
 
 impl LaunchArguments {
+    /// Generate the launch arguments, with the warnings about the options ignored or adjusted
     pub async fn from_launch_options(
         launch_options: LaunchOptions,
         version: ResolvedVersion,
-    ) -> Result<Self> {
+    ) -> Result<(Self, Vec<LaunchWarning>)> {
         // todo: if launch_options.game_path.is_absolute() { return Err(); }
         let platform = PlatformInfo::new().await;
         let minecraft = MinecraftLocation::new(&launch_options.resource_path);
+        let supports_quick_play = version.supports_quick_play();
+        let features = launch_options.enabled_features(supports_quick_play);
+        let game_directory = launch_options.game_directory();
+        fs::create_dir_all(&game_directory).await?;
 
         let game_icon = match launch_options.game_icon.clone() {
             Some(icon_path) => icon_path,
@@ -61,6 +112,7 @@ impl LaunchArguments {
         };
 
         let mut command_arguments = Vec::new();
+        let mut warnings = Vec::new();
 
         command_arguments.push(format!(
             "-Dminecraft.client.jar={version_jar}",
@@ -81,8 +133,14 @@ impl LaunchArguments {
             ));
         }
 
-        command_arguments.push(format!("-Xms{}M", launch_options.min_memory));
-        command_arguments.push(format!("-Xmx{}M", launch_options.max_memory));
+        let memory = launch_options.memory();
+        warnings.extend(
+            launch_options
+                .memory_warnings()
+                .into_iter()
+                .map(LaunchWarning::Memory),
+        );
+        command_arguments.extend(generate_memory_args(&memory));
 
         if launch_options.ignore_invalid_minecraft_certificates {
             command_arguments.push("-Dfml.ignoreInvalidMinecraftCertificates=true".to_string());
@@ -91,38 +149,32 @@ impl LaunchArguments {
             command_arguments.push("-Dfml.ignorePatchDiscrepancies=true".to_string());
         }
 
-        match launch_options.gc {
-            GC::G1 => {
-                command_arguments.extend([
-                    "-XX:+UseG1GC".to_string(),
-                    "-XX:+UnlockExperimentalVMOptions".to_string(),
-                    "-XX:G1NewSizePercent=20".to_string(),
-                    "-XX:G1ReservePercent=20".to_string(),
-                    "-XX:MaxGCPauseMillis=50".to_string(),
-                    "-XX:G1HeapRegionSize=16M".to_string(),
-                ]);
-            }
-            GC::Parallel => {
-                command_arguments.extend([
-                    "-XX:+UseParallelGC".to_string(),
-                    format!(
-                        "-XX:ParallelGCThreads={num}",
-                        num = num_cpus::get_physical()
-                    ),
-                ]);
-            }
-            GC::ParallelOld => {
-                command_arguments.push("-XX:+UseParallelOldGC".to_string());
-            }
-            GC::Serial => {
-                command_arguments.push("-XX:+UseSerialGC".to_string());
-            }
-            GC::Z => {
-                command_arguments.push("-XX:+UseZGC".to_string());
+        if let Some(jvm_profile) = &launch_options.jvm_profile {
+            command_arguments.extend(jvm_profile.to_args(memory.max_mb));
+        } else {
+            // the java of the version is assumed if the java can't be run
+            let java_path = launch_options.java_path.clone();
+            let java = tokio::task::spawn_blocking(move || detect_java_version(&java_path))
+                .await?
+                .unwrap_or(JavaVersion {
+                    major: version.java_version.major_version.max(0) as u32,
+                    ..Default::default()
+                });
+            if !is_gc_supported(&launch_options.gc, &java) {
+                warnings.push(LaunchWarning::UnsupportedGc {
+                    gc: launch_options.gc.clone(),
+                    java_major: java.major,
+                });
             }
+            command_arguments.extend(generate_gc_args(&launch_options.gc, &java));
         }
 
-        if let Some(ygg) = launch_options.yggdrasil_agent.clone() {
+        let yggdrasil_agent = match (&launch_options.yggdrasil_agent, &launch_options.auth) {
+            (Some(agent), _) => Some(agent.clone()),
+            (None, Some(auth)) => yggdrasil_agent(auth, &minecraft).await?,
+            (None, None) => None,
+        };
+        if let Some(ygg) = yggdrasil_agent {
             command_arguments.push(format!(
                 "-javaagent:{jar}={server}",
                 jar = ygg.jar.to_string_lossy(),
@@ -171,18 +223,20 @@ impl LaunchArguments {
                 let argument = &client.argument;
                 let file_path = minecraft.get_log_config(&client.file.id);
                 if tokio::fs::try_exists(&file_path).await? {
-                    jvm_arguments.push(
-                        argument.replace("${path}", &file_path.to_string_lossy().to_string()),
-                    );
+                    let vars = HashMap::from([("path", file_path.to_string_lossy().to_string())]);
+                    jvm_arguments.push(substitute(argument, &vars, UnknownVarPolicy::LeaveAsIs)?);
                 }
             }
         }
 
-        command_arguments.extend(
-            jvm_arguments
-                .iter()
-                .map(|arg| format(arg, jvm_options.clone())),
-        );
+        // the placeholders without a value, like `${clientid}`, are replaced with nothing
+        for arg in &jvm_arguments {
+            command_arguments.push(substitute(
+                arg,
+                &jvm_options,
+                UnknownVarPolicy::RemoveToken,
+            )?);
+        }
         command_arguments.extend(launch_options.extra_jvm_args);
 
         command_arguments.push(version.main_class);
@@ -201,7 +255,7 @@ impl LaunchArguments {
             "version_type",
             match launch_options.version_type {
                 Some(v) => v,
-                None => version.version_type,
+                None => version.version_type.to_string(),
             },
         );
         game_options.insert("assets_root", assets_dir.to_string_lossy().to_string());
@@ -225,30 +279,70 @@ impl LaunchArguments {
         game_options.insert("assets_index_name", version.assets);
         game_options.insert(
             "game_directory",
-            launch_options.game_path.to_string_lossy().to_string(),
+            game_directory.to_string_lossy().to_string(),
         );
-        game_options.insert("auth_player_name", launch_options.game_profile.name);
-        game_options.insert("auth_uuid", launch_options.game_profile.uuid);
-        game_options.insert("auth_access_token", launch_options.access_token);
+        let user_type = match &launch_options.auth {
+            Some(auth) => {
+                game_options.insert("auth_player_name", auth.username().to_string());
+                game_options.insert("auth_uuid", auth.uuid());
+                game_options.insert("auth_access_token", auth.access_token());
+                game_options.insert("auth_xuid", auth.xuid().unwrap_or_default().to_string());
+                auth.user_type()
+            }
+            None => {
+                game_options.insert("auth_player_name", launch_options.game_profile.name);
+                game_options.insert("auth_uuid", launch_options.game_profile.uuid);
+                game_options.insert("auth_access_token", launch_options.access_token);
+                launch_options.user_type
+            }
+        };
         game_options.insert("user_properties", launch_options.properties);
         game_options.insert(
             "user_type",
-            match launch_options.user_type {
+            match user_type {
                 UserType::Mojang => "mojang".to_string(),
                 UserType::Legacy => "legacy".to_string(),
+                UserType::Msa => "msa".to_string(),
             },
         );
-        game_options.insert("resolution_width", launch_options.width.to_string());
-        game_options.insert("resolution_height", launch_options.height.to_string());
+        if let Some((width, height)) = launch_options.resolution {
+            game_options.insert("resolution_width", width.to_string());
+            game_options.insert("resolution_height", height.to_string());
+        }
+        if let Some((placeholder, value)) = launch_options
+            .quick_play
+            .as_ref()
+            .and_then(QuickPlay::argument)
+        {
+            game_options.insert(placeholder, value);
+        }
+        if let Some(path) = &launch_options.quick_play_path {
+            game_options.insert("quickPlayPath", path.to_string_lossy().to_string());
+        }
 
-        command_arguments.extend(
-            version
-                .arguments
-                .unwrap()
-                .game
-                .iter()
-                .map(|arg| format(arg, game_options.clone())),
-        );
+        let game_arguments = version
+            .arguments
+            .unwrap()
+            .game
+            .into_iter()
+            .chain(resolve_arguments(&FEATURE_GAME_ARGS, &platform, &features));
+        for arg in game_arguments {
+            command_arguments.push(substitute(
+                &arg,
+                &game_options,
+                UnknownVarPolicy::RemoveToken,
+            )?);
+        }
+        if let Some(quick_play) = &launch_options.quick_play {
+            if !supports_quick_play || quick_play.feature().is_none() {
+                match quick_play.legacy_arguments() {
+                    Some(arguments) => command_arguments.extend(arguments),
+                    None => warnings.push(LaunchWarning::QuickPlayIgnored {
+                        version_id: launch_options.version_id.clone(),
+                    }),
+                }
+            }
+        }
         command_arguments.extend(launch_options.extra_mc_args);
         if let Some(server) = launch_options.server {
             command_arguments.extend(vec!["--server".to_string(), server.ip]);
@@ -259,130 +353,81 @@ impl LaunchArguments {
         if launch_options.fullscreen {
             command_arguments.push("--fullscreen".to_string());
         }
-        let no_width_arguments = None
-            == command_arguments
-                .iter()
-                .find(|v| v == &&"--width".to_string());
-        if no_width_arguments && !launch_options.fullscreen {
-            command_arguments.extend(vec![
-                "--width".to_string(),
-                launch_options.width.to_string(),
-                "--height".to_string(),
-                launch_options.height.to_string(),
-            ]);
-        }
 
-        Ok(LaunchArguments(command_arguments))
+        Ok((LaunchArguments(command_arguments), warnings))
     }
 
-    /// spawn a command instance, you can use this to launch the game
+    /// Build the command running the game in `version_root`.
+    ///
+    /// Every argument is passed as an item of argv, the values with spaces are never quoted or
+    /// split. The command is prefixed with `nice` for a non-normal `process_priority` on unix, and
+    /// with the `wrapper_command` of the launch options.
     pub async fn to_async_command(
         &self,
         java_exec: JavaExec,
         launch_options: LaunchOptions,
         platform: &PlatformInfo,
-    ) -> Result<std::process::Command> {
-        let mut command = format!(
-            "cd {}\n",
-            launch_options.version_root.to_string_lossy().to_string()
-        );
-        match platform.os_type {
-            OsType::Windows => {}
-            _ => command.push_str("nice "),
-        };
-
-        if let OsType::Windows = platform.os_type {
-            command.push_str(" -c ");
-        }
-
+    ) -> Result<Command> {
+        let mut argv = Vec::new();
         if platform.os_type != OsType::Windows {
-            match launch_options.process_priority {
-                ProcessPriority::High => {
-                    command.push_str("-n 0 ");
-                }
-                ProcessPriority::AboveNormal => {
-                    command.push_str("-n 5 ");
-                }
-                ProcessPriority::Normal => (), // nothing to do
-                ProcessPriority::BelowNormal => {
-                    command.push_str("-n 15 ");
-                }
-                ProcessPriority::LOW => {
-                    command.push_str("-n 19 ");
-                }
+            let niceness = match launch_options.process_priority {
+                ProcessPriority::High => Some("0"),
+                ProcessPriority::AboveNormal => Some("5"),
+                ProcessPriority::Normal => None,
+                ProcessPriority::BelowNormal => Some("15"),
+                ProcessPriority::LOW => Some("19"),
             };
+            if let Some(niceness) = niceness {
+                argv.extend(["nice".into(), "-n".into(), niceness.into()]);
+            }
         }
         // todo(after java exec): add -Dfile.encoding=encoding.name() and other
-        // let launch_options = self.0.join(" ").to_string();
-        // command.arg(format!(
-        //     "{java} {launch_options}",
-        //     java = java_exec.binary.to_string_lossy().to_string()
-        // ));
-        let mut launch_command = java_exec.binary.to_string_lossy().to_string();
-        launch_command.push_str(" ");
-        launch_command.push_str(&self.0.clone().join(" "));
-        command.push_str(&launch_command);
-        match platform.os_type {
-            OsType::Windows => command.push_str(&format!(
-                "\ndel /F /Q {}\n",
-                launch_options.native_path.to_string_lossy()
-            )),
-            _ => command.push_str(&format!(
-                "\n rm -rf {}",
-                launch_options.native_path.to_string_lossy()
-            )),
+        if let Some(wrapper_command) = &launch_options.wrapper_command {
+            argv.extend(wrapper_command.iter().map(OsString::from));
         }
-        let script_path = match platform.os_type {
-            OsType::Linux => launch_options.version_root.join(".cache").join("launch.sh"),
-            OsType::Osx => launch_options.version_root.join(".cache").join("launch.sh"),
-            OsType::Windows => launch_options
-                .version_root
-                .join(".cache")
-                .join("launch.bat"),
-        };
+        argv.push(java_exec.binary.into_os_string());
+        argv.extend(self.0.iter().map(OsString::from));
 
-        fs::create_dir_all(script_path.parent().unwrap()).await?;
-        fs::write(&script_path, command).await?;
-
-        let mut command = match platform.os_type {
-            OsType::Windows => {
-                let vars = vars().find(|v| v.0 == "PATH").unwrap();
-
-                let path_vars = vars.1.as_str().split(";").collect::<Vec<&str>>(); // todo: test it in windows
-                let powershell_folder = PathBuf::from(
-                    path_vars
-                        .into_iter()
-                        .find(|v| v.to_lowercase().contains("powershell"))
-                        .unwrap(),
-                );
-
-                std::process::Command::new(
-                    powershell_folder
-                        .join("powershell.exe")
-                        .to_string_lossy()
-                        .to_string(),
-                )
-            }
-            _ => {
-                let mut chmod = Command::new("chmod");
-                chmod.args(&["+x", script_path.to_string_lossy().to_string().as_ref()]);
-                chmod.status().await?;
-                std::process::Command::new("bash")
-            }
-        };
-        command.arg(script_path);
+        let mut command = Command::new(&argv[0]);
+        command
+            .args(&argv[1..])
+            .current_dir(&launch_options.version_root);
         Ok(command)
     }
 }
 
+impl ResolvedVersion {
+    /// Generate the complete launch arguments with all the tokens substituted, see
+    /// [`LaunchArguments::from_launch_options`].
+    ///
+    /// The memory flags come first, then the jvm arguments, the main class and the game arguments.
+    /// Every argument is an item of its own, so the paths with spaces need no extra quoting when
+    /// passed to [`std::process::Command::args`].
+    pub async fn to_launch_args(
+        &self,
+        minecraft: &MinecraftLocation,
+        auth: &AuthProfile,
+        options: &LaunchOptions,
+    ) -> Result<Vec<OsString>> {
+        let options = LaunchOptions {
+            auth: Some(auth.clone()),
+            resource_path: minecraft.root.clone(),
+            minecraft_location: minecraft.clone(),
+            ..options.clone()
+        };
+        let (arguments, _) = LaunchArguments::from_launch_options(options, self.clone()).await?;
+        Ok(arguments.0.into_iter().map(OsString::from).collect())
+    }
+}
+
 fn resolve_classpath(
     options: &LaunchOptions,
     version: &ResolvedVersion,
     minecraft: &MinecraftLocation,
     extra_class_paths: Option<Vec<String>>,
 ) -> String {
-    let mut classpath = version
-        .libraries
+    // a library listed by both the loader and Minecraft is only on the classpath once
+    let mut classpath = deduplicate_libraries(version.libraries.clone())
         .iter()
         .filter(|lib| {
             if lib.is_native_library {
@@ -420,14 +465,358 @@ fn resolve_classpath(
     classpath.join(DELIMITER)
 }
 
-fn format(template: &str, args: HashMap<&str, String>) -> String {
-    let regex = Regex::new(r"\$\{(.*?)}").unwrap();
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, path::PathBuf};
 
-    regex
-        .replace_all(&template, |caps: &regex::Captures| {
-            let key = String::from(&caps[1]);
-            let value = args.get(&caps[1]).unwrap_or(&key);
-            value.to_string()
-        })
-        .to_string()
+    use serde_json::json;
+
+    use crate::launch::jvm::{
+        resolve_memory, MemoryConfig, DEFAULT_MAX_MEMORY_MB, MAX_RECOMMENDED_MEMORY_MB,
+        MIN_MEMORY_MB,
+    };
+
+    use super::*;
+
+    fn game_dir_argument(arguments: &LaunchArguments) -> &str {
+        let index = arguments
+            .0
+            .iter()
+            .position(|arg| arg == "--gameDir")
+            .unwrap();
+        &arguments.0[index + 1]
+    }
+
+    /// Create the launch options of a mocked vanilla version
+    async fn mock_launch_options(
+        minecraft: &MinecraftLocation,
+        id: &str,
+    ) -> (LaunchOptions, ResolvedVersion) {
+        std::fs::create_dir_all(&minecraft.assets).unwrap();
+        let version_json = minecraft.get_version_json(id);
+        std::fs::create_dir_all(version_json.parent().unwrap()).unwrap();
+        std::fs::write(
+            &version_json,
+            json!({
+                "id": id,
+                "type": "release",
+                "mainClass": "net.minecraft.client.main.Main",
+                "assets": "5",
+                "assetIndex": { "id": "5", "sha1": "", "size": 1, "totalSize": 1, "url": "" },
+                "downloads": { "client": { "sha1": "", "size": 0, "url": "" } },
+                "arguments": {
+                    "game": ["--gameDir", "${game_directory}"],
+                    "jvm": []
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let options = LaunchOptions::new(id, minecraft.clone()).await.unwrap();
+        let version = options
+            .version
+            .parse(minecraft, &PlatformInfo::new().await)
+            .await
+            .unwrap();
+        (options, version)
+    }
+
+    #[tokio::test]
+    async fn test_isolation() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let (mut options, version) = mock_launch_options(&minecraft, "1.20.1").await;
+        let arguments = LaunchArguments::from_launch_options(options.clone(), version.clone())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(
+            game_dir_argument(&arguments),
+            minecraft.root.to_string_lossy()
+        );
+
+        options.isolation = true;
+        let arguments = LaunchArguments::from_launch_options(options, version)
+            .await
+            .unwrap()
+            .0;
+        let isolated = minecraft.get_isolated_game_root("1.20.1");
+        assert_eq!(game_dir_argument(&arguments), isolated.to_string_lossy());
+        assert!(isolated.is_dir());
+        assert_eq!(
+            minecraft.isolated("1.20.1").saves,
+            minecraft.versions.join("1.20.1/.minecraft/saves")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let (mut options, version) = mock_launch_options(&minecraft, "1.20.1").await;
+        options.min_memory = Some(1024);
+        options.max_memory = Some(3072);
+        let arguments = LaunchArguments::from_launch_options(options.clone(), version.clone())
+            .await
+            .unwrap()
+            .0;
+        assert!(arguments.0.contains(&"-Xms1024M".to_string()));
+        assert!(arguments.0.contains(&"-Xmx3072M".to_string()));
+
+        options.min_memory = None;
+        options.max_memory = None;
+        let arguments = LaunchArguments::from_launch_options(options, version)
+            .await
+            .unwrap()
+            .0;
+        let xmx = arguments
+            .0
+            .iter()
+            .find_map(|arg| arg.strip_prefix("-Xmx"))
+            .unwrap();
+        let xmx: u32 = xmx.trim_end_matches('M').parse().unwrap();
+        assert!((MIN_MEMORY_MB..=MAX_RECOMMENDED_MEMORY_MB).contains(&xmx));
+
+        // the unset sizes are recommended by the system memory
+        const GB: u64 = 1024 * 1024 * 1024;
+        let memory = |min_mb, max_mb| MemoryConfig { min_mb, max_mb };
+        assert_eq!(resolve_memory(None, None, Ok(16 * GB)), memory(512, 4096));
+        assert_eq!(resolve_memory(None, None, Ok(GB)), memory(512, 512));
+        assert_eq!(resolve_memory(None, None, Ok(64 * GB)), memory(512, 8192));
+        assert_eq!(
+            resolve_memory(Some(1024), None, Ok(16 * GB)),
+            memory(1024, 4096)
+        );
+        assert_eq!(
+            resolve_memory(None, None, Err(anyhow::anyhow!("unknown"))),
+            memory(MIN_MEMORY_MB, DEFAULT_MAX_MEMORY_MB)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_launch_warnings() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let (mut options, version) = mock_launch_options(&minecraft, "1.20.1").await;
+        // no java here, the java of the version is used
+        options.java_path = dir.path().join("bin/java");
+        let (_, warnings) = LaunchArguments::from_launch_options(options.clone(), version.clone())
+            .await
+            .unwrap();
+        assert!(warnings.is_empty());
+
+        options.min_memory = Some(2048);
+        options.max_memory = Some(1024);
+        options.gc = GC::Z;
+        let (arguments, warnings) = LaunchArguments::from_launch_options(options, version)
+            .await
+            .unwrap();
+        assert!(!arguments.0.contains(&"-XX:+UseZGC".to_string()));
+        assert_eq!(
+            warnings,
+            vec![
+                LaunchWarning::Memory(MemoryWarning::MinExceedsMax {
+                    min_mb: 2048,
+                    max_mb: 1024
+                }),
+                LaunchWarning::UnsupportedGc {
+                    gc: GC::Z,
+                    java_major: 8
+                }
+            ]
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            "Z is not supported by Java 8, the default GC is used"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quick_play() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let launch = |id: &'static str, quick_play: Option<QuickPlay>| {
+            let minecraft = minecraft.clone();
+            async move {
+                let (mut options, version) = mock_launch_options(&minecraft, id).await;
+                options.quick_play = quick_play;
+                options.quick_play_path = Some(PathBuf::from("quickPlay/log.json"));
+                let (LaunchArguments(arguments), _) =
+                    LaunchArguments::from_launch_options(options, version)
+                        .await
+                        .unwrap();
+                // the arguments after the default game arguments
+                let index = arguments
+                    .iter()
+                    .position(|arg| arg == "--versionType")
+                    .unwrap();
+                arguments[index + 2..].to_vec()
+            }
+        };
+        let singleplayer = QuickPlay::Singleplayer {
+            world: "New World".to_string(),
+        };
+        let multiplayer = QuickPlay::Multiplayer {
+            host: "mc.example.com".to_string(),
+            port: Some(25566),
+        };
+        let realms = QuickPlay::Realms {
+            id: "1234".to_string(),
+        };
+        let legacy_server = QuickPlay::LegacyServer {
+            host: "mc.example.com".to_string(),
+            port: None,
+        };
+
+        let path = ["--quickPlayPath", "quickPlay/log.json"];
+        assert_eq!(launch("1.20.1", None).await, path);
+        assert_eq!(
+            launch("1.20.1", Some(singleplayer.clone())).await,
+            [&path[..], &["--quickPlaySingleplayer", "New World"]].concat()
+        );
+        assert_eq!(
+            launch("1.20.1", Some(multiplayer.clone())).await,
+            [
+                &path[..],
+                &["--quickPlayMultiplayer", "mc.example.com:25566"]
+            ]
+            .concat()
+        );
+        assert_eq!(
+            launch("1.20.1", Some(realms.clone())).await,
+            [&path[..], &["--quickPlayRealms", "1234"]].concat()
+        );
+        assert_eq!(
+            launch("1.20.1", Some(legacy_server.clone())).await,
+            [&path[..], &["--server", "mc.example.com"]].concat()
+        );
+
+        assert!(launch("1.16.5", None).await.is_empty());
+        assert!(launch("1.16.5", Some(singleplayer)).await.is_empty());
+        assert_eq!(
+            launch("1.16.5", Some(multiplayer)).await,
+            ["--server", "mc.example.com", "--port", "25566"]
+        );
+        assert!(launch("1.16.5", Some(realms)).await.is_empty());
+        assert_eq!(
+            launch("1.16.5", Some(legacy_server)).await,
+            ["--server", "mc.example.com"]
+        );
+
+        let (mut options, version) = mock_launch_options(&minecraft, "1.16.5").await;
+        options.quick_play = Some(QuickPlay::Realms {
+            id: "1234".to_string(),
+        });
+        let (_, warnings) = LaunchArguments::from_launch_options(options, version)
+            .await
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![LaunchWarning::QuickPlayIgnored {
+                version_id: "1.16.5".to_string()
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_demo_and_resolution() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        let (mut options, version) = mock_launch_options(&minecraft, "1.20.1").await;
+        let (LaunchArguments(arguments), _) =
+            LaunchArguments::from_launch_options(options.clone(), version.clone())
+                .await
+                .unwrap();
+        assert!(!arguments.contains(&"--demo".to_string()));
+        assert!(!arguments.contains(&"--width".to_string()));
+        assert!(!arguments.iter().any(|arg| arg.contains("resolution_")));
+
+        options.is_demo = true;
+        options.resolution = Some((1280, 720));
+        let (LaunchArguments(arguments), _) =
+            LaunchArguments::from_launch_options(options, version)
+                .await
+                .unwrap();
+        assert!(arguments.contains(&"--demo".to_string()));
+        let index = arguments.iter().position(|arg| arg == "--width").unwrap();
+        assert_eq!(
+            arguments[index..index + 4],
+            ["--width", "1280", "--height", "720"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_to_launch_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(&dir.path().join("game with spaces"));
+        let (options, version) = mock_launch_options(&minecraft, "1.20.1").await;
+        let auth = AuthProfile::Offline {
+            username: "Steve".to_string(),
+        };
+        let arguments = version
+            .to_launch_args(&minecraft, &auth, &options)
+            .await
+            .unwrap();
+
+        assert!(arguments[..3]
+            .iter()
+            .any(|arg| arg.to_string_lossy().starts_with("-Xmx")));
+        let main_class = arguments
+            .iter()
+            .position(|arg| arg == "net.minecraft.client.main.Main")
+            .unwrap();
+        let game_dir = arguments.iter().position(|arg| arg == "--gameDir").unwrap();
+        assert!(main_class < game_dir);
+        assert_eq!(arguments[game_dir + 1], minecraft.root.as_os_str());
+        assert!(!arguments
+            .iter()
+            .any(|arg| arg.to_string_lossy().contains("${")));
+        let library_path = format!("-Djava.library.path={}", options.native_path.display());
+        assert!(arguments.contains(&OsString::from(library_path)));
+    }
+
+    #[tokio::test]
+    async fn test_async_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(&dir.path().join("game with spaces"));
+        let (mut options, version) = mock_launch_options(&minecraft, "1.20.1").await;
+        options.wrapper_command = Some(vec!["gamemoderun".to_string()]);
+        let platform = PlatformInfo::new().await;
+        let arguments = LaunchArguments::from_launch_options(options.clone(), version)
+            .await
+            .unwrap()
+            .0;
+        let java = JavaExec::new("/opt/my java").await;
+
+        let command = arguments
+            .to_async_command(java.clone(), options.clone(), &platform)
+            .await
+            .unwrap();
+        assert_eq!(command.get_program(), "gamemoderun");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args[0], java.binary.as_os_str());
+        assert_eq!(
+            args[1..],
+            arguments.0.iter().map(OsStr::new).collect::<Vec<_>>()
+        );
+        assert!(args.contains(&minecraft.root.as_os_str()));
+        assert_eq!(
+            command.get_current_dir(),
+            Some(options.version_root.as_path())
+        );
+
+        if platform.os_type != OsType::Windows {
+            options.process_priority = ProcessPriority::BelowNormal;
+            let command = arguments
+                .to_async_command(java, options, &platform)
+                .await
+                .unwrap();
+            assert_eq!(command.get_program(), "nice");
+            assert_eq!(
+                command.get_args().take(3).collect::<Vec<_>>(),
+                ["-n", "15", "gamemoderun"]
+            );
+        }
+    }
 }
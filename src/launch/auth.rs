@@ -0,0 +1,133 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The accounts used to launch the game.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::launch::auth::AuthProfile;
+//! use mgl_core::launch::options::LaunchOptions;
+//!
+//! fn fn_name(options: &mut LaunchOptions) {
+//!     options.auth = Some(AuthProfile::offline("Steve"));
+//! }
+//! ```
+
+use md5::{Digest, Md5};
+use uuid::{Builder, Uuid};
+
+use super::options::UserType;
+
+#[derive(Debug, Clone)]
+pub enum AuthProfile {
+    /// Play without an account, the uuid is derived from the username like the vanilla server
+    Offline { username: String },
+
+    /// An account logged in with an access token
+    Token {
+        username: String,
+        uuid: String,
+        access_token: String,
+        /// The Xbox user id of Microsoft accounts
+        xuid: Option<String>,
+        user_type: UserType,
+    },
+
+    /// An account of a yggdrasil server, the game is launched with authlib-injector to use the
+    /// server, see [`crate::auth::yggdrasil`]
+    Yggdrasil {
+        /// The api root of the server, like `https://littleskin.cn/api/yggdrasil`
+        api_base: String,
+        username: String,
+        uuid: String,
+        access_token: String,
+    },
+}
+
+impl AuthProfile {
+    pub fn offline(username: &str) -> Self {
+        Self::Offline {
+            username: username.to_string(),
+        }
+    }
+
+    /// The uuid of an offline player, the same as `UUID.nameUUIDFromBytes("OfflinePlayer:<name>")`
+    /// in java
+    pub fn offline_uuid(username: &str) -> Uuid {
+        let hash = Md5::digest(format!("OfflinePlayer:{username}").as_bytes());
+        Builder::from_md5_bytes(hash.into()).into_uuid()
+    }
+
+    pub fn username(&self) -> &str {
+        match self {
+            Self::Offline { username }
+            | Self::Token { username, .. }
+            | Self::Yggdrasil { username, .. } => username,
+        }
+    }
+
+    /// The uuid without hyphens
+    pub fn uuid(&self) -> String {
+        match self {
+            Self::Offline { username } => Self::offline_uuid(username).simple().to_string(),
+            Self::Token { uuid, .. } | Self::Yggdrasil { uuid, .. } => uuid.replace('-', ""),
+        }
+    }
+
+    /// The offline profile uses the uuid as the access token
+    pub fn access_token(&self) -> String {
+        match self {
+            Self::Offline { .. } => self.uuid(),
+            Self::Token { access_token, .. } | Self::Yggdrasil { access_token, .. } => {
+                access_token.clone()
+            }
+        }
+    }
+
+    pub fn xuid(&self) -> Option<&str> {
+        match self {
+            Self::Offline { .. } | Self::Yggdrasil { .. } => None,
+            Self::Token { xuid, .. } => xuid.as_deref(),
+        }
+    }
+
+    pub fn user_type(&self) -> UserType {
+        match self {
+            Self::Offline { .. } => UserType::Legacy,
+            Self::Token { user_type, .. } => user_type.clone(),
+            Self::Yggdrasil { .. } => UserType::Mojang,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_uuid() {
+        assert_eq!(
+            AuthProfile::offline_uuid("Notch").to_string(),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+        let profile = AuthProfile::offline("Notch");
+        assert_eq!(profile.uuid(), "b50ad385829d3141a2167e7d7539ba7f");
+        assert_eq!(profile.access_token(), profile.uuid());
+    }
+}
@@ -0,0 +1,677 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Parse the crash reports written by the game, and analyze them or the game logs to find the
+//! known causes of crashes.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::Path;
+//! use mgl_core::launch::crash::{analyze, find_latest_crash, parse_crash_report};
+//!
+//! if let Some(report) = parse_crash_report(Path::new(".minecraft")).unwrap() {
+//!     println!("{}: {:?}", report.description, report.likely_cause());
+//! }
+//! if let Some(path) = find_latest_crash(".minecraft").unwrap() {
+//!     let analysis = analyze(&std::fs::read_to_string(path).unwrap());
+//!     println!("{:#?}", analysis.problems);
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    pub description: String,
+    pub stacktrace: String,
+    /// Entries of `-- System Details --`, like `Minecraft Version` and `Java Version`
+    pub system_details: HashMap<String, String>,
+    /// The crash reports don't contain the time zone, it's treated as UTC
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrashCause {
+    OutOfMemory,
+    GraphicsDriver,
+    /// A mod failed to load or conflicts with another mod, with the suspected mod if found
+    ModConflict(String),
+    /// The java version is not supported by the game or mods
+    JavaCrash,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrashProblem {
+    /// The java is too old or too new, the versions are java major versions like `17`
+    WrongJava {
+        required: Option<u32>,
+        current: Option<u32>,
+    },
+    DuplicateMods {
+        mod_id: String,
+        files: Vec<String>,
+    },
+    /// `mod_id` requires `dependency`, which is missing or in a wrong version
+    MissingDependency {
+        mod_id: String,
+        dependency: String,
+        /// `None` if any version is accepted
+        version: Option<String>,
+    },
+    /// OpenGL or GLFW failed to initialize, usually the graphics driver is outdated
+    GraphicsDriver {
+        message: String,
+    },
+    OutOfMemory,
+}
+
+/// A mod listed in `Fabric Mods` or `Mod List` of a crash report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashModEntry {
+    pub mod_id: String,
+    pub name: String,
+    pub version: String,
+    /// Only provided by Forge
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrashAnalysis {
+    pub problems: Vec<CrashProblem>,
+    pub mods: Vec<CrashModEntry>,
+}
+
+/// Formats used by `Time:` in different versions
+const TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%m/%d/%y %I:%M %p",
+    "%d.%m.%y %H:%M",
+];
+
+static SUSPECTED_MOD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:from mod|Mod File:.*?|Suspected Mods?:) ?\[?([\w\-.]+)").unwrap());
+
+const GRAPHICS_DRIVER_PATTERNS: [&str; 9] = [
+    "GLFW error",
+    "Pixel format not accelerated",
+    "org.lwjgl.LWJGLException",
+    "Could not create context",
+    "No OpenGL context found",
+    "The driver does not appear to support OpenGL",
+    "Failed to create the GLFW window",
+    "atio6axx.dll",
+    "nvoglv64.dll",
+];
+
+static MOD_CONFLICT_PATTERNS: [&str; 5] = [
+    "org.spongepowered.asm.mixin",
+    "net.fabricmc.loader.impl.FormattedException",
+    "net.minecraftforge.fml.ModLoadingException",
+    "java.lang.NoSuchMethodError",
+    "java.lang.NoClassDefFoundError",
+];
+
+static JAVA_CRASH_PATTERNS: [&str; 3] = [
+    "java.lang.UnsupportedClassVersionError",
+    "Unsupported class file major version",
+    "java.lang.reflect.InaccessibleObjectException",
+];
+
+/// The class file version of Java 8 is 52
+const CLASS_VERSION_OFFSET: u32 = 44;
+
+static CLASS_VERSION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"class file version (\d+)(?:\.\d+)?\), this version of the Java Runtime only recognizes class file versions up to (\d+)").unwrap()
+});
+
+static UNSUPPORTED_CLASS_VERSION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Unsupported class file major version (\d+)").unwrap());
+
+static FABRIC_DEPENDENCY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Mod '[^']*' \(([\w\-.]+)\) \S+ requires (any version|version .+?) of (?:mod )?(?:'[^']*' \(([\w\-.]+)\)|([\w\-.]+))").unwrap()
+});
+
+static FORGE_DEPENDENCY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Mod ID: '([^']+)', Requested by: '([^']+)', Expected range: '([^']+)'").unwrap()
+});
+
+static FORGE_DUPLICATE_MODS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Mod ID: '([^']+)' from mod files: (.+)").unwrap());
+
+/// The lines after `DuplicateModsFoundException` in Forge 1.12, like `\tjei : mods/jei.jar`
+static LEGACY_FORGE_DUPLICATE_MOD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s+([\w\-.]+) : (.+)$").unwrap());
+
+/// Find and parse the most recent crash report in `crash_dir/crash-reports`.
+///
+/// `crash_dir` can be either the game directory or the `crash-reports` directory itself.
+/// Returns `None` if there is no crash report.
+pub fn parse_crash_report(crash_dir: &Path) -> Result<Option<CrashReport>> {
+    match find_latest_crash(crash_dir)? {
+        Some(path) => Ok(Some(CrashReport::parse(&fs::read_to_string(path)?)?)),
+        None => Ok(None),
+    }
+}
+
+/// Find the latest crash report in `crash-reports`.
+///
+/// `game_dir` can be either the game directory or the `crash-reports` directory itself.
+pub fn find_latest_crash<P: AsRef<Path>>(game_dir: P) -> Result<Option<PathBuf>> {
+    let game_dir = game_dir.as_ref();
+    let reports_dir = if game_dir.join("crash-reports").is_dir() {
+        game_dir.join("crash-reports")
+    } else {
+        game_dir.to_path_buf()
+    };
+    if !reports_dir.is_dir() {
+        return Ok(None);
+    }
+    // the file names contain the time, like `crash-2023-06-15_12.34.56-client.txt`
+    Ok(fs::read_dir(&reports_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with("crash-") && name.ends_with(".txt")
+        })
+        .max())
+}
+
+impl CrashReport {
+    pub fn parse(content: &str) -> Result<CrashReport> {
+        let lines = content.lines().collect::<Vec<_>>();
+
+        let time = lines
+            .iter()
+            .find_map(|line| line.strip_prefix("Time: "))
+            .ok_or(anyhow!("missing `Time` in crash report"))?
+            .trim();
+        let time = TIME_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+            .ok_or(anyhow!("unknown time format: {time}"))?
+            .and_utc();
+
+        let description_line = lines
+            .iter()
+            .position(|line| line.starts_with("Description: "))
+            .ok_or(anyhow!("missing `Description` in crash report"))?;
+        let description = lines[description_line]["Description: ".len()..]
+            .trim()
+            .to_string();
+
+        // the stacktrace follows the description until the detailed walkthrough
+        let mut stacktrace = lines[description_line + 1..]
+            .iter()
+            .take_while(|line| !line.starts_with("A detailed walkthrough"))
+            .copied()
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+        if stacktrace.is_empty() {
+            stacktrace = section(&lines, "Head")
+                .iter()
+                .skip_while(|line| !line.starts_with("Stacktrace:"))
+                .skip(1)
+                .copied()
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string();
+        }
+
+        let mut system_details: HashMap<String, String> = HashMap::new();
+        let mut last_key: Option<String> = None;
+        for line in section(&lines, "System Details") {
+            let Some(line) = line.strip_prefix('\t') else {
+                continue;
+            };
+            // multi-line values are indented again
+            if line.starts_with('\t') {
+                if let Some(value) = last_key
+                    .as_ref()
+                    .and_then(|key| system_details.get_mut(key))
+                {
+                    value.push('\n');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                system_details.insert(key.trim().to_string(), value.trim().to_string());
+                last_key = Some(key.trim().to_string());
+            }
+        }
+
+        Ok(CrashReport {
+            description,
+            stacktrace,
+            system_details,
+            time,
+        })
+    }
+
+    /// Guess the cause of the crash from the stacktrace
+    pub fn likely_cause(&self) -> CrashCause {
+        let stacktrace = &self.stacktrace;
+        if stacktrace.contains("java.lang.OutOfMemoryError") {
+            return CrashCause::OutOfMemory;
+        }
+        if GRAPHICS_DRIVER_PATTERNS
+            .iter()
+            .any(|pattern| stacktrace.contains(pattern))
+        {
+            return CrashCause::GraphicsDriver;
+        }
+        if MOD_CONFLICT_PATTERNS
+            .iter()
+            .any(|pattern| stacktrace.contains(pattern))
+        {
+            let suspected = SUSPECTED_MOD
+                .captures(stacktrace)
+                .or(self
+                    .system_details
+                    .get("Suspected Mods")
+                    .and_then(|mods| SUSPECTED_MOD.captures(mods)))
+                .map(|captures| captures[1].to_string())
+                .unwrap_or_default();
+            return CrashCause::ModConflict(suspected);
+        }
+        if JAVA_CRASH_PATTERNS
+            .iter()
+            .any(|pattern| stacktrace.contains(pattern))
+        {
+            return CrashCause::JavaCrash;
+        }
+        CrashCause::Unknown
+    }
+}
+
+/// The lines between `-- <name> --` and the next section
+fn section<'a>(lines: &[&'a str], name: &str) -> Vec<&'a str> {
+    let header = format!("-- {name} --");
+    lines
+        .iter()
+        .skip_while(|line| line.trim() != header)
+        .skip(1)
+        .take_while(|line| !line.starts_with("-- "))
+        .copied()
+        .collect()
+}
+
+/// Analyze a crash report or a game log
+pub fn analyze(report_text: &str) -> CrashAnalysis {
+    let mut problems = Vec::new();
+
+    if let Some(captures) = CLASS_VERSION.captures(report_text) {
+        let java = |index: usize| {
+            captures[index]
+                .parse::<u32>()
+                .ok()
+                .map(|version| version - CLASS_VERSION_OFFSET)
+        };
+        problems.push(CrashProblem::WrongJava {
+            required: java(1),
+            current: java(2),
+        });
+    } else if let Some(captures) = UNSUPPORTED_CLASS_VERSION.captures(report_text) {
+        // the java is newer than the one supported by the mod loader
+        problems.push(CrashProblem::WrongJava {
+            required: None,
+            current: captures[1]
+                .parse::<u32>()
+                .ok()
+                .map(|version| version - CLASS_VERSION_OFFSET),
+        });
+    }
+
+    for captures in FORGE_DUPLICATE_MODS.captures_iter(report_text) {
+        problems.push(CrashProblem::DuplicateMods {
+            mod_id: captures[1].to_string(),
+            files: captures[2]
+                .split(',')
+                .map(|file| file.trim().to_string())
+                .collect(),
+        });
+    }
+    if let Some((_, duplicates)) = report_text.split_once("DuplicateModsFoundException") {
+        let mut duplicate_mods: Vec<(String, Vec<String>)> = Vec::new();
+        for captures in duplicates
+            .lines()
+            .skip(1)
+            .map_while(|line| LEGACY_FORGE_DUPLICATE_MOD.captures(line))
+        {
+            let file = captures[2].trim().to_string();
+            match duplicate_mods.iter_mut().find(|(id, _)| id == &captures[1]) {
+                Some((_, files)) => files.push(file),
+                None => duplicate_mods.push((captures[1].to_string(), vec![file])),
+            }
+        }
+        problems.extend(
+            duplicate_mods
+                .into_iter()
+                .map(|(mod_id, files)| CrashProblem::DuplicateMods { mod_id, files }),
+        );
+    }
+
+    for captures in FABRIC_DEPENDENCY.captures_iter(report_text) {
+        let dependency = captures.get(3).or(captures.get(4)).unwrap().as_str();
+        problems.push(CrashProblem::MissingDependency {
+            mod_id: captures[1].to_string(),
+            dependency: dependency.to_string(),
+            version: captures[2]
+                .strip_prefix("version ")
+                .map(|version| version.to_string()),
+        });
+    }
+    for captures in FORGE_DEPENDENCY.captures_iter(report_text) {
+        problems.push(CrashProblem::MissingDependency {
+            mod_id: captures[2].to_string(),
+            dependency: captures[1].to_string(),
+            version: Some(captures[3].to_string()),
+        });
+    }
+
+    if let Some(line) = report_text.lines().find(|line| {
+        GRAPHICS_DRIVER_PATTERNS
+            .iter()
+            .any(|pattern| line.contains(pattern))
+    }) {
+        problems.push(CrashProblem::GraphicsDriver {
+            message: line.trim().to_string(),
+        });
+    }
+
+    if report_text.contains("java.lang.OutOfMemoryError") {
+        problems.push(CrashProblem::OutOfMemory);
+    }
+
+    let mut deduplicated = Vec::with_capacity(problems.len());
+    for problem in problems {
+        if !deduplicated.contains(&problem) {
+            deduplicated.push(problem);
+        }
+    }
+    CrashAnalysis {
+        problems: deduplicated,
+        mods: parse_mod_list(report_text),
+    }
+}
+
+/// Parse `Fabric Mods` or `Mod List` in the system details of a crash report
+fn parse_mod_list(report_text: &str) -> Vec<CrashModEntry> {
+    let mut lines = report_text.lines();
+    let Some(header) = lines
+        .by_ref()
+        .find(|line| matches!(line.trim(), "Fabric Mods:" | "Mod List:"))
+    else {
+        return vec![];
+    };
+    let is_fabric = header.trim() == "Fabric Mods:";
+    lines
+        .take_while(|line| line.starts_with("\t\t"))
+        .filter_map(|line| {
+            if is_fabric {
+                // `fabric-api: Fabric API 0.87.0+1.20.1`
+                let (mod_id, rest) = line.trim().split_once(": ")?;
+                let (name, version) = rest.rsplit_once(' ')?;
+                Some(CrashModEntry {
+                    mod_id: mod_id.to_string(),
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    file: None,
+                })
+            } else {
+                // `file |name |mod id |version |state |Manifest: ...`
+                let columns = line
+                    .split('|')
+                    .map(|column| column.trim())
+                    .collect::<Vec<_>>();
+                if columns.len() < 4 {
+                    return None;
+                }
+                Some(CrashModEntry {
+                    mod_id: columns[2].to_string(),
+                    name: columns[1].to_string(),
+                    version: columns[3].to_string(),
+                    file: Some(columns[0].to_string()),
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Timelike};
+
+    use super::*;
+
+    const REPORT: &str = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2023-06-15 12:34:56
+Description: Rendering overlay
+
+java.lang.OutOfMemoryError: Java heap space
+\tat java.base/java.util.Arrays.copyOf(Arrays.java:3537)
+\tat net.minecraft.client.Minecraft.run(Minecraft.java:123)
+
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- Head --
+Thread: Render thread
+Stacktrace:
+\tat java.base/java.util.Arrays.copyOf(Arrays.java:3537)
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.20.1
+\tJava Version: 17.0.8, Eclipse Adoptium
+\tMemory: 12345 bytes (0 MiB) / 67890 bytes (0 MiB) up to 1073741824 bytes (1024 MiB)
+\tJVM Flags: 2 total;
+\t\t-Xmx1024M
+\t\t-Xms256M
+";
+
+    #[test]
+    fn test_parse() {
+        let report = CrashReport::parse(REPORT).unwrap();
+        assert_eq!(report.description, "Rendering overlay");
+        assert!(report
+            .stacktrace
+            .starts_with("java.lang.OutOfMemoryError: Java heap space"));
+        assert_eq!(report.time.year(), 2023);
+        assert_eq!(report.time.hour(), 12);
+        assert_eq!(report.system_details["Minecraft Version"], "1.20.1");
+        assert_eq!(
+            report.system_details["JVM Flags"],
+            "2 total;\n-Xmx1024M\n-Xms256M"
+        );
+        assert_eq!(report.likely_cause(), CrashCause::OutOfMemory);
+    }
+
+    #[test]
+    fn test_likely_cause() {
+        let mut report = CrashReport::parse(REPORT).unwrap();
+        report.stacktrace = "org.spongepowered.asm.mixin.transformer.throwables.MixinTransformerError: An unexpected critical error was encountered
+Caused by: org.spongepowered.asm.mixin.injection.throwables.InjectionError: Critical injection failure: Redirector redirect$zzb000 in sodium.mixins.json:core.MixinWindow from mod sodium failed injection check".to_string();
+        assert_eq!(
+            report.likely_cause(),
+            CrashCause::ModConflict("sodium".to_string())
+        );
+        report.stacktrace = "java.lang.IllegalStateException: GLFW error before init: [0x10008]Cannot use IME API on this platform\norg.lwjgl.LWJGLException: Pixel format not accelerated".to_string();
+        assert_eq!(report.likely_cause(), CrashCause::GraphicsDriver);
+        report.stacktrace =
+            "java.lang.NullPointerException: Cannot invoke \"Object.toString()\"".to_string();
+        assert_eq!(report.likely_cause(), CrashCause::Unknown);
+    }
+
+    #[test]
+    fn test_latest_report() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(parse_crash_report(dir.path()).unwrap(), None);
+        let reports = dir.path().join("crash-reports");
+        fs::create_dir(&reports).unwrap();
+        fs::write(
+            reports.join("crash-2023-06-14_10.00.00-client.txt"),
+            REPORT.replace("Rendering overlay", "Ticking entity"),
+        )
+        .unwrap();
+        fs::write(reports.join("crash-2023-06-15_12.34.56-client.txt"), REPORT).unwrap();
+        let report = parse_crash_report(dir.path()).unwrap().unwrap();
+        assert_eq!(report.description, "Rendering overlay");
+    }
+
+    #[test]
+    fn test_fabric_missing_dependency() {
+        let analysis = analyze(include_str!(
+            "../../mock/crash/fabric-missing-dependency.txt"
+        ));
+        assert_eq!(
+            analysis.problems,
+            vec![
+                CrashProblem::MissingDependency {
+                    mod_id: "modmenu".to_string(),
+                    dependency: "fabric-api".to_string(),
+                    version: None,
+                },
+                CrashProblem::MissingDependency {
+                    mod_id: "iris".to_string(),
+                    dependency: "sodium".to_string(),
+                    version: Some("0.5.0 or later".to_string()),
+                },
+            ]
+        );
+        assert_eq!(analysis.mods.len(), 6);
+        assert_eq!(
+            analysis.mods[5],
+            CrashModEntry {
+                mod_id: "sodium".to_string(),
+                name: "Sodium".to_string(),
+                version: "0.4.10+build.27".to_string(),
+                file: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_forge() {
+        let analysis = analyze(include_str!(
+            "../../mock/crash/forge-missing-dependency.log"
+        ));
+        assert_eq!(
+            analysis.problems,
+            vec![
+                CrashProblem::MissingDependency {
+                    mod_id: "alexsmobs".to_string(),
+                    dependency: "geckolib".to_string(),
+                    version: Some("[4.2,)".to_string()),
+                },
+                CrashProblem::MissingDependency {
+                    mod_id: "alexsmobs".to_string(),
+                    dependency: "citadel".to_string(),
+                    version: Some("[2.4.1,)".to_string()),
+                },
+            ]
+        );
+
+        let analysis = analyze(include_str!("../../mock/crash/forge-mod-list.txt"));
+        assert!(analysis.problems.is_empty());
+        assert_eq!(
+            analysis.mods[2],
+            CrashModEntry {
+                mod_id: "alexsmobs".to_string(),
+                name: "Alex's Mobs".to_string(),
+                version: "1.22.5".to_string(),
+                file: Some("alexsmobs-1.22.5.jar".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_duplicate_mods() {
+        for report in [
+            include_str!("../../mock/crash/forge-duplicate-mods.txt"),
+            include_str!("../../mock/crash/forge-duplicate-mods-new.log"),
+        ] {
+            let analysis = analyze(report);
+            let [CrashProblem::DuplicateMods { mod_id, files }] = analysis.problems.as_slice()
+            else {
+                panic!("unexpected problems: {:?}", analysis.problems);
+            };
+            assert_eq!(mod_id, "jei");
+            assert_eq!(files.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_environment_problems() {
+        assert_eq!(
+            analyze(include_str!("../../mock/crash/wrong-java.log")).problems,
+            vec![CrashProblem::WrongJava {
+                required: Some(17),
+                current: Some(8),
+            }]
+        );
+        assert_eq!(
+            analyze(include_str!("../../mock/crash/out-of-memory.txt")).problems,
+            vec![CrashProblem::OutOfMemory]
+        );
+        let problems = analyze(include_str!("../../mock/crash/opengl.log")).problems;
+        assert!(matches!(
+            problems.as_slice(),
+            [CrashProblem::GraphicsDriver { message }] if message.ends_with("does not appear to support OpenGL")
+        ));
+        assert_eq!(analyze("").problems, vec![]);
+    }
+
+    #[test]
+    fn test_find_latest_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(find_latest_crash(dir.path()).unwrap(), None);
+        let reports = dir.path().join("crash-reports");
+        fs::create_dir_all(&reports).unwrap();
+        for name in [
+            "crash-2023-06-15_12.34.56-client.txt",
+            "crash-2023-08-02_21.14.07-client.txt",
+            "readme.md",
+        ] {
+            fs::write(reports.join(name), "").unwrap();
+        }
+        assert_eq!(
+            find_latest_crash(dir.path()).unwrap(),
+            Some(reports.join("crash-2023-08-02_21.14.07-client.txt"))
+        );
+    }
+}
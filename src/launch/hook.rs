@@ -0,0 +1,163 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Run the user commands around the game, like a pre-launch script or a post-exit backup.
+//!
+//! The hooks and the game get the environment variables of [`instance_env`], so a hook can find
+//! the instance with `$INST_MC_DIR`.
+
+use std::{collections::HashMap, fmt::Display, process::ExitStatus};
+
+use anyhow::Result;
+use tokio::process::Command;
+
+use crate::core::version::ResolvedVersion;
+
+use super::{argument::LaunchWarning, options::LaunchOptions};
+
+/// A hook exited with an error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookFailed {
+    pub command: Vec<String>,
+    pub status: ExitStatus,
+}
+
+impl Display for HookFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hook `{}` failed with {}",
+            self.command.join(" "),
+            self.status
+        )
+    }
+}
+
+impl std::error::Error for HookFailed {}
+
+/// The environment variables of the game and the hooks, the `env` of the launch options plus
+///
+/// * `INST_NAME` - the version id
+/// * `INST_DIR` - the version root
+/// * `INST_MC_DIR` - the game directory
+/// * `INST_MC_VER` - the Minecraft version
+/// * `INST_JAVA` - the java executable
+pub fn instance_env(options: &LaunchOptions, version: &ResolvedVersion) -> HashMap<String, String> {
+    let minecraft_version = version
+        .inheritances
+        .last()
+        .unwrap_or(&version.minecraft_version);
+    let mut env = HashMap::from([
+        ("INST_NAME".to_string(), options.version_id.clone()),
+        (
+            "INST_DIR".to_string(),
+            options.version_root.to_string_lossy().to_string(),
+        ),
+        (
+            "INST_MC_DIR".to_string(),
+            options.game_directory().to_string_lossy().to_string(),
+        ),
+        ("INST_MC_VER".to_string(), minecraft_version.clone()),
+        (
+            "INST_JAVA".to_string(),
+            options.java_path.to_string_lossy().to_string(),
+        ),
+    ]);
+    env.extend(options.env.clone());
+    env
+}
+
+/// Run a hook to completion, the first item of `command` is the program. An empty command does
+/// nothing.
+pub async fn run_hook(command: &[String], env: &HashMap<String, String>) -> Result<()> {
+    let Some((program, args)) = command.split_first() else {
+        return Ok(());
+    };
+    let status = Command::new(program).args(args).envs(env).status().await?;
+    if !status.success() {
+        return Err(HookFailed {
+            command: command.to_vec(),
+            status,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Run the `pre_launch` hook of the launch options, its failure is returned as a warning if
+/// `ignore_hook_failure` is set
+pub async fn run_pre_launch(
+    options: &LaunchOptions,
+    env: &HashMap<String, String>,
+) -> Result<Option<LaunchWarning>> {
+    let Some(command) = &options.pre_launch else {
+        return Ok(None);
+    };
+    match run_hook(command, env).await {
+        Ok(()) => Ok(None),
+        Err(err) if options.ignore_hook_failure => Ok(Some(LaunchWarning::HookFailed {
+            error: err.to_string(),
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Run the `post_exit` hook, the game has exited so a failure is only returned as a warning
+pub async fn run_post_exit(
+    command: &[String],
+    env: &HashMap<String, String>,
+) -> Option<LaunchWarning> {
+    run_hook(command, env)
+        .await
+        .err()
+        .map(|err| LaunchWarning::HookFailed {
+            error: err.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker");
+        let env = HashMap::from([("INST_MC_VER".to_string(), "1.20.1".to_string())]);
+        let command = vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            format!("echo $INST_MC_VER > {}", marker.display()),
+        ];
+        run_hook(&command, &env).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "1.20.1\n");
+
+        run_hook(&[], &env).await.unwrap();
+
+        let command = vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "exit 2".to_string(),
+        ];
+        let err = run_hook(&command, &env).await.unwrap_err();
+        let failed = err.downcast_ref::<HookFailed>().unwrap();
+        assert_eq!(failed.command, command);
+        assert_eq!(failed.status.code(), Some(2));
+    }
+}
@@ -0,0 +1,639 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Generate JVM arguments.
+//!
+//! # Example
+//!
+//! Configure the heap size by the system memory:
+//!
+//! ```
+//! use mgl_core::launch::jvm::{generate_memory_args, get_total_system_ram, recommend_memory};
+//!
+//! let config = recommend_memory(get_total_system_ram().unwrap());
+//! let args = generate_memory_args(&config);
+//! ```
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::options::GC;
+
+/// The minimum heap size the game can run with
+pub const MIN_MEMORY_MB: u32 = 512;
+
+/// The recommended max heap size will not exceed this, a larger heap makes GC pauses longer
+pub const MAX_RECOMMENDED_MEMORY_MB: u32 = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    pub min_mb: u32,
+    pub max_mb: u32,
+}
+
+/// Recommend the heap size: max is 25% of the system memory, between 512 MB and 8 GB
+pub fn recommend_memory(total_ram_bytes: u64) -> MemoryConfig {
+    let quarter_mb = total_ram_bytes / 1024 / 1024 / 4;
+    let max_mb = quarter_mb.clamp(MIN_MEMORY_MB as u64, MAX_RECOMMENDED_MEMORY_MB as u64) as u32;
+    MemoryConfig {
+        min_mb: MIN_MEMORY_MB,
+        max_mb,
+    }
+}
+
+/// The max heap size used when the system memory can't be detected
+pub const DEFAULT_MAX_MEMORY_MB: u32 = 2048;
+
+/// Fill the unset heap sizes, the max is recommended by the system memory if it's detected.
+///
+/// The min is never larger than the max.
+pub fn resolve_memory(
+    min_mb: Option<u32>,
+    max_mb: Option<u32>,
+    total_ram_bytes: Result<u64>,
+) -> MemoryConfig {
+    let recommended = match total_ram_bytes {
+        Ok(total_ram_bytes) => recommend_memory(total_ram_bytes),
+        Err(_) => MemoryConfig {
+            min_mb: MIN_MEMORY_MB,
+            max_mb: DEFAULT_MAX_MEMORY_MB,
+        },
+    };
+    let max_mb = max_mb.unwrap_or(recommended.max_mb);
+    MemoryConfig {
+        min_mb: min_mb.unwrap_or(recommended.min_mb).min(max_mb),
+        max_mb,
+    }
+}
+
+/// Generate `-Xms` and `-Xmx`
+pub fn generate_memory_args(config: &MemoryConfig) -> Vec<String> {
+    vec![
+        format!("-Xms{}M", config.min_mb),
+        format!("-Xmx{}M", config.max_mb),
+    ]
+}
+
+/// The heap sizes are set but may not work well
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryWarning {
+    /// The min heap size is larger than the max, the max is used as the min
+    MinExceedsMax { min_mb: u32, max_mb: u32 },
+    /// The max heap size is larger than the physical memory
+    MaxExceedsPhysical { max_mb: u32, physical_mb: u32 },
+}
+
+impl Display for MemoryWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryWarning::MinExceedsMax { min_mb, max_mb } => write!(
+                f,
+                "the min heap size {min_mb}M is larger than the max {max_mb}M"
+            ),
+            MemoryWarning::MaxExceedsPhysical {
+                max_mb,
+                physical_mb,
+            } => write!(
+                f,
+                "the max heap size {max_mb}M is larger than the physical memory {physical_mb}M"
+            ),
+        }
+    }
+}
+
+/// Check the heap sizes set by the user, the physical memory is not checked if it can't be
+/// detected
+pub fn check_memory(
+    min_mb: Option<u32>,
+    max_mb: Option<u32>,
+    total_ram_bytes: Result<u64>,
+) -> Vec<MemoryWarning> {
+    let mut warnings = Vec::new();
+    if let (Some(min_mb), Some(max_mb)) = (min_mb, max_mb) {
+        if min_mb > max_mb {
+            warnings.push(MemoryWarning::MinExceedsMax { min_mb, max_mb });
+        }
+    }
+    if let (Some(max_mb), Ok(total_ram_bytes)) = (max_mb, total_ram_bytes) {
+        let physical_mb = (total_ram_bytes / 1024 / 1024) as u32;
+        if max_mb > physical_mb {
+            warnings.push(MemoryWarning::MaxExceedsPhysical {
+                max_mb,
+                physical_mb,
+            });
+        }
+    }
+    warnings
+}
+
+/// The version of a Java installation, reported by the java executable
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JavaVersion {
+    /// Like `8` for `1.8.0_382`, or `17` for `17.0.8`
+    pub major: u32,
+    pub minor: u32,
+    /// The update of Java 8 and earlier, like `382` for `1.8.0_382`
+    pub patch: u32,
+    /// The `java.vendor` property, like `Oracle Corporation` or `Eclipse Adoptium`
+    pub vendor: String,
+    /// The `os.arch` property, like `amd64` or `aarch64`
+    pub arch: String,
+}
+
+impl JavaVersion {
+    /// Shenandoah is excluded from the builds of Oracle, the Java of an unknown vendor is assumed
+    /// to be one of them
+    fn has_shenandoah(&self) -> bool {
+        self.major >= 12 && !self.vendor.is_empty() && !self.vendor.contains("Oracle")
+    }
+}
+
+/// The Java can't run the game or the installer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleJava {
+    pub path: PathBuf,
+    pub required_major: u32,
+    pub found_major: u32,
+}
+
+impl Display for IncompatibleJava {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Java {} or newer is required, but {} is Java {}",
+            self.required_major,
+            self.path.display(),
+            self.found_major
+        )
+    }
+}
+
+impl std::error::Error for IncompatibleJava {}
+
+static JAVA_VERSION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"version "(?:1\.)?(\d+)(?:\.(\d+))?(?:\.(\d+))?(?:_(\d+))?"#).unwrap()
+});
+
+/// Run `<java> -XshowSettings:properties -version` and parse its output, which is printed to
+/// stderr
+pub fn detect_java_version(java_executable: &Path) -> Result<JavaVersion> {
+    let output = Command::new(java_executable)
+        .args(["-XshowSettings:properties", "-version"])
+        .output()
+        .map_err(|err| anyhow!("failed to run {}: {err}", java_executable.display()))?;
+    parse_java_version(&String::from_utf8_lossy(&output.stderr))
+}
+
+/// Parse the output of `java -XshowSettings:properties -version`, the vendor and the arch are
+/// empty if the properties are not printed
+fn parse_java_version(output: &str) -> Result<JavaVersion> {
+    let captures = JAVA_VERSION_REGEX
+        .captures(output)
+        .ok_or(anyhow!("no java version in the output: {output}"))?;
+    let number = |index: usize| {
+        captures
+            .get(index)
+            .map_or(Ok(0), |number| number.as_str().parse())
+    };
+    let property = |key: &str| {
+        output
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.trim().split_once(" = ")?;
+                (name == key).then(|| value.to_string())
+            })
+            .unwrap_or_default()
+    };
+    Ok(JavaVersion {
+        major: number(1)?,
+        minor: number(2)?,
+        patch: match captures.get(4) {
+            Some(_) => number(4)?,
+            None => number(3)?,
+        },
+        vendor: property("java.vendor"),
+        arch: property("os.arch"),
+    })
+}
+
+/// Check if the Java can run a program requiring `required_major`, a newer Java is compatible
+pub fn is_java_compatible(java: &JavaVersion, required_major: u32) -> bool {
+    java.major >= required_major
+}
+
+/// Detect the version of the java executable, [`IncompatibleJava`] is returned if it's older
+/// than `required_major`
+pub fn check_java(java_executable: &Path, required_major: u32) -> Result<JavaVersion> {
+    let java = detect_java_version(java_executable)?;
+    if !is_java_compatible(&java, required_major) {
+        return Err(IncompatibleJava {
+            path: java_executable.to_path_buf(),
+            required_major,
+            found_major: java.major,
+        }
+        .into());
+    }
+    Ok(java)
+}
+
+/// Whether the Java supports the GC
+pub fn is_gc_supported(gc: &GC, java: &JavaVersion) -> bool {
+    match gc {
+        // removed in Java 15, `UseParallelGC` collects the old generation in parallel too
+        GC::ParallelOld => java.major < 15,
+        GC::Z => java.major >= 15,
+        GC::Shenandoah => java.has_shenandoah(),
+        _ => true,
+    }
+}
+
+/// Generate the flags of the GC. The GC not supported by the Java is skipped, so the default GC of
+/// the JVM is used instead of failing to start, see [`is_gc_supported`].
+pub fn generate_gc_args(gc: &GC, java: &JavaVersion) -> Vec<String> {
+    if !is_gc_supported(gc, java) {
+        return vec![];
+    }
+    match gc {
+        GC::G1 => vec![
+            "-XX:+UseG1GC".to_string(),
+            "-XX:+UnlockExperimentalVMOptions".to_string(),
+            "-XX:G1NewSizePercent=20".to_string(),
+            "-XX:G1ReservePercent=20".to_string(),
+            "-XX:MaxGCPauseMillis=50".to_string(),
+            "-XX:G1HeapRegionSize=16M".to_string(),
+        ],
+        GC::Parallel => vec![
+            "-XX:+UseParallelGC".to_string(),
+            format!(
+                "-XX:ParallelGCThreads={num}",
+                num = num_cpus::get_physical()
+            ),
+        ],
+        GC::ParallelOld => vec!["-XX:+UseParallelOldGC".to_string()],
+        GC::Serial => vec!["-XX:+UseSerialGC".to_string()],
+        GC::Z => vec!["-XX:+UseZGC".to_string()],
+        GC::Shenandoah => vec!["-XX:+UseShenandoahGC".to_string()],
+        GC::None => vec![],
+    }
+}
+
+/// GC tuning flags to reduce the GC stutter of the game
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum JvmProfile {
+    /// No flags, use the default GC of the JVM
+    #[default]
+    Default,
+    /// G1 with a 200ms pause target and the region size scaled with the heap
+    G1GC,
+    /// The commonly recommended G1 flags by Aikar, see <https://docs.papermc.io/paper/aikars-flags>
+    Aikar,
+    /// ZGC, requires Java 15 or newer
+    ZGC,
+    /// G1 with a shorter pause target, uses more CPU
+    Aggressive,
+    Custom(Vec<String>),
+}
+
+impl JvmProfile {
+    pub fn to_args(&self, max_heap_mb: u32) -> Vec<String> {
+        let args: Vec<&str> = match self {
+            JvmProfile::Default => vec![],
+            JvmProfile::G1GC => vec![
+                "-XX:+UseG1GC",
+                "-XX:+UnlockExperimentalVMOptions",
+                "-XX:MaxGCPauseMillis=200",
+                g1_region_size(max_heap_mb),
+            ],
+            JvmProfile::Aikar => g1_args(max_heap_mb, "-XX:MaxGCPauseMillis=200"),
+            JvmProfile::Aggressive => {
+                let mut args = g1_args(max_heap_mb, "-XX:MaxGCPauseMillis=50");
+                args.push("-XX:+UseStringDeduplication");
+                args
+            }
+            JvmProfile::ZGC => vec![
+                "-XX:+UseZGC",
+                "-XX:+DisableExplicitGC",
+                "-XX:+AlwaysPreTouch",
+                "-XX:+PerfDisableSharedMem",
+            ],
+            JvmProfile::Custom(args) => return args.clone(),
+        };
+        args.into_iter().map(String::from).collect()
+    }
+}
+
+/// Larger regions for heaps over 12 GB
+fn g1_region_size(max_heap_mb: u32) -> &'static str {
+    if max_heap_mb > 12 * 1024 {
+        "-XX:G1HeapRegionSize=16M"
+    } else {
+        "-XX:G1HeapRegionSize=8M"
+    }
+}
+
+/// See <https://docs.papermc.io/paper/aikars-flags>, larger young generation for heaps over 12 GB
+fn g1_args(max_heap_mb: u32, max_pause: &'static str) -> Vec<&'static str> {
+    let large_heap = max_heap_mb > 12 * 1024;
+    let mut args = vec![
+        "-XX:+UseG1GC",
+        "-XX:+ParallelRefProcEnabled",
+        "-XX:+UnlockExperimentalVMOptions",
+        "-XX:+DisableExplicitGC",
+        "-XX:+AlwaysPreTouch",
+        "-XX:G1HeapWastePercent=5",
+        "-XX:G1MixedGCCountTarget=4",
+        "-XX:G1MixedGCLiveThresholdPercent=90",
+        "-XX:G1RSetUpdatingPauseTimePercent=5",
+        "-XX:SurvivorRatio=32",
+        "-XX:+PerfDisableSharedMem",
+        "-XX:MaxTenuringThreshold=1",
+        max_pause,
+        g1_region_size(max_heap_mb),
+    ];
+    if large_heap {
+        args.extend([
+            "-XX:G1NewSizePercent=40",
+            "-XX:G1MaxNewSizePercent=50",
+            "-XX:G1ReservePercent=15",
+            "-XX:InitiatingHeapOccupancyPercent=20",
+        ]);
+    } else {
+        args.extend([
+            "-XX:G1NewSizePercent=30",
+            "-XX:G1MaxNewSizePercent=40",
+            "-XX:G1ReservePercent=20",
+            "-XX:InitiatingHeapOccupancyPercent=15",
+        ]);
+    }
+    args
+}
+
+/// Get the total physical memory in bytes
+pub fn get_total_system_ram() -> Result<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        parse_meminfo(&std::fs::read_to_string("/proc/meminfo")?)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("sysctl")
+            .args(["-n", "hw.memsize"])
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?.trim().parse()?)
+    }
+    #[cfg(windows)]
+    {
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-c",
+                "(Get-CimInstance Win32_ComputerSystem).TotalPhysicalMemory",
+            ])
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?.trim().parse()?)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    {
+        Err(anyhow!("unsupported platform"))
+    }
+}
+
+/// Read `MemTotal` from the content of `/proc/meminfo`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_meminfo(meminfo: &str) -> Result<u64> {
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemTotal:"))
+        .ok_or(anyhow!("missing `MemTotal` in /proc/meminfo"))?;
+    // like `MemTotal:       16314564 kB`
+    let kb: u64 = line
+        .trim_start_matches("MemTotal:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()?;
+    Ok(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_recommend_memory() {
+        assert_eq!(recommend_memory(16 * GB).max_mb, 4096);
+        assert_eq!(recommend_memory(64 * GB).max_mb, 8192);
+        assert_eq!(recommend_memory(GB).max_mb, 512);
+        assert_eq!(
+            generate_memory_args(&recommend_memory(8 * GB)),
+            vec!["-Xms512M", "-Xmx2048M"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_memory() {
+        assert_eq!(
+            resolve_memory(Some(1024), Some(3072), Ok(16 * GB)),
+            MemoryConfig {
+                min_mb: 1024,
+                max_mb: 3072
+            }
+        );
+        let auto = resolve_memory(None, None, Ok(12 * GB));
+        assert!((MIN_MEMORY_MB..=MAX_RECOMMENDED_MEMORY_MB).contains(&auto.max_mb));
+        assert_eq!(auto.max_mb, 3072);
+        assert_eq!(resolve_memory(None, None, Ok(128 * GB)).max_mb, 8192);
+        assert_eq!(
+            resolve_memory(None, None, Err(anyhow!("unsupported platform"))).max_mb,
+            DEFAULT_MAX_MEMORY_MB
+        );
+        // the min follows an explicit max smaller than it
+        assert_eq!(resolve_memory(None, Some(256), Ok(16 * GB)).min_mb, 256);
+    }
+
+    #[test]
+    fn test_jvm_profile() {
+        assert!(JvmProfile::Default.to_args(4096).is_empty());
+        let g1 = JvmProfile::G1GC.to_args(4096);
+        assert!(g1.contains(&"-XX:+UseG1GC".to_string()));
+        assert!(g1.contains(&"-XX:MaxGCPauseMillis=200".to_string()));
+        assert!(g1.contains(&"-XX:G1HeapRegionSize=8M".to_string()));
+        assert!(JvmProfile::G1GC
+            .to_args(16384)
+            .contains(&"-XX:G1HeapRegionSize=16M".to_string()));
+        let aikar = JvmProfile::Aikar.to_args(8192);
+        assert!(aikar.contains(&"-XX:G1HeapRegionSize=8M".to_string()));
+        assert!(aikar.contains(&"-XX:+ParallelRefProcEnabled".to_string()));
+        assert!(!g1.contains(&"-XX:+ParallelRefProcEnabled".to_string()));
+        assert!(JvmProfile::Aikar
+            .to_args(16384)
+            .contains(&"-XX:G1HeapRegionSize=16M".to_string()));
+        assert!(JvmProfile::Aggressive
+            .to_args(4096)
+            .contains(&"-XX:MaxGCPauseMillis=50".to_string()));
+        assert_eq!(JvmProfile::ZGC.to_args(4096)[0], "-XX:+UseZGC");
+        assert_eq!(
+            JvmProfile::Custom(vec!["-XX:+UseShenandoahGC".to_string()]).to_args(4096),
+            vec!["-XX:+UseShenandoahGC"]
+        );
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let meminfo = "MemTotal:       16314564 kB\nMemFree:         1234567 kB\n";
+        assert_eq!(parse_meminfo(meminfo).unwrap(), 16314564 * 1024);
+        assert!(parse_meminfo("MemFree: 1 kB").is_err());
+    }
+
+    #[test]
+    fn test_check_memory() {
+        assert!(check_memory(Some(1024), Some(4096), Ok(16 * GB)).is_empty());
+        assert!(check_memory(None, None, Ok(GB)).is_empty());
+        assert_eq!(
+            check_memory(Some(4096), Some(2048), Ok(16 * GB)),
+            vec![MemoryWarning::MinExceedsMax {
+                min_mb: 4096,
+                max_mb: 2048
+            }]
+        );
+        assert_eq!(
+            check_memory(None, Some(32768), Ok(16 * GB)),
+            vec![MemoryWarning::MaxExceedsPhysical {
+                max_mb: 32768,
+                physical_mb: 16384
+            }]
+        );
+        assert!(check_memory(None, Some(32768), Err(anyhow!("unsupported platform"))).is_empty());
+    }
+
+    #[test]
+    fn test_parse_java_version() {
+        let output = "java version \"1.8.0_382\"\nJava(TM) SE Runtime Environment (build 1.8.0_382-b05)\nJava HotSpot(TM) 64-Bit Server VM (build 25.382-b05, mixed mode)\n";
+        assert_eq!(
+            parse_java_version(output).unwrap(),
+            JavaVersion {
+                major: 8,
+                minor: 0,
+                patch: 382,
+                vendor: String::new(),
+                arch: String::new(),
+            }
+        );
+        let output = "Property settings:\n    java.vendor = Eclipse Adoptium\n    java.version = 17.0.8\n    os.arch = amd64\n\nopenjdk version \"17.0.8\" 2023-07-18\nOpenJDK Runtime Environment Temurin-17.0.8+7 (build 17.0.8+7)\n";
+        assert_eq!(
+            parse_java_version(output).unwrap(),
+            JavaVersion {
+                major: 17,
+                minor: 0,
+                patch: 8,
+                vendor: "Eclipse Adoptium".to_string(),
+                arch: "amd64".to_string(),
+            }
+        );
+        let java = parse_java_version("openjdk version \"21\" 2023-09-19").unwrap();
+        assert_eq!((java.major, java.minor, java.patch), (21, 0, 0));
+        assert!(is_java_compatible(&java, 17));
+        assert!(!is_java_compatible(&java, 22));
+        assert!(parse_java_version("Error: could not find libjava.so").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_java() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let java = dir.path().join("java");
+        std::fs::write(
+            &java,
+            "#!/bin/sh\necho 'openjdk version \"11.0.20\" 2023-07-18' >&2\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&java, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(check_java(&java, 8).unwrap().major, 11);
+        let err = check_java(&java, 17).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<IncompatibleJava>(),
+            Some(&IncompatibleJava {
+                path: java,
+                required_major: 17,
+                found_major: 11,
+            })
+        );
+        assert!(detect_java_version(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_gc_args() {
+        let java = |major: u32, vendor: &str| JavaVersion {
+            major,
+            vendor: vendor.to_string(),
+            ..Default::default()
+        };
+        let java_8 = java(8, "Oracle Corporation");
+        let java_17 = java(17, "Eclipse Adoptium");
+        let oracle_17 = java(17, "Oracle Corporation");
+
+        assert_eq!(generate_gc_args(&GC::G1, &java_8)[0], "-XX:+UseG1GC");
+        assert!(generate_gc_args(&GC::G1, &java_8).contains(&"-XX:G1NewSizePercent=20".to_string()));
+        assert_eq!(
+            generate_gc_args(&GC::G1, &java_17),
+            generate_gc_args(&GC::G1, &java_8)
+        );
+        assert_eq!(
+            generate_gc_args(&GC::Parallel, &java_17)[0],
+            "-XX:+UseParallelGC"
+        );
+        assert_eq!(
+            generate_gc_args(&GC::Serial, &java_8),
+            vec!["-XX:+UseSerialGC"]
+        );
+
+        assert_eq!(
+            generate_gc_args(&GC::ParallelOld, &java_8),
+            vec!["-XX:+UseParallelOldGC"]
+        );
+        assert!(generate_gc_args(&GC::ParallelOld, &java_17).is_empty());
+
+        assert!(generate_gc_args(&GC::Z, &java_8).is_empty());
+        assert_eq!(generate_gc_args(&GC::Z, &java_17), vec!["-XX:+UseZGC"]);
+
+        assert!(generate_gc_args(&GC::Shenandoah, &java(11, "Eclipse Adoptium")).is_empty());
+        assert!(generate_gc_args(&GC::Shenandoah, &oracle_17).is_empty());
+        let unknown_vendor = java(17, "");
+        assert!(generate_gc_args(&GC::Shenandoah, &unknown_vendor).is_empty());
+        assert_eq!(
+            generate_gc_args(&GC::Shenandoah, &java_17),
+            vec!["-XX:+UseShenandoahGC"]
+        );
+
+        assert!(generate_gc_args(&GC::None, &java_17).is_empty());
+        assert!(is_gc_supported(&GC::None, &java_8));
+        assert!(!is_gc_supported(&GC::Z, &java_8));
+    }
+}
@@ -25,9 +25,14 @@ use std::{
 
 use anyhow::Result;
 
-use crate::core::{folder::MinecraftLocation, JavaExec, PlatformInfo};
+use crate::core::{folder::MinecraftLocation, JavaExec};
 
-use super::{argument::LaunchArguments, options::LaunchOptions};
+use super::{
+    argument::LaunchWarning,
+    hook::{run_post_exit, run_pre_launch},
+    options::LaunchOptions,
+    process::GameProcessBuilder,
+};
 
 /// All game launcher
 ///
@@ -41,6 +46,9 @@ pub struct Launcher {
 
     pub exit_status: Option<ExitStatus>,
 
+    /// The warnings of the last launch, like the ignored launch options and the failed hooks
+    pub warnings: Vec<LaunchWarning>,
+
     pub java: JavaExec,
 }
 
@@ -57,6 +65,7 @@ impl Launcher {
             minecraft,
             check_game_integrity: true,
             exit_status: None,
+            warnings: Vec::new(),
             java,
         })
     }
@@ -68,6 +77,7 @@ impl Launcher {
             launch_options,
             check_game_integrity: true,
             exit_status: None,
+            warnings: Vec::new(),
             java,
         }
     }
@@ -99,17 +109,13 @@ impl Launcher {
             Some(on_exit) => on_exit,
         };
 
-        let platform = PlatformInfo::new().await;
         let options = self.launch_options.clone();
-        let version = self
-            .launch_options
-            .version
-            .parse(&self.minecraft, &platform)
-            .await?;
-        let mut command = LaunchArguments::from_launch_options(options.clone(), version.clone())
-            .await?
-            .to_async_command(self.java.clone(), options, &platform)
-            .await?;
+        let (mut command, env, warnings) =
+            GameProcessBuilder::new(options.clone(), self.java.clone())
+                .prepare()
+                .await?;
+        self.warnings = warnings;
+        self.warnings.extend(run_pre_launch(&options, &env).await?);
 
         let mut child = command
             .stdout(Stdio::piped())
@@ -164,6 +170,9 @@ impl Launcher {
                 break;
             }
         }
+        if let Some(post_exit) = &options.post_exit {
+            self.warnings.extend(run_post_exit(post_exit, &env).await);
+        }
 
         Ok(())
     }
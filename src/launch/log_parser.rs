@@ -0,0 +1,373 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Parse the log output of the game.
+//!
+//! The game prints plain lines like `[12:34:56] [Render thread/INFO]: message` by default. When
+//! the log4j config of the version is used (the `logging` argument of the version json), the
+//! events are printed as log4j XML instead. [`LogParser`] accepts both formats, the lines of a
+//! multi-line message, such as a stack trace, are joined into one [`LogEvent`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use futures::StreamExt;
+//! use mgl_core::launch::{log_parser::LogEvent, process::GameProcess};
+//!
+//! async fn fn_name(mut process: GameProcess) {
+//!     let mut events = Box::pin(process.stdout_events());
+//!     while let Some(LogEvent { time, level, thread, message, .. }) = events.next().await {
+//!         println!("[{time}] [{thread}/{level}]: {message}");
+//!     }
+//! }
+//! ```
+
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::anyhow;
+use chrono::{Local, NaiveTime, TimeZone};
+use futures::{stream, Stream, StreamExt};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static PLAIN_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\[(\d{2}:\d{2}:\d{2}(?:\.\d+)?)\] \[([^\]]+)/([A-Za-z]+)\](?: \[([^\]]*)\])?: ?(.*)$",
+    )
+    .unwrap()
+});
+static XML_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(\w+)="([^"]*)""#).unwrap());
+static XML_MESSAGE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<log4j:Message>(?:<!\[CDATA\[(.*?)\]\]>|(.*?))</log4j:Message>").unwrap()
+});
+static XML_THROWABLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<log4j:Throwable>(?:<!\[CDATA\[(.*?)\]\]>|(.*?))</log4j:Throwable>").unwrap()
+});
+
+const XML_EVENT_START: &str = "<log4j:Event";
+const XML_EVENT_END: &str = "</log4j:Event>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl FromStr for LogLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "TRACE" => Ok(Self::Trace),
+            "DEBUG" => Ok(Self::Debug),
+            "INFO" => Ok(Self::Info),
+            "WARN" | "WARNING" => Ok(Self::Warn),
+            "ERROR" => Ok(Self::Error),
+            "FATAL" => Ok(Self::Fatal),
+            _ => Err(anyhow!("unknown log level: {s}")),
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self {
+            Self::Trace => "TRACE",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+            Self::Fatal => "FATAL",
+        };
+        write!(f, "{level}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEvent {
+    /// The local time of the event
+    pub time: NaiveTime,
+    pub level: LogLevel,
+    pub thread: String,
+
+    /// Not printed by the default format of vanilla
+    pub logger: Option<String>,
+
+    /// The lines of a multi-line message or the stack trace are joined by `\n`
+    pub message: String,
+}
+
+/// Parse the game output line by line
+///
+/// A plain event is returned when the next event starts or [`LogParser::finish`] is called,
+/// because the following lines may belong to it. The lines before the first event are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct LogParser {
+    pending: Option<LogEvent>,
+    xml: Option<String>,
+}
+
+impl LogParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a line without the line ending, returns the event completed by this line
+    pub fn push(&mut self, line: &str) -> Option<LogEvent> {
+        if let Some(xml) = &mut self.xml {
+            xml.push('\n');
+            xml.push_str(line);
+            if !line.contains(XML_EVENT_END) {
+                return None;
+            }
+            let xml = self.xml.take().unwrap();
+            return parse_xml_event(&xml);
+        }
+        if line.trim_start().starts_with(XML_EVENT_START) {
+            let completed = self.pending.take();
+            if line.contains(XML_EVENT_END) {
+                let event = parse_xml_event(line);
+                // only one event can be returned, the other one waits for the next event
+                return match completed {
+                    Some(completed) => {
+                        self.pending = event;
+                        Some(completed)
+                    }
+                    None => event,
+                };
+            }
+            self.xml = Some(line.to_string());
+            return completed;
+        }
+        if let Some(event) = parse_plain_line(line) {
+            return self.pending.replace(event);
+        }
+        if let Some(pending) = &mut self.pending {
+            pending.message.push('\n');
+            pending.message.push_str(line);
+        }
+        None
+    }
+
+    /// Returns the last event, call it after the output ends
+    pub fn finish(&mut self) -> Option<LogEvent> {
+        let xml = self.xml.take();
+        self.pending
+            .take()
+            .or_else(|| xml.and_then(|xml| parse_xml_event(&xml)))
+    }
+}
+
+fn parse_plain_line(line: &str) -> Option<LogEvent> {
+    let captures = PLAIN_LINE.captures(line)?;
+    let time = NaiveTime::parse_from_str(&captures[1], "%H:%M:%S%.f").ok()?;
+    let level = captures[3].parse().ok()?;
+    // forge prints the marker after the logger, like `[cp.mo.mo.Launcher/MODLAUNCHER]`
+    let logger = captures
+        .get(4)
+        .and_then(|logger| logger.as_str().split('/').next())
+        .filter(|logger| !logger.is_empty())
+        .map(|logger| logger.to_string());
+    Some(LogEvent {
+        time,
+        level,
+        thread: captures[2].to_string(),
+        logger,
+        message: captures[5].to_string(),
+    })
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn xml_text(regex: &Regex, xml: &str) -> Option<String> {
+    let captures = regex.captures(xml)?;
+    match captures.get(1) {
+        Some(cdata) => Some(cdata.as_str().to_string()),
+        None => Some(unescape_xml(captures.get(2)?.as_str())),
+    }
+}
+
+fn parse_xml_event(xml: &str) -> Option<LogEvent> {
+    let start = xml.find(XML_EVENT_START)?;
+    let tag = &xml[start..start + xml[start..].find('>')?];
+    let attribute = |name: &str| {
+        XML_ATTRIBUTE
+            .captures_iter(tag)
+            .find(|captures| &captures[1] == name)
+            .map(|captures| unescape_xml(&captures[2]))
+    };
+    let time = Local
+        .timestamp_millis_opt(attribute("timestamp")?.parse().ok()?)
+        .single()?
+        .time();
+    let mut message = xml_text(&XML_MESSAGE, xml).unwrap_or_default();
+    if let Some(throwable) = xml_text(&XML_THROWABLE, xml) {
+        message.push('\n');
+        message.push_str(throwable.trim_end());
+    }
+    Some(LogEvent {
+        time,
+        level: attribute("level")?.parse().ok()?,
+        thread: attribute("thread").unwrap_or_default(),
+        logger: attribute("logger").filter(|logger| !logger.is_empty()),
+        message,
+    })
+}
+
+/// Parse a whole log
+pub fn parse_log(text: &str) -> Vec<LogEvent> {
+    let mut parser = LogParser::new();
+    let mut events: Vec<_> = text.lines().filter_map(|line| parser.push(line)).collect();
+    events.extend(parser.finish());
+    events
+}
+
+/// Parse a stream of lines, like [`super::process::GameProcess::stdout_lines`]
+pub fn log_events<S: Stream<Item = String>>(lines: S) -> impl Stream<Item = LogEvent> {
+    stream::unfold(
+        (Box::pin(lines), LogParser::new(), false),
+        |(mut lines, mut parser, finished)| async move {
+            if finished {
+                return None;
+            }
+            while let Some(line) = lines.next().await {
+                if let Some(event) = parser.push(&line) {
+                    return Some((event, (lines, parser, false)));
+                }
+            }
+            let event = parser.finish()?;
+            Some((event, (lines, parser, true)))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(h: u32, m: u32, s: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, s).unwrap()
+    }
+
+    fn local_time(timestamp: i64) -> NaiveTime {
+        Local.timestamp_millis_opt(timestamp).unwrap().time()
+    }
+
+    #[test]
+    fn test_plain() {
+        let events = parse_log(include_str!("../../mock/logs/plain.log"));
+        assert_eq!(events.len(), 5);
+        assert_eq!(
+            events[0],
+            LogEvent {
+                time: time(12, 1, 2),
+                level: LogLevel::Info,
+                thread: "main".to_string(),
+                logger: None,
+                message: "Loading Minecraft 1.20.1 with Fabric Loader 0.14.22".to_string(),
+            }
+        );
+        assert_eq!(events[1].level, LogLevel::Warn);
+        assert_eq!(
+            events[1].logger.as_deref(),
+            Some("net.fabricmc.loader.impl.discovery.ModResolver")
+        );
+        assert_eq!(events[2].thread, "Render thread");
+        assert_eq!(events[3].level, LogLevel::Error);
+        assert_eq!(
+            events[3].message,
+            "Failed to load shaders\n\
+             java.lang.IllegalStateException: Invalid shader\n\
+             \tat net.minecraft.client.renderer.GameRenderer.loadShaders(GameRenderer.java:432)\n\
+             \tat net.minecraft.client.Minecraft.<init>(Minecraft.java:520)"
+        );
+        assert_eq!(
+            events[4].time,
+            NaiveTime::from_hms_milli_opt(12, 1, 10, 123).unwrap()
+        );
+        assert_eq!(events[4].logger.as_deref(), Some("cp.mo.mo.Launcher"));
+    }
+
+    #[test]
+    fn test_xml() {
+        let events = parse_log(include_str!("../../mock/logs/xml-events.log"));
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[0],
+            LogEvent {
+                time: local_time(1690000000000),
+                level: LogLevel::Info,
+                thread: "Render thread".to_string(),
+                logger: Some("net.minecraft.client.Minecraft".to_string()),
+                message: "Setting user: Player".to_string(),
+            }
+        );
+        assert_eq!(
+            events[1].message,
+            "Missing sound for event: minecraft:item.goat_horn.play\n\
+             Missing sound for event: minecraft:entity.goat.screaming.horn_break"
+        );
+        assert_eq!(
+            events[2].message,
+            "Failed to load shaders\n\
+             java.lang.IllegalStateException: Invalid shader\n\
+             \tat net.minecraft.client.renderer.GameRenderer.loadShaders(GameRenderer.java:432)"
+        );
+        assert_eq!(events[3].logger.as_deref(), Some("\"quoted\""));
+        assert_eq!(events[3].message, "single line");
+    }
+
+    #[test]
+    fn test_mixed() {
+        let mut parser = LogParser::new();
+        assert_eq!(parser.push("Exception before the first event"), None);
+        assert_eq!(parser.push("[08:00:00] [main/INFO]: plain"), None);
+        let plain = parser
+            .push(r#"<log4j:Event logger="a" timestamp="0" level="FATAL" thread="main">"#)
+            .unwrap();
+        assert_eq!(plain.message, "plain");
+        assert_eq!(
+            parser.push("<log4j:Message><![CDATA[xml]]></log4j:Message>"),
+            None
+        );
+        let xml = parser.push("</log4j:Event>").unwrap();
+        assert_eq!(xml.level, LogLevel::Fatal);
+        assert_eq!(xml.message, "xml");
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[tokio::test]
+    async fn test_log_events() {
+        let lines = include_str!("../../mock/logs/plain.log")
+            .lines()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        let events = log_events(stream::iter(lines)).collect::<Vec<_>>().await;
+        assert_eq!(events, parse_log(include_str!("../../mock/logs/plain.log")));
+    }
+}
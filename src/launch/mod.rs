@@ -67,4 +67,10 @@
 
 pub mod options;
 pub mod argument;
+pub mod auth;
+pub mod crash;
+pub mod hook;
+pub mod jvm;
 pub mod launch;
+pub mod log_parser;
+pub mod process;
@@ -0,0 +1,440 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Manage the running game process.
+//!
+//! # Example
+//!
+//! ```
+//! use futures::StreamExt;
+//! use mgl_core::core::JavaExec;
+//! use mgl_core::launch::options::LaunchOptions;
+//! use mgl_core::launch::process::{GameProcessBuilder, GameProcessEvent};
+//!
+//! async fn fn_name(options: LaunchOptions, java: JavaExec) {
+//!     let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+//!     let mut process = GameProcessBuilder::new(options, java)
+//!         .events(sender)
+//!         .spawn()
+//!         .await
+//!         .unwrap();
+//!     let mut stdout = Box::pin(process.stdout_lines());
+//!     while let Some(line) = stdout.next().await {
+//!         println!("{line}");
+//!     }
+//!     let status = process.wait().await.unwrap();
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    process::{ExitStatus, Stdio},
+};
+
+use anyhow::{anyhow, Result};
+use futures::{stream, Stream};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{Child, Command},
+    sync::mpsc::UnboundedSender,
+};
+
+use crate::core::{JavaExec, PlatformInfo};
+
+use super::{
+    argument::{LaunchArguments, LaunchWarning},
+    hook::{instance_env, run_post_exit, run_pre_launch},
+    jvm::check_java,
+    log_parser::{log_events, LogEvent},
+    options::LaunchOptions,
+};
+
+/// How the stdout or stderr of the game is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StdioMode {
+    /// Capture the output, read it with [`GameProcess::stdout_lines`] or
+    /// [`GameProcess::stderr_lines`]
+    #[default]
+    Pipe,
+    /// Write to the stdout or stderr of the launcher
+    Inherit,
+}
+
+impl From<StdioMode> for Stdio {
+    fn from(mode: StdioMode) -> Self {
+        match mode {
+            StdioMode::Pipe => Stdio::piped(),
+            StdioMode::Inherit => Stdio::inherit(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameProcessEvent {
+    /// Something in the launch options is ignored, or a hook failed but the failure is ignored
+    Warning(LaunchWarning),
+    /// The game is spawned with the pid
+    Started(u32),
+    /// A line read from stdout or stderr
+    LogLine(String),
+    Exited(ExitStatus),
+}
+
+/// A running game
+pub struct GameProcess {
+    child: Child,
+    pid: u32,
+    events: Option<UnboundedSender<GameProcessEvent>>,
+
+    /// The `post_exit` hook and its environment variables
+    post_exit: Option<(Vec<String>, HashMap<String, String>)>,
+}
+
+impl GameProcess {
+    /// Spawn a command and monitor it
+    pub fn spawn(
+        command: std::process::Command,
+        stdout: StdioMode,
+        stderr: StdioMode,
+        events: Option<UnboundedSender<GameProcessEvent>>,
+    ) -> Result<Self> {
+        let mut command = Command::from(command);
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
+            .spawn()?;
+        let pid = child
+            .id()
+            .ok_or(anyhow!("the game exited before it can be monitored"))?;
+        if let Some(events) = &events {
+            let _ = events.send(GameProcessEvent::Started(pid));
+        }
+        Ok(Self {
+            child,
+            pid,
+            events,
+            post_exit: None,
+        })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Run a hook after the game exits, it's run by [`GameProcess::wait`]
+    pub fn post_exit(mut self, command: Vec<String>, env: HashMap<String, String>) -> Self {
+        self.post_exit = Some((command, env));
+        self
+    }
+
+    /// Wait for the game to exit, then run the `post_exit` hook
+    pub async fn wait(&mut self) -> Result<ExitStatus> {
+        let status = self.child.wait().await?;
+        if let Some((command, env)) = self.post_exit.take() {
+            if let (Some(warning), Some(events)) =
+                (run_post_exit(&command, &env).await, &self.events)
+            {
+                let _ = events.send(GameProcessEvent::Warning(warning));
+            }
+        }
+        if let Some(events) = &self.events {
+            let _ = events.send(GameProcessEvent::Exited(status));
+        }
+        Ok(status)
+    }
+
+    pub async fn kill(&mut self) -> Result<()> {
+        Ok(self.child.kill().await?)
+    }
+
+    /// Lines of stdout, it's empty if stdout is not captured or already taken.
+    ///
+    /// Every line is also sent as [`GameProcessEvent::LogLine`].
+    pub fn stdout_lines(&mut self) -> impl Stream<Item = String> {
+        lines(self.child.stdout.take(), self.events.clone())
+    }
+
+    /// Events parsed from stdout by [`LogParser`](super::log_parser::LogParser), both the plain
+    /// and log4j XML output are supported.
+    ///
+    /// Every line is also sent as [`GameProcessEvent::LogLine`].
+    pub fn stdout_events(&mut self) -> impl Stream<Item = LogEvent> {
+        log_events(self.stdout_lines())
+    }
+
+    /// Lines of stderr, it's empty if stderr is not captured or already taken.
+    ///
+    /// Every line is also sent as [`GameProcessEvent::LogLine`].
+    pub fn stderr_lines(&mut self) -> impl Stream<Item = String> {
+        lines(self.child.stderr.take(), self.events.clone())
+    }
+}
+
+fn lines<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    events: Option<UnboundedSender<GameProcessEvent>>,
+) -> impl Stream<Item = String> {
+    let lines = reader.map(|reader| BufReader::new(reader).lines());
+    stream::unfold((lines, events), |(mut lines, events)| async move {
+        let line = lines.as_mut()?.next_line().await.ok()??;
+        if let Some(events) = &events {
+            let _ = events.send(GameProcessEvent::LogLine(line.clone()));
+        }
+        Some((line, (lines, events)))
+    })
+}
+
+/// Build the launch command from launch options and spawn the game
+pub struct GameProcessBuilder {
+    launch_options: LaunchOptions,
+    java: JavaExec,
+    stdout: StdioMode,
+    stderr: StdioMode,
+    events: Option<UnboundedSender<GameProcessEvent>>,
+}
+
+impl GameProcessBuilder {
+    pub fn new(launch_options: LaunchOptions, java: JavaExec) -> Self {
+        Self {
+            launch_options,
+            java,
+            stdout: StdioMode::default(),
+            stderr: StdioMode::default(),
+            events: None,
+        }
+    }
+
+    pub fn stdout(mut self, mode: StdioMode) -> Self {
+        self.stdout = mode;
+        self
+    }
+
+    pub fn stderr(mut self, mode: StdioMode) -> Self {
+        self.stderr = mode;
+        self
+    }
+
+    /// Send [`GameProcessEvent`]s of the game to the channel
+    pub fn events(mut self, sender: UnboundedSender<GameProcessEvent>) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    /// Build the launch command without spawning it, the Java is checked against the version
+    pub async fn build_command(&self) -> Result<std::process::Command> {
+        Ok(self.prepare().await?.0)
+    }
+
+    /// Build the launch command with the environment variables of the instance, and the warnings
+    /// about the launch options
+    pub(super) async fn prepare(
+        &self,
+    ) -> Result<(
+        std::process::Command,
+        HashMap<String, String>,
+        Vec<LaunchWarning>,
+    )> {
+        let platform = PlatformInfo::new().await;
+        let version = self
+            .launch_options
+            .version
+            .parse_with_options(
+                &self.launch_options.minecraft_location,
+                &platform,
+                &self.launch_options.parse_options,
+            )
+            .await?;
+        let java = self.java.binary.clone();
+        let required_java = version.java_version.major_version.max(0) as u32;
+        tokio::task::spawn_blocking(move || check_java(&java, required_java)).await??;
+        let env = instance_env(&self.launch_options, &version);
+        let (arguments, warnings) =
+            LaunchArguments::from_launch_options(self.launch_options.clone(), version).await?;
+        let mut command = arguments
+            .to_async_command(self.java.clone(), self.launch_options.clone(), &platform)
+            .await?;
+        command.envs(&env);
+        Ok((command, env, warnings))
+    }
+
+    fn send_warnings(&self, warnings: impl IntoIterator<Item = LaunchWarning>) {
+        if let Some(events) = &self.events {
+            for warning in warnings {
+                let _ = events.send(GameProcessEvent::Warning(warning));
+            }
+        }
+    }
+
+    /// Run the `pre_launch` hook and spawn the game, the `post_exit` hook is run by
+    /// [`GameProcess::wait`]
+    pub async fn spawn(self) -> Result<GameProcess> {
+        let (command, env, warnings) = self.prepare().await?;
+        self.send_warnings(warnings);
+        let warning = run_pre_launch(&self.launch_options, &env).await?;
+        self.send_warnings(warning);
+        let process = GameProcess::spawn(command, self.stdout, self.stderr, self.events)?;
+        Ok(match self.launch_options.post_exit {
+            Some(post_exit) => process.post_exit(post_exit, env),
+            None => process,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_game_process() {
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", "echo line1; echo line2; echo error >&2; exit 3"]);
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let mut process =
+            GameProcess::spawn(command, StdioMode::Pipe, StdioMode::Pipe, Some(sender)).unwrap();
+        let stdout = process.stdout_lines().collect::<Vec<_>>().await;
+        let stderr = process.stderr_lines().collect::<Vec<_>>().await;
+        let status = process.wait().await.unwrap();
+        assert_eq!(stdout, vec!["line1", "line2"]);
+        assert_eq!(stderr, vec!["error"]);
+        assert_eq!(status.code(), Some(3));
+
+        assert_eq!(
+            receiver.recv().await,
+            Some(GameProcessEvent::Started(process.pid()))
+        );
+        assert_eq!(
+            receiver.recv().await,
+            Some(GameProcessEvent::LogLine("line1".to_string()))
+        );
+        let mut last = None;
+        while let Ok(event) = receiver.try_recv() {
+            last = Some(event);
+        }
+        assert_eq!(last, Some(GameProcessEvent::Exited(status)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_launch_hooks() {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        use crate::{core::folder::MinecraftLocation, launch::hook::HookFailed};
+
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = MinecraftLocation::new(dir.path());
+        fs::create_dir_all(&minecraft.assets).unwrap();
+        let version_json = minecraft.get_version_json("1.19.4");
+        fs::create_dir_all(version_json.parent().unwrap()).unwrap();
+        fs::write(
+            &version_json,
+            include_str!("../../mock/versions/1.19.4.json"),
+        )
+        .unwrap();
+
+        // a fake java that records the environment and crashes
+        let java_home = dir.path().join("java");
+        fs::create_dir_all(java_home.join("bin")).unwrap();
+        let java = java_home.join("bin").join("java");
+        let game_marker = dir.path().join("game");
+        fs::write(
+            &java,
+            format!(
+                "#!/bin/sh\nif [ \"$1\" = -XshowSettings:properties ]; then\n  echo 'openjdk version \"17.0.8\"' >&2\n  exit 0\nfi\necho \"$INST_NAME $INST_MC_VER $FOO\" > {}\nexit 1\n",
+                game_marker.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&java, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let hook = |marker: &str| {
+            Some(vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                format!("echo $INST_NAME > {}", dir.path().join(marker).display()),
+            ])
+        };
+        let mut options = LaunchOptions::new("1.19.4", minecraft.clone())
+            .await
+            .unwrap();
+        options.wrapper_command = Some(vec!["env".to_string()]);
+        options.env = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+        options.pre_launch = hook("pre");
+        options.post_exit = hook("post");
+
+        let java_exec = JavaExec::new(&java_home).await;
+        let mut process = GameProcessBuilder::new(options.clone(), java_exec.clone())
+            .stdout(StdioMode::Inherit)
+            .stderr(StdioMode::Inherit)
+            .spawn()
+            .await
+            .unwrap();
+        assert!(dir.path().join("pre").exists());
+        process.wait().await.unwrap();
+        assert_eq!(
+            fs::read_to_string(&game_marker).unwrap(),
+            "1.19.4 1.19.4 bar\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("post")).unwrap(),
+            "1.19.4\n"
+        );
+
+        fs::remove_file(&game_marker).unwrap();
+        options.pre_launch = Some(vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "exit 1".to_string(),
+        ]);
+        let err = GameProcessBuilder::new(options.clone(), java_exec.clone())
+            .spawn()
+            .await
+            .err()
+            .unwrap();
+        assert!(err.downcast_ref::<HookFailed>().is_some());
+        assert!(!game_marker.exists());
+
+        // the ignored failures are sent as warnings
+        options.ignore_hook_failure = true;
+        options.post_exit = Some(vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "exit 3".to_string(),
+        ]);
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let mut process = GameProcessBuilder::new(options, java_exec)
+            .events(sender)
+            .spawn()
+            .await
+            .unwrap();
+        process.wait().await.unwrap();
+        assert!(game_marker.exists());
+        let mut warnings = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            if let GameProcessEvent::Warning(LaunchWarning::HookFailed { error }) = event {
+                warnings.push(error);
+            }
+        }
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("exit 1"));
+        assert!(warnings[1].contains("exit 3"));
+    }
+}
@@ -16,10 +16,17 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod auth;
 pub mod core;
+pub mod error;
+pub mod game_data;
 pub mod install;
+pub mod instance;
 pub mod launch;
 pub mod utils;
-pub mod mod_parser;
-pub mod resourcepack;
+pub mod mod_platform;
+pub mod mods;
+pub mod profile;
 pub mod saves;
+
+pub use error::{Error, Result};
@@ -0,0 +1,574 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! CurseForge API client
+//!
+//! See <https://docs.curseforge.com/> for the API reference.
+//! An API key is required, you can apply one from the CurseForge console.
+
+use std::time::Duration;
+
+use reqwest::{header::RETRY_AFTER, Client, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{utils::download::DownloadError, Result};
+
+pub const DEFAULT_CURSEFORGE_API_URL: &str = "https://api.curseforge.com";
+
+/// The game id of Minecraft on CurseForge
+pub const MINECRAFT_GAME_ID: u32 = 432;
+
+/// The class id of mods on CurseForge
+pub const MODS_CLASS_ID: u32 = 6;
+
+/// How many times a rate limited request is retried by default
+pub const DEFAULT_MAX_RETRIES: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModLoaderType {
+    Any = 0,
+    Forge = 1,
+    Cauldron = 2,
+    LiteLoader = 3,
+    Fabric = 4,
+    Quilt = 5,
+    NeoForge = 6,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CurseForgeResponse<T> {
+    data: T,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeModLinks {
+    pub website_url: Option<String>,
+    pub wiki_url: Option<String>,
+    pub issues_url: Option<String>,
+    pub source_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeAuthor {
+    pub id: u32,
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeAsset {
+    pub id: u32,
+    pub mod_id: u32,
+    pub title: String,
+    pub thumbnail_url: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeMod {
+    pub id: u32,
+    pub game_id: u32,
+    pub name: String,
+    pub slug: String,
+    pub links: CurseForgeModLinks,
+    pub summary: String,
+    pub download_count: f64,
+    pub class_id: Option<u32>,
+    pub authors: Vec<CurseForgeAuthor>,
+    pub logo: Option<CurseForgeAsset>,
+    pub main_file_id: u32,
+    pub date_modified: String,
+    pub allow_mod_distribution: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFileHash {
+    pub value: String,
+
+    /// 1 is sha1, 2 is md5
+    pub algo: u8,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFileDependency {
+    pub mod_id: u32,
+
+    /// 1 = EmbeddedLibrary, 2 = OptionalDependency, 3 = RequiredDependency, 4 = Tool,
+    /// 5 = Incompatible, 6 = Include
+    pub relation_type: u8,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFile {
+    pub id: u32,
+    pub game_id: u32,
+    pub mod_id: u32,
+    pub is_available: bool,
+    pub display_name: String,
+    pub file_name: String,
+
+    /// 1 = Release, 2 = Beta, 3 = Alpha
+    pub release_type: u8,
+    pub hashes: Vec<CurseForgeFileHash>,
+    pub file_date: String,
+    pub file_length: u64,
+
+    /// `None` if the author has disabled third-party distribution
+    pub download_url: Option<String>,
+    pub game_versions: Vec<String>,
+    pub dependencies: Vec<CurseForgeFileDependency>,
+    pub file_fingerprint: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintMatch {
+    /// The mod id
+    pub id: u32,
+    pub file: CurseForgeFile,
+    pub latest_files: Vec<CurseForgeFile>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintMatchesResult {
+    pub is_cache_built: bool,
+    pub exact_matches: Vec<FingerprintMatch>,
+    pub exact_fingerprints: Vec<u32>,
+    pub unmatched_fingerprints: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchModsOptions {
+    pub search_filter: Option<String>,
+    pub game_version: Option<String>,
+    pub mod_loader_type: Option<ModLoaderType>,
+
+    /// Default is [`MODS_CLASS_ID`]
+    pub class_id: Option<u32>,
+    pub index: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+/// CurseForge API client
+///
+/// The client is `Send` and `Sync`, put it in an [`Arc`](std::sync::Arc) to share it across
+/// async tasks.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use mgl_core::mod_platform::curseforge::CurseForgeClient;
+///
+/// async fn fn_name() {
+///     let client = Arc::new(CurseForgeClient::new("your api key"));
+///     let jei = client.get_mod(238222).await.unwrap();
+///     println!("{:#?}", jei);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CurseForgeClient {
+    client: Client,
+    api_key: Option<String>,
+    base_url: String,
+    retry_delay: Duration,
+    max_retries: u32,
+}
+
+impl CurseForgeClient {
+    pub fn new(api_key: &str) -> Self {
+        Self::with_base_url(api_key, DEFAULT_CURSEFORGE_API_URL)
+    }
+
+    /// Use another api host, like a proxy of the CurseForge API
+    pub fn with_base_url(api_key: &str, base_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            api_key: Some(api_key.to_string()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            retry_delay: Duration::from_secs(1),
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    /// Use a proxy of the CurseForge API which doesn't require an api key
+    pub fn without_key(base_url: &str) -> Self {
+        Self {
+            api_key: None,
+            ..Self::with_base_url("", base_url)
+        }
+    }
+
+    /// Change how rate limited requests are retried, the delay doubles after every retry.
+    ///
+    /// Default is 1 second and [`DEFAULT_MAX_RETRIES`] times.
+    pub fn with_backoff(self, initial_delay: Duration, max_retries: u32) -> Self {
+        Self {
+            retry_delay: initial_delay,
+            max_retries,
+            ..self
+        }
+    }
+
+    fn request(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = request.header("Accept", "application/json");
+        match &self.api_key {
+            Some(api_key) => request.header("x-api-key", api_key),
+            None => request,
+        }
+    }
+
+    /// Send the request, it's retried with exponential back-off if the API responds
+    /// `429 Too Many Requests`
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = self.request(request);
+        let mut delay = self.retry_delay;
+        let mut retries = 0;
+        loop {
+            let response = request
+                .try_clone()
+                .expect("the requests have no streaming body")
+                .send()
+                .await?;
+            if response.status() != StatusCode::TOO_MANY_REQUESTS || retries >= self.max_retries {
+                return Ok(response.error_for_status()?);
+            }
+            // the server may tell how long to wait
+            let wait = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()?.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(delay);
+            tokio::time::sleep(wait).await;
+            delay *= 2;
+            retries += 1;
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}{path}", self.base_url))
+                    .query(query),
+            )
+            .await?;
+        Ok(response.json::<CurseForgeResponse<T>>().await?.data)
+    }
+
+    pub async fn search_mods(&self, options: &SearchModsOptions) -> Result<Vec<CurseForgeMod>> {
+        let mut query = vec![
+            ("gameId", MINECRAFT_GAME_ID.to_string()),
+            (
+                "classId",
+                options.class_id.unwrap_or(MODS_CLASS_ID).to_string(),
+            ),
+        ];
+        if let Some(search_filter) = &options.search_filter {
+            query.push(("searchFilter", search_filter.clone()));
+        }
+        if let Some(game_version) = &options.game_version {
+            query.push(("gameVersion", game_version.clone()));
+        }
+        if let Some(mod_loader_type) = options.mod_loader_type {
+            query.push(("modLoaderType", (mod_loader_type as u8).to_string()));
+        }
+        if let Some(index) = options.index {
+            query.push(("index", index.to_string()));
+        }
+        if let Some(page_size) = options.page_size {
+            query.push(("pageSize", page_size.to_string()));
+        }
+        self.get("/v1/mods/search", &query).await
+    }
+
+    pub async fn get_mod(&self, mod_id: u32) -> Result<CurseForgeMod> {
+        self.get(&format!("/v1/mods/{mod_id}"), &[]).await
+    }
+
+    pub async fn get_mod_file(&self, mod_id: u32, file_id: u32) -> Result<CurseForgeFile> {
+        self.get(&format!("/v1/mods/{mod_id}/files/{file_id}"), &[])
+            .await
+    }
+
+    /// Get all files of the mod, filtered by game version and mod loader
+    pub async fn get_mod_files(
+        &self,
+        mod_id: u32,
+        game_version: Option<&str>,
+        loader: Option<ModLoaderType>,
+    ) -> Result<Vec<CurseForgeFile>> {
+        let mut query = vec![];
+        if let Some(game_version) = game_version {
+            query.push(("gameVersion", game_version.to_string()));
+        }
+        if let Some(loader) = loader {
+            query.push(("modLoaderType", (loader as u8).to_string()));
+        }
+        self.get(&format!("/v1/mods/{mod_id}/files"), &query).await
+    }
+
+    /// Get several files at once by their ids
+    pub async fn get_files(&self, file_ids: &[u32]) -> Result<Vec<CurseForgeFile>> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Body<'a> {
+            file_ids: &'a [u32],
+        }
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/v1/mods/files", self.base_url))
+                    .json(&Body { file_ids }),
+            )
+            .await?;
+        Ok(response
+            .json::<CurseForgeResponse<Vec<CurseForgeFile>>>()
+            .await?
+            .data)
+    }
+
+    /// Get the download url of the file.
+    ///
+    /// If the author has disabled third-party distribution, `downloadUrl` is null and
+    /// [`DownloadError::DistributionDenied`] is returned with the page of the file on the website.
+    pub async fn get_mod_file_download_url(&self, mod_id: u32, file_id: u32) -> Result<String> {
+        let file = self.get_mod_file(mod_id, file_id).await?;
+        if let Some(download_url) = file.download_url {
+            return Ok(download_url);
+        }
+        let website_url = self
+            .get_mod(mod_id)
+            .await?
+            .links
+            .website_url
+            .unwrap_or(format!("https://www.curseforge.com/projects/{mod_id}"));
+        Err(DownloadError::DistributionDenied {
+            url: format!("{website_url}/files/{file_id}"),
+        }
+        .into())
+    }
+
+    /// Match local files by their fingerprints, see [`get_fingerprint`]
+    pub async fn get_fingerprint_matches(
+        &self,
+        fingerprints: &[u32],
+    ) -> Result<FingerprintMatchesResult> {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            fingerprints: &'a [u32],
+        }
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/v1/fingerprints", self.base_url))
+                    .json(&Body { fingerprints }),
+            )
+            .await?;
+        Ok(response
+            .json::<CurseForgeResponse<FingerprintMatchesResult>>()
+            .await?
+            .data)
+    }
+
+    /// Get the exact matches of local files by their fingerprints, see [`get_fingerprint`]
+    pub async fn fingerprint_lookup(&self, fingerprints: &[u32]) -> Result<Vec<FingerprintMatch>> {
+        Ok(self
+            .get_fingerprint_matches(fingerprints)
+            .await?
+            .exact_matches)
+    }
+}
+
+/// 32-bit MurmurHash2 by Austin Appleby
+pub fn murmur2(data: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1e995;
+    const R: u32 = 24;
+
+    let mut hash = seed ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M);
+        hash ^= k;
+    }
+    let remainder = chunks.remainder();
+    if remainder.len() == 3 {
+        hash ^= (remainder[2] as u32) << 16;
+    }
+    if remainder.len() >= 2 {
+        hash ^= (remainder[1] as u32) << 8;
+    }
+    if !remainder.is_empty() {
+        hash ^= remainder[0] as u32;
+        hash = hash.wrapping_mul(M);
+    }
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> 15;
+    hash
+}
+
+/// Calculate the CurseForge fingerprint of a file.
+///
+/// It is the murmur2 hash (seed 1) of the file content without whitespace characters
+/// (`\t`, `\n`, `\r` and space).
+pub fn get_fingerprint(data: &[u8]) -> u32 {
+    let data = data
+        .iter()
+        .copied()
+        .filter(|byte| !matches!(byte, 9 | 10 | 13 | 32))
+        .collect::<Vec<u8>>();
+    murmur2(&data, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_murmur2() {
+        assert_eq!(murmur2(b"", 0), 0);
+        assert_eq!(murmur2(b"", 1), 1540447798);
+        assert_eq!(murmur2(b"a", 1), 626045324);
+        assert_eq!(murmur2(b"abc", 0), 324500635);
+        assert_eq!(murmur2(b"Hello, world!", 1), 1671593801);
+        assert_eq!(
+            murmur2(b"The quick brown fox jumps over the lazy dog", 0),
+            556214736
+        );
+        assert_eq!(get_fingerprint(b"Hello,\r\n world!\t"), 2173663876);
+    }
+
+    #[tokio::test]
+    async fn test_distribution_denied() {
+        let mut server = mockito::Server::new_async().await;
+        let file = server
+            .mock("GET", "/v1/mods/1/files/2")
+            .match_header("x-api-key", "key")
+            .with_body(r#"{"data":{"id":2,"gameId":432,"modId":1,"isAvailable":true,"displayName":"a","fileName":"a.jar","releaseType":1,"hashes":[],"fileDate":"2023-01-01T00:00:00Z","fileLength":1,"downloadUrl":null,"gameVersions":[],"dependencies":[],"fileFingerprint":1}}"#)
+            .create_async()
+            .await;
+        let mod_info = server
+            .mock("GET", "/v1/mods/1")
+            .with_body(r#"{"data":{"id":1,"gameId":432,"name":"A","slug":"a","links":{"websiteUrl":"https://www.curseforge.com/minecraft/mc-mods/a"},"summary":"","downloadCount":1.0,"classId":6,"authors":[],"logo":null,"mainFileId":2,"dateModified":"2023-01-01T00:00:00Z","allowModDistribution":false}}"#)
+            .create_async()
+            .await;
+
+        let client = CurseForgeClient::with_base_url("key", &server.url());
+        let error = client.get_mod_file_download_url(1, 2).await.unwrap_err();
+        match error {
+            Error::Download(DownloadError::DistributionDenied { url }) => assert_eq!(
+                url,
+                "https://www.curseforge.com/minecraft/mc-mods/a/files/2"
+            ),
+            _ => panic!("unexpected error: {error}"),
+        }
+        file.assert_async().await;
+        mod_info.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_fingerprint_matches() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/fingerprints")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({"fingerprints": [2173663876u32]}),
+            ))
+            .with_body(r#"{"data":{"isCacheBuilt":true,"exactMatches":[],"exactFingerprints":[],"unmatchedFingerprints":[2173663876]}}"#)
+            .create_async()
+            .await;
+        let client = CurseForgeClient::with_base_url("key", &server.url());
+        let result = client.get_fingerprint_matches(&[2173663876]).await.unwrap();
+        assert_eq!(result.unmatched_fingerprints, Some(vec![2173663876]));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_shared_keyless_client() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/v1/mods/1/files/2")
+            .match_header("x-api-key", mockito::Matcher::Missing)
+            .with_body(r#"{"data":{"id":2,"gameId":432,"modId":1,"isAvailable":true,"displayName":"a","fileName":"a.jar","releaseType":1,"hashes":[],"fileDate":"2023-01-01T00:00:00Z","fileLength":1,"downloadUrl":"https://edge.forgecdn.net/files/0/2/a.jar","gameVersions":[],"dependencies":[],"fileFingerprint":1}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let client = std::sync::Arc::new(CurseForgeClient::without_key(&server.url()));
+        let tasks = (0..2).map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.get_mod_file_download_url(1, 2).await })
+        });
+        for url in futures::future::join_all(tasks).await {
+            assert_eq!(
+                url.unwrap().unwrap(),
+                "https://edge.forgecdn.net/files/0/2/a.jar"
+            );
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_backoff() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server
+            .mock("POST", "/v1/mods/files")
+            .with_status(429)
+            .expect(2)
+            .create_async()
+            .await;
+        let files = server
+            .mock("POST", "/v1/mods/files")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({"fileIds": [2]}),
+            ))
+            .with_body(r#"{"data":[{"id":2,"gameId":432,"modId":1,"isAvailable":true,"displayName":"a","fileName":"a.jar","releaseType":1,"hashes":[],"fileDate":"2023-01-01T00:00:00Z","fileLength":1,"downloadUrl":null,"gameVersions":[],"dependencies":[],"fileFingerprint":1}]}"#)
+            .create_async()
+            .await;
+        let client = CurseForgeClient::with_base_url("key", &server.url())
+            .with_backoff(Duration::from_millis(10), 3);
+        let result = client.get_files(&[2]).await.unwrap();
+        assert_eq!(result[0].file_name, "a.jar");
+        limited.assert_async().await;
+        files.assert_async().await;
+
+        let client = client.with_backoff(Duration::from_millis(10), 0);
+        server.reset();
+        server
+            .mock("POST", "/v1/mods/files")
+            .with_status(429)
+            .create_async()
+            .await;
+        assert!(client.get_files(&[2]).await.is_err());
+    }
+}
@@ -0,0 +1,40 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Clients of the mod hosting platforms
+//!
+//! # Example
+//!
+//! Identify a local mod jar on CurseForge:
+//!
+//! ```rust
+//! use mgl_core::mod_platform::curseforge::{get_fingerprint, CurseForgeClient};
+//!
+//! async fn fn_name() {
+//!     let client = CurseForgeClient::new("your api key");
+//!     let jar = std::fs::read("mods/example.jar").unwrap();
+//!     let matches = client
+//!         .get_fingerprint_matches(&[get_fingerprint(&jar)])
+//!         .await
+//!         .unwrap();
+//!     println!("{:#?}", matches.exact_matches);
+//! }
+//! ```
+
+pub mod curseforge;
+pub mod modrinth;
@@ -0,0 +1,483 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Modrinth API client
+//!
+//! See <https://docs.modrinth.com/api/> for the API reference.
+//! Modrinth requires a unique user agent which identifies the launcher, like
+//! `github_username/project_name/1.56.0 (launcher.com)`.
+
+use std::{collections::HashMap, path::Path};
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha512};
+
+use crate::{
+    utils::{download::DownloadError, sha1::calculate_sha1_from_read},
+    Result,
+};
+
+pub const DEFAULT_MODRINTH_API_URL: &str = "https://api.modrinth.com";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthVersionFile {
+    /// `sha1` and `sha512`
+    pub hashes: HashMap<String, String>,
+    pub url: String,
+    pub filename: String,
+    pub primary: bool,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthDependency {
+    pub version_id: Option<String>,
+    pub project_id: Option<String>,
+    pub file_name: Option<String>,
+
+    /// `required`, `optional`, `incompatible` or `embedded`
+    pub dependency_type: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthVersion {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    pub files: Vec<ModrinthVersionFile>,
+
+    /// `release`, `beta` or `alpha`
+    #[serde(default)]
+    pub version_type: String,
+    #[serde(default)]
+    pub date_published: String,
+    #[serde(default)]
+    pub dependencies: Vec<ModrinthDependency>,
+}
+
+impl ModrinthVersion {
+    /// Find the file with the hash, the primary file is returned if not found
+    pub fn file_by_hash(&self, hash: &str) -> Option<&ModrinthVersionFile> {
+        self.files
+            .iter()
+            .find(|file| file.hashes.values().any(|value| value == hash))
+            .or(self.files.iter().find(|file| file.primary))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthProject {
+    pub id: String,
+    pub slug: String,
+
+    /// `mod`, `modpack`, `resourcepack` or `shader`
+    pub project_type: String,
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub body: String,
+    pub categories: Vec<String>,
+
+    /// `required`, `optional` or `unsupported`
+    pub client_side: String,
+    pub server_side: String,
+    pub downloads: u64,
+    pub icon_url: Option<String>,
+
+    /// The ids of the versions
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthSearchHit {
+    pub project_id: String,
+    pub project_type: String,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub categories: Vec<String>,
+    pub downloads: u64,
+    pub icon_url: Option<String>,
+    pub author: String,
+
+    /// The supported game versions
+    pub versions: Vec<String>,
+    pub latest_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthSearchResult {
+    pub hits: Vec<ModrinthSearchHit>,
+    pub offset: u32,
+    pub limit: u32,
+    pub total_hits: u32,
+}
+
+/// The filters of [`ModrinthClient::search_projects`], the empty ones are not applied
+#[derive(Debug, Clone, Default)]
+pub struct ModrinthSearchFilters {
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    pub categories: Vec<String>,
+
+    /// `mod`, `modpack`, `resourcepack` or `shader`
+    pub project_type: Option<String>,
+
+    /// `relevance`, `downloads`, `follows`, `newest` or `updated`
+    pub index: Option<String>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl ModrinthSearchFilters {
+    /// Build the facets, the values in a group are `OR`, the groups are `AND`
+    fn facets(&self) -> Vec<Vec<String>> {
+        let group = |prefix: &str, values: &[String]| {
+            values
+                .iter()
+                .map(|value| format!("{prefix}:{value}"))
+                .collect::<Vec<_>>()
+        };
+        let mut facets = vec![
+            group("versions", &self.game_versions),
+            // loaders are categories on Modrinth
+            group("categories", &self.loaders),
+        ];
+        facets.extend(
+            self.categories
+                .iter()
+                .map(|category| vec![format!("categories:{category}")]),
+        );
+        if let Some(project_type) = &self.project_type {
+            facets.push(vec![format!("project_type:{project_type}")]);
+        }
+        facets.retain(|group| !group.is_empty());
+        facets
+    }
+}
+
+/// Modrinth API client
+///
+/// # Example
+///
+/// ```rust
+/// use mgl_core::mod_platform::modrinth::ModrinthClient;
+///
+/// async fn fn_name() {
+///     let client = ModrinthClient::new("Broken-Deer/magical-launcher-core/0.1.0");
+///     let versions = client
+///         .get_versions_from_hashes(&["c84dd4b3580c02b79958a0590afd5783d80ef504".to_string()], "sha1")
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ModrinthClient {
+    client: Client,
+    user_agent: String,
+    base_url: String,
+}
+
+impl ModrinthClient {
+    pub fn new(user_agent: &str) -> Self {
+        Self::with_base_url(user_agent, DEFAULT_MODRINTH_API_URL)
+    }
+
+    /// Use another api host, like the staging server `https://staging-api.modrinth.com`
+    pub fn with_base_url(user_agent: &str, base_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            user_agent: user_agent.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Get the versions of files by their hashes, the result is keyed by the hash.
+    ///
+    /// * `algorithm` - `sha1` or `sha512`
+    pub async fn get_versions_from_hashes(
+        &self,
+        hashes: &[String],
+        algorithm: &str,
+    ) -> Result<HashMap<String, ModrinthVersion>> {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            hashes: &'a [String],
+            algorithm: &'a str,
+        }
+        let response = self
+            .client
+            .post(format!("{}/v2/version_files", self.base_url))
+            .header("User-Agent", &self.user_agent)
+            .json(&Body { hashes, algorithm })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        let response = self
+            .client
+            .get(format!("{}{path}", self.base_url))
+            .header("User-Agent", &self.user_agent)
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn search_projects(
+        &self,
+        query: &str,
+        filters: ModrinthSearchFilters,
+    ) -> Result<ModrinthSearchResult> {
+        let mut params = vec![("query", query.to_string())];
+        let facets = filters.facets();
+        if !facets.is_empty() {
+            params.push(("facets", json!(facets).to_string()));
+        }
+        if let Some(index) = filters.index {
+            params.push(("index", index));
+        }
+        if let Some(offset) = filters.offset {
+            params.push(("offset", offset.to_string()));
+        }
+        if let Some(limit) = filters.limit {
+            params.push(("limit", limit.to_string()));
+        }
+        self.get_json("/v2/search", &params).await
+    }
+
+    /// Get a project by its id or slug
+    pub async fn get_project(&self, id: &str) -> Result<ModrinthProject> {
+        self.get_json(&format!("/v2/project/{id}"), &[]).await
+    }
+
+    /// Get the versions of a project, newest first. The empty filters are not applied.
+    pub async fn get_project_versions(
+        &self,
+        project_id: &str,
+        game_versions: &[&str],
+        loaders: &[&str],
+    ) -> Result<Vec<ModrinthVersion>> {
+        let mut params = Vec::new();
+        if !game_versions.is_empty() {
+            params.push(("game_versions", json!(game_versions).to_string()));
+        }
+        if !loaders.is_empty() {
+            params.push(("loaders", json!(loaders).to_string()));
+        }
+        self.get_json(&format!("/v2/project/{project_id}/version"), &params)
+            .await
+    }
+
+    /// Download a file of a version to `dest` and verify it with sha512, or sha1 if sha512 is
+    /// not provided. Nothing is written if the verification fails.
+    pub async fn download_version_file(
+        &self,
+        file: &ModrinthVersionFile,
+        dest: &Path,
+    ) -> Result<()> {
+        let content = self
+            .client
+            .get(&file.url)
+            .header("User-Agent", &self.user_agent)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let (algorithm, expected, actual) =
+            match (file.hashes.get("sha512"), file.hashes.get("sha1")) {
+                (Some(sha512), _) => ("sha512", sha512, format!("{:x}", Sha512::digest(&content))),
+                (None, Some(sha1)) => (
+                    "sha1",
+                    sha1,
+                    calculate_sha1_from_read(&mut content.as_ref()),
+                ),
+                (None, None) => {
+                    return Err(DownloadError::MissingChecksum {
+                        url: file.url.clone(),
+                    }
+                    .into())
+                }
+            };
+        if !expected.eq_ignore_ascii_case(&actual) {
+            return Err(DownloadError::HashMismatch {
+                url: file.url.clone(),
+                algorithm,
+                expected: expected.clone(),
+                actual,
+            }
+            .into());
+        }
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(dest, content).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_versions_from_hashes() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v2/version_files")
+            .match_header("User-Agent", "test/1.0")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "hashes": ["aaa"],
+                "algorithm": "sha1"
+            })))
+            .with_body(
+                serde_json::json!({
+                    "aaa": {
+                        "id": "IZskON6d",
+                        "project_id": "P7dR8mSH",
+                        "name": "Fabric API 0.87.0",
+                        "version_number": "0.87.0+1.20.1",
+                        "game_versions": ["1.20.1"],
+                        "loaders": ["fabric"],
+                        "files": [{
+                            "hashes": { "sha1": "aaa", "sha512": "bbb" },
+                            "url": "https://cdn.modrinth.com/data/P7dR8mSH/versions/IZskON6d/fabric-api.jar",
+                            "filename": "fabric-api.jar",
+                            "primary": true,
+                            "size": 3
+                        }]
+                    }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let client = ModrinthClient::with_base_url("test/1.0", &server.url());
+        let versions = client
+            .get_versions_from_hashes(&["aaa".to_string()], "sha1")
+            .await
+            .unwrap();
+        let file = versions["aaa"].file_by_hash("aaa").unwrap();
+        assert_eq!(file.hashes["sha512"], "bbb");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_search_projects() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/v2/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("query".into(), "sodium".into()),
+                mockito::Matcher::UrlEncoded(
+                    "facets".into(),
+                    r#"[["versions:1.20.1"],["categories:fabric"],["project_type:mod"]]"#.into(),
+                ),
+                mockito::Matcher::UrlEncoded("limit".into(), "1".into()),
+            ]))
+            .with_body(
+                serde_json::json!({
+                    "hits": [{
+                        "project_id": "AANobbMI",
+                        "project_type": "mod",
+                        "slug": "sodium",
+                        "title": "Sodium",
+                        "description": "A modern rendering engine",
+                        "categories": ["fabric", "optimization"],
+                        "downloads": 100,
+                        "icon_url": null,
+                        "author": "jellysquid3",
+                        "versions": ["1.20.1"],
+                        "latest_version": "1.20.1"
+                    }],
+                    "offset": 0,
+                    "limit": 1,
+                    "total_hits": 10
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let client = ModrinthClient::with_base_url("test/1.0", &server.url());
+        let result = client
+            .search_projects(
+                "sodium",
+                ModrinthSearchFilters {
+                    game_versions: vec!["1.20.1".to_string()],
+                    loaders: vec!["fabric".to_string()],
+                    project_type: Some("mod".to_string()),
+                    limit: Some(1),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.hits[0].slug, "sodium");
+        assert_eq!(result.total_hits, 10);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_download_version_file() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/fabric-api.jar")
+            .with_body("jar")
+            .create_async()
+            .await;
+        let client = ModrinthClient::with_base_url("test/1.0", &server.url());
+        let mut file = ModrinthVersionFile {
+            hashes: HashMap::from([(
+                "sha512".to_string(),
+                format!("{:x}", Sha512::digest(b"jar")),
+            )]),
+            url: format!("{}/fabric-api.jar", server.url()),
+            filename: "fabric-api.jar".to_string(),
+            primary: true,
+            size: 3,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("mods").join("fabric-api.jar");
+        client.download_version_file(&file, &dest).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"jar");
+
+        file.hashes.insert("sha512".to_string(), "0".repeat(128));
+        let dest = dir.path().join("mods").join("broken.jar");
+        assert!(client.download_version_file(&file, &dest).await.is_err());
+        assert!(!dest.exists());
+    }
+}
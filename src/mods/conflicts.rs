@@ -0,0 +1,193 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Find the mods which can't be loaded together.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+
+use super::scan::{scan_mods, ModInfo, ModMetadata};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictType {
+    /// Several jars declare the same mod id
+    DuplicateId,
+    /// A mod declares it conflicts with another installed mod, the game still loads but may
+    /// misbehave
+    DeclaredIncompatibility,
+    /// A mod declares it breaks with another installed mod, the game refuses to load
+    BreaksOther,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModConflict {
+    pub conflict_type: ConflictType,
+    /// The duplicated mod id, or the mod declaring the incompatibility
+    pub mod_id: String,
+    /// The mod declared incompatible, `None` for [`ConflictType::DuplicateId`]
+    pub other_mod_id: Option<String>,
+    /// The version range declared incompatible
+    pub version_range: Option<String>,
+    /// The jars of the mods involved
+    pub paths: Vec<PathBuf>,
+}
+
+/// Detect the conflicts between the enabled mods in a mods folder.
+///
+/// The declared incompatibilities are reported whatever version of the other mod is installed,
+/// [`ModConflict::version_range`] tells which versions are meant. The mods bundled in jars are
+/// not checked, the loaders pick one of the duplicated bundled mods themselves.
+pub fn detect_conflicts(mods_dir: &Path) -> Result<Vec<ModConflict>> {
+    if !mods_dir.is_dir() {
+        return Err(anyhow!("{} is not a folder", mods_dir.display()));
+    }
+    let mods: Vec<(PathBuf, ModMetadata)> = scan_mods(mods_dir)
+        .into_iter()
+        .filter_map(|mod_info| match mod_info {
+            ModInfo::Mod {
+                path,
+                enabled: true,
+                metadata,
+                ..
+            } => Some((path, metadata)),
+            _ => None,
+        })
+        .collect();
+
+    let mut by_id: BTreeMap<&str, Vec<PathBuf>> = BTreeMap::new();
+    for (path, metadata) in &mods {
+        by_id
+            .entry(metadata.mod_id.as_str())
+            .or_default()
+            .push(path.clone());
+    }
+
+    let mut conflicts: Vec<_> = by_id
+        .iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(mod_id, paths)| ModConflict {
+            conflict_type: ConflictType::DuplicateId,
+            mod_id: mod_id.to_string(),
+            other_mod_id: None,
+            version_range: None,
+            paths: paths.clone(),
+        })
+        .collect();
+    for (path, metadata) in &mods {
+        for incompatibility in &metadata.incompatibilities {
+            if incompatibility.mod_id == metadata.mod_id {
+                continue;
+            }
+            let Some(others) = by_id.get(incompatibility.mod_id.as_str()) else {
+                continue;
+            };
+            conflicts.push(ModConflict {
+                conflict_type: if incompatibility.breaks {
+                    ConflictType::BreaksOther
+                } else {
+                    ConflictType::DeclaredIncompatibility
+                },
+                mod_id: metadata.mod_id.clone(),
+                other_mod_id: Some(incompatibility.mod_id.clone()),
+                version_range: incompatibility.version_range.clone(),
+                paths: [path.clone()].into_iter().chain(others.clone()).collect(),
+            });
+        }
+    }
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor, io::Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    fn write_fabric_mod(path: &Path, json: &str) {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("fabric.mod.json", FileOptions::default())
+            .unwrap();
+        writer.write_all(json.as_bytes()).unwrap();
+        fs::write(path, writer.finish().unwrap().into_inner()).unwrap();
+    }
+
+    #[test]
+    fn test_detect_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(detect_conflicts(&dir.path().join("mods")).is_err());
+
+        let mods = dir.path();
+        write_fabric_mod(
+            &mods.join("sodium-0.4.jar"),
+            r#"{ "id": "sodium", "version": "0.4.10" }"#,
+        );
+        write_fabric_mod(
+            &mods.join("sodium-0.5.jar"),
+            r#"{ "id": "sodium", "version": "0.5.3", "breaks": { "optifabric": "*" } }"#,
+        );
+        write_fabric_mod(
+            &mods.join("optifabric.jar"),
+            r#"{ "id": "optifabric", "version": "1.13.0", "conflicts": { "iris": "*", "lithium": "<0.11" } }"#,
+        );
+        write_fabric_mod(
+            &mods.join("lithium.jar.disabled"),
+            r#"{ "id": "lithium", "version": "0.10.0", "breaks": { "sodium": "*" } }"#,
+        );
+
+        let conflicts = detect_conflicts(mods).unwrap();
+        assert_eq!(
+            conflicts,
+            vec![
+                ModConflict {
+                    conflict_type: ConflictType::DuplicateId,
+                    mod_id: "sodium".to_string(),
+                    other_mod_id: None,
+                    version_range: None,
+                    paths: vec![mods.join("sodium-0.4.jar"), mods.join("sodium-0.5.jar")],
+                },
+                ModConflict {
+                    conflict_type: ConflictType::BreaksOther,
+                    mod_id: "sodium".to_string(),
+                    other_mod_id: Some("optifabric".to_string()),
+                    version_range: Some("*".to_string()),
+                    paths: vec![mods.join("sodium-0.5.jar"), mods.join("optifabric.jar")],
+                },
+            ]
+        );
+
+        write_fabric_mod(
+            &mods.join("iris.jar"),
+            r#"{ "id": "iris", "version": "1.6.4" }"#,
+        );
+        let conflicts = detect_conflicts(mods).unwrap();
+        assert_eq!(conflicts.len(), 3);
+        assert_eq!(
+            conflicts[1].conflict_type,
+            ConflictType::DeclaredIncompatibility
+        );
+        assert_eq!(conflicts[1].mod_id, "optifabric");
+        assert_eq!(conflicts[1].other_mod_id.as_deref(), Some("iris"));
+    }
+}
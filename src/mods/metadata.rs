@@ -0,0 +1,564 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Read the metadata of a mod jar, see [`ModMetadata::from_jar`].
+
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use toml::Table;
+use zip::ZipArchive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModLoader {
+    Fabric,
+    Quilt,
+    /// Mods declared by `META-INF/mods.toml`
+    Forge,
+    /// Mods declared by `META-INF/neoforge.mods.toml`, or by `META-INF/mods.toml` depending on
+    /// `neoforge`
+    NeoForge,
+    /// Mods declared by `mcmod.info`
+    LegacyForge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModDependency {
+    pub mod_id: String,
+    pub version_range: Option<String>,
+    pub required: bool,
+}
+
+/// A mod declared incompatible by another mod
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModIncompatibility {
+    pub mod_id: String,
+    pub version_range: Option<String>,
+    /// The game refuses to load with the mod, like `breaks` of fabric. Otherwise it only warns,
+    /// like `conflicts` of fabric.
+    pub breaks: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModMetadata {
+    pub mod_id: String,
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    /// The content of the icon file
+    pub icon: Option<Vec<u8>>,
+    pub loader: ModLoader,
+    pub dependencies: Vec<ModDependency>,
+    pub incompatibilities: Vec<ModIncompatibility>,
+}
+
+/// The mod ids of the loaders, which the mods depend on like other mods
+const LOADER_IDS: [&str; 5] = ["fabricloader", "quilt_loader", "forge", "neoforge", "fml"];
+
+impl ModMetadata {
+    /// Read the metadata of a mod jar, in `quilt.mod.json`, `fabric.mod.json`,
+    /// `META-INF/neoforge.mods.toml`, `META-INF/mods.toml` or `mcmod.info`.
+    ///
+    /// Returns `None` if the jar has none of them, like a resource pack or a library. The mods
+    /// bundled in the jar are not read.
+    pub fn from_jar(path: &Path) -> Result<Option<Self>> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        read_metadata(&mut archive)
+    }
+
+    /// The Minecraft versions the mod supports, like `>=1.20` or `[1.20,1.21)`
+    pub fn minecraft_dependencies(&self) -> Vec<&str> {
+        self.dependency_ranges(|mod_id| mod_id == "minecraft")
+    }
+
+    /// The loader versions the mod supports
+    pub fn loader_dependencies(&self) -> Vec<&str> {
+        self.dependency_ranges(|mod_id| LOADER_IDS.contains(&mod_id))
+    }
+
+    fn dependency_ranges(&self, filter: impl Fn(&str) -> bool) -> Vec<&str> {
+        self.dependencies
+            .iter()
+            .filter(|dependency| filter(&dependency.mod_id))
+            .filter_map(|dependency| dependency.version_range.as_deref())
+            .collect()
+    }
+}
+
+pub(crate) fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(name.trim_start_matches('/')).ok()?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).ok()?;
+    Some(content)
+}
+
+fn read_string_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    read_entry(archive, name).map(|content| String::from_utf8_lossy(&content).to_string())
+}
+
+/// Read the metadata in an archive, `None` if it has no metadata
+pub(crate) fn read_metadata<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+) -> Result<Option<ModMetadata>> {
+    if let Some(content) = read_string_entry(archive, "quilt.mod.json") {
+        return parse_quilt(archive, &content).map(Some);
+    }
+    if let Some(content) = read_string_entry(archive, "fabric.mod.json") {
+        return parse_fabric(archive, &content).map(Some);
+    }
+    if let Some(content) = read_string_entry(archive, "META-INF/neoforge.mods.toml") {
+        return parse_forge(archive, &content, true).map(Some);
+    }
+    if let Some(content) = read_string_entry(archive, "META-INF/mods.toml") {
+        return parse_forge(archive, &content, false).map(Some);
+    }
+    if let Some(content) = read_string_entry(archive, "mcmod.info") {
+        return parse_legacy_forge(archive, &content).map(Some);
+    }
+    Ok(None)
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(v) => Some(v.clone()),
+        Value::Array(v) => Some(
+            v.iter()
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+                .join(" || "),
+        ),
+        _ => None,
+    }
+}
+
+/// Get the icon path from `"icon": "path"` or `"icon": { "16": "path", "32": "path" }`,
+/// the largest one is preferred.
+fn icon_path(icon: &Value) -> Option<String> {
+    match icon {
+        Value::String(v) => Some(v.clone()),
+        Value::Object(v) => v
+            .iter()
+            .max_by_key(|(size, _)| size.parse::<u32>().unwrap_or(0))
+            .and_then(|(_, path)| path.as_str().map(|path| path.to_string())),
+        _ => None,
+    }
+}
+
+fn parse_fabric<R: Read + Seek>(archive: &mut ZipArchive<R>, content: &str) -> Result<ModMetadata> {
+    let json: Value = serde_json::from_str(content)?;
+    let mod_id = json["id"]
+        .as_str()
+        .ok_or(anyhow!("missing `id` in fabric.mod.json"))?
+        .to_string();
+    let authors = json["authors"]
+        .as_array()
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|author| match author {
+                    Value::String(v) => Some(v.clone()),
+                    Value::Object(v) => v.get("name").and_then(|v| v.as_str()).map(String::from),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut dependencies = Vec::new();
+    for (key, required) in [
+        ("depends", true),
+        ("recommends", false),
+        ("suggests", false),
+    ] {
+        if let Some(depends) = json[key].as_object() {
+            dependencies.extend(depends.iter().map(|(id, range)| ModDependency {
+                mod_id: id.clone(),
+                version_range: value_to_string(range),
+                required,
+            }));
+        }
+    }
+    let mut incompatibilities = Vec::new();
+    for (key, breaks) in [("breaks", true), ("conflicts", false)] {
+        if let Some(declared) = json[key].as_object() {
+            incompatibilities.extend(declared.iter().map(|(id, range)| ModIncompatibility {
+                mod_id: id.clone(),
+                version_range: value_to_string(range),
+                breaks,
+            }));
+        }
+    }
+    let icon = icon_path(&json["icon"]).and_then(|icon| read_entry(archive, &icon));
+    Ok(ModMetadata {
+        name: json["name"].as_str().unwrap_or(&mod_id).to_string(),
+        version: json["version"].as_str().unwrap_or_default().to_string(),
+        description: json["description"].as_str().map(String::from),
+        mod_id,
+        authors,
+        icon,
+        loader: ModLoader::Fabric,
+        dependencies,
+        incompatibilities,
+    })
+}
+
+fn parse_quilt<R: Read + Seek>(archive: &mut ZipArchive<R>, content: &str) -> Result<ModMetadata> {
+    let json: Value = serde_json::from_str(content)?;
+    let loader = &json["quilt_loader"];
+    let metadata = &loader["metadata"];
+    let mod_id = loader["id"]
+        .as_str()
+        .ok_or(anyhow!("missing `quilt_loader.id` in quilt.mod.json"))?
+        .to_string();
+    let authors = metadata["contributors"]
+        .as_object()
+        .map(|contributors| contributors.keys().cloned().collect())
+        .unwrap_or_default();
+    let dependencies = loader["depends"]
+        .as_array()
+        .map(|depends| {
+            depends
+                .iter()
+                .filter_map(|depend| match depend {
+                    Value::String(id) => Some(ModDependency {
+                        mod_id: id.clone(),
+                        version_range: None,
+                        required: true,
+                    }),
+                    Value::Object(v) => Some(ModDependency {
+                        mod_id: v.get("id")?.as_str()?.to_string(),
+                        version_range: v.get("versions").and_then(value_to_string),
+                        required: !v.get("optional").and_then(|v| v.as_bool()).unwrap_or(false),
+                    }),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let incompatibilities = loader["breaks"]
+        .as_array()
+        .map(|breaks| {
+            breaks
+                .iter()
+                .filter_map(|declared| match declared {
+                    Value::String(id) => Some(ModIncompatibility {
+                        mod_id: id.clone(),
+                        version_range: None,
+                        breaks: true,
+                    }),
+                    Value::Object(v) => Some(ModIncompatibility {
+                        mod_id: v.get("id")?.as_str()?.to_string(),
+                        version_range: v.get("versions").and_then(value_to_string),
+                        breaks: true,
+                    }),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let icon = icon_path(&metadata["icon"]).and_then(|icon| read_entry(archive, &icon));
+    Ok(ModMetadata {
+        name: metadata["name"].as_str().unwrap_or(&mod_id).to_string(),
+        version: loader["version"].as_str().unwrap_or_default().to_string(),
+        description: metadata["description"].as_str().map(String::from),
+        mod_id,
+        authors,
+        icon,
+        loader: ModLoader::Quilt,
+        dependencies,
+        incompatibilities,
+    })
+}
+
+/// Read `Implementation-Version` from `META-INF/MANIFEST.MF`, which replaces `${file.jarVersion}`
+fn read_jar_version<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<String> {
+    let manifest = read_string_entry(archive, "META-INF/MANIFEST.MF")?;
+    manifest.lines().find_map(|line| {
+        line.strip_prefix("Implementation-Version:")
+            .map(|version| version.trim().to_string())
+    })
+}
+
+/// Parse `mods.toml`, or `neoforge.mods.toml` if `neoforge`
+fn parse_forge<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    content: &str,
+    neoforge: bool,
+) -> Result<ModMetadata> {
+    let toml: Table = toml::from_str(content)?;
+    let mod_info = toml
+        .get("mods")
+        .and_then(|mods| mods.as_array())
+        .and_then(|mods| mods.first())
+        .and_then(|mod_info| mod_info.as_table())
+        .ok_or(anyhow!("missing `[[mods]]` in mods.toml"))?;
+    let get_str = |key: &str| {
+        mod_info
+            .get(key)
+            .or(toml.get(key))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+    let mod_id = get_str("modId").ok_or(anyhow!("missing `modId` in mods.toml"))?;
+    let mut version = get_str("version").unwrap_or_default();
+    if version.contains("${file.jarVersion}") {
+        if let Some(jar_version) = read_jar_version(archive) {
+            version = version.replace("${file.jarVersion}", &jar_version);
+        }
+    }
+    let authors = get_str("authors")
+        .map(|authors| {
+            authors
+                .split(',')
+                .map(|author| author.trim().to_string())
+                .filter(|author| !author.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut dependencies = Vec::new();
+    let mut incompatibilities = Vec::new();
+    let declared = toml
+        .get("dependencies")
+        .and_then(|dependencies| dependencies.get(&mod_id))
+        .and_then(|dependencies| dependencies.as_array());
+    for depend in declared.into_iter().flatten() {
+        let Some(depend) = depend.as_table() else {
+            continue;
+        };
+        let Some(depend_id) = depend.get("modId").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let version_range = depend
+            .get("versionRange")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        // `mandatory` is replaced by `type` since forge 1.20.6
+        match depend.get("type").and_then(|v| v.as_str()) {
+            Some(r#type @ ("incompatible" | "discouraged")) => {
+                incompatibilities.push(ModIncompatibility {
+                    mod_id: depend_id.to_string(),
+                    version_range,
+                    breaks: r#type == "incompatible",
+                })
+            }
+            r#type => dependencies.push(ModDependency {
+                mod_id: depend_id.to_string(),
+                version_range,
+                required: match r#type {
+                    Some(r#type) => r#type == "required",
+                    None => depend
+                        .get("mandatory")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(true),
+                },
+            }),
+        }
+    }
+    // the mods for the early versions of neoforge still use `mods.toml`
+    let loader = if neoforge
+        || dependencies
+            .iter()
+            .any(|dependency| dependency.mod_id == "neoforge")
+    {
+        ModLoader::NeoForge
+    } else {
+        ModLoader::Forge
+    };
+    let icon = get_str("logoFile").and_then(|icon| read_entry(archive, &icon));
+    Ok(ModMetadata {
+        name: get_str("displayName").unwrap_or(mod_id.clone()),
+        description: get_str("description").map(|v| v.trim().to_string()),
+        mod_id,
+        version,
+        authors,
+        icon,
+        loader,
+        dependencies,
+        incompatibilities,
+    })
+}
+
+fn parse_legacy_forge<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    content: &str,
+) -> Result<ModMetadata> {
+    // some mods have raw line breaks in strings
+    let content = content
+        .trim_start_matches('\u{feff}')
+        .replace("\n\n", "\\n")
+        .replace('\n', "");
+    let json: Value = serde_json::from_str(&content)?;
+    // `[{...}]` or `{ "modListVersion": 2, "modList": [{...}] }`
+    let info = match &json {
+        Value::Array(v) => v.first(),
+        Value::Object(v) => v
+            .get("modList")
+            .and_then(|v| v.as_array())
+            .and_then(|v| v.first()),
+        _ => None,
+    }
+    .ok_or(anyhow!("no mod declared in mcmod.info"))?;
+    let mod_id = info["modid"]
+        .as_str()
+        .ok_or(anyhow!("missing `modid` in mcmod.info"))?
+        .to_string();
+    let authors = info["authorList"]
+        .as_array()
+        .or(info["authors"].as_array())
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    let dependencies = info["requiredMods"]
+        .as_array()
+        .map(|depends| {
+            depends
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|depend| match depend.split_once('@') {
+                    Some((id, range)) => ModDependency {
+                        mod_id: id.to_string(),
+                        version_range: Some(range.to_string()),
+                        required: true,
+                    },
+                    None => ModDependency {
+                        mod_id: depend.to_string(),
+                        version_range: None,
+                        required: true,
+                    },
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let icon = info["logoFile"]
+        .as_str()
+        .filter(|icon| !icon.is_empty())
+        .and_then(|icon| read_entry(archive, icon));
+    Ok(ModMetadata {
+        name: info["name"].as_str().unwrap_or(&mod_id).to_string(),
+        version: info["version"].as_str().unwrap_or_default().to_string(),
+        description: info["description"].as_str().map(String::from),
+        mod_id,
+        authors,
+        icon,
+        loader: ModLoader::LegacyForge,
+        dependencies,
+        incompatibilities: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor, io::Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    fn write_jar(path: &Path, files: &[(&str, &str)]) {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        fs::write(path, writer.finish().unwrap().into_inner()).unwrap();
+    }
+
+    #[test]
+    fn test_from_jar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mod.jar");
+
+        write_jar(
+            &path,
+            &[(
+                "META-INF/neoforge.mods.toml",
+                r#"
+[[mods]]
+modId = "example"
+version = "1.0.0"
+
+[[dependencies.example]]
+modId = "neoforge"
+type = "required"
+versionRange = "[20.6,)"
+
+[[dependencies.example]]
+modId = "minecraft"
+type = "required"
+versionRange = "[1.20.6,1.21)"
+"#,
+            )],
+        );
+        let metadata = ModMetadata::from_jar(&path).unwrap().unwrap();
+        assert_eq!(metadata.loader, ModLoader::NeoForge);
+        assert_eq!(metadata.minecraft_dependencies(), vec!["[1.20.6,1.21)"]);
+        assert_eq!(metadata.loader_dependencies(), vec!["[20.6,)"]);
+
+        write_jar(
+            &path,
+            &[(
+                "META-INF/mods.toml",
+                r#"
+[[mods]]
+modId = "example"
+
+[[dependencies.example]]
+modId = "neoforge"
+mandatory = true
+versionRange = "[20.4,)"
+"#,
+            )],
+        );
+        let metadata = ModMetadata::from_jar(&path).unwrap().unwrap();
+        assert_eq!(metadata.loader, ModLoader::NeoForge);
+
+        write_jar(
+            &path,
+            &[(
+                "fabric.mod.json",
+                r#"{ "id": "example", "version": "1.0.0", "depends": { "fabricloader": ">=0.15", "minecraft": "~1.20.4" } }"#,
+            )],
+        );
+        let metadata = ModMetadata::from_jar(&path).unwrap().unwrap();
+        assert_eq!(metadata.loader, ModLoader::Fabric);
+        assert_eq!(metadata.minecraft_dependencies(), vec!["~1.20.4"]);
+        assert_eq!(metadata.loader_dependencies(), vec![">=0.15"]);
+
+        write_jar(
+            &path,
+            &[("pack.mcmeta", r#"{ "pack": { "pack_format": 15 } }"#)],
+        );
+        assert_eq!(ModMetadata::from_jar(&path).unwrap(), None);
+
+        write_jar(&path, &[("fabric.mod.json", "{}")]);
+        assert!(ModMetadata::from_jar(&path).is_err());
+        assert!(ModMetadata::from_jar(&dir.path().join("missing.jar")).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Manage the mods installed in a game directory.
+//!
+//! The scanner detects the metadata format of every jar in the folder, see
+//! [`metadata::ModMetadata::from_jar`], and reports the jars it can't read.
+//!
+//! # Example
+//!
+//! ```
+//! use mgl_core::mods::scan::{scan_mods, ModInfo};
+//!
+//! for mod_info in scan_mods(".minecraft/mods") {
+//!     match mod_info {
+//!         ModInfo::Mod { metadata, .. } => println!("{} {}", metadata.mod_id, metadata.version),
+//!         ModInfo::Unknown { path, reason } => println!("{}: {reason}", path.display()),
+//!     }
+//! }
+//! ```
+
+pub mod conflicts;
+pub mod metadata;
+pub mod scan;
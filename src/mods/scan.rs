@@ -0,0 +1,398 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs::{self, File},
+    io::{Cursor, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use zip::ZipArchive;
+
+use super::metadata::{read_entry, read_metadata};
+pub use super::metadata::{ModDependency, ModIncompatibility, ModLoader, ModMetadata};
+
+/// The suffix appended to the file name of a disabled mod
+pub const DISABLED_SUFFIX: &str = ".disabled";
+
+// boxing the metadata would only make matching on the variants harder, most are `Mod`
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModInfo {
+    Mod {
+        path: PathBuf,
+        enabled: bool,
+        metadata: ModMetadata,
+        /// Mods bundled in the jar (Jar-in-Jar), including the ones nested in them
+        embedded: Vec<ModMetadata>,
+    },
+    Unknown {
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+impl ModInfo {
+    pub fn path(&self) -> &Path {
+        match self {
+            ModInfo::Mod { path, .. } => path,
+            ModInfo::Unknown { path, .. } => path,
+        }
+    }
+}
+
+/// Scan all `.jar` and `.jar.disabled` files in the mods folder.
+///
+/// Jars which are unreadable or don't contain any supported metadata are reported as
+/// [`ModInfo::Unknown`]. The result is sorted by path.
+pub fn scan_mods<P: AsRef<Path>>(mods_dir: P) -> Vec<ModInfo> {
+    let entries = match fs::read_dir(mods_dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.ends_with(".jar") || name.ends_with(&format!(".jar{DISABLED_SUFFIX}"))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.into_iter().map(scan_mod).collect()
+}
+
+/// Read the metadata of a single mod jar
+pub fn scan_mod<P: AsRef<Path>>(path: P) -> ModInfo {
+    let path = path.as_ref().to_path_buf();
+    let enabled = !path.to_string_lossy().ends_with(DISABLED_SUFFIX);
+    let result = File::open(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| Ok(ZipArchive::new(file)?))
+        .and_then(|mut archive| {
+            let metadata = read_metadata(&mut archive)?.ok_or(anyhow!("no mod metadata found"))?;
+            let embedded = read_embedded_mods(&mut archive);
+            Ok((metadata, embedded))
+        });
+    match result {
+        Ok((metadata, embedded)) => ModInfo::Mod {
+            path,
+            enabled,
+            metadata,
+            embedded,
+        },
+        Err(err) => ModInfo::Unknown {
+            path,
+            reason: err.to_string(),
+        },
+    }
+}
+
+/// Enable a mod by removing the `.disabled` suffix. Returns the new path.
+pub fn enable_mod<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let new_name = file_name
+        .strip_suffix(DISABLED_SUFFIX)
+        .ok_or(anyhow!("{} is not disabled", path.display()))?;
+    rename_mod(path, path.with_file_name(new_name))
+}
+
+/// Disable a mod by appending the `.disabled` suffix. Returns the new path.
+pub fn disable_mod<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    if file_name.ends_with(DISABLED_SUFFIX) {
+        return Err(anyhow!("{} is already disabled", path.display()));
+    }
+    rename_mod(
+        path,
+        path.with_file_name(format!("{file_name}{DISABLED_SUFFIX}")),
+    )
+}
+
+fn rename_mod(from: &Path, to: PathBuf) -> Result<PathBuf> {
+    if to.exists() {
+        return Err(anyhow!("{} already exists", to.display()));
+    }
+    fs::rename(from, &to)?;
+    Ok(to)
+}
+
+fn read_embedded_mods<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Vec<ModMetadata> {
+    let names = archive
+        .file_names()
+        .filter(|name| name.starts_with("META-INF/") && name.ends_with(".jar"))
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    let mut result = Vec::new();
+    for name in names {
+        let content = match read_entry(archive, &name) {
+            Some(content) => content,
+            None => continue,
+        };
+        let mut nested = match ZipArchive::new(Cursor::new(content)) {
+            Ok(nested) => nested,
+            Err(_) => continue,
+        };
+        // libraries bundled in the jar don't have metadata
+        if let Ok(Some(metadata)) = read_metadata(&mut nested) {
+            result.push(metadata);
+            result.extend(read_embedded_mods(&mut nested));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    fn build_jar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn metadata(mod_info: &ModInfo) -> &ModMetadata {
+        match mod_info {
+            ModInfo::Mod { metadata, .. } => metadata,
+            ModInfo::Unknown { path, reason } => panic!("{}: {reason}", path.display()),
+        }
+    }
+
+    #[test]
+    fn test_fabric() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = build_jar(&[(
+            "fabric.mod.json",
+            br#"{ "schemaVersion": 1, "id": "fabric-api-base", "version": "0.4.29" }"#,
+        )]);
+        let jar = build_jar(&[
+            (
+                "fabric.mod.json",
+                br#"{
+                    "schemaVersion": 1,
+                    "id": "carpet",
+                    "version": "1.4.112",
+                    "name": "Carpet Mod",
+                    "authors": ["gnembon", { "name": "altrisi" }],
+                    "icon": { "16": "assets/carpet/icon16.png", "128": "assets/carpet/icon.png" },
+                    "depends": { "minecraft": ["1.20", "1.20.1"], "fabricloader": ">=0.14.18" },
+                    "recommends": { "fabric-api": "*" },
+                    "breaks": { "optifabric": "*" },
+                    "conflicts": { "lithium": "<0.11" },
+                    "jars": [{ "file": "META-INF/jars/fabric-api-base.jar" }]
+                }"#,
+            ),
+            ("assets/carpet/icon.png", b"icon"),
+            ("META-INF/jars/fabric-api-base.jar", &nested),
+        ]);
+        fs::write(dir.path().join("carpet.jar"), jar).unwrap();
+
+        let mods = scan_mods(dir.path());
+        assert_eq!(mods.len(), 1);
+        let ModInfo::Mod {
+            metadata,
+            embedded,
+            enabled,
+            ..
+        } = &mods[0]
+        else {
+            panic!("{:?}", mods[0]);
+        };
+        assert!(enabled);
+        assert_eq!(metadata.name, "Carpet Mod");
+        assert_eq!(metadata.loader, ModLoader::Fabric);
+        assert_eq!(metadata.authors, vec!["gnembon", "altrisi"]);
+        assert_eq!(metadata.icon.as_deref(), Some(b"icon".as_slice()));
+        assert!(metadata.dependencies.contains(&ModDependency {
+            mod_id: "minecraft".to_string(),
+            version_range: Some("1.20 || 1.20.1".to_string()),
+            required: true,
+        }));
+        assert!(metadata.dependencies.contains(&ModDependency {
+            mod_id: "fabric-api".to_string(),
+            version_range: Some("*".to_string()),
+            required: false,
+        }));
+        assert_eq!(
+            metadata.incompatibilities,
+            vec![
+                ModIncompatibility {
+                    mod_id: "optifabric".to_string(),
+                    version_range: Some("*".to_string()),
+                    breaks: true,
+                },
+                ModIncompatibility {
+                    mod_id: "lithium".to_string(),
+                    version_range: Some("<0.11".to_string()),
+                    breaks: false,
+                },
+            ]
+        );
+        assert_eq!(embedded.len(), 1);
+        assert_eq!(embedded[0].mod_id, "fabric-api-base");
+    }
+
+    #[test]
+    fn test_quilt() {
+        let dir = tempfile::tempdir().unwrap();
+        let jar = build_jar(&[(
+            "quilt.mod.json",
+            br#"{
+                "schema_version": 1,
+                "quilt_loader": {
+                    "group": "org.quiltmc",
+                    "id": "example_mod",
+                    "version": "1.0.0",
+                    "metadata": { "name": "Example", "contributors": { "Someone": "Owner" } },
+                    "depends": ["quilt_loader", { "id": "minecraft", "versions": ">=1.20", "optional": true }]
+                }
+            }"#,
+        )]);
+        let path = dir.path().join("example.jar");
+        fs::write(&path, jar).unwrap();
+
+        let mod_info = scan_mod(&path);
+        let metadata = metadata(&mod_info);
+        assert_eq!(metadata.loader, ModLoader::Quilt);
+        assert_eq!(metadata.name, "Example");
+        assert_eq!(metadata.authors, vec!["Someone"]);
+        assert_eq!(
+            metadata.dependencies[1],
+            ModDependency {
+                mod_id: "minecraft".to_string(),
+                version_range: Some(">=1.20".to_string()),
+                required: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_forge() {
+        let dir = tempfile::tempdir().unwrap();
+        let jar = build_jar(&[
+            (
+                "META-INF/mods.toml",
+                br#"
+modLoader = "javafml"
+loaderVersion = "[47,)"
+license = "MIT"
+authors = "mezz, someone"
+
+[[mods]]
+modId = "jei"
+version = "${file.jarVersion}"
+displayName = "Just Enough Items"
+logoFile = "logo.png"
+description = '''
+JEI is an item and recipe viewing mod.
+'''
+
+[[dependencies.jei]]
+modId = "forge"
+mandatory = true
+versionRange = "[47,)"
+
+[[dependencies.jei]]
+modId = "rei"
+type = "incompatible"
+"#,
+            ),
+            (
+                "META-INF/MANIFEST.MF",
+                b"Manifest-Version: 1.0\r\nImplementation-Version: 15.2.0.27\r\n",
+            ),
+            ("logo.png", b"logo"),
+        ]);
+        let path = dir.path().join("jei.jar");
+        fs::write(&path, jar).unwrap();
+
+        let mod_info = scan_mod(&path);
+        let metadata = metadata(&mod_info);
+        assert_eq!(metadata.loader, ModLoader::Forge);
+        assert_eq!(metadata.version, "15.2.0.27");
+        assert_eq!(metadata.authors, vec!["mezz", "someone"]);
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("JEI is an item and recipe viewing mod.")
+        );
+        assert_eq!(metadata.icon.as_deref(), Some(b"logo".as_slice()));
+        assert_eq!(metadata.dependencies.len(), 1);
+        assert_eq!(metadata.dependencies[0].mod_id, "forge");
+        assert!(metadata.dependencies[0].required);
+        assert_eq!(metadata.incompatibilities[0].mod_id, "rei");
+        assert!(metadata.incompatibilities[0].breaks);
+    }
+
+    #[test]
+    fn test_legacy_forge() {
+        let dir = tempfile::tempdir().unwrap();
+        let jar = build_jar(&[(
+            "mcmod.info",
+            b"[{\"modid\": \"journeymap\", \"name\": \"JourneyMap\", \"version\": \"5.7.1\",\n\"authorList\": [\"techbrew\"], \"requiredMods\": [\"Forge@[14.23,)\"]}]",
+        )]);
+        let path = dir.path().join("journeymap.jar");
+        fs::write(&path, jar).unwrap();
+
+        let mod_info = scan_mod(&path);
+        let metadata = metadata(&mod_info);
+        assert_eq!(metadata.loader, ModLoader::LegacyForge);
+        assert_eq!(metadata.mod_id, "journeymap");
+        assert_eq!(metadata.authors, vec!["techbrew"]);
+        assert_eq!(
+            metadata.dependencies[0].version_range.as_deref(),
+            Some("[14.23,)")
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_toggle() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("broken.jar"), b"not a zip").unwrap();
+        fs::write(
+            dir.path().join("library.jar.disabled"),
+            build_jar(&[("a.class", b"")]),
+        )
+        .unwrap();
+        fs::write(dir.path().join("readme.txt"), b"").unwrap();
+
+        let mods = scan_mods(dir.path());
+        assert_eq!(mods.len(), 2);
+        assert!(mods
+            .iter()
+            .all(|mod_info| matches!(mod_info, ModInfo::Unknown { .. })));
+
+        let enabled = enable_mod(dir.path().join("library.jar.disabled")).unwrap();
+        assert_eq!(enabled, dir.path().join("library.jar"));
+        assert!(enable_mod(&enabled).is_err());
+        let disabled = disable_mod(&enabled).unwrap();
+        assert_eq!(disabled, dir.path().join("library.jar.disabled"));
+        assert!(disabled.exists() && !enabled.exists());
+    }
+}
@@ -0,0 +1,302 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Game profiles stored in a json file.
+//!
+//! A profile is a named launch configuration of an installed version. Every profile has a game
+//! directory, use the minecraft root to share `mods`, `saves` and so on with the other profiles,
+//! or another directory to isolate them. An isolated game directory is an [`Instance`], the
+//! profiles only keep the list of them and which one is active, see
+//! [`ProfileManager::add_instances`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::path::Path;
+//! use mgl_core::core::folder::MinecraftLocation;
+//! use mgl_core::profile::{GameProfile, ProfileManager};
+//!
+//! fn fn_name() {
+//!     let path = Path::new(".minecraft/mgl_profiles.json");
+//!     let mut manager = ProfileManager::load_from_disk(path).unwrap_or_default();
+//!     let id = manager.add(GameProfile::new("Modded", "1.20.1-fabric", ".minecraft/profiles/modded"));
+//!     manager.set_active(id).unwrap();
+//!     manager.save_to_disk(path).unwrap();
+//!
+//!     let minecraft = MinecraftLocation::new(".minecraft");
+//!     let location = manager.active_profile().unwrap().minecraft_location(&minecraft);
+//! }
+//! ```
+//!
+//! Add the instances created by [`InstanceManager`] as profiles:
+//!
+//! ```rust
+//! use mgl_core::instance::InstanceManager;
+//! use mgl_core::profile::ProfileManager;
+//!
+//! async fn fn_name() {
+//!     let mut manager = ProfileManager::default();
+//!     manager.add_instances(&InstanceManager::new(".minecraft/instances")).unwrap();
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    core::folder::MinecraftLocation,
+    instance::{Instance, InstanceManager},
+    launch::{jvm::MemoryConfig, options::LaunchOptions},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    pub version_id: String,
+
+    /// The directory of `mods`, `saves`, `options.txt` and so on
+    pub game_dir: PathBuf,
+    pub java_path: Option<PathBuf>,
+    pub memory: Option<MemoryConfig>,
+
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    pub icon: Option<PathBuf>,
+}
+
+impl GameProfile {
+    /// Create a profile with a random id
+    pub fn new<P: AsRef<Path>>(name: &str, version_id: &str, game_dir: P) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            version_id: version_id.to_string(),
+            game_dir: game_dir.as_ref().to_path_buf(),
+            java_path: None,
+            memory: None,
+            jvm_args: vec![],
+            icon: None,
+        }
+    }
+
+    /// Whether the game content is isolated from the minecraft root
+    pub fn is_isolated(&self, minecraft: &MinecraftLocation) -> bool {
+        self.game_dir != minecraft.root
+    }
+
+    /// The instance in the game directory with the settings of this profile. It's read from
+    /// `instance.toml` if exists, the loader and the game arguments of the instance are kept.
+    pub fn instance(&self) -> Instance {
+        let mut instance = Instance::load(&self.game_dir)
+            .unwrap_or_else(|_| Instance::new(&self.name, &self.game_dir, &self.version_id));
+        instance.name = self.name.clone();
+        instance.version_id = self.version_id.clone();
+        instance.java = self.java_path.clone();
+        instance.memory = self.memory;
+        instance.jvm_args = self.jvm_args.clone();
+        instance
+    }
+
+    /// The folders of this profile, `versions`, `libraries` and `assets` are shared in `minecraft`
+    pub fn minecraft_location(&self, minecraft: &MinecraftLocation) -> MinecraftLocation {
+        if self.is_isolated(minecraft) {
+            self.instance().minecraft_location(minecraft)
+        } else {
+            minecraft.clone()
+        }
+    }
+
+    /// Create the launch options, `${game_directory}` is the game directory of this profile
+    pub async fn launch_options(&self, minecraft: &MinecraftLocation) -> Result<LaunchOptions> {
+        self.instance().launch_options(minecraft).await
+    }
+}
+
+impl From<&Instance> for GameProfile {
+    fn from(instance: &Instance) -> Self {
+        Self {
+            java_path: instance.java.clone(),
+            memory: instance.memory,
+            jvm_args: instance.jvm_args.clone(),
+            ..GameProfile::new(&instance.name, &instance.version_id, &instance.path)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileManager {
+    pub profiles: Vec<GameProfile>,
+    pub active: Option<Uuid>,
+}
+
+impl ProfileManager {
+    /// Add a profile, a profile with the same id is replaced
+    pub fn add(&mut self, profile: GameProfile) -> Uuid {
+        let id = profile.id;
+        match self.profiles.iter_mut().find(|exist| exist.id == id) {
+            Some(exist) => *exist = profile,
+            None => self.profiles.push(profile),
+        }
+        id
+    }
+
+    /// Remove a profile, the active profile is unset if it's removed
+    pub fn remove(&mut self, id: Uuid) -> Option<GameProfile> {
+        let index = self.profiles.iter().position(|profile| profile.id == id)?;
+        if self.active == Some(id) {
+            self.active = None;
+        }
+        Some(self.profiles.remove(index))
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&GameProfile> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: Uuid) -> Option<&mut GameProfile> {
+        self.profiles.iter_mut().find(|profile| profile.id == id)
+    }
+
+    pub fn set_active(&mut self, id: Uuid) -> Result<()> {
+        if self.get(id).is_none() {
+            return Err(anyhow!("profile {id} does not exist"));
+        }
+        self.active = Some(id);
+        Ok(())
+    }
+
+    pub fn active_profile(&self) -> Option<&GameProfile> {
+        self.get(self.active?)
+    }
+
+    /// Add a profile for every instance whose folder is not used by a profile yet, returns the ids
+    /// of the added profiles
+    pub fn add_instances(&mut self, instances: &InstanceManager) -> Result<Vec<Uuid>> {
+        let added = instances
+            .list()?
+            .iter()
+            .filter(|instance| {
+                !self
+                    .profiles
+                    .iter()
+                    .any(|profile| profile.game_dir == instance.path)
+            })
+            .map(GameProfile::from)
+            .collect::<Vec<_>>();
+        Ok(added.into_iter().map(|profile| self.add(profile)).collect())
+    }
+
+    pub fn save_to_disk(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load_from_disk(path: &Path) -> Result<ProfileManager> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instance::INSTANCE_FILE;
+
+    use super::*;
+
+    #[test]
+    fn test_manage_profiles() {
+        let mut manager = ProfileManager::default();
+        let vanilla = manager.add(GameProfile::new("Vanilla", "1.20.1", ".minecraft"));
+        let modded = manager.add(GameProfile::new(
+            "Modded",
+            "1.20.1-fabric",
+            ".minecraft/profiles/modded",
+        ));
+        assert!(manager.set_active(Uuid::new_v4()).is_err());
+        manager.set_active(modded).unwrap();
+        assert_eq!(manager.active_profile().unwrap().name, "Modded");
+
+        let minecraft = MinecraftLocation::new(".minecraft");
+        let location = manager.get(modded).unwrap().minecraft_location(&minecraft);
+        assert_eq!(location.mods, Path::new(".minecraft/profiles/modded/mods"));
+        assert_eq!(location.versions, Path::new(".minecraft/versions"));
+        let location = manager.get(vanilla).unwrap().minecraft_location(&minecraft);
+        assert_eq!(location.mods, Path::new(".minecraft/mods"));
+
+        assert_eq!(manager.remove(modded).unwrap().name, "Modded");
+        assert_eq!(manager.active, None);
+        assert!(manager.remove(modded).is_none());
+        assert_eq!(manager.profiles.len(), 1);
+    }
+
+    #[test]
+    fn test_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let instances = InstanceManager::new(dir.path());
+        let mut instance = instances.create("Modded", "1.20.1-fabric").unwrap();
+        instance.game_args = vec!["--demo".to_string()];
+        instance.save().unwrap();
+        instances.create("Vanilla", "1.20.1").unwrap();
+
+        let mut manager = ProfileManager::default();
+        let added = manager.add_instances(&instances).unwrap();
+        assert_eq!(added.len(), 2);
+        assert!(manager.add_instances(&instances).unwrap().is_empty());
+
+        let profile = manager.get_mut(added[0]).unwrap();
+        assert_eq!(profile.game_dir, dir.path().join("Modded"));
+        profile.jvm_args = vec!["-XX:+UseG1GC".to_string()];
+        let instance = profile.instance();
+        assert_eq!(instance.jvm_args, profile.jvm_args);
+        assert_eq!(instance.game_args, vec!["--demo"]);
+
+        let profile = GameProfile::new("New", "1.20.1", dir.path().join("New"));
+        assert!(!profile.game_dir.join(INSTANCE_FILE).exists());
+        assert_eq!(profile.instance().path, dir.path().join("New"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profiles/mgl_profiles.json");
+        let mut manager = ProfileManager::default();
+        let mut profile = GameProfile::new("Modded", "1.20.1-fabric", dir.path().join("modded"));
+        profile.memory = Some(MemoryConfig {
+            min_mb: 512,
+            max_mb: 4096,
+        });
+        profile.jvm_args = vec!["-XX:+UseG1GC".to_string()];
+        let id = manager.add(profile);
+        manager.set_active(id).unwrap();
+        manager.save_to_disk(&path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["active"], id.to_string());
+        assert_eq!(json["profiles"][0]["versionId"], "1.20.1-fabric");
+        assert_eq!(ProfileManager::load_from_disk(&path).unwrap(), manager);
+    }
+}
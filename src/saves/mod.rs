@@ -19,6 +19,7 @@
 pub mod gamerule;
 pub mod player;
 pub mod level;
+pub mod world;
 
 pub struct PlayerData {
     // todo
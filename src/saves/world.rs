@@ -0,0 +1,319 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! List, back up and restore the worlds in `saves`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::path::Path;
+//!
+//! use mgl_core::saves::world::{backup_save, list_saves, restore_save};
+//!
+//! fn fn_name() {
+//!     let saves = list_saves(Path::new(".minecraft")).unwrap();
+//!     let backup = backup_save(&saves[0], Path::new("backups")).unwrap();
+//!     let restored = restore_save(&backup, Path::new(".minecraft")).unwrap();
+//!     println!("{} is restored to {}", restored.level_name, restored.path.display());
+//! }
+//! ```
+
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::Deserialize;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+const SAVES_DIR: &str = "saves";
+const LEVEL_DAT: &str = "level.dat";
+
+/// The summary of a world, read from its `level.dat`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldSave {
+    /// The folder name in `saves`
+    pub name: String,
+    pub path: PathBuf,
+
+    /// The name shown in the game
+    pub level_name: String,
+    pub last_played: DateTime<Utc>,
+
+    /// The default game mode, 0 is Survival, 1 is Creative, 2 is Adventure and 3 is Spectator
+    pub game_type: i32,
+
+    /// The Minecraft version the world was last saved in, absent before 1.9
+    pub version_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LevelSummary {
+    #[serde(rename = "Data")]
+    data: LevelSummaryData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LevelSummaryData {
+    level_name: String,
+    last_played: i64,
+    game_type: i32,
+    version: Option<LevelSummaryVersion>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LevelSummaryVersion {
+    name: String,
+}
+
+impl WorldSave {
+    /// Read the world in a folder, only the summary fields of `level.dat` are parsed
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path.join(LEVEL_DAT))?;
+        let summary: LevelSummary = nbt::from_gzip_reader(io::BufReader::new(file))?;
+        let name = path
+            .file_name()
+            .ok_or(anyhow!("{} is not a world folder", path.display()))?
+            .to_string_lossy()
+            .to_string();
+        Ok(Self {
+            name,
+            path: path.to_path_buf(),
+            level_name: summary.data.level_name,
+            last_played: DateTime::from_timestamp_millis(summary.data.last_played)
+                .unwrap_or_default(),
+            game_type: summary.data.game_type,
+            version_name: summary.data.version.map(|version| version.name),
+        })
+    }
+}
+
+/// List the worlds of a game directory from the last played, the folders without a readable
+/// `level.dat` are skipped
+pub fn list_saves(game_dir: &Path) -> Result<Vec<WorldSave>> {
+    let dir = game_dir.join(SAVES_DIR);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut saves = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if let Ok(save) = WorldSave::from_path(&path) {
+            saves.push(save);
+        }
+    }
+    saves.sort_by(|a, b| {
+        b.last_played
+            .cmp(&a.last_played)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(saves)
+}
+
+/// Zip a world to `<dest>/<world folder>_<local time>.zip`, the files are put in a folder named
+/// after the world in the zip
+pub fn backup_save(save: &WorldSave, dest: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dest)?;
+    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let backup = dest.join(format!("{}_{timestamp}.zip", save.name));
+    let mut writer = ZipWriter::new(File::create(&backup)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut dirs = vec![(save.path.clone(), save.name.clone())];
+    while let Some((dir, prefix)) = dirs.pop() {
+        writer.add_directory(format!("{prefix}/"), options)?;
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = format!("{prefix}/{}", entry.file_name().to_string_lossy());
+            if path.is_dir() {
+                dirs.push((path, name));
+            } else {
+                writer.start_file(name, options)?;
+                io::copy(&mut File::open(&path)?, &mut writer)?;
+            }
+        }
+    }
+    writer.finish()?;
+    Ok(backup)
+}
+
+/// Extract a backup made by [`backup_save`] to `saves` of a game directory.
+///
+/// The world keeps its folder name, a suffix like ` (1)` is added if the folder exists, so an
+/// existing world is never overwritten.
+pub fn restore_save(backup_zip: &Path, game_dir: &Path) -> Result<WorldSave> {
+    let mut archive = ZipArchive::new(File::open(backup_zip)?)?;
+    let level_dat = archive
+        .file_names()
+        .filter_map(|name| Some(Path::new(name).parent()?.to_path_buf()))
+        .filter(|parent| parent.components().count() <= 1)
+        .find(|parent| {
+            archive
+                .file_names()
+                .any(|name| Path::new(name) == parent.join(LEVEL_DAT))
+        })
+        .ok_or(anyhow!("{} has no {LEVEL_DAT}", backup_zip.display()))?;
+    // the world is the root of the zip if it has no folder
+    let name = match level_dat.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => backup_zip
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+    };
+
+    let saves = game_dir.join(SAVES_DIR);
+    let mut target = saves.join(&name);
+    let mut index = 1;
+    while target.exists() {
+        target = saves.join(format!("{name} ({index})"));
+        index += 1;
+    }
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let Some(path) = file.enclosed_name().map(Path::to_path_buf) else {
+            continue;
+        };
+        let Ok(relative) = path.strip_prefix(&level_dat) else {
+            continue;
+        };
+        let output = target.join(relative);
+        if file.is_dir() {
+            fs::create_dir_all(output)?;
+            continue;
+        }
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut File::create(output)?)?;
+    }
+    WorldSave::from_path(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use nbt::{Blob, Value};
+
+    use super::*;
+
+    fn write_world(dir: &Path, level_name: &str, last_played: i64, version: Option<&str>) {
+        let mut data = HashMap::from([
+            (
+                "LevelName".to_string(),
+                Value::String(level_name.to_string()),
+            ),
+            ("LastPlayed".to_string(), Value::Long(last_played)),
+            ("GameType".to_string(), Value::Int(1)),
+            ("DayTime".to_string(), Value::Long(6000)),
+        ]);
+        if let Some(version) = version {
+            data.insert(
+                "Version".to_string(),
+                Value::Compound(HashMap::from([
+                    ("Name".to_string(), Value::String(version.to_string())),
+                    ("Id".to_string(), Value::Int(3465)),
+                ])),
+            );
+        }
+        let mut level = Blob::new();
+        level.insert("Data", Value::Compound(data)).unwrap();
+        fs::create_dir_all(dir.join("region")).unwrap();
+        level
+            .to_gzip_writer(&mut File::create(dir.join(LEVEL_DAT)).unwrap())
+            .unwrap();
+        fs::write(dir.join("region").join("r.0.0.mca"), b"region").unwrap();
+    }
+
+    #[test]
+    fn test_list_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list_saves(dir.path()).unwrap().is_empty());
+
+        let saves = dir.path().join(SAVES_DIR);
+        write_world(
+            &saves.join("New World"),
+            "My World",
+            1_690_000_000_000,
+            Some("1.20.1"),
+        );
+        write_world(&saves.join("Old"), "Old World", 1_400_000_000_000, None);
+        fs::create_dir_all(saves.join("empty")).unwrap();
+        fs::write(saves.join("readme.txt"), b"").unwrap();
+
+        let list = list_saves(dir.path()).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, "New World");
+        assert_eq!(list[0].level_name, "My World");
+        assert_eq!(list[0].last_played.timestamp_millis(), 1_690_000_000_000);
+        assert_eq!(list[0].game_type, 1);
+        assert_eq!(list[0].version_name.as_deref(), Some("1.20.1"));
+        assert_eq!(list[1].name, "Old");
+        assert_eq!(list[1].version_name, None);
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_dir = dir.path().join(".minecraft");
+        write_world(
+            &game_dir.join(SAVES_DIR).join("World"),
+            "World",
+            1_690_000_000_000,
+            Some("1.20.1"),
+        );
+        let save = list_saves(&game_dir).unwrap().remove(0);
+
+        let backup = backup_save(&save, &dir.path().join("backups")).unwrap();
+        let file_name = backup.file_name().unwrap().to_string_lossy().to_string();
+        assert!(file_name.starts_with("World_") && file_name.ends_with(".zip"));
+
+        let restored = restore_save(&backup, &game_dir).unwrap();
+        assert_eq!(restored.name, "World (1)");
+        assert_eq!(restored.level_name, "World");
+        assert_eq!(
+            fs::read(restored.path.join("region").join("r.0.0.mca")).unwrap(),
+            b"region"
+        );
+
+        let other_game_dir = dir.path().join("other");
+        let restored = restore_save(&backup, &other_game_dir).unwrap();
+        assert_eq!(restored.path, other_game_dir.join(SAVES_DIR).join("World"));
+        assert_eq!(list_saves(&other_game_dir).unwrap(), vec![restored]);
+
+        let not_a_world = dir.path().join("not_a_world.zip");
+        let mut writer = ZipWriter::new(File::create(&not_a_world).unwrap());
+        writer
+            .start_file("readme.txt", FileOptions::default())
+            .unwrap();
+        writer.finish().unwrap();
+        assert!(restore_save(&not_a_world, &game_dir).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A file cache of the json responses
+//!
+//! # Example
+//!
+//! ```rust
+//! use mgl_core::utils::cache::CachedHttpClient;
+//!
+//! async fn fn_name() {
+//!     let client = CachedHttpClient::new(".cache/http", 600);
+//!     let manifest: serde_json::Value = client
+//!         .get_json("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json")
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+/// A http client caching the json responses in `cache_dir`, the responses older than
+/// `max_age_seconds` are fetched again
+#[derive(Debug, Clone)]
+pub struct CachedHttpClient {
+    pub client: Client,
+    pub cache_dir: PathBuf,
+    pub max_age_seconds: u64,
+}
+
+impl CachedHttpClient {
+    pub fn new<P: AsRef<Path>>(cache_dir: P, max_age_seconds: u64) -> Self {
+        Self {
+            client: Client::new(),
+            cache_dir: cache_dir.as_ref().to_path_buf(),
+            max_age_seconds,
+        }
+    }
+
+    /// The cache file of the url, `<sha256 of the url>.json`
+    pub fn cache_path(&self, url: &str) -> PathBuf {
+        self.cache_dir
+            .join(format!("{:x}.json", Sha256::digest(url.as_bytes())))
+    }
+
+    /// Get the json from the cache if it's fresh, otherwise fetch it and update the cache
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let path = self.cache_path(url);
+        if self.is_fresh(&path).await {
+            if let Ok(value) = serde_json::from_slice(&fs::read(&path).await?) {
+                return Ok(value);
+            }
+        }
+        let content = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let value = serde_json::from_slice(&content)?;
+        fs::create_dir_all(&self.cache_dir).await?;
+        // write to a temporary file first, so a concurrent reader never sees a partial file
+        let temp_path = path.with_extension("json.part");
+        fs::write(&temp_path, &content).await?;
+        fs::rename(&temp_path, &path).await?;
+        Ok(value)
+    }
+
+    /// Remove the cached response of the url
+    pub async fn invalidate(&self, url: &str) -> Result<()> {
+        match fs::remove_file(self.cache_path(url)).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove all the cached responses
+    pub async fn clear_all(&self) -> Result<()> {
+        let mut entries = match fs::read_dir(&self.cache_dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                fs::remove_file(entry.path()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn is_fresh(&self, path: &Path) -> bool {
+        let Ok(modified) = fs::metadata(path)
+            .await
+            .and_then(|metadata| metadata.modified())
+        else {
+            return false;
+        };
+        SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age < Duration::from_secs(self.max_age_seconds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cached_http_client() {
+        let mut server = mockito::Server::new_async().await;
+        let manifest = server
+            .mock("GET", "/manifest.json")
+            .with_body(json!({ "latest": "1.20.1" }).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let client = CachedHttpClient::new(dir.path(), 600);
+        let url = format!("{}/manifest.json", server.url());
+
+        for _ in 0..2 {
+            let value: Value = client.get_json(&url).await.unwrap();
+            assert_eq!(value["latest"], "1.20.1");
+        }
+        assert!(client.cache_path(&url).is_file());
+        client.invalidate(&url).await.unwrap();
+        assert!(!client.cache_path(&url).exists());
+        let _: Value = client.get_json(&url).await.unwrap();
+        manifest.assert_async().await;
+
+        client.clear_all().await.unwrap();
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        client.invalidate(&url).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let manifest = server
+            .mock("GET", "/manifest.json")
+            .with_body("[]")
+            .expect(2)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let client = CachedHttpClient::new(dir.path(), 0);
+        let url = format!("{}/manifest.json", server.url());
+        for _ in 0..2 {
+            let _: Vec<String> = client.get_json(&url).await.unwrap();
+        }
+        manifest.assert_async().await;
+    }
+}
@@ -16,19 +16,30 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::fmt::Display;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
 
-use anyhow::Result;
 use futures::StreamExt;
+use md5::Md5;
 use once_cell::sync::Lazy;
-use reqwest::{Client, Response};
+use reqwest::{header::RANGE, Client, StatusCode, Url};
+use sha2::{digest, Digest, Sha256, Sha512};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::{channel, Receiver};
+use tokio::task::JoinHandle;
 
-use crate::core::task::TaskEventListeners;
+use crate::core::download_source::DownloadSource;
+use crate::core::task::{CancelHandle, Cancelled, TaskEvent, TaskEventListeners, TaskReporter};
+use crate::Result;
 
 use super::sha1::calculate_sha1_from_read;
 
@@ -36,34 +47,360 @@ use super::sha1::calculate_sha1_from_read;
 pub struct Download<P: AsRef<Path> + AsRef<OsStr>> {
     pub url: String,
     pub file: P,
+    /// The sha1 of the file, it's verified if `checksum` is not set
     pub sha1: Option<String>,
+    /// Verify the file with this checksum instead of `sha1`
+    pub checksum: Option<Checksum>,
+    /// The size of the file in bytes if known, it weights the progress of the downloads. The
+    /// download fails with [`SizeMismatch`] if a different number of bytes is received, it's the
+    /// only check of the files without a checksum.
+    pub size: Option<u64>,
+    /// Retry this file as this config says, instead of the `retry` of [`DownloadOptions`]
+    pub retry: Option<RetryConfig>,
 }
 
-static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| Client::new());
+impl<P: AsRef<Path> + AsRef<OsStr>> Download<P> {
+    /// Download `url` to `file` without verifying it, the checks are added by the `with_`
+    /// methods
+    pub fn new(url: impl Into<String>, file: P) -> Self {
+        Self {
+            url: url.into(),
+            file,
+            sha1: None,
+            checksum: None,
+            size: None,
+            retry: None,
+        }
+    }
+
+    pub fn with_sha1(mut self, sha1: impl Into<String>) -> Self {
+        self.sha1 = Some(sha1.into());
+        self
+    }
+
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_retry(mut self, config: RetryConfig) -> Self {
+        self.retry = Some(config);
+        self
+    }
+
+    /// The checksum verifying the file, `checksum` or else `sha1`
+    pub fn checksum(&self) -> Option<Checksum> {
+        self.checksum
+            .clone()
+            .or_else(|| self.sha1.clone().map(Checksum::Sha1))
+    }
+}
+
+/// The expected hash of a file in lowercase or uppercase hex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    Sha1(String),
+    Sha256(String),
+    Sha512(String),
+    Md5(String),
+}
+
+impl Checksum {
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Checksum::Sha1(_) => "sha1",
+            Checksum::Sha256(_) => "sha256",
+            Checksum::Sha512(_) => "sha512",
+            Checksum::Md5(_) => "md5",
+        }
+    }
+
+    pub fn expected(&self) -> &str {
+        match self {
+            Checksum::Sha1(hash)
+            | Checksum::Sha256(hash)
+            | Checksum::Sha512(hash)
+            | Checksum::Md5(hash) => hash,
+        }
+    }
+
+    /// Calculate the hash of `source` with the algorithm of this checksum, in lowercase hex
+    pub fn calculate<R: Read>(&self, source: &mut R) -> std::io::Result<String> {
+        Ok(match self {
+            Checksum::Sha1(_) => calculate_sha1_from_read(source),
+            Checksum::Sha256(_) => digest_hex::<Sha256, _>(source)?,
+            Checksum::Sha512(_) => digest_hex::<Sha512, _>(source)?,
+            Checksum::Md5(_) => digest_hex::<Md5, _>(source)?,
+        })
+    }
+
+    /// Check if `hash` is the expected one, ignoring the case
+    pub fn matches(&self, hash: &str) -> bool {
+        self.expected().eq_ignore_ascii_case(hash)
+    }
+}
+
+fn digest_hex<D: Digest, R: Read>(source: &mut R) -> std::io::Result<String>
+where
+    digest::Output<D>: std::fmt::LowerHex,
+{
+    let mut hasher = D::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let bytes_read = source.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// How many times a failed download is attempted, and how long to wait between the attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryConfig {
+    /// The attempts of a file, including the first one
+    pub max_attempts: u32,
+    /// The delay before the first retry
+    pub initial_delay_ms: u64,
+    /// The delay is multiplied by this for every retry
+    pub backoff_factor: f64,
+    /// The upper bound of the delay
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay_ms: 500,
+            backoff_factor: 2.0,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The delay before the retry after `attempt` attempts, without the jitter
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial_delay_ms as f64 * self.backoff_factor.powi(exponent);
+        Duration::from_millis(delay.min(self.max_delay_ms as f64) as u64)
+    }
+}
+
+/// Why a download failed
+#[derive(Debug)]
+pub enum DownloadError {
+    /// The request failed, like the host can't be connected or the connection is closed
+    Network(reqwest::Error),
+    /// The file can't be read or written
+    Io(std::io::Error),
+    /// The checksum of the downloaded file mismatches, the file is removed
+    HashMismatch {
+        url: String,
+        /// Like `sha1`, see [`Checksum::algorithm`]
+        algorithm: &'static str,
+        expected: String,
+        actual: String,
+    },
+    /// There's no checksum to verify the file with, though it's required
+    MissingChecksum { url: String },
+    /// The downloaded file isn't the expected size, like the connection is closed early. Nothing
+    /// is written.
+    SizeMismatch {
+        url: String,
+        expected: u64,
+        actual: u64,
+    },
+    /// The author of the file disabled third-party downloads, it can only be downloaded from
+    /// `url` in the browser
+    DistributionDenied { url: String },
+    /// The server responded this error status, nothing is written
+    Status(u16),
+    /// Every url failed in [`download_from_urls`] or [`download_with_fallback`], with the error
+    /// of the last attempt on each
+    AllSourcesFailed {
+        attempts: Vec<(String, DownloadError)>,
+    },
+    /// The download is cancelled by a [`CancelHandle`]
+    Cancelled,
+}
+
+impl Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::Network(err) => write!(f, "network error: {err}"),
+            DownloadError::Io(err) => write!(f, "io error: {err}"),
+            DownloadError::HashMismatch {
+                url,
+                algorithm,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{algorithm} mismatch of {url}, expected {expected}, got {actual}"
+            ),
+            DownloadError::MissingChecksum { url } => {
+                write!(f, "no checksum is provided for {url}")
+            }
+            DownloadError::SizeMismatch {
+                url,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "size mismatch of {url}, expected {expected} bytes, got {actual}"
+            ),
+            DownloadError::DistributionDenied { url } => write!(
+                f,
+                "third-party downloads are disabled, please download it from {url}"
+            ),
+            DownloadError::Status(status) => match StatusCode::from_u16(*status) {
+                Ok(status) => write!(f, "server responded {status}"),
+                Err(_) => write!(f, "server responded {status}"),
+            },
+            DownloadError::AllSourcesFailed { attempts } => {
+                write!(f, "all {} download sources failed", attempts.len())?;
+                for (url, err) in attempts {
+                    write!(f, "\n  {url}: {err}")?;
+                }
+                Ok(())
+            }
+            DownloadError::Cancelled => write!(f, "{Cancelled}"),
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DownloadError::Network(err) => Some(err),
+            DownloadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(err: reqwest::Error) -> Self {
+        DownloadError::Network(err)
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(err: std::io::Error) -> Self {
+        DownloadError::Io(err)
+    }
+}
+
+impl From<Cancelled> for DownloadError {
+    fn from(_: Cancelled) -> Self {
+        DownloadError::Cancelled
+    }
+}
+
+pub(crate) static HTTP_CLIENT: Lazy<Client> = Lazy::new(Client::new);
 
 // todo: 接受url列表以便轮询
 pub async fn download<P: AsRef<Path> + AsRef<OsStr>>(
     download_task: Download<P>,
-) -> Result<Response> {
+) -> Result<(), DownloadError> {
+    download_cancellable(download_task, &CancelHandle::default()).await
+}
+
+/// The file is written to `<file>.tmp` first, and renamed when the download completes
+fn temp_path(file: &Path) -> PathBuf {
+    let mut temp = file.as_os_str().to_owned();
+    temp.push(".tmp");
+    PathBuf::from(temp)
+}
+
+/// Download a file like [`download`], the request is aborted when `cancel` is cancelled.
+///
+/// Nothing is left when the download fails or is cancelled, a cancelled download returns
+/// [`Cancelled`].
+pub async fn download_cancellable<P: AsRef<Path> + AsRef<OsStr>>(
+    download_task: Download<P>,
+    cancel: &CancelHandle,
+) -> Result<(), DownloadError> {
+    download_to_file(download_task, cancel, &|_| {}).await
+}
+
+/// Called with the bytes written so far after every chunk of a download
+type OnChunk<'a> = dyn Fn(u64) + Send + Sync + 'a;
+
+/// Called before a failed download is retried, with the failed attempts so far, the delay before
+/// the next attempt and the error
+type OnRetry<'a> = dyn Fn(u32, Duration, &DownloadError) + Send + Sync + 'a;
+
+async fn download_to_file<P: AsRef<Path> + AsRef<OsStr>>(
+    download_task: Download<P>,
+    cancel: &CancelHandle,
+    on_chunk: &OnChunk<'_>,
+) -> Result<(), DownloadError> {
+    cancel.check()?;
     // todo: 读取下载信息结构体中的文件大小
     let file_path = PathBuf::from(&download_task.file);
-    let direction = file_path.parent().unwrap();
-    if !direction.exists() {
-        fs::create_dir_all(&direction).await?
+    if let Some(direction) = file_path.parent().filter(|parent| !parent.exists()) {
+        fs::create_dir_all(direction).await?
+    }
+    let temp_path = temp_path(&file_path);
+    let result = tokio::select! {
+        result = write_response(&download_task.url, &temp_path, on_chunk) => result,
+        _ = cancel.cancelled() => Err(DownloadError::Cancelled),
+    };
+    let result = result.and_then(|written| match download_task.size {
+        Some(expected) if expected != written => Err(DownloadError::SizeMismatch {
+            url: download_task.url.clone(),
+            expected,
+            actual: written,
+        }),
+        _ => Ok(()),
+    });
+    match result {
+        Ok(()) => {
+            fs::rename(&temp_path, &file_path).await?;
+            Ok(())
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path).await;
+            Err(err)
+        }
+    }
+}
+
+/// Write the response body to `file`, returns the bytes written
+async fn write_response(
+    url: &str,
+    file: &Path,
+    on_chunk: &OnChunk<'_>,
+) -> Result<u64, DownloadError> {
+    let mut response = HTTP_CLIENT.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(DownloadError::Status(response.status().as_u16()));
     }
-    let mut response = HTTP_CLIENT.get(&download_task.url).send().await?;
-    let mut file = fs::File::create(&download_task.file).await?;
+    let mut file = fs::File::create(file).await?;
+    let mut written = 0;
     while let Some(chunk) = response.chunk().await? {
         file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+        on_chunk(written);
     }
-    Ok(response)
+    file.flush().await?;
+    Ok(written)
 }
 
 pub async fn download_files(
     download_tasks: Vec<Download<String>>,
     listeners: TaskEventListeners,
     verify_exists: bool,
-) -> Result<()> {
+) -> Result<(), DownloadError> {
     listeners.start();
     listeners.progress(0, 0, 1);
     let download_tasks: Vec<_> = download_tasks
@@ -85,45 +422,1493 @@ pub async fn download_files(
                     return true;
                 }
             };
-            let file_sha1 = calculate_sha1_from_read(&mut file);
-            let sha1 = match download_task.sha1.clone() {
-                None => return true,
-                Some(sha1) => sha1,
-            };
-            if file_sha1 == sha1 {
-                false
-            } else {
-                true
+            match download_task.checksum() {
+                None => true,
+                Some(checksum) => !checksum
+                    .calculate(&mut file)
+                    .is_ok_and(|hash| checksum.matches(&hash)),
             }
         })
         .collect();
 
     let total = download_tasks.len();
-    let counter: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
-
-    let stream = futures::stream::iter(download_tasks)
-        .map(|download_task| {
-            let counter = Arc::clone(&counter);
-            async move {
-                let result = download(download_task.clone()).await;
-                counter.fetch_add(1, Ordering::SeqCst);
-                result
+    let mut succeeded = 0;
+    let mut first_error = None;
+    let mut results = futures::stream::iter(download_tasks)
+        .map(|download_task| download(download_task.clone()))
+        .buffer_unordered(16);
+    while let Some(result) = results.next().await {
+        match result {
+            Ok(()) => {
+                succeeded += 1;
+                listeners.progress(succeeded, total, 2);
+            }
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+
+    match first_error {
+        None => {
+            listeners.succeed();
+            Ok(())
+        }
+        Some(err) => {
+            listeners.failed();
+            Err(err)
+        }
+    }
+}
+
+/// Download a file if it doesn't exist or its checksum mismatches, and verify it
+pub async fn download_verified<P: AsRef<Path> + AsRef<OsStr> + Clone>(
+    download_task: Download<P>,
+    cancel: &CancelHandle,
+) -> Result<(), DownloadError> {
+    verify_or_download(download_task, cancel, &|_| {}).await
+}
+
+async fn verify_or_download<P: AsRef<Path> + AsRef<OsStr> + Clone>(
+    download_task: Download<P>,
+    cancel: &CancelHandle,
+    on_chunk: &OnChunk<'_>,
+) -> Result<(), DownloadError> {
+    let checksum = download_task.checksum();
+    if let Some(checksum) = &checksum {
+        if let Ok(mut file) = std::fs::File::open(&download_task.file) {
+            if checksum
+                .calculate(&mut file)
+                .is_ok_and(|hash| checksum.matches(&hash))
+            {
+                return Ok(());
+            }
+        }
+    }
+    download_to_file(download_task.clone(), cancel, on_chunk).await?;
+    if let Some(checksum) = &checksum {
+        verify_checksum(checksum, Path::new(&download_task.file), &download_task.url).await?;
+    }
+    Ok(())
+}
+
+/// Fail with [`DownloadError::HashMismatch`] and remove `file` if its checksum mismatches
+async fn verify_checksum(checksum: &Checksum, file: &Path, url: &str) -> Result<(), DownloadError> {
+    let hash = checksum.calculate(&mut std::fs::File::open(file)?)?;
+    if !checksum.matches(&hash) {
+        fs::remove_file(file).await?;
+        return Err(DownloadError::HashMismatch {
+            url: url.to_string(),
+            algorithm: checksum.algorithm(),
+            expected: checksum.expected().to_string(),
+            actual: hash,
+        });
+    }
+    Ok(())
+}
+
+/// The partial file of a resumable download, see [`download_resumable`]
+fn part_path(file: &Path) -> PathBuf {
+    let mut part = file.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Download a file to `<file>.part`, continuing from the bytes already in it, and rename it when
+/// it's complete and verified. Useful for the large files like the client jar.
+///
+/// The rest of the file is requested with a `Range` header, the whole file is downloaded again
+/// if the server ignores it. The partial file is kept when the download fails or is cancelled,
+/// so the next call resumes it, unless the complete file has a wrong size or checksum.
+pub async fn download_resumable<P: AsRef<Path> + AsRef<OsStr>>(
+    download_task: Download<P>,
+    cancel: &CancelHandle,
+) -> Result<(), DownloadError> {
+    cancel.check()?;
+    let file_path = PathBuf::from(&download_task.file);
+    if let Some(direction) = file_path.parent().filter(|parent| !parent.exists()) {
+        fs::create_dir_all(direction).await?
+    }
+    let part_path = part_path(&file_path);
+    let offset = fs::metadata(&part_path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let written = tokio::select! {
+        result = append_response(&download_task.url, &part_path, offset) => result?,
+        _ = cancel.cancelled() => return Err(DownloadError::Cancelled),
+    };
+    if let Some(expected) = download_task.size.filter(|expected| *expected != written) {
+        fs::remove_file(&part_path).await?;
+        return Err(DownloadError::SizeMismatch {
+            url: download_task.url.clone(),
+            expected,
+            actual: written,
+        });
+    }
+    if let Some(checksum) = download_task.checksum() {
+        verify_checksum(&checksum, &part_path, &download_task.url).await?;
+    }
+    fs::rename(&part_path, &file_path).await?;
+    Ok(())
+}
+
+/// Append the bytes after `offset` to `file`, returns the size of the file
+async fn append_response(url: &str, file: &Path, offset: u64) -> Result<u64, DownloadError> {
+    let mut request = HTTP_CLIENT.get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    }
+    let mut response = request.send().await?;
+    let status = response.status();
+    // the partial file is already complete
+    if offset > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(offset);
+    }
+    if !status.is_success() {
+        return Err(DownloadError::Status(status.as_u16()));
+    }
+    let (mut file, mut written) = if status == StatusCode::PARTIAL_CONTENT {
+        let file = fs::OpenOptions::new().append(true).open(file).await?;
+        (file, offset)
+    } else {
+        (fs::File::create(file).await?, 0)
+    };
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+    file.flush().await?;
+    Ok(written)
+}
+
+/// How [`download_all_with_options`] downloads the files and retries the failed ones
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// The number of files downloaded at the same time
+    pub concurrency: usize,
+
+    /// The attempts and the back-off of the files without their own [`RetryConfig`]
+    pub retry: RetryConfig,
+
+    /// A random delay up to this is added to every retry, so the retries of the files failed
+    /// together don't hit the server at the same time
+    pub jitter: Duration,
+
+    /// Retry the responses with these status codes
+    pub retry_statuses: Vec<u16>,
+
+    /// Retry the connection errors, the timeouts and the connections reset while downloading
+    pub retry_network_errors: bool,
+
+    /// Retry the files whose sha1 or size mismatches
+    pub retry_checksum_mismatch: bool,
+
+    /// After this many consecutive failed attempts on a host, the remaining downloads from the
+    /// host use its fallback mirror
+    pub host_failure_threshold: u32,
+
+    /// The fallback mirrors, from the host (with the port if it's not the default one) to the
+    /// base url replacing the scheme and the host, like
+    /// `"piston-data.mojang.com" => "https://bmclapi2.bangbang93.com"`
+    pub fallback_mirrors: HashMap<String, String>,
+
+    /// Stop at the first file failed after the retries and return its error, instead of
+    /// collecting the failures in [`DownloadReport`]. The downloads in flight are finished.
+    pub fail_fast: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 16,
+            retry: RetryConfig::default(),
+            jitter: Duration::from_millis(250),
+            retry_statuses: vec![408, 429, 500, 502, 503, 504],
+            retry_network_errors: true,
+            retry_checksum_mismatch: true,
+            host_failure_threshold: 5,
+            fallback_mirrors: HashMap::new(),
+            fail_fast: false,
+        }
+    }
+}
+
+impl DownloadOptions {
+    fn should_retry(&self, err: &DownloadError) -> bool {
+        match err {
+            DownloadError::Status(status) => self.retry_statuses.contains(status),
+            DownloadError::HashMismatch { .. } | DownloadError::SizeMismatch { .. } => {
+                self.retry_checksum_mismatch
             }
+            DownloadError::Network(err) => {
+                self.retry_network_errors
+                    && (err.is_connect() || err.is_timeout() || err.is_request() || err.is_body())
+            }
+            DownloadError::Io(_)
+            | DownloadError::MissingChecksum { .. }
+            | DownloadError::DistributionDenied { .. }
+            | DownloadError::AllSourcesFailed { .. }
+            | DownloadError::Cancelled => false,
+        }
+    }
+
+    /// The delay before the retry after `attempt` attempts, with the jitter
+    fn retry_delay(&self, retry: &RetryConfig, attempt: u32) -> Duration {
+        let delay = retry.delay(attempt);
+        if self.jitter.is_zero() {
+            return delay;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        delay + Duration::from_nanos(random % self.jitter.as_nanos().max(1) as u64)
+    }
+}
+
+/// The result of [`download_all_with_options`]
+#[derive(Debug)]
+pub struct DownloadReport<P: AsRef<Path> + AsRef<OsStr>> {
+    pub succeeded: Vec<Download<P>>,
+    /// The files failed after the retries, with the error of the last attempt
+    pub failed: Vec<(Download<P>, DownloadError)>,
+}
+
+impl<P: AsRef<Path> + AsRef<OsStr>> DownloadReport<P> {
+    /// The urls of the failed files and the reasons
+    pub fn failures(&self) -> Vec<(String, String)> {
+        self.failed
+            .iter()
+            .map(|(download_task, err)| (download_task.url.clone(), err.to_string()))
+            .collect()
+    }
+}
+
+/// The consecutive failed attempts of the hosts
+#[derive(Debug, Default)]
+struct HostFailures(Mutex<HashMap<String, u32>>);
+
+impl HostFailures {
+    /// Like `piston-data.mojang.com` or `127.0.0.1:8080`
+    fn host(url: &Url) -> Option<String> {
+        let host = url.host_str()?;
+        Some(match url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
         })
-        .buffer_unordered(16);
-    stream
-        .for_each_concurrent(1, |_| async {
-            let completed = counter.clone().load(Ordering::SeqCst);
-            listeners.progress(completed, total, 2);
-            //println!("{completed}/{total}");
+    }
+
+    fn record(&self, url: &str, succeeded: bool) {
+        let Some(host) = Url::parse(url).ok().as_ref().and_then(Self::host) else {
+            return;
+        };
+        let mut failures = self.0.lock().unwrap();
+        if succeeded {
+            failures.remove(&host);
+        } else {
+            *failures.entry(host).or_default() += 1;
+        }
+    }
+
+    /// Use the fallback mirror if the host of `url` failed too many times
+    fn rewrite(&self, url: &str, options: &DownloadOptions) -> String {
+        let Ok(parsed) = Url::parse(url) else {
+            return url.to_string();
+        };
+        let Some(host) = Self::host(&parsed) else {
+            return url.to_string();
+        };
+        let failures = self.0.lock().unwrap().get(&host).copied().unwrap_or(0);
+        match options.fallback_mirrors.get(&host) {
+            Some(mirror) if failures >= options.host_failure_threshold => {
+                let query = parsed
+                    .query()
+                    .map(|query| format!("?{query}"))
+                    .unwrap_or_default();
+                format!("{}{}{query}", mirror.trim_end_matches('/'), parsed.path())
+            }
+            _ => url.to_string(),
+        }
+    }
+}
+
+/// Download a file by [`download_verified`], and retry it as `options` says.
+///
+/// `on_chunk` restarts from zero when the file is retried
+async fn download_with_retry<P: AsRef<Path> + AsRef<OsStr> + Clone>(
+    download_task: &Download<P>,
+    options: &DownloadOptions,
+    hosts: &HostFailures,
+    cancel: &CancelHandle,
+    on_chunk: &OnChunk<'_>,
+    on_retry: &OnRetry<'_>,
+) -> Result<(), DownloadError> {
+    let retry = download_task.retry.as_ref().unwrap_or(&options.retry);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let url = hosts.rewrite(&download_task.url, options);
+        let task = Download {
+            url: url.clone(),
+            ..download_task.clone()
+        };
+        let err = match verify_or_download(task, cancel, on_chunk).await {
+            Ok(()) => {
+                hosts.record(&url, true);
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+        if let DownloadError::Cancelled = err {
+            return Err(err);
+        }
+        hosts.record(&url, false);
+        if attempt >= retry.max_attempts || !options.should_retry(&err) {
+            return Err(err);
+        }
+        let delay = options.retry_delay(retry, attempt);
+        on_retry(attempt, delay, &err);
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = cancel.cancelled() => return Err(DownloadError::Cancelled),
+        }
+    }
+}
+
+/// Download a file from the sources in order, like BMCLAPI first and the official source if it's
+/// down.
+///
+/// The url is rewritten by [`DownloadSource::rewrite_url`] for every source, the next source is
+/// tried after the retries on the current one fail. The file is verified by its checksum.
+/// [`DownloadError::AllSourcesFailed`] is returned if no source works,
+/// [`DownloadError::Cancelled`] if `cancel` is cancelled.
+pub async fn download_with_fallback<P: AsRef<Path> + AsRef<OsStr> + Clone>(
+    download_task: Download<P>,
+    sources: &[DownloadSource],
+    cancel: &CancelHandle,
+) -> Result<(), DownloadError> {
+    let mut urls: Vec<String> = Vec::with_capacity(sources.len());
+    for source in sources {
+        let url = source.rewrite_url(&download_task.url);
+        // the urls not from the official hosts are the same for every source
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    if urls.is_empty() {
+        urls.push(download_task.url.clone());
+    }
+    download_from_urls(download_task, &urls, false, cancel).await
+}
+
+/// Download a file from the urls in order, like the repositories of a library from
+/// [`ResolvedLibrary::urls`](crate::core::version::ResolvedLibrary::urls).
+///
+/// If `fetch_sha1` is set and the file has no checksum, the `.sha1` file next to each url is
+/// fetched to verify it, the file isn't verified if there's no such file.
+/// [`DownloadError::AllSourcesFailed`] is returned if no url works, [`DownloadError::Cancelled`]
+/// if `cancel` is cancelled.
+pub async fn download_from_urls<P: AsRef<Path> + AsRef<OsStr> + Clone>(
+    download_task: Download<P>,
+    urls: &[String],
+    fetch_sha1: bool,
+    cancel: &CancelHandle,
+) -> Result<(), DownloadError> {
+    let options = DownloadOptions::default();
+    let mut attempts = Vec::with_capacity(urls.len());
+    for url in urls {
+        let mut task = Download {
+            url: url.clone(),
+            ..download_task.clone()
+        };
+        if fetch_sha1 && task.checksum().is_none() {
+            task.sha1 = fetch_sha1_file(url).await;
+        }
+        let hosts = HostFailures::default();
+        match download_with_retry(&task, &options, &hosts, cancel, &|_| {}, &|_, _, _| {}).await {
+            Ok(()) => return Ok(()),
+            Err(DownloadError::Cancelled) => return Err(DownloadError::Cancelled),
+            Err(err) => attempts.push((url.clone(), err)),
+        }
+    }
+    Err(DownloadError::AllSourcesFailed { attempts })
+}
+
+/// The sha1 in the `.sha1` file next to `url` in a maven repository, `None` if it's missing
+async fn fetch_sha1_file(url: &str) -> Option<String> {
+    let response = HTTP_CLIENT
+        .get(format!("{url}.sha1"))
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    // some repositories write the file name after the hash
+    let sha1 = response.text().await.ok()?;
+    let sha1 = sha1.split_whitespace().next()?;
+    (sha1.len() == 40 && sha1.chars().all(|c| c.is_ascii_hexdigit())).then(|| sha1.to_lowercase())
+}
+
+/// The message reported when a failed download is retried
+fn retry_message(attempt: u32, delay: Duration, err: &DownloadError) -> String {
+    format!("retrying in {delay:?} after {attempt} failed attempts: {err}")
+}
+
+/// Download files by [`download_with_retry`], at most `options.concurrency` files at the same
+/// time.
+///
+/// `on_start` is called before a file starts, its return value is passed to `on_retry` with the
+/// message of every retry, and to `on_finish` with the error if the file failed. When `cancel` is
+/// cancelled, the in-flight downloads are aborted and [`Cancelled`] is returned.
+async fn download_each<P, T, S, R, F>(
+    download_tasks: Vec<Download<P>>,
+    options: &DownloadOptions,
+    cancel: &CancelHandle,
+    on_start: S,
+    on_retry: R,
+    mut on_finish: F,
+) -> Result<DownloadReport<P>, DownloadError>
+where
+    P: AsRef<Path> + AsRef<OsStr> + Clone,
+    T: Sync,
+    S: Fn(&Download<P>) -> T,
+    R: Fn(&T, String) + Sync,
+    F: FnMut(T, &Download<P>, Option<&DownloadError>),
+{
+    let mut report = DownloadReport {
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+    };
+    let hosts = HostFailures::default();
+    let (on_start, on_retry, hosts) = (&on_start, &on_retry, &hosts);
+    let failed_fast = AtomicBool::new(false);
+    let failed_fast = &failed_fast;
+    let mut results = futures::stream::iter(download_tasks)
+        .map(|download_task| async move {
+            // the files not started yet are skipped after a failure in fail-fast mode
+            if failed_fast.load(Ordering::SeqCst) {
+                return None;
+            }
+            let state = on_start(&download_task);
+            let retry = |attempt, delay, err: &DownloadError| {
+                on_retry(&state, retry_message(attempt, delay, err))
+            };
+            let result =
+                download_with_retry(&download_task, options, hosts, cancel, &|_| {}, &retry).await;
+            Some((state, download_task, result))
         })
-        .await;
+        .buffer_unordered(options.concurrency.max(1));
+    // the cancelled downloads finish quickly, wait for them to clean up their files
+    while let Some(result) = results.next().await {
+        let Some((state, download_task, result)) = result else {
+            continue;
+        };
+        if cancel.is_cancelled() {
+            continue;
+        }
+        match result {
+            Ok(()) => {
+                on_finish(state, &download_task, None);
+                report.succeeded.push(download_task);
+            }
+            Err(err) => {
+                on_finish(state, &download_task, Some(&err));
+                if options.fail_fast {
+                    failed_fast.store(true, Ordering::SeqCst);
+                }
+                report.failed.push((download_task, err));
+            }
+        }
+    }
+    cancel.check()?;
+    if options.fail_fast && !report.failed.is_empty() {
+        return Err(report.failed.swap_remove(0).1);
+    }
+    Ok(report)
+}
 
-    if counter.load(Ordering::SeqCst) == total {
-        listeners.succeed();
-    } else {
-        listeners.failed();
+/// Download files by [`download_verified`], at most `concurrency` files at the same time.
+///
+/// `on_progress` is called with `(completed, total)` after every file. The failed files don't
+/// stop the others, they are returned with the urls and the reasons. When `cancel` is cancelled,
+/// the in-flight downloads are aborted and [`Cancelled`] is returned.
+pub async fn download_all<P, F>(
+    download_tasks: Vec<Download<P>>,
+    concurrency: usize,
+    cancel: &CancelHandle,
+    on_progress: F,
+) -> Result<Vec<(String, String)>, DownloadError>
+where
+    P: AsRef<Path> + AsRef<OsStr> + Clone,
+    F: FnMut(usize, usize),
+{
+    let options = DownloadOptions {
+        concurrency,
+        ..Default::default()
+    };
+    let report = download_all_with_options(download_tasks, &options, cancel, on_progress).await?;
+    Ok(report.failures())
+}
+
+/// Download files like [`download_all`], the failed files are retried with exponential backoff
+/// and the hosts failing too many times are replaced by their fallback mirrors, see
+/// [`DownloadOptions`].
+///
+/// The failed files are collected in the report, unless `options.fail_fast` is set.
+pub async fn download_all_with_options<P, F>(
+    download_tasks: Vec<Download<P>>,
+    options: &DownloadOptions,
+    cancel: &CancelHandle,
+    mut on_progress: F,
+) -> Result<DownloadReport<P>, DownloadError>
+where
+    P: AsRef<Path> + AsRef<OsStr> + Clone,
+    F: FnMut(usize, usize),
+{
+    let total = download_tasks.len();
+    let mut completed = 0;
+    download_each(
+        download_tasks,
+        options,
+        cancel,
+        |_| (),
+        |_, _| (),
+        |_, _, _| {
+            completed += 1;
+            on_progress(completed, total);
+        },
+    )
+    .await
+}
+
+/// Download files like [`download_all`], as the sub-task `name` of `parent`.
+///
+/// Every file is reported as a sub-task named by its url. The progress is weighted by
+/// [`Download::size`], a file without size counts as the average size of the others.
+pub async fn download_all_reported<P>(
+    download_tasks: Vec<Download<P>>,
+    concurrency: usize,
+    cancel: &CancelHandle,
+    parent: &TaskReporter,
+    name: &str,
+) -> Result<Vec<(String, String)>, DownloadError>
+where
+    P: AsRef<Path> + AsRef<OsStr> + Clone,
+{
+    let sizes: Vec<_> = download_tasks.iter().filter_map(|task| task.size).collect();
+    let average = match sizes.len() {
+        0 => 1,
+        len => (sizes.iter().sum::<u64>() / len as u64).max(1),
+    };
+    let weight = |download_task: &Download<P>| download_task.size.unwrap_or(average);
+    let total = download_tasks.iter().map(weight).sum();
+    let task = parent.child(name, total);
+    let (mut done, mut bytes) = (0, 0);
+    let options = DownloadOptions {
+        concurrency,
+        ..Default::default()
+    };
+    let result = download_each(
+        download_tasks,
+        &options,
+        cancel,
+        |download_task| task.child(&download_task.url, weight(download_task)),
+        |file, message| file.warn(message),
+        |file, download_task, error| {
+            match error {
+                None => {
+                    file.report(TaskEvent::Completed);
+                    bytes += download_task.size.unwrap_or(0);
+                }
+                Some(error) => file.report(TaskEvent::Failed {
+                    error: error.to_string(),
+                }),
+            }
+            done += weight(download_task);
+            task.progress(done, total, bytes);
+        },
+    )
+    .await
+    .map(|report| report.failures());
+    match &result {
+        Ok(failures) if failures.is_empty() => task.report(TaskEvent::Completed),
+        Ok(failures) => task.report(TaskEvent::Failed {
+            error: format!("{} files failed to download", failures.len()),
+        }),
+        Err(err) => task.report(TaskEvent::Failed {
+            error: err.to_string(),
+        }),
     }
+    result
+}
 
-    Ok(())
+/// An event of [`download_with_channel`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadEvent {
+    Started {
+        url: String,
+        total_bytes: Option<u64>,
+    },
+    /// `downloaded` is the bytes written so far, it restarts from zero if the file is retried
+    Progress {
+        url: String,
+        downloaded: u64,
+    },
+    /// The file failed after `attempt` attempts, it's retried after `delay`
+    Retrying {
+        url: String,
+        attempt: u32,
+        delay: Duration,
+        error: String,
+    },
+    Completed {
+        url: String,
+        path: PathBuf,
+    },
+    Failed {
+        url: String,
+        error: String,
+    },
+}
+
+/// The capacity of the channel of [`download_with_channel`]
+const DOWNLOAD_EVENT_CAPACITY: usize = 256;
+
+/// Download files in a spawned task, and send the events of every file to the returned receiver.
+///
+/// The files are downloaded like [`download_all`], at most `concurrency` at the same time. The
+/// `Progress` and `Retrying` events are dropped when the channel is full, the others are always
+/// sent while the receiver exists. Every file is tried, the task returns the first error and
+/// every failed file is sent as a `Failed` event.
+///
+/// # Example
+///
+/// ```rust
+/// use mgl_core::utils::download::{download_with_channel, Download, DownloadEvent};
+///
+/// async fn fn_name(tasks: Vec<Download<String>>) {
+///     let (handle, mut events) = download_with_channel(tasks, 16);
+///     while let Some(event) = events.recv().await {
+///         if let DownloadEvent::Progress { url, downloaded } = event {
+///             println!("{url}: {downloaded} bytes");
+///         }
+///     }
+///     if let Err(err) = handle.await.unwrap() {
+///         println!("download failed: {err}");
+///     }
+/// }
+/// ```
+pub fn download_with_channel<P>(
+    download_tasks: Vec<Download<P>>,
+    concurrency: usize,
+) -> (
+    JoinHandle<Result<(), DownloadError>>,
+    Receiver<DownloadEvent>,
+)
+where
+    P: AsRef<Path> + AsRef<OsStr> + Clone + Send + Sync + 'static,
+{
+    let (sender, receiver) = channel(DOWNLOAD_EVENT_CAPACITY);
+    let handle = tokio::spawn(async move {
+        let options = DownloadOptions {
+            concurrency,
+            ..Default::default()
+        };
+        let hosts = HostFailures::default();
+        let cancel = CancelHandle::new();
+        let (options, hosts, cancel, sender) = (&options, &hosts, &cancel, &sender);
+        let mut results = futures::stream::iter(download_tasks)
+            .map(|download_task| async move {
+                let url = download_task.url.clone();
+                let _ = sender
+                    .send(DownloadEvent::Started {
+                        url: url.clone(),
+                        total_bytes: download_task.size,
+                    })
+                    .await;
+                let on_chunk = |downloaded| {
+                    let _ = sender.try_send(DownloadEvent::Progress {
+                        url: url.clone(),
+                        downloaded,
+                    });
+                };
+                let on_retry = |attempt, delay, err: &DownloadError| {
+                    let _ = sender.try_send(DownloadEvent::Retrying {
+                        url: url.clone(),
+                        attempt,
+                        delay,
+                        error: err.to_string(),
+                    });
+                };
+                let result = download_with_retry(
+                    &download_task,
+                    options,
+                    hosts,
+                    cancel,
+                    &on_chunk,
+                    &on_retry,
+                )
+                .await;
+                let event = match &result {
+                    Ok(()) => DownloadEvent::Completed {
+                        url,
+                        path: PathBuf::from(&download_task.file),
+                    },
+                    Err(err) => DownloadEvent::Failed {
+                        url,
+                        error: err.to_string(),
+                    },
+                };
+                let _ = sender.send(event).await;
+                result
+            })
+            .buffer_unordered(options.concurrency.max(1));
+        let mut first_error = None;
+        while let Some(result) = results.next().await {
+            if let Err(err) = result {
+                first_error.get_or_insert(err);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    });
+    (handle, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_download() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/slow/".to_string()))
+            .with_chunked_body(|writer| {
+                writer.write_all(b"first chunk")?;
+                writer.flush()?;
+                std::thread::sleep(Duration::from_secs(5));
+                writer.write_all(b"second chunk")
+            })
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let tasks: Vec<_> = (0..4)
+            .map(|index| {
+                Download::new(
+                    format!("{}/slow/{index}", server.url()),
+                    dir.path().join(format!("{index}.bin")),
+                )
+            })
+            .collect();
+
+        let cancel = CancelHandle::new();
+        let watcher = {
+            let cancel = cancel.clone();
+            let temp = temp_path(&dir.path().join("0.bin"));
+            tokio::spawn(async move {
+                // cancel after the download started writing the file
+                while !temp.exists() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                cancel.cancel();
+            })
+        };
+        let result = tokio::time::timeout(
+            Duration::from_secs(3),
+            download_all(tasks, 4, &cancel, |_, _| {}),
+        )
+        .await
+        .unwrap();
+        watcher.await.unwrap();
+        assert!(matches!(result.unwrap_err(), DownloadError::Cancelled));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+    fn options() -> DownloadOptions {
+        DownloadOptions {
+            concurrency: 1,
+            retry: RetryConfig {
+                initial_delay_ms: 10,
+                ..Default::default()
+            },
+            jitter: Duration::ZERO,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/file")
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("GET", "/file")
+            .with_body("content")
+            .expect(1)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let task = Download::new(format!("{}/file", server.url()), dir.path().join("file"))
+            .with_sha1(calculate_sha1_from_read(&mut b"content".as_slice()));
+
+        let report =
+            download_all_with_options(vec![task], &options(), &CancelHandle::new(), |_, _| {})
+                .await
+                .unwrap();
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+        assert_eq!(report.succeeded.len(), 1);
+        assert!(report.failed.is_empty());
+        assert_eq!(std::fs::read(dir.path().join("file")).unwrap(), b"content");
+    }
+
+    #[tokio::test]
+    async fn test_download_with_fallback() {
+        let mut down = mockito::Server::new_async().await;
+        let unavailable = down
+            .mock("GET", "/maven/com/example/a.jar")
+            .with_status(500)
+            .expect(2)
+            .create_async()
+            .await;
+        let mut up = mockito::Server::new_async().await;
+        let ok = up
+            .mock("GET", "/maven/com/example/a.jar")
+            .with_body("content")
+            .expect(1)
+            .create_async()
+            .await;
+        let source = |server: &mockito::Server| DownloadSource {
+            libraries_base_url: format!("{}/maven", server.url()),
+            ..DownloadSource::official()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let task = Download::new(
+            "https://libraries.minecraft.net/com/example/a.jar".to_string(),
+            dir.path().join("a.jar"),
+        )
+        .with_sha1(calculate_sha1_from_read(&mut b"content".as_slice()))
+        .with_retry(RetryConfig {
+            max_attempts: 2,
+            initial_delay_ms: 10,
+            ..Default::default()
+        });
+
+        download_with_fallback(
+            task.clone(),
+            &[source(&down), source(&up)],
+            &CancelHandle::default(),
+        )
+        .await
+        .unwrap();
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+        assert_eq!(std::fs::read(dir.path().join("a.jar")).unwrap(), b"content");
+
+        std::fs::remove_file(dir.path().join("a.jar")).unwrap();
+        let err = download_with_fallback(
+            task,
+            &[source(&down), source(&down)],
+            &CancelHandle::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains(&down.url()));
+        let DownloadError::AllSourcesFailed { attempts } = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(attempts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_from_urls() {
+        let sha1 = calculate_sha1_from_read(&mut b"content".as_slice());
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/missing/a.jar")
+            .with_status(404)
+            .create_async()
+            .await;
+        let jar = server
+            .mock("GET", "/maven/a.jar")
+            .with_body("content")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/maven/a.jar.sha1")
+            .with_body(format!("{sha1}  a.jar\n"))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/broken/a.jar")
+            .with_body("broken")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/broken/a.jar.sha1")
+            .with_body(&sha1)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let task = Download::new(String::new(), dir.path().join("a.jar")).with_retry(RetryConfig {
+            max_attempts: 1,
+            ..Default::default()
+        });
+        let url = |repo: &str| format!("{}/{repo}/a.jar", server.url());
+
+        download_from_urls(
+            task.clone(),
+            &[url("missing"), url("maven")],
+            true,
+            &CancelHandle::default(),
+        )
+        .await
+        .unwrap();
+        jar.assert_async().await;
+        assert_eq!(std::fs::read(dir.path().join("a.jar")).unwrap(), b"content");
+
+        std::fs::remove_file(dir.path().join("a.jar")).unwrap();
+        let err = download_from_urls(
+            task.clone(),
+            &[url("missing"), url("broken")],
+            true,
+            &CancelHandle::default(),
+        )
+        .await
+        .unwrap_err();
+        let DownloadError::AllSourcesFailed { attempts } = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(attempts.len(), 2);
+        assert!(matches!(attempts[0].1, DownloadError::Status(404)));
+        assert!(matches!(attempts[1].1, DownloadError::HashMismatch { .. }));
+
+        let cancel = CancelHandle::default();
+        cancel.cancel();
+        let err = download_from_urls(task, &[url("missing"), url("maven")], true, &cancel)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DownloadError::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_size_mismatch() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/file")
+            .with_body("content")
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let task = |size| Download {
+            size,
+            ..Download::new(format!("{}/file", server.url()), dir.path().join("file"))
+        };
+
+        let err = download(task(Some(8))).await.unwrap_err();
+        assert!(matches!(
+            err,
+            DownloadError::SizeMismatch {
+                expected: 8,
+                actual: 7,
+                ..
+            }
+        ));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        download(task(Some(7))).await.unwrap();
+        assert_eq!(std::fs::read(dir.path().join("file")).unwrap(), b"content");
+        download(task(None)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_files() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/found")
+            .with_body("content")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let task = |name: &str| {
+            Download::new(
+                format!("{}/{name}", server.url()),
+                dir.path().join(name).to_string_lossy().to_string(),
+            )
+        };
+        let listeners = || {
+            let succeeded = Rc::new(Cell::new(None));
+            let on_succeed = Rc::clone(&succeeded);
+            let on_failed = Rc::clone(&succeeded);
+            let listeners = TaskEventListeners::default()
+                .on_succeed(Box::new(move || on_succeed.set(Some(true))))
+                .on_failed(Box::new(move || on_failed.set(Some(false))));
+            (listeners, succeeded)
+        };
+
+        let (found, succeeded) = listeners();
+        download_files(vec![task("found")], found, false)
+            .await
+            .unwrap();
+        assert_eq!(succeeded.get(), Some(true));
+
+        let (missing, succeeded) = listeners();
+        let err = download_files(vec![task("found"), task("missing")], missing, true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DownloadError::Status(404)));
+        assert_eq!(succeeded.get(), Some(false));
+        assert!(!dir.path().join("missing").exists());
+    }
+
+    #[tokio::test]
+    async fn test_retry_config() {
+        let retry = RetryConfig {
+            max_delay_ms: 3000,
+            ..Default::default()
+        };
+        assert_eq!(retry.delay(1), Duration::from_millis(500));
+        assert_eq!(retry.delay(2), Duration::from_millis(1000));
+        assert_eq!(retry.delay(3), Duration::from_millis(2000));
+        assert_eq!(retry.delay(4), Duration::from_millis(3000));
+
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/file")
+            .with_status(503)
+            .expect(5)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let task = Download::new(format!("{}/file", server.url()), dir.path().join("file"))
+            .with_retry(RetryConfig {
+                max_attempts: 5,
+                initial_delay_ms: 1,
+                backoff_factor: 1.5,
+                max_delay_ms: 10,
+            });
+
+        let report =
+            download_all_with_options(vec![task], &options(), &CancelHandle::new(), |_, _| {})
+                .await
+                .unwrap();
+        unavailable.assert_async().await;
+        assert_eq!(report.failed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_mirror() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mirror = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", mockito::Matcher::Any)
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let mirrored = mirror
+            .mock("GET", mockito::Matcher::Regex("^/files/".to_string()))
+            .with_body("content")
+            .expect(4)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let tasks: Vec<_> = (0..4)
+            .map(|index| {
+                Download::new(
+                    format!("{}/files/{index}", server.url()),
+                    dir.path().join(index.to_string()),
+                )
+            })
+            .collect();
+        let host = server.host_with_port();
+        let options = DownloadOptions {
+            host_failure_threshold: 2,
+            fallback_mirrors: HashMap::from([(host, mirror.url())]),
+            ..options()
+        };
+
+        let report = download_all_with_options(tasks, &options, &CancelHandle::new(), |_, _| {})
+            .await
+            .unwrap();
+        // the first file failed twice, then it and the others are downloaded from the mirror
+        unavailable.assert_async().await;
+        mirrored.assert_async().await;
+        assert_eq!(report.succeeded.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast() {
+        let mut server = mockito::Server::new_async().await;
+        let missing = server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/file")
+            .with_body("content")
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let tasks: Vec<_> = ["missing", "file", "file"]
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                Download::new(
+                    format!("{}/{path}", server.url()),
+                    dir.path().join(index.to_string()),
+                )
+            })
+            .collect();
+
+        // 404 is not retried, the others are downloaded
+        let report =
+            download_all_with_options(tasks.clone(), &options(), &CancelHandle::new(), |_, _| {})
+                .await
+                .unwrap();
+        assert_eq!(report.succeeded.len(), 2);
+        let (failed, err) = &report.failed[0];
+        assert_eq!(failed.url, tasks[0].url);
+        assert!(matches!(err, DownloadError::Status(404)));
+        assert!(!dir.path().join("0").exists());
+
+        let options = DownloadOptions {
+            fail_fast: true,
+            ..options()
+        };
+        let err = download_all_with_options(tasks, &options, &CancelHandle::new(), |_, _| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DownloadError::Status(404)));
+        missing.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_download_with_channel() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/file")
+            .with_body("content")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/unavailable")
+            .with_status(503)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        let tasks = vec![
+            Download::new(format!("{}/file", server.url()), file.clone()).with_size(7),
+            Download::new(
+                format!("{}/missing", server.url()),
+                dir.path().join("missing"),
+            ),
+            Download::new(
+                format!("{}/unavailable", server.url()),
+                dir.path().join("unavailable"),
+            )
+            .with_retry(RetryConfig {
+                max_attempts: 2,
+                initial_delay_ms: 10,
+                ..Default::default()
+            }),
+        ];
+
+        let (handle, mut receiver) = download_with_channel(tasks, 2);
+        let mut events = vec![];
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
+        }
+        let err = handle.await.unwrap().unwrap_err();
+        assert!(matches!(err, DownloadError::Status(404 | 503)));
+        let mut failures: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                DownloadEvent::Failed { url, .. } => Some(url.clone()),
+                _ => None,
+            })
+            .collect();
+        failures.sort();
+        assert_eq!(
+            failures,
+            [
+                format!("{}/missing", server.url()),
+                format!("{}/unavailable", server.url())
+            ]
+        );
+
+        let file_url = format!("{}/file", server.url());
+        let missing_url = format!("{}/missing", server.url());
+        let file_events: Vec<_> = events
+            .iter()
+            .filter(|event| match event {
+                DownloadEvent::Started { url, .. }
+                | DownloadEvent::Progress { url, .. }
+                | DownloadEvent::Retrying { url, .. }
+                | DownloadEvent::Completed { url, .. }
+                | DownloadEvent::Failed { url, .. } => *url == file_url,
+            })
+            .collect();
+        assert_eq!(
+            file_events.first().unwrap(),
+            &&DownloadEvent::Started {
+                url: file_url.clone(),
+                total_bytes: Some(7)
+            }
+        );
+        assert_eq!(
+            file_events[file_events.len() - 2],
+            &DownloadEvent::Progress {
+                url: file_url.clone(),
+                downloaded: 7
+            }
+        );
+        assert_eq!(
+            file_events.last().unwrap(),
+            &&DownloadEvent::Completed {
+                url: file_url,
+                path: file.clone()
+            }
+        );
+        assert_eq!(std::fs::read(file).unwrap(), b"content");
+        assert!(events.iter().any(|event| matches!(
+            event,
+            DownloadEvent::Failed { url, error } if *url == missing_url && error.contains("404")
+        )));
+        let unavailable_url = format!("{}/unavailable", server.url());
+        assert!(events.iter().any(|event| matches!(
+            event,
+            DownloadEvent::Retrying { url, attempt: 1, error, .. }
+                if *url == unavailable_url && error.contains("503")
+        )));
+        // 404 is not retried
+        assert!(!events.iter().any(|event| matches!(
+            event,
+            DownloadEvent::Retrying { url, .. } if *url == missing_url
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_checksum() {
+        let hash = |checksum: Checksum| checksum.calculate(&mut b"abc".as_slice()).unwrap();
+        assert_eq!(
+            hash(Checksum::Sha1(String::new())),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hash(Checksum::Sha256(String::new())),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(hash(Checksum::Sha512(String::new())).starts_with("ddaf35a193617aba"));
+        assert_eq!(
+            hash(Checksum::Md5(String::new())),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert!(
+            Checksum::Md5("900150983CD24FB0D6963F7D28E17F72".to_string())
+                .matches("900150983cd24fb0d6963f7d28e17f72")
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/file")
+            .with_body("abc")
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let task = |checksum: Option<Checksum>| Download {
+            checksum,
+            ..Download::new(format!("{}/file", server.url()), dir.path().join("file"))
+                .with_sha1("a9993e364706816aba3e25717850c26c9cd0d89d".to_string())
+        };
+        assert_eq!(
+            task(None).checksum(),
+            Some(Checksum::Sha1(
+                "a9993e364706816aba3e25717850c26c9cd0d89d".to_string()
+            ))
+        );
+        let sha256 = Checksum::Sha256(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string(),
+        );
+        download_verified(task(Some(sha256)), &CancelHandle::new())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(dir.path().join("file")).unwrap(), b"abc");
+
+        let md5 = Checksum::Md5("00000000000000000000000000000000".to_string());
+        let err = download_verified(task(Some(md5)), &CancelHandle::new())
+            .await
+            .unwrap_err();
+        let DownloadError::HashMismatch {
+            algorithm, actual, ..
+        } = err
+        else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(algorithm, "md5");
+        assert_eq!(actual, "900150983cd24fb0d6963f7d28e17f72");
+        assert!(!dir.path().join("file").exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_error() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/file")
+            .with_body("abc")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let task = |url: String, file: PathBuf| Download::new(url, file);
+        let file = dir.path().join("file");
+        let cancel = CancelHandle::new();
+
+        let err = download_verified(
+            task(format!("{}/missing", server.url()), file.clone()),
+            &cancel,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, DownloadError::Status(404)));
+        assert_eq!(err.to_string(), "server responded 404 Not Found");
+
+        let err = download_verified(
+            Download {
+                sha1: Some("0000000000000000000000000000000000000000".to_string()),
+                ..task(format!("{}/file", server.url()), file.clone())
+            },
+            &cancel,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, DownloadError::HashMismatch { .. }));
+
+        let err = download_verified(
+            Download {
+                size: Some(4),
+                ..task(format!("{}/file", server.url()), file.clone())
+            },
+            &cancel,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            DownloadError::SizeMismatch {
+                expected: 4,
+                actual: 3,
+                ..
+            }
+        ));
+
+        // nothing listens on the port 1
+        let err = download_verified(task("http://127.0.0.1:1/file".to_string(), file), &cancel)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DownloadError::Network(_)));
+
+        // the parent of the file is a file
+        std::fs::write(dir.path().join("parent"), "").unwrap();
+        let err = download_verified(
+            task(
+                format!("{}/file", server.url()),
+                dir.path().join("parent").join("file"),
+            ),
+            &cancel,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, DownloadError::Io(_)));
+
+        assert!(matches!(
+            DownloadError::from(Cancelled),
+            DownloadError::Cancelled
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_download_resumable() {
+        let mut server = mockito::Server::new_async().await;
+        let resumed = server
+            .mock("GET", "/client.jar")
+            .match_header("range", "bytes=3-")
+            .with_status(206)
+            .with_header("content-range", "bytes 3-7/8")
+            .with_body("defgh")
+            .expect(1)
+            .create_async()
+            .await;
+        let full = server
+            .mock("GET", "/client.jar")
+            .match_header("range", mockito::Matcher::Missing)
+            .with_body("abcdefgh")
+            .expect(0)
+            .create_async()
+            .await;
+        // a server without range support
+        server
+            .mock("GET", "/ignored.jar")
+            .with_body("abcdefgh")
+            .create_async()
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let sha1 = Checksum::Sha1(String::new())
+            .calculate(&mut b"abcdefgh".as_slice())
+            .unwrap();
+        let task = |name: &str| {
+            Download::new(format!("{}/{name}", server.url()), dir.path().join(name))
+                .with_sha1(sha1.clone())
+                .with_size(8)
+        };
+
+        std::fs::write(dir.path().join("client.jar.part"), "abc").unwrap();
+        download_resumable(task("client.jar"), &CancelHandle::new())
+            .await
+            .unwrap();
+        resumed.assert_async().await;
+        full.assert_async().await;
+        assert_eq!(
+            std::fs::read(dir.path().join("client.jar")).unwrap(),
+            b"abcdefgh"
+        );
+        assert!(!dir.path().join("client.jar.part").exists());
+
+        std::fs::write(dir.path().join("ignored.jar.part"), "xyz").unwrap();
+        download_resumable(task("ignored.jar"), &CancelHandle::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(dir.path().join("ignored.jar")).unwrap(),
+            b"abcdefgh"
+        );
+
+        // the resumed file is corrupted
+        std::fs::write(dir.path().join("client.jar.part"), "xyz").unwrap();
+        let err = download_resumable(task("client.jar"), &CancelHandle::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DownloadError::HashMismatch { .. }));
+        assert!(!dir.path().join("client.jar.part").exists());
+    }
 }
@@ -16,7 +16,9 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod cache;
 pub mod download;
 pub mod sha1;
+pub mod template;
 pub mod unzip;
 pub mod nbt;
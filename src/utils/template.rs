@@ -0,0 +1,206 @@
+/*
+ * Magical Launcher Core
+ * Copyright (C) 2023 Broken-Deer <old_driver__@outlook.com> and contributors
+ *
+ * This program is free software, you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Substitute the `${name}` placeholders of version json arguments and other templates.
+//!
+//! `$${` is an escaped `${`, the text after it is kept as is.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use mgl_core::utils::template::{substitute, UnknownVarPolicy};
+//!
+//! let vars = HashMap::from([("natives_directory", "/home/steve/my natives".to_string())]);
+//! let arg = substitute(
+//!     "-Djava.library.path=${natives_directory}",
+//!     &vars,
+//!     UnknownVarPolicy::Error,
+//! )
+//! .unwrap();
+//! assert_eq!(arg, "-Djava.library.path=/home/steve/my natives");
+//! ```
+//!
+//! The result is a single argument, pass it to [`std::process::Command::arg`] rather than
+//! quoting it, so the values with spaces need no escaping.
+
+use std::{collections::HashMap, fmt::Display};
+
+use anyhow::Result;
+
+/// What to do with a placeholder missing in the variables
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownVarPolicy {
+    /// Fail with [`UnknownVariable`]
+    Error,
+    /// Keep the placeholder, like `${clientid}`
+    #[default]
+    LeaveAsIs,
+    /// Replace the placeholder with nothing
+    RemoveToken,
+}
+
+/// A placeholder is not in the variables, with [`UnknownVarPolicy::Error`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownVariable(pub String);
+
+impl Display for UnknownVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown variable ${{{}}}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownVariable {}
+
+/// Replace the `${name}` placeholders of a template with the variables.
+///
+/// An unclosed `${` is kept as is.
+pub fn substitute(
+    template: &str,
+    vars: &HashMap<&str, String>,
+    policy: UnknownVarPolicy,
+) -> Result<String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            result.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some((name, after)) = rest
+            .strip_prefix("${")
+            .and_then(|placeholder| placeholder.split_once('}'))
+        else {
+            result.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+        match (vars.get(name), policy) {
+            (Some(value), _) => result.push_str(value),
+            (None, UnknownVarPolicy::Error) => return Err(UnknownVariable(name.to_string()).into()),
+            (None, UnknownVarPolicy::LeaveAsIs) => result.push_str(&rest[..name.len() + 3]),
+            (None, UnknownVarPolicy::RemoveToken) => {}
+        }
+        rest = after;
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<&'static str, String> {
+        HashMap::from([
+            ("version_name", "1.20.1".to_string()),
+            (
+                "game_directory",
+                "/home/steve/My Games/.minecraft".to_string(),
+            ),
+            ("empty", String::new()),
+            ("dollar", "$${not_a_var}".to_string()),
+        ])
+    }
+
+    fn substitute_all(template: &str, policy: UnknownVarPolicy) -> Result<String> {
+        substitute(template, &vars(), policy)
+    }
+
+    #[test]
+    fn test_substitute() {
+        let leave = UnknownVarPolicy::LeaveAsIs;
+        assert_eq!(substitute_all("--version", leave).unwrap(), "--version");
+        assert_eq!(substitute_all("", leave).unwrap(), "");
+        assert_eq!(substitute_all("${version_name}", leave).unwrap(), "1.20.1");
+        assert_eq!(
+            substitute_all("${game_directory}", leave).unwrap(),
+            "/home/steve/My Games/.minecraft"
+        );
+        assert_eq!(
+            substitute_all("-Dpath=${game_directory}/natives", leave).unwrap(),
+            "-Dpath=/home/steve/My Games/.minecraft/natives"
+        );
+        assert_eq!(substitute_all("a${empty}b", leave).unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_adjacent_placeholders() {
+        let leave = UnknownVarPolicy::LeaveAsIs;
+        assert_eq!(
+            substitute_all("${version_name}${version_name}", leave).unwrap(),
+            "1.20.11.20.1"
+        );
+        assert_eq!(
+            substitute_all("${version_name}-${empty}${version_name}", leave).unwrap(),
+            "1.20.1-1.20.1"
+        );
+        assert_eq!(
+            substitute_all("${unknown}${version_name}", leave).unwrap(),
+            "${unknown}1.20.1"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        let error = UnknownVarPolicy::Error;
+        assert_eq!(
+            substitute_all("$${version_name}", error).unwrap(),
+            "${version_name}"
+        );
+        assert_eq!(
+            substitute_all("$$${version_name}", error).unwrap(),
+            "$${version_name}"
+        );
+        assert_eq!(
+            substitute_all("$${a}${version_name}", error).unwrap(),
+            "${a}1.20.1"
+        );
+        // the values are not substituted again
+        assert_eq!(substitute_all("${dollar}", error).unwrap(), "$${not_a_var}");
+        assert_eq!(substitute_all("$5 and $", error).unwrap(), "$5 and $");
+        assert_eq!(substitute_all("${unclosed", error).unwrap(), "${unclosed");
+    }
+
+    #[test]
+    fn test_unknown_var_policy() {
+        let template = "--clientId ${clientid} --version ${version_name}";
+        assert_eq!(
+            substitute_all(template, UnknownVarPolicy::LeaveAsIs).unwrap(),
+            "--clientId ${clientid} --version 1.20.1"
+        );
+        assert_eq!(
+            substitute_all(template, UnknownVarPolicy::RemoveToken).unwrap(),
+            "--clientId  --version 1.20.1"
+        );
+        let err = substitute_all(template, UnknownVarPolicy::Error).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<UnknownVariable>(),
+            Some(&UnknownVariable("clientid".to_string()))
+        );
+        assert_eq!(err.to_string(), "unknown variable ${clientid}");
+        assert_eq!(
+            substitute_all("${}", UnknownVarPolicy::LeaveAsIs).unwrap(),
+            "${}"
+        );
+    }
+}